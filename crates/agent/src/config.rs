@@ -1,6 +1,172 @@
+use anyhow::{bail, Context};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub const DEFAULT_HERALD_URL: &str = "wss://api.herald.dev/v1/tunnel";
+pub const DEFAULT_PING_TIMEOUT_SECS: u64 = 90;
+
 #[derive(Debug, Clone)]
 pub struct AgentConfig {
     pub token: String,
     pub forward_url: String,
     pub herald_url: String,
+    /// When set, local forwards are signed the same way Herald signs webhooks.
+    pub forward_secret: Option<String>,
+    /// Reconnect if the server sends nothing (not even a ping) for this long.
+    pub ping_timeout: std::time::Duration,
+    /// Where signals the local endpoint keeps refusing are kept for replay.
+    pub spool_dir: Option<PathBuf>,
+    /// Extra CA certificate (PEM) trusted for Herald and the local endpoint.
+    pub ca_cert: Option<PathBuf>,
+    /// Skip TLS certificate verification. Development only.
+    pub insecure_skip_verify: bool,
+}
+
+/// Agent settings from a single source — CLI flags, a config file, or the
+/// environment. Unset fields are filled from lower-priority sources by
+/// [`PartialConfig::merge`].
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PartialConfig {
+    pub token: Option<String>,
+    pub forward_url: Option<String>,
+    pub herald_url: Option<String>,
+    pub forward_secret: Option<String>,
+    /// Seconds.
+    pub ping_timeout: Option<u64>,
+    pub spool_dir: Option<PathBuf>,
+    pub ca_cert: Option<PathBuf>,
+    pub insecure_skip_verify: Option<bool>,
+}
+
+impl PartialConfig {
+    /// Read a TOML config file with the same fields as the CLI flags.
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read config file {}", path.display()))?;
+        toml::from_str(&text)
+            .with_context(|| format!("failed to parse config file {}", path.display()))
+    }
+
+    /// The token from `HERALD_TOKEN`, if set.
+    pub fn from_env() -> Self {
+        Self {
+            token: std::env::var("HERALD_TOKEN").ok(),
+            ..Self::default()
+        }
+    }
+
+    /// Keep the fields set here, taking the rest from `fallback`.
+    pub fn merge(self, fallback: Self) -> Self {
+        Self {
+            token: non_empty(self.token).or(non_empty(fallback.token)),
+            forward_url: non_empty(self.forward_url).or(non_empty(fallback.forward_url)),
+            herald_url: non_empty(self.herald_url).or(non_empty(fallback.herald_url)),
+            forward_secret: self.forward_secret.or(fallback.forward_secret),
+            ping_timeout: self.ping_timeout.or(fallback.ping_timeout),
+            spool_dir: self.spool_dir.or(fallback.spool_dir),
+            ca_cert: self.ca_cert.or(fallback.ca_cert),
+            insecure_skip_verify: self.insecure_skip_verify.or(fallback.insecure_skip_verify),
+        }
+    }
+
+    /// Apply defaults and check that the required fields were supplied.
+    pub fn build(self) -> anyhow::Result<AgentConfig> {
+        let Some(token) = non_empty(self.token) else {
+            bail!("no token given: use --token, `token` in the config file, or HERALD_TOKEN");
+        };
+        let Some(forward_url) = non_empty(self.forward_url) else {
+            bail!("no forward URL: pass --forward or set `forward_url` in the config file");
+        };
+        Ok(AgentConfig {
+            token,
+            forward_url,
+            herald_url: non_empty(self.herald_url)
+                .unwrap_or_else(|| DEFAULT_HERALD_URL.to_string()),
+            forward_secret: self.forward_secret,
+            ping_timeout: Duration::from_secs(
+                self.ping_timeout.unwrap_or(DEFAULT_PING_TIMEOUT_SECS),
+            ),
+            spool_dir: self.spool_dir,
+            ca_cert: self.ca_cert,
+            insecure_skip_verify: self.insecure_skip_verify.unwrap_or(false),
+        })
+    }
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|value| !value.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_config_parses_documented_example() {
+        let file: PartialConfig = toml::from_str(
+            r#"
+token = "hld_sub_xxx"
+forward_url = "http://localhost:8080/hooks/herald"
+ping_timeout = 45
+"#,
+        )
+        .unwrap();
+
+        let config = file.build().unwrap();
+        assert_eq!(config.token, "hld_sub_xxx");
+        assert_eq!(config.forward_url, "http://localhost:8080/hooks/herald");
+        assert_eq!(config.herald_url, DEFAULT_HERALD_URL);
+        assert_eq!(config.ping_timeout, Duration::from_secs(45));
+    }
+
+    #[test]
+    fn test_file_config_rejects_unknown_keys() {
+        let err = toml::from_str::<PartialConfig>(
+            r#"
+token = "hld_sub_xxx"
+forward-url = "http://localhost:8080/hooks/herald"
+"#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("forward-url"));
+    }
+
+    #[test]
+    fn test_flags_override_file_and_env_fills_token() {
+        let cli = PartialConfig {
+            forward_url: Some("http://localhost:9000".to_string()),
+            ..PartialConfig::default()
+        };
+        let file = PartialConfig {
+            forward_url: Some("http://localhost:8080".to_string()),
+            herald_url: Some("wss://eu.herald.dev/v1/tunnel".to_string()),
+            ..PartialConfig::default()
+        };
+        let env = PartialConfig {
+            token: Some("hld_sub_env".to_string()),
+            ..PartialConfig::default()
+        };
+
+        let config = cli.merge(file).merge(env).build().unwrap();
+        assert_eq!(config.forward_url, "http://localhost:9000");
+        assert_eq!(config.herald_url, "wss://eu.herald.dev/v1/tunnel");
+        assert_eq!(config.token, "hld_sub_env");
+    }
+
+    #[test]
+    fn test_missing_token_after_all_sources_errors() {
+        let cli = PartialConfig {
+            forward_url: Some("http://localhost:9000".to_string()),
+            ..PartialConfig::default()
+        };
+        let env = PartialConfig {
+            token: Some(String::new()),
+            ..PartialConfig::default()
+        };
+
+        let err = cli.merge(env).build().unwrap_err();
+        assert!(err.to_string().contains("HERALD_TOKEN"));
+    }
 }
@@ -1,18 +1,79 @@
+use rustls::ClientConfig;
 use serde::Serialize;
+use std::time::Duration;
+use tracing::warn;
 
+use core::auth::{sign_versioned, SignatureAlgorithm};
 use core::tunnel::TunnelSignal;
 
+/// Waits between attempts to reach the local endpoint; a signal gets one
+/// more attempt than there are delays.
+const FORWARD_RETRY_DELAYS: [Duration; 3] = [
+    Duration::from_millis(500),
+    Duration::from_secs(2),
+    Duration::from_secs(5),
+];
+
 pub struct Forwarder {
     client: reqwest::Client,
     forward_url: String,
+    signing_secret: Option<String>,
+    retry_delays: Vec<Duration>,
 }
 
 impl Forwarder {
-    pub fn new(forward_url: String) -> anyhow::Result<Self> {
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
-            .build()?;
-        Ok(Self { client, forward_url })
+    /// `tls` replaces the default trust roots when set; see [`crate::tls`].
+    pub fn new(forward_url: String, tls: Option<&ClientConfig>) -> anyhow::Result<Self> {
+        let mut builder = reqwest::Client::builder().timeout(std::time::Duration::from_secs(30));
+        if let Some(tls) = tls {
+            builder = builder.use_preconfigured_tls(tls.clone());
+        }
+        let client = builder.build()?;
+        Ok(Self {
+            client,
+            forward_url,
+            signing_secret: None,
+            retry_delays: FORWARD_RETRY_DELAYS.to_vec(),
+        })
+    }
+
+    /// Sign forwarded bodies with a versioned `X-Herald-Signature`
+    /// (`t=<ts>,v1=<hmac>`); `X-Herald-Timestamp` repeats `t`.
+    pub fn with_signing_secret(mut self, secret: String) -> Self {
+        self.signing_secret = Some(secret);
+        self
+    }
+
+    #[cfg(test)]
+    fn with_retry_delays(mut self, delays: Vec<Duration>) -> Self {
+        self.retry_delays = delays;
+        self
+    }
+
+    /// [`Forwarder::deliver_signal`], retried with backoff while the local
+    /// endpoint is failing. Returns the last error once attempts run out.
+    pub async fn deliver_with_retry(
+        &self,
+        delivery_id: &str,
+        channel_id: &str,
+        channel_slug: &str,
+        signal: &TunnelSignal,
+    ) -> anyhow::Result<()> {
+        let mut delays = self.retry_delays.iter();
+        loop {
+            let err = match self
+                .deliver_signal(delivery_id, channel_id, channel_slug, signal)
+                .await
+            {
+                Ok(()) => return Ok(()),
+                Err(err) => err,
+            };
+            let Some(delay) = delays.next() else {
+                return Err(err);
+            };
+            warn!(error = %err, %delivery_id, ?delay, "local forward failed, retrying");
+            tokio::time::sleep(*delay).await;
+        }
     }
 
     pub async fn deliver_signal(
@@ -29,13 +90,21 @@ impl Forwarder {
             signal,
         };
 
-        let resp = self
+        let body = serde_json::to_string(&payload)?;
+        let mut req = self
             .client
             .post(&self.forward_url)
-            .header("Content-Type", "application/json")
-            .json(&payload)
-            .send()
-            .await?;
+            .header("Content-Type", "application/json");
+
+        if let Some(secret) = self.signing_secret.as_deref() {
+            let timestamp = chrono::Utc::now().timestamp();
+            let (timestamp, signature) = signed_headers(secret, timestamp, &body);
+            req = req
+                .header("X-Herald-Timestamp", timestamp)
+                .header("X-Herald-Signature", signature);
+        }
+
+        let resp = req.body(body).send().await?;
 
         if resp.status().is_success() {
             Ok(())
@@ -45,6 +114,13 @@ impl Forwarder {
     }
 }
 
+/// The `X-Herald-Timestamp` and versioned `X-Herald-Signature` values for
+/// `body`.
+fn signed_headers(secret: &str, timestamp: i64, body: &str) -> (String, String) {
+    let signature = sign_versioned(secret, timestamp, body, SignatureAlgorithm::Sha256);
+    (timestamp.to_string(), signature)
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct ForwardPayload<'a> {
@@ -58,11 +134,12 @@ struct ForwardPayload<'a> {
 mod tests {
     use super::*;
     use chrono::Utc;
+    use core::auth::{verify_signature_header, SignatureCheck};
     use core::types::SignalUrgency;
 
     #[test]
     fn test_forwarder_new_succeeds() {
-        let forwarder = Forwarder::new("http://localhost:8080/webhook".to_string());
+        let forwarder = Forwarder::new("http://localhost:8080/webhook".to_string(), None);
         assert!(forwarder.is_ok());
     }
 
@@ -77,7 +154,7 @@ mod tests {
         ];
 
         for url in urls {
-            let result = Forwarder::new(url.to_string());
+            let result = Forwarder::new(url.to_string(), None);
             assert!(result.is_ok(), "Should accept valid URL: {}", url);
         }
     }
@@ -191,4 +268,88 @@ mod tests {
         assert_eq!(parsed["signal"]["metadata"]["nested"]["deep"]["value"], true);
         assert!(parsed["signal"]["metadata"]["nullField"].is_null());
     }
+
+    #[test]
+    fn test_signed_headers_match_fixed_vector() {
+        let body = r#"{"deliveryId":"del_sig"}"#;
+        let (timestamp, signature) = signed_headers("fwd_secret", 1_700_000_000, body);
+        assert_eq!(timestamp, "1700000000");
+        assert_eq!(
+            signature,
+            "t=1700000000,v1=a1343fed65847a2538ec9e4feaf9c85856aed736007e56ec09209d84f58603bf"
+        );
+
+        let check = verify_signature_header("fwd_secret", body, &signature, 300, 1_700_000_060);
+        assert_eq!(check, SignatureCheck::Valid);
+    }
+
+    /// Serve one canned HTTP status per connection, in order.
+    async fn serve_statuses(statuses: Vec<u16>) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            for status in statuses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 4096];
+                let _ = socket.read(&mut buf).await;
+                let response = format!(
+                    "HTTP/1.1 {status} X\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        format!("http://{addr}/hooks")
+    }
+
+    fn test_signal() -> TunnelSignal {
+        TunnelSignal {
+            id: "sig_retry".to_string(),
+            title: "Retry".to_string(),
+            body: "Retry body".to_string(),
+            urgency: SignalUrgency::Normal,
+            metadata: serde_json::json!({}),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_deliver_with_retry_recovers_after_failures() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let url = serve_statuses(vec![503, 500, 200]).await;
+            let forwarder = Forwarder::new(url, None)
+                .unwrap()
+                .with_retry_delays(vec![Duration::ZERO; 2]);
+
+            forwarder
+                .deliver_with_retry("del_retry", "ch_retry", "retry", &test_signal())
+                .await
+                .unwrap();
+        });
+    }
+
+    #[test]
+    fn test_deliver_with_retry_gives_up_after_last_attempt() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let url = serve_statuses(vec![503, 503, 200]).await;
+            let forwarder = Forwarder::new(url, None)
+                .unwrap()
+                .with_retry_delays(vec![Duration::ZERO]);
+
+            let err = forwarder
+                .deliver_with_retry("del_retry", "ch_retry", "retry", &test_signal())
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("503"));
+        });
+    }
 }
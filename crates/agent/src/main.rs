@@ -3,20 +3,61 @@ use tracing_subscriber::EnvFilter;
 
 mod config;
 mod forward;
+mod spool;
+mod tls;
 mod tunnel;
 
-use config::AgentConfig;
+use config::PartialConfig;
 
 #[derive(Debug, Parser)]
 #[command(name = "herald-agent")]
 #[command(about = "Herald Agent tunnel client", version)]
 struct Args {
+    /// TOML file with the same settings as the flags; flags take precedence.
     #[arg(long)]
-    token: String,
+    config: Option<std::path::PathBuf>,
+    /// Subscriber token. Falls back to the config file, then HERALD_TOKEN.
     #[arg(long)]
-    forward: String,
-    #[arg(long, default_value = "wss://api.herald.dev/v1/tunnel")]
-    herald_url: String,
+    token: Option<String>,
+    #[arg(long)]
+    forward: Option<String>,
+    /// Tunnel endpoint. [default: wss://api.herald.dev/v1/tunnel]
+    #[arg(long)]
+    herald_url: Option<String>,
+    /// Sign forwarded requests with a versioned X-Herald-Signature using this secret.
+    #[arg(long)]
+    forward_secret: Option<String>,
+    /// Seconds without any message from Herald before reconnecting. Keep it
+    /// above the server's ping interval. [default: 90]
+    #[arg(long)]
+    ping_timeout: Option<u64>,
+    /// Keep signals the local endpoint refuses in this directory and replay
+    /// them until it accepts them.
+    #[arg(long)]
+    spool_dir: Option<std::path::PathBuf>,
+    /// PEM CA certificate to trust in addition to the public roots, for
+    /// Herald deployments behind a private CA.
+    #[arg(long)]
+    ca_cert: Option<std::path::PathBuf>,
+    /// Disable TLS certificate verification. For local development only;
+    /// never use this in production.
+    #[arg(long)]
+    insecure_skip_verify: bool,
+}
+
+impl Args {
+    fn into_partial(self) -> PartialConfig {
+        PartialConfig {
+            token: self.token,
+            forward_url: self.forward,
+            herald_url: self.herald_url,
+            forward_secret: self.forward_secret,
+            ping_timeout: self.ping_timeout,
+            spool_dir: self.spool_dir,
+            ca_cert: self.ca_cert,
+            insecure_skip_verify: self.insecure_skip_verify.then_some(true),
+        }
+    }
 }
 
 #[tokio::main]
@@ -27,11 +68,15 @@ async fn main() -> anyhow::Result<()> {
         .init();
 
     let args = Args::parse();
-    let config = AgentConfig {
-        token: args.token,
-        forward_url: args.forward,
-        herald_url: args.herald_url,
+    let file = match &args.config {
+        Some(path) => PartialConfig::from_file(path)?,
+        None => PartialConfig::default(),
     };
+    let config = args
+        .into_partial()
+        .merge(file)
+        .merge(PartialConfig::from_env())
+        .build()?;
 
     tunnel::run_tunnel(config).await
 }
@@ -50,9 +95,22 @@ mod tests {
             "--herald-url", "wss://custom.herald.dev/tunnel",
         ]).unwrap();
 
-        assert_eq!(args.token, "hld_sub_test123");
-        assert_eq!(args.forward, "http://localhost:8080/hooks");
-        assert_eq!(args.herald_url, "wss://custom.herald.dev/tunnel");
+        assert_eq!(args.token.as_deref(), Some("hld_sub_test123"));
+        assert_eq!(args.forward.as_deref(), Some("http://localhost:8080/hooks"));
+        assert_eq!(args.herald_url.as_deref(), Some("wss://custom.herald.dev/tunnel"));
+        assert_eq!(args.ping_timeout, None);
+    }
+
+    #[test]
+    fn test_args_with_ping_timeout() {
+        let args = Args::try_parse_from([
+            "herald-agent",
+            "--token", "hld_sub_test123",
+            "--forward", "http://localhost:8080/hooks",
+            "--ping-timeout", "45",
+        ]).unwrap();
+
+        assert_eq!(args.ping_timeout, Some(45));
     }
 
     #[test]
@@ -63,42 +121,82 @@ mod tests {
             "--forward", "http://localhost:8080/hooks",
         ]).unwrap();
 
-        assert_eq!(args.token, "hld_sub_test123");
-        assert_eq!(args.forward, "http://localhost:8080/hooks");
-        assert_eq!(args.herald_url, "wss://api.herald.dev/v1/tunnel");
+        let config = args.into_partial().build().unwrap();
+        assert_eq!(config.token, "hld_sub_test123");
+        assert_eq!(config.forward_url, "http://localhost:8080/hooks");
+        assert_eq!(config.herald_url, "wss://api.herald.dev/v1/tunnel");
+        assert_eq!(config.ping_timeout, std::time::Duration::from_secs(90));
     }
 
     #[test]
     fn test_args_missing_token_fails() {
-        let result = Args::try_parse_from([
+        let args = Args::try_parse_from([
             "herald-agent",
             "--forward", "http://localhost:8080/hooks",
-        ]);
-        assert!(result.is_err());
+        ]).unwrap();
+        assert!(args.into_partial().build().is_err());
     }
 
     #[test]
     fn test_args_missing_forward_fails() {
-        let result = Args::try_parse_from([
+        let args = Args::try_parse_from([
             "herald-agent",
             "--token", "hld_sub_test123",
-        ]);
-        assert!(result.is_err());
+        ]).unwrap();
+        assert!(args.into_partial().build().is_err());
+    }
+
+    #[test]
+    fn test_args_config_path() {
+        let args = Args::try_parse_from([
+            "herald-agent",
+            "--config", "/etc/herald/agent.toml",
+            "--spool-dir", "/var/spool/herald",
+        ]).unwrap();
+        assert_eq!(
+            args.config.as_deref(),
+            Some(std::path::Path::new("/etc/herald/agent.toml"))
+        );
+        assert_eq!(
+            args.spool_dir.as_deref(),
+            Some(std::path::Path::new("/var/spool/herald"))
+        );
+        assert!(args.token.is_none());
+    }
+
+    #[test]
+    fn test_args_tls_options() {
+        let args = Args::try_parse_from([
+            "herald-agent",
+            "--token", "hld_sub_test123",
+            "--forward", "https://localhost:8443/hooks",
+            "--ca-cert", "/etc/herald/ca.pem",
+            "--insecure-skip-verify",
+        ]).unwrap();
+
+        let config = args.into_partial().build().unwrap();
+        assert_eq!(
+            config.ca_cert.as_deref(),
+            Some(std::path::Path::new("/etc/herald/ca.pem"))
+        );
+        assert!(config.insecure_skip_verify);
     }
 
     #[test]
     fn test_config_from_args() {
         let args = Args {
-            token: "test_token".to_string(),
-            forward: "http://localhost:9999".to_string(),
-            herald_url: "wss://test.herald.dev".to_string(),
+            config: None,
+            token: Some("test_token".to_string()),
+            forward: Some("http://localhost:9999".to_string()),
+            herald_url: Some("wss://test.herald.dev".to_string()),
+            forward_secret: None,
+            ping_timeout: Some(90),
+            spool_dir: None,
+            ca_cert: None,
+            insecure_skip_verify: false,
         };
 
-        let config = AgentConfig {
-            token: args.token.clone(),
-            forward_url: args.forward.clone(),
-            herald_url: args.herald_url.clone(),
-        };
+        let config = args.into_partial().build().unwrap();
 
         assert_eq!(config.token, "test_token");
         assert_eq!(config.forward_url, "http://localhost:9999");
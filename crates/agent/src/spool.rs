@@ -0,0 +1,146 @@
+//! On-disk queue for signals the local endpoint could not take.
+//!
+//! Each entry is one JSON file named so that lexical order is arrival order.
+//! Entries are replayed whenever the server pings and removed once the
+//! local endpoint accepts them.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use core::tunnel::TunnelSignal;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpooledSignal {
+    pub delivery_id: String,
+    pub channel_id: String,
+    pub channel_slug: String,
+    pub signal: TunnelSignal,
+}
+
+pub struct Spool {
+    dir: PathBuf,
+}
+
+impl Spool {
+    pub async fn open(dir: &Path) -> anyhow::Result<Self> {
+        tokio::fs::create_dir_all(dir).await?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+        })
+    }
+
+    /// Persist `entry`. Written to a temp file first so a crash never leaves
+    /// a half-written entry behind.
+    pub async fn push(&self, entry: &SpooledSignal) -> anyhow::Result<PathBuf> {
+        let name = format!(
+            "{:020}_{}",
+            chrono::Utc::now().timestamp_micros(),
+            file_safe(&entry.delivery_id)
+        );
+        let path = self.dir.join(format!("{name}.json"));
+        let tmp = self.dir.join(format!("{name}.tmp"));
+        tokio::fs::write(&tmp, serde_json::to_vec(entry)?).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        Ok(path)
+    }
+
+    /// Spooled entries, oldest first. Unreadable files are skipped.
+    pub async fn pending(&self) -> anyhow::Result<Vec<(PathBuf, SpooledSignal)>> {
+        let mut paths = Vec::new();
+        let mut dir = tokio::fs::read_dir(&self.dir).await?;
+        while let Some(entry) = dir.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        let mut entries = Vec::with_capacity(paths.len());
+        for path in paths {
+            let bytes = tokio::fs::read(&path).await?;
+            match serde_json::from_slice(&bytes) {
+                Ok(entry) => entries.push((path, entry)),
+                Err(err) => {
+                    warn!(path = %path.display(), error = %err, "skipping bad spool entry");
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    pub async fn remove(&self, path: &Path) -> anyhow::Result<()> {
+        tokio::fs::remove_file(path).await?;
+        Ok(())
+    }
+}
+
+/// Delivery ids come from the server; keep only characters that are safe
+/// in a file name.
+fn file_safe(id: &str) -> String {
+    id.chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use core::types::SignalUrgency;
+
+    fn entry(delivery_id: &str) -> SpooledSignal {
+        SpooledSignal {
+            delivery_id: delivery_id.to_string(),
+            channel_id: "ch_spool".to_string(),
+            channel_slug: "spool-test".to_string(),
+            signal: TunnelSignal {
+                id: "sig_spool".to_string(),
+                title: "Spooled".to_string(),
+                body: "Held for later".to_string(),
+                urgency: SignalUrgency::Normal,
+                metadata: serde_json::json!({}),
+                created_at: Utc::now(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_spool_replays_in_arrival_order_and_removes() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let dir = std::env::temp_dir().join(format!("herald-spool-{}", unique_suffix()));
+            let spool = Spool::open(&dir).await.unwrap();
+
+            spool.push(&entry("del_first")).await.unwrap();
+            spool.push(&entry("../del_second")).await.unwrap();
+
+            let pending = spool.pending().await.unwrap();
+            let ids: Vec<_> = pending
+                .iter()
+                .map(|(_, e)| e.delivery_id.as_str())
+                .collect();
+            assert_eq!(ids, vec!["del_first", "../del_second"]);
+            assert!(pending
+                .iter()
+                .all(|(path, _)| path.parent() == Some(dir.as_path())));
+
+            spool.remove(&pending[0].0).await.unwrap();
+            assert_eq!(spool.pending().await.unwrap().len(), 1);
+
+            tokio::fs::remove_dir_all(&dir).await.unwrap();
+        });
+    }
+
+    fn unique_suffix() -> u128 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos()
+    }
+}
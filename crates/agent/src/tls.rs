@@ -0,0 +1,141 @@
+//! TLS settings shared by the tunnel connection and the local forwarder.
+//!
+//! By default both trust the bundled web PKI roots. Self-hosted Herald
+//! deployments behind a private CA pass `--ca-cert` to add that CA;
+//! `--insecure-skip-verify` turns verification off entirely and exists only
+//! for local development.
+
+use anyhow::{bail, Context};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use std::path::Path;
+use std::sync::Arc;
+
+/// A client config for the given options, or `None` when the defaults
+/// apply.
+pub fn client_config(
+    ca_cert: Option<&Path>,
+    insecure_skip_verify: bool,
+) -> anyhow::Result<Option<Arc<ClientConfig>>> {
+    if !insecure_skip_verify && ca_cert.is_none() {
+        return Ok(None);
+    }
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()?;
+
+    let config = if insecure_skip_verify {
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(SkipVerify(provider)))
+            .with_no_client_auth()
+    } else {
+        let mut roots = RootCertStore::empty();
+        roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        if let Some(path) = ca_cert {
+            for cert in load_certs(path)? {
+                roots
+                    .add(cert)
+                    .with_context(|| format!("invalid CA certificate in {}", path.display()))?;
+            }
+        }
+        builder.with_root_certificates(roots).with_no_client_auth()
+    };
+    Ok(Some(Arc::new(config)))
+}
+
+/// PEM certificates from `path`.
+fn load_certs(path: &Path) -> anyhow::Result<Vec<CertificateDer<'static>>> {
+    let pem = std::fs::read(path)
+        .with_context(|| format!("failed to read CA certificate {}", path.display()))?;
+    let certs = rustls_pemfile::certs(&mut pem.as_slice())
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("failed to parse CA certificate {}", path.display()))?;
+    if certs.is_empty() {
+        bail!("no certificates found in {}", path.display());
+    }
+    Ok(certs)
+}
+
+/// Accepts any server certificate. Handshake signatures are still checked
+/// so the connection is at least consistent with the presented cert.
+#[derive(Debug)]
+struct SkipVerify(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for SkipVerify {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_need_no_custom_config() {
+        assert!(client_config(None, false).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_insecure_skip_verify_builds_config() {
+        assert!(client_config(None, true).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_ca_cert_file_without_certificates_errors() {
+        let path = std::env::temp_dir().join(format!("herald-ca-{}.pem", std::process::id()));
+        std::fs::write(&path, "not a certificate").unwrap();
+        let err = client_config(Some(&path), false).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert!(err.to_string().contains("no certificates found"));
+    }
+
+    #[test]
+    fn test_missing_ca_cert_file_errors() {
+        let err = client_config(Some(Path::new("/nonexistent/ca.pem")), false).unwrap_err();
+        assert!(err.to_string().contains("failed to read CA certificate"));
+    }
+}
@@ -1,21 +1,47 @@
 use backoff::{backoff::Backoff, ExponentialBackoff};
 use futures_util::{SinkExt, StreamExt};
+use rustls::ClientConfig;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use tokio::sync::{mpsc, Notify};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
 use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::Connector;
 use tracing::{error, info, warn};
 
-use core::tunnel::{ClientMessage, ServerMessage};
+use core::tunnel::{
+    ClientMessage, ServerMessage, TunnelSignal, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION,
+};
 
 use crate::config::AgentConfig;
 use crate::forward::Forwarder;
+use crate::spool::{Spool, SpooledSignal};
+use crate::tls;
+
+type WsWrite = futures_util::stream::SplitSink<
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>,
+    Message,
+>;
 
 pub async fn run_tunnel(config: AgentConfig) -> anyhow::Result<()> {
+    if config.insecure_skip_verify {
+        warn!("TLS certificate verification is disabled; do not use this in production");
+    }
+    let tls = tls::client_config(config.ca_cert.as_deref(), config.insecure_skip_verify)?;
+    let spool = match &config.spool_dir {
+        Some(dir) => Some(Arc::new(Spool::open(dir).await?)),
+        None => None,
+    };
     let mut backoff = ExponentialBackoff {
         max_elapsed_time: None,
         ..Default::default()
     };
+    // Survives reconnects so the server can replay what arrived in between.
+    let mut seqs = SeqTracker::default();
 
     loop {
-        match connect_and_run(&config).await {
+        match connect_and_run(&config, spool.as_ref(), tls.as_ref(), &mut seqs).await {
             Ok(()) => {
                 info!("tunnel disconnected cleanly");
                 backoff.reset();
@@ -33,29 +59,86 @@ pub async fn run_tunnel(config: AgentConfig) -> anyhow::Result<()> {
     }
 }
 
-async fn connect_and_run(config: &AgentConfig) -> anyhow::Result<()> {
-    let (ws_stream, _) = tokio_tungstenite::connect_async(&config.herald_url).await?;
+async fn connect_and_run(
+    config: &AgentConfig,
+    spool: Option<&Arc<Spool>>,
+    tls: Option<&Arc<ClientConfig>>,
+    seqs: &mut SeqTracker,
+) -> anyhow::Result<()> {
+    let connector = tls.map(|tls| Connector::Rustls(tls.clone()));
+    let (ws_stream, _) = tokio_tungstenite::connect_async_tls_with_config(
+        &config.herald_url,
+        None,
+        false,
+        connector,
+    )
+    .await?;
     let (mut write, mut read) = ws_stream.split();
 
+    // The version goes in the auth message rather than a `herald.vN`
+    // subprotocol: servers that predate negotiation never echo one back, and
+    // the handshake would fail against them.
     let auth = ClientMessage::Auth {
         token: config.token.clone(),
+        version: Some(PROTOCOL_VERSION),
+        last_seen_seq: seqs.last_seen(),
     };
     write
         .send(Message::Text(serde_json::to_string(&auth)?))
         .await?;
+    seqs.reconnected();
+
+    let mut forwarder = Forwarder::new(config.forward_url.clone(), tls.map(Arc::as_ref))?;
+    if let Some(secret) = config.forward_secret.clone() {
+        forwarder = forwarder.with_signing_secret(secret);
+    }
+
+    // Forwarding runs on its own task: retries against a failing endpoint
+    // outlast the server's ping window, and pongs must keep going out.
+    let (signal_tx, signal_rx) = mpsc::unbounded_channel();
+    let (handled_tx, mut handled_rx) = mpsc::unbounded_channel();
+    let replay = Arc::new(Notify::new());
+    let _deliveries = AbortOnDrop(tokio::spawn(run_deliveries(
+        forwarder,
+        spool.cloned(),
+        signal_rx,
+        replay.clone(),
+        handled_tx,
+    )));
 
-    let forwarder = Forwarder::new(config.forward_url.clone())?;
+    let mut deadline = Instant::now() + config.ping_timeout;
+    loop {
+        let message = tokio::select! {
+            message = tokio::time::timeout_at(deadline, read.next()) => match message {
+                Ok(Some(message)) => message?,
+                Ok(None) => break,
+                Err(_) => {
+                    return Err(anyhow::anyhow!(
+                        "no message from server in {:?}, reconnecting",
+                        config.ping_timeout
+                    ));
+                }
+            },
+            Some(handled) = handled_rx.recv() => {
+                if let Some(delivery_id) = handled.ack {
+                    send_ack(&mut write, delivery_id).await?;
+                }
+                if let Some(seq) = handled.seq {
+                    seqs.record(seq, handled.dropped);
+                }
+                continue;
+            }
+        };
+        deadline = Instant::now() + config.ping_timeout;
 
-    while let Some(message) = read.next().await {
-        let message = message?;
         match message {
             Message::Text(text) => {
-                handle_server_message(&forwarder, &mut write, &text).await?;
+                handle_server_message(&signal_tx, &replay, &mut write, &text).await?;
             }
             Message::Binary(bytes) => {
                 match String::from_utf8(bytes) {
                     Ok(text) => {
-                        handle_server_message(&forwarder, &mut write, &text).await?;
+                        handle_server_message(&signal_tx, &replay, &mut write, &text).await?;
                     }
                     Err(err) => {
                         warn!(error = %err, "received non-utf8 binary message");
@@ -77,12 +160,39 @@ async fn connect_and_run(config: &AgentConfig) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// A signal handed from the read loop to the delivery task.
+struct IncomingSignal {
+    delivery_id: String,
+    channel_id: String,
+    channel_slug: String,
+    signal: TunnelSignal,
+    seq: Option<u64>,
+}
+
+/// What the delivery task reports back for the read loop to send.
+struct Handled {
+    /// Delivery to ack, once the local endpoint has taken it.
+    ack: Option<String>,
+    /// Sequence number of the signal, if it came with one.
+    seq: Option<u64>,
+    /// Set when the signal was neither forwarded nor spooled.
+    dropped: bool,
+}
+
+/// Aborts the delivery task when its connection ends; the server redelivers
+/// anything past the last recorded sequence number on reconnect.
+struct AbortOnDrop(JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
 async fn handle_server_message(
-    forwarder: &Forwarder,
-    write: &mut futures_util::stream::SplitSink<
-        tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>,
-        Message,
-    >,
+    signals: &mpsc::UnboundedSender<IncomingSignal>,
+    replay: &Notify,
+    write: &mut WsWrite,
     text: &str,
 ) -> anyhow::Result<()> {
     let message: ServerMessage = match serde_json::from_str(text) {
@@ -97,8 +207,18 @@ async fn handle_server_message(
         ServerMessage::AuthOk {
             connection_id,
             subscriber_id,
+            protocol_version,
         } => {
-            info!(%connection_id, %subscriber_id, "tunnel authenticated");
+            if !(MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&protocol_version) {
+                return Err(anyhow::anyhow!(
+                    "server negotiated tunnel protocol v{}, this agent speaks v{} to v{}",
+                    protocol_version,
+                    MIN_PROTOCOL_VERSION,
+                    PROTOCOL_VERSION
+                ));
+            }
+            info!(%connection_id, %subscriber_id, protocol_version, "tunnel authenticated");
+            replay.notify_one();
         }
         ServerMessage::AuthError { message } => {
             return Err(anyhow::anyhow!(message));
@@ -108,29 +228,260 @@ async fn handle_server_message(
             write
                 .send(Message::Text(serde_json::to_string(&pong)?))
                 .await?;
+            replay.notify_one();
         }
         ServerMessage::Signal {
             delivery_id,
             channel_id,
             channel_slug,
             signal,
+            seq,
         } => {
-            match forwarder
-                .deliver_signal(&delivery_id, &channel_id, &channel_slug, &signal)
-                .await
-            {
-                Ok(()) => {
-                    let ack = ClientMessage::Ack { delivery_id };
-                    write
-                        .send(Message::Text(serde_json::to_string(&ack)?))
-                        .await?;
+            let incoming = IncomingSignal {
+                delivery_id,
+                channel_id,
+                channel_slug,
+                signal,
+                seq,
+            };
+            if signals.send(incoming).is_err() {
+                return Err(anyhow::anyhow!("delivery task stopped"));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Forward signals one at a time, and replay the spool whenever `replay` is
+/// notified. Repeated notifications while busy collapse into one replay.
+async fn run_deliveries(
+    forwarder: Forwarder,
+    spool: Option<Arc<Spool>>,
+    mut signals: mpsc::UnboundedReceiver<IncomingSignal>,
+    replay: Arc<Notify>,
+    handled: mpsc::UnboundedSender<Handled>,
+) {
+    loop {
+        tokio::select! {
+            incoming = signals.recv() => {
+                let Some(incoming) = incoming else {
+                    return;
+                };
+                let outcome = deliver(&forwarder, spool.as_deref(), incoming).await;
+                if handled.send(outcome).is_err() {
+                    return;
                 }
-                Err(err) => {
-                    warn!(error = %err, "local forward failed");
+            }
+            _ = replay.notified() => {
+                if let Some(spool) = &spool {
+                    replay_spool(&forwarder, spool, &handled).await;
                 }
             }
         }
     }
+}
+
+/// Forward one signal, spooling it if the local endpoint keeps failing.
+async fn deliver(
+    forwarder: &Forwarder,
+    spool: Option<&Spool>,
+    incoming: IncomingSignal,
+) -> Handled {
+    let IncomingSignal {
+        delivery_id,
+        channel_id,
+        channel_slug,
+        signal,
+        seq,
+    } = incoming;
+    let err = match forwarder
+        .deliver_with_retry(&delivery_id, &channel_id, &channel_slug, &signal)
+        .await
+    {
+        Ok(()) => {
+            return Handled {
+                ack: Some(delivery_id),
+                seq,
+                dropped: false,
+            }
+        }
+        Err(err) => err,
+    };
+
+    let Some(spool) = spool else {
+        warn!(error = %err, %delivery_id, "local forward failed");
+        return Handled {
+            ack: None,
+            seq,
+            dropped: true,
+        };
+    };
+    let entry = SpooledSignal {
+        delivery_id,
+        channel_id,
+        channel_slug,
+        signal,
+    };
+    match spool.push(&entry).await {
+        Ok(_) => {
+            // The spool now owns it and acks it on replay.
+            warn!(
+                error = %err,
+                delivery_id = %entry.delivery_id,
+                "local forward failed, spooled for replay"
+            );
+            Handled {
+                ack: None,
+                seq,
+                dropped: false,
+            }
+        }
+        Err(spool_err) => {
+            error!(
+                error = %err,
+                spool_error = %spool_err,
+                delivery_id = %entry.delivery_id,
+                "local forward failed and could not be spooled"
+            );
+            Handled {
+                ack: None,
+                seq,
+                dropped: true,
+            }
+        }
+    }
+}
+
+/// The `lastSeenSeq` to resume from: the highest sequence number handled,
+/// held below any signal this connection dropped so the server replays it.
+#[derive(Debug, Default)]
+struct SeqTracker {
+    last_seen: Option<u64>,
+    dropped: BTreeSet<u64>,
+}
+
+impl SeqTracker {
+    fn last_seen(&self) -> Option<u64> {
+        self.last_seen
+    }
+
+    /// Record the outcome for the signal numbered `seq`. Deliveries can
+    /// arrive out of order, so the highest number wins unless a lower one
+    /// was dropped.
+    fn record(&mut self, seq: u64, dropped: bool) {
+        if dropped {
+            self.dropped.insert(seq);
+        } else {
+            self.dropped.remove(&seq);
+            self.last_seen = Some(self.last_seen.map_or(seq, |seen| seen.max(seq)));
+        }
+        if let Some(&first) = self.dropped.first() {
+            self.last_seen = match first.checked_sub(1) {
+                Some(before) => Some(self.last_seen.map_or(before, |seen| seen.min(before))),
+                None => None,
+            };
+        }
+    }
+
+    /// The server has been sent `last_seen` and replays from there, dropped
+    /// signals included; forget them so a new connection can move past.
+    fn reconnected(&mut self) {
+        self.dropped.clear();
+    }
+}
 
+async fn send_ack(write: &mut WsWrite, delivery_id: String) -> anyhow::Result<()> {
+    let ack = ClientMessage::Ack { delivery_id };
+    write
+        .send(Message::Text(serde_json::to_string(&ack)?))
+        .await?;
     Ok(())
 }
+
+/// Forward spooled signals oldest first, stopping at the first one the
+/// local endpoint still refuses.
+async fn replay_spool(
+    forwarder: &Forwarder,
+    spool: &Spool,
+    handled: &mpsc::UnboundedSender<Handled>,
+) {
+    let pending = match spool.pending().await {
+        Ok(pending) => pending,
+        Err(err) => {
+            warn!(error = %err, "failed to read spool");
+            return;
+        }
+    };
+
+    for (path, entry) in pending {
+        if let Err(err) = forwarder
+            .deliver_signal(
+                &entry.delivery_id,
+                &entry.channel_id,
+                &entry.channel_slug,
+                &entry.signal,
+            )
+            .await
+        {
+            warn!(error = %err, delivery_id = %entry.delivery_id, "spooled forward still failing");
+            break;
+        }
+        if let Err(err) = spool.remove(&path).await {
+            warn!(error = %err, path = %path.display(), "failed to remove spooled signal");
+        }
+        info!(delivery_id = %entry.delivery_id, "replayed spooled signal");
+        let replayed = Handled {
+            ack: Some(entry.delivery_id),
+            seq: None,
+            dropped: false,
+        };
+        if handled.send(replayed).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seq_tracker_keeps_the_highest() {
+        let mut seqs = SeqTracker::default();
+        assert_eq!(seqs.last_seen(), None);
+        seqs.record(4, false);
+        seqs.record(2, false);
+        assert_eq!(seqs.last_seen(), Some(4));
+        seqs.record(9, false);
+        assert_eq!(seqs.last_seen(), Some(9));
+    }
+
+    #[test]
+    fn test_seq_tracker_holds_below_a_dropped_signal() {
+        let mut seqs = SeqTracker::default();
+        seqs.record(3, false);
+        seqs.record(4, true);
+        seqs.record(5, false);
+        assert_eq!(seqs.last_seen(), Some(3));
+
+        // Handled on replay after a reconnect.
+        seqs.reconnected();
+        seqs.record(4, false);
+        seqs.record(6, false);
+        assert_eq!(seqs.last_seen(), Some(6));
+    }
+
+    #[test]
+    fn test_seq_tracker_lowers_past_an_earlier_drop() {
+        let mut seqs = SeqTracker::default();
+        seqs.record(7, false);
+        seqs.record(5, true);
+        assert_eq!(seqs.last_seen(), Some(4));
+
+        let mut seqs = SeqTracker::default();
+        seqs.record(1, false);
+        seqs.record(0, true);
+        assert_eq!(seqs.last_seen(), None);
+    }
+}
@@ -33,6 +33,11 @@ pub mod postgres {
         pub async fn push(&self, _queue: &str, _job: T) -> Result<()> {
             Ok(())
         }
+
+        /// Jobs waiting to run on `queue`.
+        pub async fn len(&self, _queue: &str) -> Result<i64> {
+            Ok(0)
+        }
     }
 }
 
@@ -106,5 +111,14 @@ mod monitor {
         pub async fn run(self) -> anyhow::Result<()> {
             Ok(())
         }
+
+        /// Run until `signal` resolves, then stop fetching new jobs.
+        pub async fn run_with_signal<S>(self, signal: S) -> anyhow::Result<()>
+        where
+            S: std::future::Future<Output = std::io::Result<()>> + Send,
+        {
+            signal.await?;
+            Ok(())
+        }
     }
 }
@@ -1,4 +1,8 @@
-use axum::{http::StatusCode, response::IntoResponse, Json};
+use axum::{
+    http::{header, HeaderValue, StatusCode},
+    response::IntoResponse,
+    Json,
+};
 use serde::Serialize;
 
 #[derive(Debug, Serialize)]
@@ -16,10 +20,17 @@ pub struct ErrorBody {
 #[derive(Debug)]
 pub enum AppError {
     BadRequest(String),
-    Unauthorized,
+    /// Missing or unusable credentials; the message says which.
+    Unauthorized(String),
     Forbidden(String),
     NotFound(String),
+    Conflict(String),
     RateLimited,
+    /// A usage quota is used up until `retry_after_secs` from now.
+    QuotaExceeded {
+        message: String,
+        retry_after_secs: u64,
+    },
     Internal,
 }
 
@@ -38,22 +49,42 @@ impl AppError {
     }
 }
 
+/// Constraint violations become client errors the caller can act on; any
+/// other database failure stays an opaque `Internal`.
+impl From<sqlx::Error> for AppError {
+    fn from(err: sqlx::Error) -> Self {
+        let sqlx::Error::Database(db_err) = &err else {
+            return AppError::Internal;
+        };
+        match db_err.code().as_deref() {
+            Some("23505") => AppError::Conflict("resource already exists".to_string()),
+            Some("23503") => AppError::BadRequest("referenced resource does not exist".to_string()),
+            _ => AppError::Internal,
+        }
+    }
+}
+
 impl IntoResponse for ApiError {
     fn into_response(self) -> axum::response::Response {
+        let mut retry_after = None;
         let (status, code, message) = match self.error {
             AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, "invalid_request", msg),
-            AppError::Unauthorized => (
-                StatusCode::UNAUTHORIZED,
-                "unauthorized",
-                "Invalid API key".to_string(),
-            ),
+            AppError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, "unauthorized", msg),
             AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, "forbidden", msg),
             AppError::NotFound(msg) => (StatusCode::NOT_FOUND, "not_found", msg),
+            AppError::Conflict(msg) => (StatusCode::CONFLICT, "conflict", msg),
             AppError::RateLimited => (
                 StatusCode::TOO_MANY_REQUESTS,
                 "rate_limited",
                 "Too many requests".to_string(),
             ),
+            AppError::QuotaExceeded {
+                message,
+                retry_after_secs,
+            } => {
+                retry_after = Some(retry_after_secs.max(1));
+                (StatusCode::TOO_MANY_REQUESTS, "quota_exceeded", message)
+            }
             AppError::Internal => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "internal_error",
@@ -61,7 +92,7 @@ impl IntoResponse for ApiError {
             ),
         };
 
-        (
+        let mut response = (
             status,
             Json(ErrorResponse {
                 error: ErrorBody {
@@ -71,12 +102,66 @@ impl IntoResponse for ApiError {
                 },
             }),
         )
-            .into_response()
+            .into_response();
+        if let Some(secs) = retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(secs));
+        }
+        response
     }
 }
 
 pub type ApiResult<T> = Result<T, ApiError>;
 
+/// A database error carrying a Postgres SQLSTATE, for exercising the
+/// constraint-violation mapping without a live database.
+#[cfg(test)]
+pub(crate) fn database_error(code: &'static str) -> sqlx::Error {
+    #[derive(Debug)]
+    struct CodedError(&'static str);
+
+    impl std::fmt::Display for CodedError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "database error {}", self.0)
+        }
+    }
+
+    impl std::error::Error for CodedError {}
+
+    impl sqlx::error::DatabaseError for CodedError {
+        fn message(&self) -> &str {
+            "constraint violation"
+        }
+
+        fn code(&self) -> Option<std::borrow::Cow<'_, str>> {
+            Some(std::borrow::Cow::Borrowed(self.0))
+        }
+
+        fn as_error(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn as_error_mut(&mut self) -> &mut (dyn std::error::Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn into_error(self: Box<Self>) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+            self
+        }
+
+        fn kind(&self) -> sqlx::error::ErrorKind {
+            match self.0 {
+                "23505" => sqlx::error::ErrorKind::UniqueViolation,
+                "23503" => sqlx::error::ErrorKind::ForeignKeyViolation,
+                _ => sqlx::error::ErrorKind::Other,
+            }
+        }
+    }
+
+    sqlx::Error::Database(Box::new(CodedError(code)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -90,6 +175,31 @@ mod tests {
             .unwrap()
     }
 
+    #[test]
+    fn test_sqlx_constraint_violations_map_to_client_errors() {
+        assert!(matches!(
+            AppError::from(database_error("23505")),
+            AppError::Conflict(_)
+        ));
+        assert!(matches!(
+            AppError::from(database_error("23503")),
+            AppError::BadRequest(_)
+        ));
+        assert!(matches!(
+            AppError::from(database_error("57014")),
+            AppError::Internal
+        ));
+        assert!(matches!(
+            AppError::from(sqlx::Error::PoolTimedOut),
+            AppError::Internal
+        ));
+
+        let response = AppError::from(database_error("23505"))
+            .with_request_id("req_1")
+            .into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+
     #[test]
     fn test_with_request_id() {
         let err = AppError::Internal.with_request_id("req_123");
@@ -98,7 +208,7 @@ mod tests {
 
     #[test]
     fn test_with_request_id_empty() {
-        let err = AppError::Unauthorized.with_request_id("");
+        let err = AppError::Unauthorized("invalid api key".to_string()).with_request_id("");
         assert_eq!(err.request_id, "");
     }
 
@@ -122,7 +232,8 @@ mod tests {
     #[test]
     fn test_unauthorized_response() {
         rt().block_on(async {
-            let err = AppError::Unauthorized.with_request_id("req_002");
+            let err =
+                AppError::Unauthorized("api key expired".to_string()).with_request_id("req_002");
             let response = err.into_response();
 
             assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
@@ -131,7 +242,7 @@ mod tests {
             let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
 
             assert_eq!(json["error"]["code"], "unauthorized");
-            assert_eq!(json["error"]["message"], "Invalid API key");
+            assert_eq!(json["error"]["message"], "api key expired");
         });
     }
 
@@ -167,6 +278,22 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_conflict_response() {
+        rt().block_on(async {
+            let err = AppError::Conflict("key reused".to_string()).with_request_id("req_007");
+            let response = err.into_response();
+
+            assert_eq!(response.status(), StatusCode::CONFLICT);
+
+            let body = to_bytes(response.into_body(), 1024).await.unwrap();
+            let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+            assert_eq!(json["error"]["code"], "conflict");
+            assert_eq!(json["error"]["message"], "key reused");
+        });
+    }
+
     #[test]
     fn test_rate_limited_response() {
         rt().block_on(async {
@@ -183,6 +310,29 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_quota_exceeded_response() {
+        rt().block_on(async {
+            let err = AppError::QuotaExceeded {
+                message: "daily signal quota of 100 reached".to_string(),
+                retry_after_secs: 3600,
+            }
+            .with_request_id("req_007");
+            let response = err.into_response();
+
+            assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+            assert_eq!(response.headers()["retry-after"], "3600");
+
+            let body = to_bytes(response.into_body(), 1024).await.unwrap();
+            let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(json["error"]["code"], "quota_exceeded");
+            assert_eq!(
+                json["error"]["message"],
+                "daily signal quota of 100 reached"
+            );
+        });
+    }
+
     #[test]
     fn test_internal_error_response() {
         rt().block_on(async {
@@ -7,13 +7,16 @@ use tracing::info;
 
 mod error;
 mod middleware;
+mod pagination;
+mod profile;
 mod routes;
 mod state;
 mod tunnel;
 
 use crate::middleware::auth::api_key_auth;
+use crate::middleware::client_ip::client_ip;
 use crate::middleware::metrics::metrics;
-use crate::middleware::rate_limit::rate_limit;
+use crate::middleware::rate_limit::{ip_rate_limit, rate_limit};
 use crate::middleware::request_id::request_id;
 use crate::state::AppState;
 
@@ -27,26 +30,45 @@ async fn main() -> anyhow::Result<()> {
         .init();
 
     let settings = Settings::from_env()?;
+    settings.validate().map_err(anyhow::Error::msg)?;
 
     let db = PgPoolOptions::new()
         .max_connections(10)
         .connect(&settings.database_url)
         .await?;
 
+    let read_db = match settings.database_replica_url.as_deref() {
+        Some(url) => {
+            info!("routing read-only queries to replica");
+            Some(PgPoolOptions::new().max_connections(10).connect(url).await?)
+        }
+        None => None,
+    };
+
     let redis = redis::Client::open(settings.redis_url.clone())?;
     let storage = apalis::postgres::PostgresStorage::new(&settings.database_url).await?;
 
     let state = AppState {
         db,
+        read_db,
         redis,
         storage,
         settings: settings.clone(),
         tunnel_registry: core::tunnel::AGENT_REGISTRY.clone(),
+        instance_id: core::presence::instance_id("api"),
+        http_client: core::net::outbound_client(
+            &settings.herald_env,
+            std::time::Duration::from_secs(10),
+        )?,
     };
 
+    tokio::spawn(tunnel::relay::serve_relay(state.clone()));
+
     let v1 = routes::v1_router(state.clone())
         .layer(from_fn_with_state(state.clone(), rate_limit))
         .layer(from_fn_with_state(state.clone(), api_key_auth))
+        .layer(from_fn_with_state(state.clone(), ip_rate_limit))
+        .layer(from_fn_with_state(state.clone(), client_ip))
         .layer(from_fn(metrics))
         .layer(from_fn(request_id));
 
@@ -59,7 +81,19 @@ async fn main() -> anyhow::Result<()> {
     info!(%addr, "starting api");
 
     let listener = TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    let tunnel_registry = state.tunnel_registry.clone();
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+        .with_graceful_shutdown(async move {
+            core::shutdown::shutdown_signal().await;
+            // Open tunnels would otherwise hold graceful shutdown forever.
+            tunnel_registry.begin_shutdown();
+        })
+        .await?;
+
+    info!("api stopped");
 
     Ok(())
 }
@@ -1,19 +1,33 @@
 use axum::{
     body::Body,
-    extract::State,
-    http::{header::AUTHORIZATION, Request},
+    extract::{FromRequestParts, State},
+    http::{header::AUTHORIZATION, request::Parts, Request},
     middleware::Next,
     response::Response,
 };
 
 use crate::{
     error::{ApiError, AppError},
+    middleware::key_cache,
     state::AppState,
     state::RequestId,
 };
-use core::auth::hash_api_key;
+use chrono::{DateTime, Utc};
+use core::auth::{hash_api_key, parse_bearer};
 use db::models::{AccountTier, ApiKeyOwner};
 
+/// Minimum gap between `last_used_at` writes for one API key.
+const LAST_USED_INTERVAL_SECS: u64 = 60;
+
+/// Routes that do not take an API key: the tunnel authenticates over the
+/// socket, and account creation uses the bootstrap token.
+const KEYLESS_PATHS: [&str; 3] = ["/v1/tunnel", "/v1/publishers", "/v1/subscribers"];
+
+/// Whether `path` skips API key authentication and per-key rate limiting.
+pub fn is_keyless(path: &str) -> bool {
+    KEYLESS_PATHS.contains(&path)
+}
+
 #[derive(Debug, Clone)]
 pub struct AuthContext {
     pub owner_type: ApiKeyOwner,
@@ -22,12 +36,102 @@ pub struct AuthContext {
     pub key_id: String,
 }
 
+/// Authenticated publisher id; rejects subscriber keys with 403.
+#[derive(Debug, Clone)]
+pub struct PublisherAuth(pub String);
+
+/// Authenticated subscriber id; rejects publisher keys with 403.
+#[derive(Debug, Clone)]
+pub struct SubscriberAuth(pub String);
+
+impl<S: Send + Sync> FromRequestParts<S> for PublisherAuth {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        require_owner(parts, ApiKeyOwner::Publisher, "publisher access required").map(PublisherAuth)
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for SubscriberAuth {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        require_owner(parts, ApiKeyOwner::Subscriber, "subscriber access required")
+            .map(SubscriberAuth)
+    }
+}
+
+/// Caller presented `HERALD_BOOTSTRAP_TOKEN`; gates account creation.
+#[derive(Debug, Clone)]
+pub struct BootstrapAuth;
+
+impl FromRequestParts<AppState> for BootstrapAuth {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let request_id = parts
+            .extensions
+            .get::<RequestId>()
+            .map(|id| id.0.clone())
+            .unwrap_or_else(|| "unknown".to_string());
+        let header = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("");
+        check_bootstrap_token(state.settings.bootstrap_token.as_deref(), header)
+            .map(|()| BootstrapAuth)
+            .map_err(|err| err.with_request_id(&request_id))
+    }
+}
+
+/// Compare the bearer token against the configured bootstrap token. With no
+/// token configured, account creation is turned off.
+fn check_bootstrap_token(configured: Option<&str>, header: &str) -> Result<(), AppError> {
+    let Some(configured) = configured else {
+        return Err(AppError::Forbidden(
+            "account creation is disabled".to_string(),
+        ));
+    };
+    let token = bearer_token(header).map_err(|msg| AppError::Unauthorized(msg.to_string()))?;
+    if !bool::from(subtle::ConstantTimeEq::ct_eq(
+        token.as_bytes(),
+        configured.as_bytes(),
+    )) {
+        return Err(AppError::Unauthorized(
+            "invalid bootstrap token".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn require_owner(parts: &Parts, owner: ApiKeyOwner, message: &str) -> Result<String, ApiError> {
+    let request_id = parts
+        .extensions
+        .get::<RequestId>()
+        .map(|id| id.0.clone())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let auth = parts.extensions.get::<AuthContext>().ok_or_else(|| {
+        AppError::Unauthorized("authentication required".to_string()).with_request_id(&request_id)
+    })?;
+
+    if auth.owner_type != owner {
+        return Err(AppError::Forbidden(message.to_string()).with_request_id(&request_id));
+    }
+
+    Ok(auth.owner_id.clone())
+}
+
 pub async fn api_key_auth(
     State(state): State<AppState>,
     mut req: Request<Body>,
     next: Next,
 ) -> Result<Response, ApiError> {
-    if req.uri().path() == "/v1/tunnel" {
+    if is_keyless(req.uri().path()) {
         return Ok(next.run(req).await);
     }
 
@@ -43,35 +147,40 @@ pub async fn api_key_auth(
         .and_then(|v| v.to_str().ok())
         .unwrap_or("");
 
-    let token = auth.strip_prefix("Bearer ").unwrap_or("");
-    if token.is_empty() {
-        return Err(AppError::Unauthorized.with_request_id(&request_id));
-    }
+    let unauthorized =
+        |msg: &str| AppError::Unauthorized(msg.to_string()).with_request_id(&request_id);
+
+    let token = bearer_token(auth).map_err(unauthorized)?;
 
     let hash = hash_api_key(token);
-    let api_key = db::queries::api_keys::get_by_hash(&state.db, &hash)
-        .await
-        .map_err(|_| AppError::Internal.with_request_id(&request_id))?
-        .ok_or_else(|| AppError::Unauthorized.with_request_id(&request_id))?;
-
-    let tier = match api_key.owner_type {
-        ApiKeyOwner::Publisher => {
-            let publisher = db::queries::publishers::get_by_id(&state.db, &api_key.owner_id)
-                .await
-                .map_err(|_| AppError::Internal.with_request_id(&request_id))?
-                .ok_or_else(|| AppError::Unauthorized.with_request_id(&request_id))?;
-            publisher.tier
-        }
-        ApiKeyOwner::Subscriber => {
-            let subscriber = db::queries::subscribers::get_by_id(&state.db, &api_key.owner_id)
+    let touch = last_used_due(&state.redis, &hash).await;
+    let cache_secs = state.settings.api_key_cache_secs;
+    // A due `last_used_at` write goes to the database, which also refreshes
+    // the cache, so a busy key still reaches Postgres about once a minute.
+    let cached = if cache_secs > 0 && !touch {
+        key_cache::get(&state.redis, &hash).await
+    } else {
+        None
+    };
+    let api_key = match cached {
+        Some(api_key) => api_key,
+        None => {
+            let api_key = db::queries::api_keys::authenticate(&state.db, &hash, touch)
                 .await
                 .map_err(|_| AppError::Internal.with_request_id(&request_id))?
-                .ok_or_else(|| AppError::Unauthorized.with_request_id(&request_id))?;
-            subscriber.tier
+                .ok_or_else(|| unauthorized("invalid api key"))?;
+            if cache_secs > 0 {
+                key_cache::put(&state.redis, &hash, &api_key, cache_secs).await;
+            }
+            api_key
         }
     };
-
-    let _ = db::queries::api_keys::touch_last_used(&state.db, &api_key.id).await;
+    if key_expired(api_key.expires_at, Utc::now()) {
+        return Err(unauthorized("api key expired"));
+    }
+    let tier = api_key
+        .owner_tier
+        .ok_or_else(|| unauthorized("api key owner not found"))?;
 
     let ctx = AuthContext {
         owner_type: api_key.owner_type,
@@ -83,3 +192,161 @@ pub async fn api_key_auth(
     req.extensions_mut().insert(ctx);
     Ok(next.run(req).await)
 }
+
+/// The bearer token from an `Authorization` header, or why there isn't one.
+fn bearer_token(header: &str) -> Result<&str, &'static str> {
+    if header.trim().is_empty() {
+        return Err("missing authorization header");
+    }
+    parse_bearer(header).ok_or("malformed authorization header")
+}
+
+/// Whether this request should refresh the key's `last_used_at`. A Redis
+/// marker, keyed by the key hash since the id is not known until the lookup,
+/// limits the write to once per [`LAST_USED_INTERVAL_SECS`] per key; if Redis
+/// is unavailable every request writes, as before.
+async fn last_used_due(redis: &redis::Client, key_hash: &str) -> bool {
+    let marked: redis::RedisResult<Option<String>> = async {
+        let mut conn = redis.get_multiplexed_async_connection().await?;
+        redis::cmd("SET")
+            .arg(last_used_marker(key_hash))
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(LAST_USED_INTERVAL_SECS)
+            .query_async(&mut conn)
+            .await
+    }
+    .await;
+    marked.map_or(true, |set| set.is_some())
+}
+
+fn last_used_marker(key_hash: &str) -> String {
+    format!("ak:used:{}", key_hash)
+}
+
+pub(crate) fn key_expired(expires_at: Option<DateTime<Utc>>, now: DateTime<Utc>) -> bool {
+    expires_at.is_some_and(|at| at <= now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use axum::response::IntoResponse;
+
+    fn rt() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+    }
+
+    fn parts_for(owner_type: Option<ApiKeyOwner>) -> Parts {
+        let (mut parts, _) = Request::new(()).into_parts();
+        parts.extensions.insert(RequestId("req_auth".to_string()));
+        if let Some(owner_type) = owner_type {
+            parts.extensions.insert(AuthContext {
+                owner_type,
+                owner_id: "owner_123".to_string(),
+                tier: AccountTier::Free,
+                key_id: "key_123".to_string(),
+            });
+        }
+        parts
+    }
+
+    #[test]
+    fn test_publisher_auth_accepts_publisher() {
+        rt().block_on(async {
+            let mut parts = parts_for(Some(ApiKeyOwner::Publisher));
+            let PublisherAuth(id) = PublisherAuth::from_request_parts(&mut parts, &())
+                .await
+                .unwrap();
+            assert_eq!(id, "owner_123");
+        });
+    }
+
+    #[test]
+    fn test_publisher_auth_rejects_subscriber() {
+        rt().block_on(async {
+            let mut parts = parts_for(Some(ApiKeyOwner::Subscriber));
+            let err = PublisherAuth::from_request_parts(&mut parts, &())
+                .await
+                .unwrap_err();
+            assert_eq!(err.request_id, "req_auth");
+            assert_eq!(err.into_response().status(), StatusCode::FORBIDDEN);
+        });
+    }
+
+    #[test]
+    fn test_subscriber_auth_rejects_publisher() {
+        rt().block_on(async {
+            let mut parts = parts_for(Some(ApiKeyOwner::Publisher));
+            let err = SubscriberAuth::from_request_parts(&mut parts, &())
+                .await
+                .unwrap_err();
+            assert_eq!(err.into_response().status(), StatusCode::FORBIDDEN);
+        });
+    }
+
+    #[test]
+    fn test_missing_auth_context_is_unauthorized() {
+        rt().block_on(async {
+            let mut parts = parts_for(None);
+            let err = SubscriberAuth::from_request_parts(&mut parts, &())
+                .await
+                .unwrap_err();
+            assert_eq!(err.into_response().status(), StatusCode::UNAUTHORIZED);
+        });
+    }
+
+    #[test]
+    fn test_bootstrap_token_gate() {
+        let status = |configured: Option<&str>, header: &str| {
+            check_bootstrap_token(configured, header)
+                .map_err(|err| err.with_request_id("req_auth").into_response().status())
+        };
+        assert_eq!(status(Some("boot_secret"), "Bearer boot_secret"), Ok(()));
+        assert_eq!(
+            status(Some("boot_secret"), "Bearer boot_other"),
+            Err(StatusCode::UNAUTHORIZED)
+        );
+        assert_eq!(
+            status(Some("boot_secret"), ""),
+            Err(StatusCode::UNAUTHORIZED)
+        );
+        assert_eq!(
+            status(None, "Bearer boot_secret"),
+            Err(StatusCode::FORBIDDEN)
+        );
+
+        assert!(is_keyless("/v1/tunnel"));
+        assert!(is_keyless("/v1/subscribers"));
+        assert!(!is_keyless("/v1/subscriber/me"));
+    }
+
+    #[test]
+    fn test_bearer_token_failures_are_distinguished() {
+        assert_eq!(bearer_token("Bearer hld_pub_abc"), Ok("hld_pub_abc"));
+        assert_eq!(bearer_token(""), Err("missing authorization header"));
+        assert_eq!(bearer_token("  "), Err("missing authorization header"));
+        assert_eq!(
+            bearer_token("Basic dXNlcjpwYXNz"),
+            Err("malformed authorization header")
+        );
+        assert_eq!(
+            bearer_token("Bearer"),
+            Err("malformed authorization header")
+        );
+    }
+
+    #[test]
+    fn test_key_expiry() {
+        let now = Utc::now();
+        assert!(!key_expired(None, now));
+        assert!(!key_expired(Some(now + chrono::Duration::minutes(1)), now));
+        assert!(key_expired(Some(now), now));
+        assert!(key_expired(Some(now - chrono::Duration::minutes(1)), now));
+    }
+}
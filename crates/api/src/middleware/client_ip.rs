@@ -0,0 +1,132 @@
+//! Resolve the caller's IP address behind load balancers.
+//!
+//! Each proxy in front of the API appends the address it received the request
+//! from to `X-Forwarded-For`. With `HERALD_TRUSTED_PROXIES` set to N, the
+//! N-th entry from the right was written by our outermost proxy and is the
+//! client; anything to its left was sent by the client and is ignored. The
+//! default of 0 trusts no header, since without a proxy any client could set
+//! it and pick its own rate-limit bucket.
+
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, State},
+    http::{HeaderMap, Request},
+    middleware::Next,
+    response::Response,
+};
+use std::net::{IpAddr, SocketAddr};
+
+use crate::state::{AppState, ClientIp};
+
+/// Attach the resolved [`ClientIp`] to the request extensions.
+pub async fn client_ip(
+    State(state): State<AppState>,
+    mut req: Request<Body>,
+    next: Next,
+) -> Response {
+    let peer = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|info| info.0.ip());
+    if let Some(ip) = resolve_client_ip(req.headers(), peer, state.settings.trusted_proxies) {
+        req.extensions_mut().insert(ClientIp(ip));
+    }
+    next.run(req).await
+}
+
+/// The client address given `trusted_proxies` hops in front of the API.
+///
+/// Falls back to the socket peer when no proxies are trusted, the header is
+/// absent, or it has fewer entries than there are proxies (the request did
+/// not come through all of them).
+fn resolve_client_ip(
+    headers: &HeaderMap,
+    peer: Option<IpAddr>,
+    trusted_proxies: usize,
+) -> Option<IpAddr> {
+    if trusted_proxies == 0 {
+        return peer;
+    }
+    let forwarded: Vec<&str> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect();
+    forwarded
+        .len()
+        .checked_sub(trusted_proxies)
+        .and_then(|index| forwarded[index].parse().ok())
+        .or(peer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn forwarded(values: &[&'static str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append("x-forwarded-for", HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    fn ip(addr: &str) -> Option<IpAddr> {
+        Some(addr.parse().unwrap())
+    }
+
+    #[test]
+    fn test_peer_used_without_header() {
+        assert_eq!(
+            resolve_client_ip(&HeaderMap::new(), ip("10.0.0.5"), 1),
+            ip("10.0.0.5")
+        );
+        assert_eq!(resolve_client_ip(&HeaderMap::new(), None, 1), None);
+    }
+
+    #[test]
+    fn test_header_ignored_when_no_proxy_is_trusted() {
+        let headers = forwarded(&["203.0.113.7"]);
+        assert_eq!(
+            resolve_client_ip(&headers, ip("10.0.0.5"), 0),
+            ip("10.0.0.5")
+        );
+    }
+
+    #[test]
+    fn test_spoofed_entries_left_of_trusted_hops_are_ignored() {
+        // Client sent "1.1.1.1"; the edge proxy appended the real address,
+        // then an internal balancer appended the edge's.
+        let headers = forwarded(&["1.1.1.1, 203.0.113.7", "10.0.0.2"]);
+        assert_eq!(
+            resolve_client_ip(&headers, ip("10.0.0.3"), 1),
+            ip("10.0.0.2")
+        );
+        assert_eq!(
+            resolve_client_ip(&headers, ip("10.0.0.3"), 2),
+            ip("203.0.113.7")
+        );
+    }
+
+    #[test]
+    fn test_short_or_malformed_header_falls_back_to_peer() {
+        let headers = forwarded(&["203.0.113.7"]);
+        assert_eq!(
+            resolve_client_ip(&headers, ip("10.0.0.5"), 2),
+            ip("10.0.0.5")
+        );
+        let headers = forwarded(&["garbage"]);
+        assert_eq!(
+            resolve_client_ip(&headers, ip("10.0.0.5"), 1),
+            ip("10.0.0.5")
+        );
+        let headers = forwarded(&["2001:db8::1"]);
+        assert_eq!(
+            resolve_client_ip(&headers, ip("10.0.0.5"), 1),
+            ip("2001:db8::1")
+        );
+    }
+}
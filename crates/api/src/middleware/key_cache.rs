@@ -0,0 +1,92 @@
+//! Short-lived Redis cache of resolved API keys, keyed by key hash.
+//!
+//! A hit lets [`super::auth::api_key_auth`] skip Postgres entirely. Reads and
+//! writes fail open to the database; only invalidation reports errors, since
+//! a missed delete would leave a revoked key working until the entry expires.
+
+use db::models::ApiKeyAuth;
+use tracing::warn;
+
+fn cache_key(key_hash: &str) -> String {
+    format!("ak:auth:{}", key_hash)
+}
+
+/// The cached key for `key_hash`, if any.
+pub async fn get(redis: &redis::Client, key_hash: &str) -> Option<ApiKeyAuth> {
+    let cached: redis::RedisResult<Option<String>> = async {
+        let mut conn = redis.get_multiplexed_async_connection().await?;
+        redis::cmd("GET")
+            .arg(cache_key(key_hash))
+            .query_async(&mut conn)
+            .await
+    }
+    .await;
+    match cached {
+        Ok(value) => value.and_then(|json| serde_json::from_str(&json).ok()),
+        Err(err) => {
+            warn!(error = %err, "api key cache read failed");
+            None
+        }
+    }
+}
+
+/// Cache `key` for `ttl_secs`.
+pub async fn put(redis: &redis::Client, key_hash: &str, key: &ApiKeyAuth, ttl_secs: u64) {
+    let Ok(json) = serde_json::to_string(key) else {
+        return;
+    };
+    let result: redis::RedisResult<()> = async {
+        let mut conn = redis.get_multiplexed_async_connection().await?;
+        redis::cmd("SET")
+            .arg(cache_key(key_hash))
+            .arg(json)
+            .arg("EX")
+            .arg(ttl_secs)
+            .query_async(&mut conn)
+            .await
+    }
+    .await;
+    if let Err(err) = result {
+        warn!(error = %err, "api key cache write failed");
+    }
+}
+
+/// Drop the cached entry for `key_hash`.
+pub async fn invalidate(redis: &redis::Client, key_hash: &str) -> redis::RedisResult<()> {
+    let mut conn = redis.get_multiplexed_async_connection().await?;
+    redis::cmd("DEL")
+        .arg(cache_key(key_hash))
+        .query_async::<_, i64>(&mut conn)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use db::models::{AccountTier, ApiKeyOwner};
+
+    #[test]
+    fn test_cache_key_is_namespaced_by_hash() {
+        assert_eq!(cache_key("abc123"), "ak:auth:abc123");
+        assert_ne!(cache_key("abc123"), format!("ak:used:{}", "abc123"));
+    }
+
+    #[test]
+    fn test_cached_key_round_trips() {
+        let key = ApiKeyAuth {
+            id: "key_1".to_string(),
+            owner_type: ApiKeyOwner::Subscriber,
+            owner_id: "sub_1".to_string(),
+            expires_at: None,
+            scopes: vec!["signals:read".to_string()],
+            owner_tier: Some(AccountTier::Pro),
+        };
+        let json = serde_json::to_string(&key).unwrap();
+        let back: ApiKeyAuth = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.id, "key_1");
+        assert_eq!(back.owner_type, ApiKeyOwner::Subscriber);
+        assert_eq!(back.scopes, key.scopes);
+        assert!(matches!(back.owner_tier, Some(AccountTier::Pro)));
+    }
+}
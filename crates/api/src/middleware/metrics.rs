@@ -1,12 +1,70 @@
-use axum::{body::Body, http::Request, middleware::Next, response::Response};
+use axum::{body::Body, extract::MatchedPath, http::Request, middleware::Next, response::Response};
 
 use crate::state::METRICS;
 
+/// Path label for requests that matched no route.
+const UNMATCHED_PATH: &str = "unmatched";
+
 pub async fn metrics(req: Request<Body>, next: Next) -> Response {
     let method = req.method().to_string();
-    let path = req.uri().path().to_string();
+    let path = path_label(
+        req.extensions()
+            .get::<MatchedPath>()
+            .map(MatchedPath::as_str),
+    );
     let resp = next.run(req).await;
     let status = resp.status().as_u16();
     METRICS.record_http_request(&method, &path, status);
     resp
 }
+
+/// The route template a request matched, e.g. `/v1/channels/{id}`, so ids in
+/// the raw path don't each become a label value.
+fn path_label(matched: Option<&str>) -> String {
+    matched.unwrap_or(UNMATCHED_PATH).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_label_uses_route_template() {
+        assert_eq!(path_label(Some("/v1/channels/{id}")), "/v1/channels/{id}");
+        assert_eq!(path_label(None), "unmatched");
+    }
+
+    #[test]
+    fn test_requests_to_different_ids_share_one_series() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let app = axum::Router::new()
+                .route(
+                    "/test/metrics/{id}/signals",
+                    axum::routing::get(|| async { "ok" }),
+                )
+                .layer(axum::middleware::from_fn(metrics));
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(async move { axum::serve(listener, app).await });
+
+            for id in ["ch_first", "ch_second"] {
+                let url = format!("http://{addr}/test/metrics/{id}/signals");
+                let resp = reqwest::get(url).await.unwrap();
+                assert!(resp.status().is_success());
+            }
+
+            let output = METRICS.gather();
+            let series: Vec<&str> = output
+                .lines()
+                .filter(|line| line.contains("/test/metrics/"))
+                .collect();
+            assert_eq!(series.len(), 1, "{series:?}");
+            assert!(series[0].contains("path=\"/test/metrics/{id}/signals\""));
+            assert!(series[0].ends_with(" 2"));
+        });
+    }
+}
@@ -1,4 +1,6 @@
 pub mod auth;
+pub mod client_ip;
+pub mod key_cache;
 pub mod metrics;
 pub mod rate_limit;
 pub mod request_id;
@@ -1,17 +1,70 @@
 use crate::{
     error::{ApiError, AppError},
-    middleware::auth::AuthContext,
-    state::{AppState, RequestId},
+    middleware::auth::{is_keyless, AuthContext},
+    state::{AppState, ClientIp, RequestId},
 };
-use axum::{body::Body, extract::State, http::Request, middleware::Next, response::Response};
+use axum::{
+    body::Body,
+    extract::{MatchedPath, State},
+    http::{HeaderMap, HeaderValue, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use core::config::Settings;
 use db::models::AccountTier;
+use redis::AsyncCommands;
+use tracing::warn;
+
+/// Per-client-IP limit applied before authentication, so floods of bad or
+/// missing keys are turned away without a key lookup.
+///
+/// Counts every request, authenticated or not, in bucket `rl:ip:{addr}`. If
+/// Redis is unreachable the request passes; the per-key limiter after auth
+/// still applies.
+pub async fn ip_rate_limit(
+    State(state): State<AppState>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let capacity = state.settings.ip_rate_limit_per_min;
+    let client_ip = req.extensions().get::<ClientIp>().map(|ip| ip.0);
+    let Some(ip) = client_ip.filter(|_| capacity > 0) else {
+        return Ok(next.run(req).await);
+    };
+
+    let decision = async {
+        let mut conn = state.redis.get_multiplexed_async_connection().await?;
+        allow_request(&mut conn, &format!("ip:{}", ip), capacity, capacity).await
+    }
+    .await;
+    match decision {
+        Ok(decision) if !decision.allowed => {
+            warn!(client_ip = %ip, "client ip rate limited");
+            let request_id = req
+                .extensions()
+                .get::<RequestId>()
+                .map(|id| id.0.clone())
+                .unwrap_or_else(|| "unknown".to_string());
+            let mut response = AppError::RateLimited
+                .with_request_id(&request_id)
+                .into_response();
+            apply_rate_limit_headers(response.headers_mut(), capacity, &decision);
+            Ok(response)
+        }
+        Ok(_) => Ok(next.run(req).await),
+        Err(err) => {
+            warn!(error = %err, "ip rate limiter unavailable; allowing request");
+            Ok(next.run(req).await)
+        }
+    }
+}
 
 pub async fn rate_limit(
     State(state): State<AppState>,
     req: Request<Body>,
     next: Next,
 ) -> Result<Response, ApiError> {
-    if req.uri().path() == "/v1/tunnel" {
+    if is_keyless(req.uri().path()) {
         return Ok(next.run(req).await);
     }
 
@@ -24,13 +77,17 @@ pub async fn rate_limit(
         .extensions()
         .get::<AuthContext>()
         .cloned()
-        .ok_or_else(|| AppError::Unauthorized.with_request_id(&request_id))?;
+        .ok_or_else(|| {
+            AppError::Unauthorized("authentication required".to_string())
+                .with_request_id(&request_id)
+        })?;
 
-    let capacity = match auth.tier {
-        AccountTier::Free => state.settings.rate_limit_free,
-        AccountTier::Pro => state.settings.rate_limit_pro,
-        AccountTier::Enterprise => state.settings.rate_limit_ent,
-    };
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| req.uri().path().to_string());
+    let (bucket, capacity) = bucket_for(&state.settings, &auth, req.method().as_str(), &route);
 
     let mut conn = state
         .redis
@@ -38,27 +95,150 @@ pub async fn rate_limit(
         .await
         .map_err(|_| AppError::Internal.with_request_id(&request_id))?;
 
-    let allowed = allow_request(&mut conn, &auth.key_id, capacity, capacity)
+    let decision = allow_request(&mut conn, &bucket, capacity, capacity)
         .await
         .map_err(|_| AppError::Internal.with_request_id(&request_id))?;
 
-    if !allowed {
-        return Err(AppError::RateLimited.with_request_id(&request_id));
+    let mut response = if decision.allowed {
+        next.run(req).await
+    } else {
+        AppError::RateLimited
+            .with_request_id(&request_id)
+            .into_response()
+    };
+    apply_rate_limit_headers(response.headers_mut(), capacity, &decision);
+    Ok(response)
+}
+
+/// Outcome of one token-bucket check.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimitDecision {
+    pub allowed: bool,
+    /// Whole tokens left after this request.
+    pub remaining: u32,
+    /// Seconds until the next token is available; zero while tokens remain.
+    pub retry_after_secs: u64,
+}
+
+/// Attach `X-RateLimit-*` headers, plus `Retry-After` when the request was rejected.
+fn apply_rate_limit_headers(headers: &mut HeaderMap, limit: u32, decision: &RateLimitDecision) {
+    headers.insert("x-ratelimit-limit", HeaderValue::from(limit));
+    headers.insert(
+        "x-ratelimit-remaining",
+        HeaderValue::from(decision.remaining),
+    );
+    if !decision.allowed {
+        headers.insert(
+            "retry-after",
+            HeaderValue::from(decision.retry_after_secs.max(1)),
+        );
     }
+}
 
-    Ok(next.run(req).await)
+/// The bucket a request draws from and its capacity. Routes matching a
+/// configured group get a bucket of their own; everything else shares the
+/// key's default bucket at the tier's full limit.
+fn bucket_for(settings: &Settings, auth: &AuthContext, method: &str, route: &str) -> (String, u32) {
+    let base = capacity_for_tier(settings, &auth.tier);
+    match settings.rate_limit_groups.rule_for(method, route) {
+        Some(rule) => (group_bucket(&auth.key_id, &rule.group), rule.capacity(base)),
+        None => (auth.key_id.clone(), base),
+    }
 }
 
-async fn allow_request(
+/// A route group bucket a key draws from besides its default one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupBucket {
+    pub group: String,
+    /// Bucket name, stored in Redis as `rl:{bucket}`.
+    pub bucket: String,
+    pub capacity: u32,
+}
+
+/// Every configured group's bucket for `key_id`, in rule order.
+pub fn group_buckets(settings: &Settings, key_id: &str, tier: &AccountTier) -> Vec<GroupBucket> {
+    let base = capacity_for_tier(settings, tier);
+    settings
+        .rate_limit_groups
+        .groups()
+        .into_iter()
+        .map(|rule| GroupBucket {
+            group: rule.group.clone(),
+            bucket: group_bucket(key_id, &rule.group),
+            capacity: rule.capacity(base),
+        })
+        .collect()
+}
+
+fn group_bucket(key_id: &str, group: &str) -> String {
+    format!("{}:{}", key_id, group)
+}
+
+/// Requests per minute allowed for an account tier.
+pub fn capacity_for_tier(settings: &Settings, tier: &AccountTier) -> u32 {
+    settings
+        .tier_limits
+        .for_tier(&tier.into())
+        .rate_limit_per_min
+}
+
+/// Snapshot of a key's token bucket as stored in Redis.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BucketState {
+    pub tokens: f64,
+    pub capacity: u32,
+    /// Unix seconds of the last refill, `None` if the bucket does not exist.
+    pub last_refill_ts: Option<u64>,
+}
+
+/// Read the bucket for `key` without consuming a token.
+///
+/// Tokens are projected forward to `now` using the same refill rule as the
+/// limiter script. A missing bucket (never used or expired) reports full capacity.
+pub async fn read_bucket(
     conn: &mut redis::aio::MultiplexedConnection,
     key: &str,
     capacity: u32,
     refill_per_min: u32,
-) -> redis::RedisResult<bool> {
-    let now = std::time::SystemTime::now()
+) -> redis::RedisResult<BucketState> {
+    let bucket_key = format!("rl:{}", key);
+    let (tokens, ts): (Option<f64>, Option<u64>) = conn.hget(bucket_key, &["tokens", "ts"]).await?;
+    let now = unix_now();
+
+    Ok(BucketState {
+        tokens: projected_tokens(tokens, ts, now, capacity, refill_per_min),
+        capacity,
+        last_refill_ts: ts,
+    })
+}
+
+fn projected_tokens(
+    tokens: Option<f64>,
+    ts: Option<u64>,
+    now: u64,
+    capacity: u32,
+    refill_per_min: u32,
+) -> f64 {
+    let capacity = capacity as f64;
+    let tokens = tokens.unwrap_or(capacity);
+    let delta = now.saturating_sub(ts.unwrap_or(now)) as f64;
+    (tokens + delta * refill_per_min as f64 / 60.0).min(capacity)
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap_or(std::time::Duration::ZERO)
-        .as_secs();
+        .as_secs()
+}
+
+async fn allow_request(
+    conn: &mut redis::aio::MultiplexedConnection,
+    key: &str,
+    capacity: u32,
+    refill_per_min: u32,
+) -> redis::RedisResult<RateLimitDecision> {
+    let now = unix_now();
     let bucket_key = format!("rl:{}", key);
 
     let script = r#"
@@ -74,19 +254,23 @@ local ts = tonumber(data[2]) or now
 local delta = math.max(0, now - ts)
 local new_tokens = math.min(capacity, tokens + (delta * refill / 60))
 
-if new_tokens < 1 then
-  redis.call('HMSET', bucket, 'tokens', new_tokens, 'ts', now)
-  redis.call('EXPIRE', bucket, 120)
-  return 0
-else
+local allowed = 0
+if new_tokens >= 1 then
+  allowed = 1
   new_tokens = new_tokens - 1
-  redis.call('HMSET', bucket, 'tokens', new_tokens, 'ts', now)
-  redis.call('EXPIRE', bucket, 120)
-  return 1
 end
+redis.call('HMSET', bucket, 'tokens', new_tokens, 'ts', now)
+redis.call('EXPIRE', bucket, 120)
+
+local retry_after = 0
+if new_tokens < 1 and refill > 0 then
+  retry_after = math.ceil((1 - new_tokens) * 60 / refill)
+end
+
+return {allowed, math.floor(new_tokens), retry_after}
 "#;
 
-    let allowed: i32 = redis::Script::new(script)
+    let (allowed, remaining, retry_after_secs): (i32, u32, u64) = redis::Script::new(script)
         .key(bucket_key)
         .arg(now)
         .arg(capacity)
@@ -94,5 +278,172 @@ end
         .invoke_async(conn)
         .await?;
 
-    Ok(allowed == 1)
+    Ok(RateLimitDecision {
+        allowed: allowed == 1,
+        remaining,
+        retry_after_secs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::limits::TierLimits;
+    use core::priority::PriorityWeights;
+
+    fn settings_with_limits(tier_limits: TierLimits) -> Settings {
+        Settings {
+            database_url: "postgres://localhost/herald".to_string(),
+            database_replica_url: None,
+            redis_url: "redis://localhost".to_string(),
+            herald_env: "test".to_string(),
+            api_bind: "127.0.0.1:0".to_string(),
+            worker_concurrency: 1,
+            hmac_secret: "secret".to_string(),
+            global_signature: false,
+            tier_limits,
+            rate_limit_groups: Default::default(),
+            idempotency_retention_hours: 24,
+            webhook_response_body_limit: 2048,
+            default_page_size: 50,
+            max_page_size: 100,
+            tunnel_ack_timeout_secs: 30,
+            tunnel_ping_secs: 30,
+            tunnel_buffer: 64,
+            delivery_max_concurrency: 64,
+            webhook_transient_retries: 2,
+            delivery_priority: PriorityWeights::default(),
+            worker_metrics_port: 9091,
+            api_key_cache_secs: 60,
+            ip_rate_limit_per_min: 1200,
+            trusted_proxies: 0,
+            bootstrap_token: None,
+        }
+    }
+
+    #[test]
+    fn test_capacity_follows_key_tier() {
+        let mut limits = TierLimits::default();
+        limits.free.rate_limit_per_min = 10;
+        limits.pro.rate_limit_per_min = 100;
+        limits.enterprise.rate_limit_per_min = 1000;
+        let settings = settings_with_limits(limits);
+
+        assert_eq!(capacity_for_tier(&settings, &AccountTier::Free), 10);
+        assert_eq!(capacity_for_tier(&settings, &AccountTier::Pro), 100);
+        assert_eq!(capacity_for_tier(&settings, &AccountTier::Enterprise), 1000);
+    }
+
+    #[test]
+    fn test_route_groups_get_their_own_bucket() {
+        let auth = AuthContext {
+            owner_type: db::models::ApiKeyOwner::Publisher,
+            owner_id: "pub_1".to_string(),
+            tier: AccountTier::Free,
+            key_id: "key_1".to_string(),
+        };
+        let mut settings = settings_with_limits(TierLimits::default());
+        let signals = "/v1/channels/{id}/signals";
+
+        // No groups configured: one bucket per key, as before.
+        assert_eq!(
+            bucket_for(&settings, &auth, "POST", signals),
+            ("key_1".to_string(), 60)
+        );
+
+        settings.rate_limit_groups =
+            core::rate_groups::RateGroups::parse("publish:POST /v1/channels/{id}/signals=0.25")
+                .unwrap();
+        assert_eq!(
+            bucket_for(&settings, &auth, "POST", signals),
+            ("key_1:publish".to_string(), 15)
+        );
+        assert_eq!(
+            bucket_for(&settings, &auth, "GET", signals),
+            ("key_1".to_string(), 60)
+        );
+    }
+
+    #[test]
+    fn test_group_buckets_cover_each_group() {
+        let mut settings = settings_with_limits(TierLimits::default());
+        assert!(group_buckets(&settings, "key_1", &AccountTier::Free).is_empty());
+
+        settings.rate_limit_groups = core::rate_groups::RateGroups::parse(
+            "publish:POST /v1/channels/{id}/signals=0.25;publish:PATCH *=0.25;read:GET *=2",
+        )
+        .unwrap();
+        assert_eq!(
+            group_buckets(&settings, "key_1", &AccountTier::Free),
+            vec![
+                GroupBucket {
+                    group: "publish".to_string(),
+                    bucket: "key_1:publish".to_string(),
+                    capacity: 15,
+                },
+                GroupBucket {
+                    group: "read".to_string(),
+                    bucket: "key_1:read".to_string(),
+                    capacity: 120,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_projected_tokens_missing_bucket_is_full() {
+        assert_eq!(projected_tokens(None, None, 1_000, 60, 60), 60.0);
+    }
+
+    #[test]
+    fn test_projected_tokens_recently_limited_is_low() {
+        // Bucket drained to zero one second ago refills at 1 token/sec.
+        let tokens = projected_tokens(Some(0.0), Some(999), 1_000, 60, 60);
+        assert!(
+            tokens < 2.0,
+            "expected a nearly empty bucket, got {}",
+            tokens
+        );
+    }
+
+    #[test]
+    fn test_projected_tokens_caps_at_capacity() {
+        assert_eq!(projected_tokens(Some(10.0), Some(0), 10_000, 60, 60), 60.0);
+    }
+
+    #[test]
+    fn test_projected_tokens_ignores_clock_skew() {
+        // A timestamp in the future must not drain the bucket.
+        assert_eq!(projected_tokens(Some(5.0), Some(2_000), 1_000, 60, 60), 5.0);
+    }
+
+    #[test]
+    fn test_headers_on_allowed_request() {
+        let mut headers = HeaderMap::new();
+        let decision = RateLimitDecision {
+            allowed: true,
+            remaining: 41,
+            retry_after_secs: 0,
+        };
+        apply_rate_limit_headers(&mut headers, 60, &decision);
+
+        assert_eq!(headers["x-ratelimit-limit"], "60");
+        assert_eq!(headers["x-ratelimit-remaining"], "41");
+        assert!(headers.get("retry-after").is_none());
+    }
+
+    #[test]
+    fn test_headers_on_rejected_request() {
+        let mut headers = HeaderMap::new();
+        let decision = RateLimitDecision {
+            allowed: false,
+            remaining: 0,
+            retry_after_secs: 0,
+        };
+        apply_rate_limit_headers(&mut headers, 60, &decision);
+
+        assert_eq!(headers["x-ratelimit-remaining"], "0");
+        // Never tell a client to retry immediately after a rejection.
+        assert_eq!(headers["retry-after"], "1");
+    }
 }
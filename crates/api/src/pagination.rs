@@ -0,0 +1,39 @@
+use core::config::Settings;
+
+/// Resolve a list endpoint's `limit` query parameter against the configured
+/// default and maximum page sizes.
+pub fn page_limit(settings: &Settings, requested: Option<i64>) -> i64 {
+    clamp_page_size(
+        requested,
+        settings.default_page_size,
+        settings.max_page_size,
+    )
+}
+
+fn clamp_page_size(requested: Option<i64>, default: i64, max: i64) -> i64 {
+    requested.unwrap_or(default).clamp(1, max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::clamp_page_size;
+
+    #[test]
+    fn test_missing_limit_uses_default() {
+        assert_eq!(clamp_page_size(None, 50, 100), 50);
+        assert_eq!(clamp_page_size(None, 20, 200), 20);
+    }
+
+    #[test]
+    fn test_limit_clamped_to_configured_max() {
+        assert_eq!(clamp_page_size(Some(500), 50, 100), 100);
+        assert_eq!(clamp_page_size(Some(500), 50, 250), 250);
+        assert_eq!(clamp_page_size(Some(75), 50, 100), 75);
+    }
+
+    #[test]
+    fn test_non_positive_limit_clamped_to_one() {
+        assert_eq!(clamp_page_size(Some(0), 50, 100), 1);
+        assert_eq!(clamp_page_size(Some(-5), 50, 100), 1);
+    }
+}
@@ -0,0 +1,187 @@
+use serde::Deserialize;
+
+use crate::error::AppError;
+
+/// Longest accepted account name, in characters.
+const MAX_NAME_CHARS: usize = 100;
+
+/// Longest accepted email address (RFC 5321 path limit).
+const MAX_EMAIL_LEN: usize = 254;
+
+/// Body of `PATCH /v1/publisher/me` and `PATCH /v1/subscriber/me`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateProfileRequest {
+    pub name: Option<String>,
+    pub email: Option<String>,
+}
+
+impl UpdateProfileRequest {
+    /// The new name with surrounding whitespace removed.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref().map(str::trim)
+    }
+
+    pub fn email(&self) -> Option<&str> {
+        self.email.as_deref()
+    }
+
+    /// Reject blank or overlong names and malformed email addresses.
+    pub fn validate(&self) -> Result<(), AppError> {
+        if let Some(name) = self.name() {
+            if name.is_empty() || name.chars().count() > MAX_NAME_CHARS {
+                return Err(AppError::BadRequest(format!(
+                    "name must be 1-{MAX_NAME_CHARS} characters"
+                )));
+            }
+        }
+        if let Some(email) = self.email() {
+            if !is_valid_email(email) {
+                return Err(AppError::BadRequest("invalid email address".to_string()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Body of `POST /v1/publishers` and `POST /v1/subscribers`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateAccountRequest {
+    pub name: String,
+    pub email: String,
+}
+
+impl CreateAccountRequest {
+    pub fn name(&self) -> &str {
+        self.name.trim()
+    }
+
+    /// Same rules as a profile update, with both fields required.
+    pub fn validate(&self) -> Result<(), AppError> {
+        UpdateProfileRequest {
+            name: Some(self.name.clone()),
+            email: Some(self.email.clone()),
+        }
+        .validate()
+    }
+}
+
+/// Map a profile update failure; the only unique column is the email.
+pub fn update_profile_error(err: sqlx::Error) -> AppError {
+    if matches!(err, sqlx::Error::Protocol(_)) {
+        return AppError::BadRequest("no fields to update".to_string());
+    }
+    account_write_error(err)
+}
+
+/// Map a failed account insert or update, reporting a unique violation as a
+/// taken email.
+pub fn account_write_error(err: sqlx::Error) -> AppError {
+    match AppError::from(err) {
+        AppError::Conflict(_) => AppError::Conflict("email already in use".to_string()),
+        other => other,
+    }
+}
+
+/// A single `@` between a non-empty local part and a dotted domain, with no
+/// whitespace. Deliverability is not checked.
+fn is_valid_email(email: &str) -> bool {
+    if email.len() > MAX_EMAIL_LEN || email.chars().any(char::is_whitespace) {
+        return false;
+    }
+    let Some((local, domain)) = email.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && !domain.contains('@')
+        && domain.contains('.')
+        && domain.split('.').all(|label| !label.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(name: Option<&str>, email: Option<&str>) -> UpdateProfileRequest {
+        UpdateProfileRequest {
+            name: name.map(str::to_string),
+            email: email.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_email_format() {
+        for email in ["a@example.com", "first.last+tag@mail.example.co.uk"] {
+            assert!(is_valid_email(email), "{email}");
+        }
+        for email in [
+            "",
+            "example.com",
+            "@example.com",
+            "a@",
+            "a@localhost",
+            "a@@example.com",
+            "a b@example.com",
+            "a@example..com",
+            "a@.example.com",
+        ] {
+            assert!(!is_valid_email(email), "{email}");
+        }
+    }
+
+    #[test]
+    fn test_profile_update_validation() {
+        assert!(request(Some("  Acme Alerts "), Some("ops@acme.io"))
+            .validate()
+            .is_ok());
+        assert_eq!(request(Some("  Acme "), None).name(), Some("Acme"));
+        assert!(request(None, None).validate().is_ok());
+        assert!(matches!(
+            request(Some("   "), None).validate(),
+            Err(AppError::BadRequest(_))
+        ));
+        assert!(matches!(
+            request(Some(&"n".repeat(101)), None).validate(),
+            Err(AppError::BadRequest(_))
+        ));
+        assert!(matches!(
+            request(None, Some("not-an-email")).validate(),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_create_account_validation() {
+        let request = |name: &str, email: &str| CreateAccountRequest {
+            name: name.to_string(),
+            email: email.to_string(),
+        };
+        assert!(request(" Acme ", "ops@acme.io").validate().is_ok());
+        assert_eq!(request(" Acme ", "ops@acme.io").name(), "Acme");
+        assert!(matches!(
+            request("", "ops@acme.io").validate(),
+            Err(AppError::BadRequest(_))
+        ));
+        assert!(matches!(
+            request("Acme", "ops@acme").validate(),
+            Err(AppError::BadRequest(_))
+        ));
+        assert!(serde_json::from_value::<CreateAccountRequest>(
+            serde_json::json!({ "name": "Acme" })
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_update_error_mapping() {
+        assert!(matches!(
+            update_profile_error(sqlx::Error::Protocol("no fields to update".into())),
+            AppError::BadRequest(_)
+        ));
+        assert!(matches!(
+            update_profile_error(sqlx::Error::PoolTimedOut),
+            AppError::Internal
+        ));
+    }
+}
@@ -0,0 +1,189 @@
+//! Account creation (`POST /v1/publishers`, `POST /v1/subscribers`).
+//!
+//! Gated by `HERALD_BOOTSTRAP_TOKEN` rather than an API key, since the new
+//! account has none yet. Each call creates the account and its first API
+//! key in one transaction; the raw key, and a subscriber's webhook secret,
+//! are only ever returned here.
+
+use axum::{extract::State, routing::post, Extension, Json, Router};
+use serde::Serialize;
+
+use crate::{
+    error::{ApiResult, AppError},
+    middleware::auth::BootstrapAuth,
+    profile::{account_write_error, CreateAccountRequest},
+    routes::api_keys::key_prefix,
+    state::{AppState, RequestId},
+};
+use core::auth::{generate_api_key, generate_webhook_secret};
+use db::models::{AccountStatus, AccountTier, ApiKeyOwner};
+
+/// Name given to the key created with an account.
+const FIRST_KEY_NAME: &str = "default";
+
+pub fn router(state: AppState) -> Router {
+    Router::new()
+        .route("/v1/publishers", post(create_publisher))
+        .route("/v1/subscribers", post(create_subscriber))
+        .with_state(state)
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CreatePublisherResponse {
+    id: String,
+    name: String,
+    email: String,
+    tier: AccountTier,
+    status: AccountStatus,
+    /// The raw key; only its hash is stored.
+    api_key: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateSubscriberResponse {
+    id: String,
+    name: String,
+    email: String,
+    tier: AccountTier,
+    status: AccountStatus,
+    webhook_secret: String,
+    /// The raw key; only its hash is stored.
+    api_key: String,
+}
+
+async fn create_publisher(
+    State(state): State<AppState>,
+    _: BootstrapAuth,
+    Extension(request_id): Extension<RequestId>,
+    Json(payload): Json<CreateAccountRequest>,
+) -> ApiResult<Json<CreatePublisherResponse>> {
+    payload
+        .validate()
+        .map_err(|err| err.with_request_id(&request_id.0))?;
+
+    let mut tx = state
+        .db
+        .begin()
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+
+    let id = format!("pub_{}", nanoid::nanoid!(12));
+    let publisher = db::queries::publishers::create(&mut *tx, &id, payload.name(), &payload.email)
+        .await
+        .map_err(|err| account_write_error(err).with_request_id(&request_id.0))?;
+    let api_key = create_first_key(&mut tx, ApiKeyOwner::Publisher, &publisher.id)
+        .await
+        .map_err(|err| err.with_request_id(&request_id.0))?;
+
+    tx.commit()
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+
+    Ok(Json(CreatePublisherResponse {
+        id: publisher.id,
+        name: publisher.name,
+        email: publisher.email,
+        tier: publisher.tier,
+        status: publisher.status,
+        api_key,
+    }))
+}
+
+async fn create_subscriber(
+    State(state): State<AppState>,
+    _: BootstrapAuth,
+    Extension(request_id): Extension<RequestId>,
+    Json(payload): Json<CreateAccountRequest>,
+) -> ApiResult<Json<CreateSubscriberResponse>> {
+    payload
+        .validate()
+        .map_err(|err| err.with_request_id(&request_id.0))?;
+
+    let mut tx = state
+        .db
+        .begin()
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+
+    let id = format!("sr_{}", nanoid::nanoid!(12));
+    let subscriber = db::queries::subscribers::create(
+        &mut *tx,
+        &id,
+        payload.name(),
+        &payload.email,
+        &generate_webhook_secret(),
+    )
+    .await
+    .map_err(|err| account_write_error(err).with_request_id(&request_id.0))?;
+    let api_key = create_first_key(&mut tx, ApiKeyOwner::Subscriber, &subscriber.id)
+        .await
+        .map_err(|err| err.with_request_id(&request_id.0))?;
+
+    tx.commit()
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+
+    Ok(Json(CreateSubscriberResponse {
+        id: subscriber.id,
+        name: subscriber.name,
+        email: subscriber.email,
+        tier: subscriber.tier,
+        status: subscriber.status,
+        webhook_secret: subscriber.webhook_secret,
+        api_key,
+    }))
+}
+
+/// Issue the new account's first API key, without expiry or scopes, and
+/// return it raw.
+async fn create_first_key(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    owner_type: ApiKeyOwner,
+    owner_id: &str,
+) -> Result<String, AppError> {
+    let (raw, hash, prefix) = generate_api_key(key_prefix(&owner_type));
+    let id = format!("key_{}", nanoid::nanoid!(12));
+
+    db::queries::api_keys::create(
+        &mut **tx,
+        &id,
+        &hash,
+        &prefix,
+        owner_type,
+        owner_id,
+        Some(FIRST_KEY_NAME),
+        &[],
+        None,
+    )
+    .await
+    .map_err(|_| AppError::Internal)?;
+
+    Ok(raw)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subscriber_response_returns_secrets_once() {
+        let response = CreateSubscriberResponse {
+            id: "sr_001".to_string(),
+            name: "Acme".to_string(),
+            email: "ops@acme.io".to_string(),
+            tier: AccountTier::Free,
+            status: AccountStatus::Active,
+            webhook_secret: "whsec_abc".to_string(),
+            api_key: "hld_sub_abc".to_string(),
+        };
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["webhookSecret"], "whsec_abc");
+        assert_eq!(json["apiKey"], "hld_sub_abc");
+        assert_eq!(
+            json["tier"],
+            serde_json::to_value(AccountTier::Free).unwrap()
+        );
+    }
+}
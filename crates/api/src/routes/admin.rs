@@ -1,24 +1,43 @@
 use axum::{
-    extract::{Path, State},
+    body::{Body, Bytes},
+    extract::{Path, Query, State},
+    http::header::CONTENT_TYPE,
+    response::{IntoResponse, Response},
     routing::{get, post},
     Extension, Json, Router,
 };
 use chrono::{DateTime, Utc};
-use serde::Serialize;
+use futures_util::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
 
 use crate::{
-    error::{ApiError, ApiResult, AppError},
-    middleware::auth::AuthContext,
+    error::{ApiResult, AppError},
+    middleware::auth::PublisherAuth,
+    middleware::rate_limit::{capacity_for_tier, group_buckets, read_bucket},
+    pagination::page_limit,
     state::{AppState, RequestId},
+    tunnel::status::TunnelStatusResponse,
 };
 use core::types::DeliveryJob;
-use db::models::{ApiKeyOwner, DeliveryStatus};
+use db::models::{
+    ApiKey, ApiKeyOwner, DeadLetterEntry, Delivery, DeliveryMode, DeliveryStatus, Signal,
+    SignalStatus, Subscription, SubscriptionStatus,
+};
 
 pub fn router(state: AppState) -> Router {
     Router::new()
         .route("/v1/admin/dlq", get(list_dlq))
+        .route("/v1/admin/dlq/retry-all", post(retry_all_dlq))
+        .route("/v1/admin/dlq/{id}", get(get_dlq_entry))
         .route("/v1/admin/dlq/{id}/retry", post(retry_dlq))
         .route("/v1/admin/signals/{id}", get(get_signal_admin))
+        .route(
+            "/v1/admin/signals/{id}/deliveries.ndjson",
+            get(export_signal_deliveries),
+        )
+        .route("/v1/admin/rate-limit/{key_id}", get(get_rate_limit_bucket))
+        .route("/v1/admin/tunnels", get(list_tunnels))
         .with_state(state)
 }
 
@@ -35,6 +54,53 @@ struct DlqItem {
 #[serde(rename_all = "camelCase")]
 struct DlqListResponse {
     items: Vec<DlqItem>,
+    next_cursor: Option<String>,
+    /// Total unresolved entries, across all pages.
+    count: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DlqEntryResponse {
+    id: String,
+    delivery_id: String,
+    signal_id: String,
+    subscription_id: String,
+    /// Payload the failed delivery would have sent.
+    payload: serde_json::Value,
+    /// One element per recorded failure, oldest first.
+    error_history: Vec<serde_json::Value>,
+    resolved_at: Option<DateTime<Utc>>,
+    resolution_note: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+impl From<DeadLetterEntry> for DlqEntryResponse {
+    fn from(entry: DeadLetterEntry) -> Self {
+        let error_history = match entry.error_history {
+            serde_json::Value::Array(items) => items,
+            serde_json::Value::Null => Vec::new(),
+            other => vec![other],
+        };
+        Self {
+            id: entry.id,
+            delivery_id: entry.delivery_id,
+            signal_id: entry.signal_id,
+            subscription_id: entry.subscription_id,
+            payload: entry.payload,
+            error_history,
+            resolved_at: entry.resolved_at,
+            resolution_note: entry.resolution_note,
+            created_at: entry.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DlqListQuery {
+    limit: Option<i64>,
+    cursor: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -43,6 +109,24 @@ struct DlqRetryResponse {
     status: &'static str,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DlqRetryAllQuery {
+    channel_id: Option<String>,
+    subscription_id: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DlqRetryAllResponse {
+    /// Entries re-enqueued by this call.
+    queued: u32,
+    /// Entries resolved without a replay because they can't be delivered.
+    skipped: u32,
+    /// More matching entries remain beyond this call's cap.
+    has_more: bool,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct AdminSignalResponse {
@@ -63,22 +147,108 @@ struct AdminSignal {
 #[serde(rename_all = "camelCase")]
 struct AdminDelivery {
     id: String,
+    delivery_mode: DeliveryMode,
     status: DeliveryStatus,
     attempt: i32,
     status_code: Option<i32>,
+    error_message: Option<String>,
+    response_body: Option<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl From<Delivery> for AdminDelivery {
+    fn from(delivery: Delivery) -> Self {
+        Self {
+            id: delivery.id,
+            delivery_mode: delivery.delivery_mode,
+            status: delivery.status,
+            attempt: delivery.attempt,
+            status_code: delivery.status_code,
+            error_message: delivery.error_message,
+            response_body: delivery.response_body,
+            created_at: delivery.created_at,
+            updated_at: delivery.updated_at,
+        }
+    }
+}
+
+/// One line of the NDJSON delivery report.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeliveryReportLine {
+    id: String,
+    signal_id: String,
+    subscription_id: String,
+    webhook_id: Option<String>,
+    delivery_mode: DeliveryMode,
+    attempt: i32,
+    status: DeliveryStatus,
+    status_code: Option<i32>,
+    error_message: Option<String>,
+    response_body: Option<String>,
+    latency_ms: Option<i32>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl From<Delivery> for DeliveryReportLine {
+    fn from(delivery: Delivery) -> Self {
+        Self {
+            id: delivery.id,
+            signal_id: delivery.signal_id,
+            subscription_id: delivery.subscription_id,
+            webhook_id: delivery.webhook_id,
+            delivery_mode: delivery.delivery_mode,
+            attempt: delivery.attempt,
+            status: delivery.status,
+            status_code: delivery.status_code,
+            error_message: delivery.error_message,
+            response_body: delivery.response_body,
+            latency_ms: delivery.latency_ms,
+            created_at: delivery.created_at,
+            updated_at: delivery.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RateLimitBucketResponse {
+    key_id: String,
+    tokens: f64,
+    capacity: u32,
+    last_refill_ts: Option<u64>,
+    /// One entry per configured route group; empty without groups.
+    groups: Vec<RateLimitGroupResponse>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RateLimitGroupResponse {
+    group: String,
+    tokens: f64,
+    capacity: u32,
+    last_refill_ts: Option<u64>,
 }
 
 async fn list_dlq(
     State(state): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    PublisherAuth(_): PublisherAuth,
     Extension(request_id): Extension<RequestId>,
+    Query(query): Query<DlqListQuery>,
 ) -> ApiResult<Json<DlqListResponse>> {
-    require_publisher(&auth, &request_id)?;
-
-    let entries = db::queries::dead_letter_queue::list_unresolved(&state.db)
+    let limit = page_limit(&state.settings, query.limit);
+    let entries =
+        db::queries::dead_letter_queue::list_unresolved(&state.db, limit, query.cursor.as_deref())
+            .await
+            .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+    let count = db::queries::dead_letter_queue::count_unresolved(&state.db)
         .await
         .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
 
+    let next_cursor = entries.last().map(|entry| entry.id.clone());
+
     Ok(Json(DlqListResponse {
         items: entries
             .into_iter()
@@ -89,17 +259,34 @@ async fn list_dlq(
                 created_at: entry.created_at,
             })
             .collect(),
+        next_cursor,
+        count,
     }))
 }
 
+/// One DLQ entry with its payload and failure history.
+async fn get_dlq_entry(
+    State(state): State<AppState>,
+    PublisherAuth(_): PublisherAuth,
+    Extension(request_id): Extension<RequestId>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<DlqEntryResponse>> {
+    let entry = db::queries::dead_letter_queue::get_by_id(&state.db, &id)
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
+        .ok_or_else(|| {
+            AppError::NotFound("dlq entry not found".to_string()).with_request_id(&request_id.0)
+        })?;
+
+    Ok(Json(DlqEntryResponse::from(entry)))
+}
+
 async fn retry_dlq(
     State(state): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    PublisherAuth(_): PublisherAuth,
     Extension(request_id): Extension<RequestId>,
     Path(id): Path<String>,
 ) -> ApiResult<Json<DlqRetryResponse>> {
-    require_publisher(&auth, &request_id)?;
-
     let entry = db::queries::dead_letter_queue::get_by_id(&state.db, &id)
         .await
         .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
@@ -107,41 +294,140 @@ async fn retry_dlq(
             AppError::NotFound("dlq entry not found".to_string()).with_request_id(&request_id.0)
         })?;
 
+    if let Err(reason) = replay_dlq_entry(&state, entry, &request_id.0).await? {
+        return Err(
+            AppError::Conflict(format!("cannot replay: {reason}")).with_request_id(&request_id.0)
+        );
+    }
+
+    Ok(Json(DlqRetryResponse { status: "queued" }))
+}
+
+/// Re-enqueue a dead-lettered delivery on its signal's urgency queue and
+/// resolve the entry. An entry that can never be delivered is resolved with
+/// a note instead, and the reason is returned as `Err`.
+async fn replay_dlq_entry(
+    state: &AppState,
+    entry: DeadLetterEntry,
+    request_id: &str,
+) -> ApiResult<Result<(), &'static str>> {
     let delivery = db::queries::deliveries::get_by_id(&state.db, &entry.delivery_id)
         .await
-        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
+        .map_err(|_| AppError::Internal.with_request_id(request_id))?
         .ok_or_else(|| {
-            AppError::NotFound("delivery not found".to_string()).with_request_id(&request_id.0)
+            AppError::NotFound("delivery not found".to_string()).with_request_id(request_id)
         })?;
 
+    let signal = db::queries::signals::get_by_id(&state.db, &entry.signal_id)
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(request_id))?;
+    let subscription = db::queries::subscriptions::get_by_id(&state.db, &entry.subscription_id)
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(request_id))?;
+
+    let signal = match replayable_signal(signal, subscription.as_ref()) {
+        Ok(signal) => signal,
+        Err(reason) => {
+            // The job could never be delivered; close the entry instead.
+            db::queries::dead_letter_queue::resolve(&state.db, &entry.id, Some(reason))
+                .await
+                .map_err(|_| AppError::Internal.with_request_id(request_id))?;
+            return Ok(Err(reason));
+        }
+    };
+
     let job = DeliveryJob {
         signal_id: entry.signal_id,
         subscription_id: entry.subscription_id,
         webhook_id: delivery.webhook_id,
         attempt: 0,
+        // Manual DLQ retries go out at base priority.
+        priority: 0,
+        channel_id: signal.channel_id.clone(),
     };
 
     state
         .storage
-        .push("delivery-normal", job)
+        .push(signal.urgency.queue(), job)
         .await
-        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+        .map_err(|_| AppError::Internal.with_request_id(request_id))?;
 
-    db::queries::dead_letter_queue::resolve(&state.db, &id)
+    db::queries::dead_letter_queue::resolve(&state.db, &entry.id, None)
         .await
-        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+        .map_err(|_| AppError::Internal.with_request_id(request_id))?;
 
-    Ok(Json(DlqRetryResponse { status: "queued" }))
+    Ok(Ok(()))
+}
+
+/// Most entries a single bulk retry call will replay.
+const DLQ_RETRY_ALL_LIMIT: i64 = 500;
+
+/// Replay every unresolved entry matching the filters, up to
+/// [`DLQ_RETRY_ALL_LIMIT`] per call; `hasMore` says whether to call again.
+async fn retry_all_dlq(
+    State(state): State<AppState>,
+    PublisherAuth(_): PublisherAuth,
+    Extension(request_id): Extension<RequestId>,
+    Query(query): Query<DlqRetryAllQuery>,
+) -> ApiResult<Json<DlqRetryAllResponse>> {
+    let mut entries = db::queries::dead_letter_queue::list_unresolved_filtered(
+        &state.db,
+        query.channel_id.as_deref(),
+        query.subscription_id.as_deref(),
+        DLQ_RETRY_ALL_LIMIT + 1,
+    )
+    .await
+    .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+
+    let has_more = entries.len() as i64 > DLQ_RETRY_ALL_LIMIT;
+    entries.truncate(DLQ_RETRY_ALL_LIMIT as usize);
+
+    let mut queued = 0;
+    let mut skipped = 0;
+    for entry in entries {
+        // A failed replay only skips its own entry; the rest of the batch
+        // still goes out.
+        let entry_id = entry.id.clone();
+        match replay_dlq_entry(&state, entry, &request_id.0).await {
+            Ok(Ok(())) => queued += 1,
+            Ok(Err(_)) => skipped += 1,
+            Err(err) => {
+                warn!(dlq_id = %entry_id, error = ?err.error, "failed to replay DLQ entry");
+                skipped += 1;
+            }
+        }
+    }
+
+    Ok(Json(DlqRetryAllResponse {
+        queued,
+        skipped,
+        has_more,
+    }))
+}
+
+/// The signal to replay a dead-lettered delivery for, or why it can no
+/// longer be replayed.
+fn replayable_signal(
+    signal: Option<Signal>,
+    subscription: Option<&Subscription>,
+) -> Result<Signal, &'static str> {
+    let signal = signal.ok_or("signal no longer exists")?;
+    if matches!(signal.status, SignalStatus::Deleted) {
+        return Err("signal has been deleted");
+    }
+    let subscription = subscription.ok_or("subscription no longer exists")?;
+    if matches!(subscription.status, SubscriptionStatus::Canceled) {
+        return Err("subscription has been canceled");
+    }
+    Ok(signal)
 }
 
 async fn get_signal_admin(
     State(state): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    PublisherAuth(_): PublisherAuth,
     Extension(request_id): Extension<RequestId>,
     Path(id): Path<String>,
 ) -> ApiResult<Json<AdminSignalResponse>> {
-    require_publisher(&auth, &request_id)?;
-
     let signal = db::queries::signals::get_by_id(&state.db, &id)
         .await
         .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
@@ -160,27 +446,387 @@ async fn get_signal_admin(
             urgency: signal.urgency,
             created_at: signal.created_at,
         },
-        deliveries: deliveries
-            .into_iter()
-            .map(|delivery| AdminDelivery {
-                id: delivery.id,
-                status: delivery.status,
-                attempt: delivery.attempt,
-                status_code: delivery.status_code,
-            })
-            .collect(),
+        deliveries: deliveries.into_iter().map(AdminDelivery::from).collect(),
+    }))
+}
+
+/// Stream every delivery attempt for a signal as newline-delimited JSON.
+async fn export_signal_deliveries(
+    State(state): State<AppState>,
+    PublisherAuth(publisher_id): PublisherAuth,
+    Extension(request_id): Extension<RequestId>,
+    Path(id): Path<String>,
+) -> ApiResult<Response> {
+    let signal = db::queries::signals::get_by_id(&state.db, &id)
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
+        .ok_or_else(|| {
+            AppError::NotFound("signal not found".to_string()).with_request_id(&request_id.0)
+        })?;
+
+    let channel = db::queries::channels::get_by_id(&state.db, &signal.channel_id)
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
+        .ok_or_else(|| {
+            AppError::NotFound("channel not found".to_string()).with_request_id(&request_id.0)
+        })?;
+
+    if channel.publisher_id != publisher_id {
+        return Err(
+            AppError::Forbidden("not channel owner".to_string()).with_request_id(&request_id.0)
+        );
+    }
+
+    // The row stream borrows the pool, so drive it from a task and hand the
+    // encoded lines to the response body through a bounded channel.
+    let pool = state.db.clone();
+    let (tx, rx) = tokio::sync::mpsc::channel(64);
+    tokio::spawn(async move {
+        let mut lines = Box::pin(ndjson_lines(db::queries::deliveries::stream_by_signal(
+            &pool, &signal.id,
+        )));
+        while let Some(line) = lines.next().await {
+            if tx.send(line).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let body = Body::from_stream(futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|line| (line, rx))
+    }));
+
+    Ok(([(CONTENT_TYPE, "application/x-ndjson")], body).into_response())
+}
+
+/// Encode delivery rows as NDJSON lines.
+fn ndjson_lines(
+    rows: impl Stream<Item = Result<Delivery, sqlx::Error>>,
+) -> impl Stream<Item = std::io::Result<Bytes>> {
+    rows.map(|row| {
+        let delivery = row.map_err(std::io::Error::other)?;
+        let mut line = serde_json::to_vec(&DeliveryReportLine::from(delivery))?;
+        line.push(b'\n');
+        Ok(Bytes::from(line))
+    })
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TunnelListResponse {
+    items: Vec<TunnelStatusResponse>,
+}
+
+/// Every connected agent with its keepalive health.
+async fn list_tunnels(
+    State(state): State<AppState>,
+    PublisherAuth(_): PublisherAuth,
+) -> ApiResult<Json<TunnelListResponse>> {
+    let mut items: Vec<_> = state
+        .tunnel_registry
+        .list()
+        .await
+        .iter()
+        .map(|conn| TunnelStatusResponse::new(&conn.subscriber_id, Some(conn)))
+        .collect();
+    items.sort_by(|a, b| {
+        (&a.subscriber_id, &a.connection_id).cmp(&(&b.subscriber_id, &b.connection_id))
+    });
+    Ok(Json(TunnelListResponse { items }))
+}
+
+/// A publisher may only inspect its own keys' buckets; anyone else's key
+/// reads as not found.
+async fn get_rate_limit_bucket(
+    State(state): State<AppState>,
+    PublisherAuth(publisher_id): PublisherAuth,
+    Extension(request_id): Extension<RequestId>,
+    Path(key_id): Path<String>,
+) -> ApiResult<Json<RateLimitBucketResponse>> {
+    let api_key = db::queries::api_keys::get_by_id(&state.db, &key_id)
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
+        .filter(|key| owned_by_publisher(key, &publisher_id))
+        .ok_or_else(|| {
+            AppError::NotFound("api key not found".to_string()).with_request_id(&request_id.0)
+        })?;
+
+    let tier = db::queries::publishers::get_by_id(&state.db, &api_key.owner_id)
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
+        .map(|publisher| publisher.tier)
+        .ok_or_else(|| {
+            AppError::NotFound("api key owner not found".to_string()).with_request_id(&request_id.0)
+        })?;
+
+    let capacity = capacity_for_tier(&state.settings, &tier);
+
+    let mut conn = state
+        .redis
+        .get_multiplexed_async_connection()
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+
+    let bucket = read_bucket(&mut conn, &api_key.id, capacity, capacity)
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+
+    let mut groups = Vec::new();
+    for group in group_buckets(&state.settings, &api_key.id, &tier) {
+        let state = read_bucket(&mut conn, &group.bucket, group.capacity, group.capacity)
+            .await
+            .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+        groups.push(RateLimitGroupResponse {
+            group: group.group,
+            tokens: state.tokens,
+            capacity: state.capacity,
+            last_refill_ts: state.last_refill_ts,
+        });
+    }
+
+    Ok(Json(RateLimitBucketResponse {
+        key_id: api_key.id,
+        tokens: bucket.tokens,
+        capacity: bucket.capacity,
+        last_refill_ts: bucket.last_refill_ts,
+        groups,
     }))
 }
 
-fn require_publisher<'a>(
-    auth: &'a AuthContext,
-    request_id: &RequestId,
-) -> Result<&'a str, ApiError> {
-    match auth.owner_type {
-        ApiKeyOwner::Publisher => Ok(auth.owner_id.as_str()),
-        ApiKeyOwner::Subscriber => {
-            Err(AppError::Forbidden("publisher access required".to_string())
-                .with_request_id(&request_id.0))
+fn owned_by_publisher(key: &ApiKey, publisher_id: &str) -> bool {
+    key.owner_type == ApiKeyOwner::Publisher && key.owner_id == publisher_id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use db::models::SignalUrgency;
+
+    fn make_signal(urgency: SignalUrgency, status: SignalStatus) -> Signal {
+        Signal {
+            id: "sig_1".to_string(),
+            channel_id: "ch_1".to_string(),
+            title: "t".to_string(),
+            body: "b".to_string(),
+            urgency,
+            metadata: serde_json::json!({}),
+            delivery_count: 0,
+            delivered_count: 0,
+            failed_count: 0,
+            status,
+            min_subscriber_tier: None,
+            scheduled_at: None,
+            created_at: Utc::now(),
         }
     }
+
+    fn make_subscription(status: SubscriptionStatus) -> Subscription {
+        Subscription {
+            id: "sub_1".to_string(),
+            subscriber_id: "sr_1".to_string(),
+            channel_id: "ch_1".to_string(),
+            webhook_id: None,
+            emergency_webhook_id: None,
+            status,
+            min_urgency: SignalUrgency::Low,
+            stripe_subscription_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn make_dlq_entry(error_history: serde_json::Value) -> DeadLetterEntry {
+        DeadLetterEntry {
+            id: "dlq_1".to_string(),
+            delivery_id: "del_1".to_string(),
+            signal_id: "sig_1".to_string(),
+            subscription_id: "sub_1".to_string(),
+            payload: serde_json::json!({ "deliveryId": "del_1" }),
+            error_history,
+            resolved_at: None,
+            resolution_note: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_dlq_entry_exposes_payload_and_error_history() {
+        let history = serde_json::json!([
+            { "attempt": 4, "error": "HTTP 502" },
+            { "attempt": 5, "error": "timeout" }
+        ]);
+        let response = DlqEntryResponse::from(make_dlq_entry(history));
+        let json = serde_json::to_value(&response).unwrap();
+
+        assert_eq!(json["deliveryId"], "del_1");
+        assert_eq!(json["payload"]["deliveryId"], "del_1");
+        assert_eq!(json["errorHistory"].as_array().unwrap().len(), 2);
+        assert_eq!(json["errorHistory"][1]["error"], "timeout");
+        assert!(json["resolvedAt"].is_null());
+    }
+
+    #[test]
+    fn test_dlq_entry_error_history_is_always_an_array() {
+        let single = DlqEntryResponse::from(make_dlq_entry(serde_json::json!({ "error": "x" })));
+        assert_eq!(single.error_history.len(), 1);
+        let empty = DlqEntryResponse::from(make_dlq_entry(serde_json::Value::Null));
+        assert!(empty.error_history.is_empty());
+    }
+
+    #[test]
+    fn test_dlq_list_query_params_are_optional() {
+        let query: DlqListQuery = serde_json::from_str("{}").unwrap();
+        assert!(query.limit.is_none() && query.cursor.is_none());
+
+        let query: DlqListQuery =
+            serde_json::from_str(r#"{"limit": 25, "cursor": "dlq_001"}"#).unwrap();
+        assert_eq!(query.limit, Some(25));
+        assert_eq!(query.cursor.as_deref(), Some("dlq_001"));
+    }
+
+    #[test]
+    fn test_dlq_retry_all_filters_are_optional() {
+        let query: DlqRetryAllQuery = serde_json::from_str("{}").unwrap();
+        assert!(query.channel_id.is_none() && query.subscription_id.is_none());
+
+        let query: DlqRetryAllQuery =
+            serde_json::from_str(r#"{"channelId": "ch_1", "subscriptionId": "sub_1"}"#).unwrap();
+        assert_eq!(query.channel_id.as_deref(), Some("ch_1"));
+        assert_eq!(query.subscription_id.as_deref(), Some("sub_1"));
+    }
+
+    #[test]
+    fn test_dlq_replay_uses_signal_urgency_queue() {
+        let active = make_subscription(SubscriptionStatus::Active);
+        for (urgency, queue) in [
+            (SignalUrgency::Low, "delivery-normal"),
+            (SignalUrgency::Normal, "delivery-normal"),
+            (SignalUrgency::High, "delivery-high"),
+            (SignalUrgency::Critical, "delivery-high"),
+        ] {
+            let signal = make_signal(urgency, SignalStatus::Active);
+            let signal = replayable_signal(Some(signal), Some(&active)).unwrap();
+            assert_eq!(signal.urgency.queue(), queue);
+        }
+    }
+
+    #[test]
+    fn test_dlq_replay_blocked_for_deleted_signal_or_subscription() {
+        let active = make_subscription(SubscriptionStatus::Active);
+        let live = || make_signal(SignalUrgency::High, SignalStatus::Active);
+
+        assert_eq!(
+            replayable_signal(None, Some(&active)).unwrap_err(),
+            "signal no longer exists"
+        );
+        assert_eq!(
+            replayable_signal(
+                Some(make_signal(SignalUrgency::High, SignalStatus::Deleted)),
+                Some(&active)
+            )
+            .unwrap_err(),
+            "signal has been deleted"
+        );
+        assert_eq!(
+            replayable_signal(Some(live()), None).unwrap_err(),
+            "subscription no longer exists"
+        );
+        let canceled = make_subscription(SubscriptionStatus::Canceled);
+        assert_eq!(
+            replayable_signal(Some(live()), Some(&canceled)).unwrap_err(),
+            "subscription has been canceled"
+        );
+        // A paused subscription can still be replayed; the worker decides.
+        let paused = make_subscription(SubscriptionStatus::Paused);
+        assert!(replayable_signal(Some(live()), Some(&paused)).is_ok());
+    }
+
+    fn make_delivery(id: &str) -> Delivery {
+        Delivery {
+            id: id.to_string(),
+            signal_id: "sig_1".to_string(),
+            subscription_id: "sub_1".to_string(),
+            webhook_id: Some("wh_1".to_string()),
+            delivery_mode: DeliveryMode::Webhook,
+            attempt: 0,
+            status: DeliveryStatus::Success,
+            status_code: Some(200),
+            error_message: None,
+            response_body: None,
+            latency_ms: Some(12),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_admin_delivery_shows_when_and_why() {
+        let mut delivery = make_delivery("del_1");
+        delivery.status = DeliveryStatus::Failed;
+        delivery.status_code = Some(503);
+        delivery.error_message = Some("HTTP 503".to_string());
+
+        let value = serde_json::to_value(AdminDelivery::from(delivery)).unwrap();
+        assert_eq!(value["deliveryMode"], "Webhook");
+        assert_eq!(value["errorMessage"], "HTTP 503");
+        assert!(value["createdAt"].is_string());
+        assert!(value["updatedAt"].is_string());
+    }
+
+    #[test]
+    fn test_ndjson_report_has_one_line_per_delivery() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let rows = futures_util::stream::iter(vec![
+            Ok(make_delivery("del_1")),
+            Ok(make_delivery("del_2")),
+            Ok(make_delivery("del_3")),
+        ]);
+
+        let chunks: Vec<_> = rt.block_on(ndjson_lines(rows).collect::<Vec<_>>());
+        let output: Vec<u8> = chunks
+            .into_iter()
+            .flat_map(|chunk| chunk.unwrap().to_vec())
+            .collect();
+        let text = String::from_utf8(output).unwrap();
+
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(text.ends_with('\n'));
+        for (line, id) in lines.iter().zip(["del_1", "del_2", "del_3"]) {
+            let value: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert_eq!(value["id"], id);
+            assert_eq!(value["statusCode"], 200);
+        }
+    }
+
+    #[test]
+    fn test_rate_limit_bucket_only_for_own_publisher_keys() {
+        let key = |owner_type, owner_id: &str| ApiKey {
+            id: "key_1".to_string(),
+            key_hash: "hash".to_string(),
+            key_prefix: "hld_pub_".to_string(),
+            owner_type,
+            owner_id: owner_id.to_string(),
+            name: None,
+            scopes: Vec::new(),
+            last_used_at: None,
+            expires_at: None,
+            status: db::models::ApiKeyStatus::Active,
+            created_at: Utc::now(),
+        };
+        assert!(owned_by_publisher(
+            &key(ApiKeyOwner::Publisher, "pub_1"),
+            "pub_1"
+        ));
+        assert!(!owned_by_publisher(
+            &key(ApiKeyOwner::Publisher, "pub_2"),
+            "pub_1"
+        ));
+        assert!(!owned_by_publisher(
+            &key(ApiKeyOwner::Subscriber, "pub_1"),
+            "pub_1"
+        ));
+    }
 }
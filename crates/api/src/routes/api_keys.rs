@@ -0,0 +1,363 @@
+//! API key management for publishers and subscribers.
+//!
+//! Both account types get the same list/create/revoke endpoints under their
+//! own prefix; keys are always scoped to the authenticated owner.
+
+use axum::{
+    extract::{Path, State},
+    routing::{delete, get},
+    Extension, Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::{
+    error::{ApiResult, AppError},
+    middleware::{
+        auth::{PublisherAuth, SubscriberAuth},
+        key_cache,
+    },
+    state::{AppState, RequestId},
+};
+use core::auth::{generate_api_key, PUBLISHER_PREFIX, SUBSCRIBER_PREFIX};
+use db::models::{ApiKey, ApiKeyOwner, ApiKeyStatus};
+
+pub fn router(state: AppState) -> Router {
+    Router::new()
+        .route(
+            "/v1/publisher/api-keys",
+            get(list_publisher_keys).post(create_publisher_key),
+        )
+        .route("/v1/publisher/api-keys/{id}", delete(revoke_publisher_key))
+        .route(
+            "/v1/subscriber/api-keys",
+            get(list_subscriber_keys).post(create_subscriber_key),
+        )
+        .route(
+            "/v1/subscriber/api-keys/{id}",
+            delete(revoke_subscriber_key),
+        )
+        .with_state(state)
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiKeyItem {
+    id: String,
+    prefix: String,
+    name: Option<String>,
+    status: ApiKeyStatus,
+    scopes: Vec<String>,
+    /// Accurate to about a minute; see `middleware::auth`.
+    last_used_at: Option<DateTime<Utc>>,
+    expires_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+}
+
+impl From<ApiKey> for ApiKeyItem {
+    fn from(key: ApiKey) -> Self {
+        Self {
+            id: key.id,
+            prefix: key.key_prefix,
+            name: key.name,
+            status: key.status,
+            scopes: key.scopes,
+            last_used_at: key.last_used_at,
+            expires_at: key.expires_at,
+            created_at: key.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ListApiKeysResponse {
+    items: Vec<ApiKeyItem>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateApiKeyRequest {
+    name: Option<String>,
+    /// Expire the key this many days after creation.
+    expires_in_days: Option<i64>,
+    /// Expire the key at this time; mutually exclusive with `expires_in_days`.
+    expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateApiKeyResponse {
+    id: String,
+    key: String,
+    prefix: String,
+    expires_at: Option<DateTime<Utc>>,
+}
+
+/// Longest lifetime an expiring API key can be given.
+const MAX_KEY_LIFETIME_DAYS: i64 = 3650;
+
+#[derive(Debug, Serialize)]
+struct RevokeApiKeyResponse {
+    status: ApiKeyStatus,
+}
+
+async fn list_publisher_keys(
+    State(state): State<AppState>,
+    PublisherAuth(publisher_id): PublisherAuth,
+    Extension(request_id): Extension<RequestId>,
+) -> ApiResult<Json<ListApiKeysResponse>> {
+    list_keys(&state, ApiKeyOwner::Publisher, &publisher_id)
+        .await
+        .map(Json)
+        .map_err(|err| err.with_request_id(&request_id.0))
+}
+
+async fn create_publisher_key(
+    State(state): State<AppState>,
+    PublisherAuth(publisher_id): PublisherAuth,
+    Extension(request_id): Extension<RequestId>,
+    Json(payload): Json<CreateApiKeyRequest>,
+) -> ApiResult<Json<CreateApiKeyResponse>> {
+    create_key(&state, ApiKeyOwner::Publisher, &publisher_id, &payload)
+        .await
+        .map(Json)
+        .map_err(|err| err.with_request_id(&request_id.0))
+}
+
+async fn revoke_publisher_key(
+    State(state): State<AppState>,
+    PublisherAuth(publisher_id): PublisherAuth,
+    Extension(request_id): Extension<RequestId>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<RevokeApiKeyResponse>> {
+    revoke_key(&state, ApiKeyOwner::Publisher, &publisher_id, &id)
+        .await
+        .map(Json)
+        .map_err(|err| err.with_request_id(&request_id.0))
+}
+
+async fn list_subscriber_keys(
+    State(state): State<AppState>,
+    SubscriberAuth(subscriber_id): SubscriberAuth,
+    Extension(request_id): Extension<RequestId>,
+) -> ApiResult<Json<ListApiKeysResponse>> {
+    list_keys(&state, ApiKeyOwner::Subscriber, &subscriber_id)
+        .await
+        .map(Json)
+        .map_err(|err| err.with_request_id(&request_id.0))
+}
+
+async fn create_subscriber_key(
+    State(state): State<AppState>,
+    SubscriberAuth(subscriber_id): SubscriberAuth,
+    Extension(request_id): Extension<RequestId>,
+    Json(payload): Json<CreateApiKeyRequest>,
+) -> ApiResult<Json<CreateApiKeyResponse>> {
+    create_key(&state, ApiKeyOwner::Subscriber, &subscriber_id, &payload)
+        .await
+        .map(Json)
+        .map_err(|err| err.with_request_id(&request_id.0))
+}
+
+async fn revoke_subscriber_key(
+    State(state): State<AppState>,
+    SubscriberAuth(subscriber_id): SubscriberAuth,
+    Extension(request_id): Extension<RequestId>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<RevokeApiKeyResponse>> {
+    revoke_key(&state, ApiKeyOwner::Subscriber, &subscriber_id, &id)
+        .await
+        .map(Json)
+        .map_err(|err| err.with_request_id(&request_id.0))
+}
+
+async fn list_keys(
+    state: &AppState,
+    owner_type: ApiKeyOwner,
+    owner_id: &str,
+) -> Result<ListApiKeysResponse, AppError> {
+    let keys = db::queries::api_keys::list_by_owner(&state.db, owner_type, owner_id)
+        .await
+        .map_err(|_| AppError::Internal)?;
+
+    Ok(ListApiKeysResponse {
+        items: keys.into_iter().map(ApiKeyItem::from).collect(),
+    })
+}
+
+async fn create_key(
+    state: &AppState,
+    owner_type: ApiKeyOwner,
+    owner_id: &str,
+    payload: &CreateApiKeyRequest,
+) -> Result<CreateApiKeyResponse, AppError> {
+    let expires_at = key_expiry(payload, Utc::now())?;
+    let (raw, hash, prefix) = generate_api_key(key_prefix(&owner_type));
+    let id = format!("key_{}", nanoid::nanoid!(12));
+
+    db::queries::api_keys::create(
+        &state.db,
+        &id,
+        &hash,
+        &prefix,
+        owner_type,
+        owner_id,
+        payload.name.as_deref(),
+        &[],
+        expires_at,
+    )
+    .await?;
+
+    Ok(CreateApiKeyResponse {
+        id,
+        key: raw,
+        prefix,
+        expires_at,
+    })
+}
+
+/// Revoke one of the owner's keys. Keys belonging to anyone else are
+/// reported as missing rather than forbidden, so ids can't be probed.
+async fn revoke_key(
+    state: &AppState,
+    owner_type: ApiKeyOwner,
+    owner_id: &str,
+    id: &str,
+) -> Result<RevokeApiKeyResponse, AppError> {
+    let key = db::queries::api_keys::get_by_id(&state.db, id)
+        .await
+        .map_err(|_| AppError::Internal)?
+        .filter(|key| key.owner_type == owner_type && key.owner_id == owner_id)
+        .ok_or_else(|| AppError::NotFound("api key not found".to_string()))?;
+
+    db::queries::api_keys::revoke(&state.db, &key.id)
+        .await
+        .map_err(|_| AppError::Internal)?;
+    // Fail the request rather than leave the key usable from the cache; a
+    // retry revokes again and repeats the delete.
+    key_cache::invalidate(&state.redis, &key.key_hash)
+        .await
+        .map_err(|err| {
+            error!(key_id = %key.id, error = %err, "failed to drop cached api key");
+            AppError::Internal
+        })?;
+
+    Ok(RevokeApiKeyResponse {
+        status: ApiKeyStatus::Revoked,
+    })
+}
+
+/// Raw key prefix for the owner's role, e.g. `hld_sub_`.
+pub(crate) fn key_prefix(owner_type: &ApiKeyOwner) -> &'static str {
+    match owner_type {
+        ApiKeyOwner::Publisher => PUBLISHER_PREFIX,
+        ApiKeyOwner::Subscriber => SUBSCRIBER_PREFIX,
+    }
+}
+
+/// When a new key should expire, if ever.
+fn key_expiry(
+    payload: &CreateApiKeyRequest,
+    now: DateTime<Utc>,
+) -> Result<Option<DateTime<Utc>>, AppError> {
+    let latest = now + chrono::Duration::days(MAX_KEY_LIFETIME_DAYS);
+    match (payload.expires_in_days, payload.expires_at) {
+        (None, None) => Ok(None),
+        (Some(_), Some(_)) => Err(AppError::BadRequest(
+            "set expiresInDays or expiresAt, not both".to_string(),
+        )),
+        (Some(days), None) => {
+            if !(1..=MAX_KEY_LIFETIME_DAYS).contains(&days) {
+                return Err(AppError::BadRequest(format!(
+                    "expiresInDays must be between 1 and {MAX_KEY_LIFETIME_DAYS}"
+                )));
+            }
+            Ok(Some(now + chrono::Duration::days(days)))
+        }
+        (None, Some(at)) => {
+            if at <= now || at > latest {
+                return Err(AppError::BadRequest(format!(
+                    "expiresAt must be in the future and within {MAX_KEY_LIFETIME_DAYS} days"
+                )));
+            }
+            Ok(Some(at))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(days: Option<i64>, at: Option<DateTime<Utc>>) -> CreateApiKeyRequest {
+        CreateApiKeyRequest {
+            name: None,
+            expires_in_days: days,
+            expires_at: at,
+        }
+    }
+
+    #[test]
+    fn test_key_expiry_from_days_or_timestamp() {
+        let now = Utc::now();
+        assert_eq!(key_expiry(&request(None, None), now).unwrap(), None);
+        assert_eq!(
+            key_expiry(&request(Some(30), None), now).unwrap(),
+            Some(now + chrono::Duration::days(30))
+        );
+        let at = now + chrono::Duration::hours(6);
+        assert_eq!(key_expiry(&request(None, Some(at)), now).unwrap(), Some(at));
+
+        let payload: CreateApiKeyRequest =
+            serde_json::from_str(r#"{"name": "ci", "expiresInDays": 7}"#).unwrap();
+        assert_eq!(payload.expires_in_days, Some(7));
+    }
+
+    #[test]
+    fn test_key_listing_shows_usage_and_expiry() {
+        let last_used_at = "2026-02-08T07:20:00Z".parse::<DateTime<Utc>>().unwrap();
+        let item = ApiKeyItem::from(ApiKey {
+            id: "key_1".to_string(),
+            key_hash: "hash".to_string(),
+            key_prefix: "hld_pub_abc12345".to_string(),
+            owner_type: ApiKeyOwner::Publisher,
+            owner_id: "pub_1".to_string(),
+            name: Some("ci".to_string()),
+            scopes: vec!["signals:write".to_string()],
+            last_used_at: Some(last_used_at),
+            expires_at: None,
+            status: ApiKeyStatus::Active,
+            created_at: last_used_at,
+        });
+
+        let value = serde_json::to_value(item).unwrap();
+        assert_eq!(value["lastUsedAt"], "2026-02-08T07:20:00Z");
+        assert_eq!(value["expiresAt"], serde_json::Value::Null);
+        assert_eq!(value["scopes"], serde_json::json!(["signals:write"]));
+        assert!(value.get("keyHash").is_none());
+    }
+
+    #[test]
+    fn test_key_prefix_follows_owner() {
+        assert_eq!(key_prefix(&ApiKeyOwner::Publisher), "hld_pub_");
+        assert_eq!(key_prefix(&ApiKeyOwner::Subscriber), "hld_sub_");
+    }
+
+    #[test]
+    fn test_key_expiry_rejects_invalid_values() {
+        let now = Utc::now();
+        for payload in [
+            request(Some(0), None),
+            request(Some(MAX_KEY_LIFETIME_DAYS + 1), None),
+            request(None, Some(now - chrono::Duration::seconds(1))),
+            request(Some(7), Some(now + chrono::Duration::days(7))),
+        ] {
+            assert!(matches!(
+                key_expiry(&payload, now),
+                Err(AppError::BadRequest(_))
+            ));
+        }
+    }
+}
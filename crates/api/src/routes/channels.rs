@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     routing::{get, post},
     Extension, Json, Router,
 };
@@ -7,15 +7,19 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    error::{ApiError, ApiResult, AppError},
-    middleware::auth::AuthContext,
+    error::{ApiResult, AppError},
+    middleware::auth::{AuthContext, PublisherAuth, SubscriberAuth},
+    pagination::page_limit,
     state::{AppState, RequestId},
 };
-use db::models::{ChannelStatus, PricingTier};
+use core::types::RetryPolicy;
+use db::models::{AccountStatus, Channel, ChannelStatus, PricingTier, Publisher};
 
 pub fn router(state: AppState) -> Router {
     Router::new()
         .route("/v1/channels", post(create_channel).get(list_channels))
+        .route("/v1/channels/search", get(search_channels))
+        .route("/v1/channels/by-slug/{slug}", get(get_channel_by_slug))
         .route(
             "/v1/channels/{id}",
             get(get_channel)
@@ -23,6 +27,8 @@ pub fn router(state: AppState) -> Router {
                 .delete(delete_channel),
         )
         .route("/v1/channels/{id}/stats", get(channel_stats))
+        .route("/v1/channels/{id}/transfer", post(transfer_channel))
+        .route("/v1/publisher/channels", get(list_publisher_channels))
         .with_state(state)
 }
 
@@ -36,6 +42,7 @@ struct CreateChannelRequest {
     pricing_tier: Option<PricingTier>,
     price_cents: Option<i32>,
     is_public: Option<bool>,
+    retry_policy: Option<RetryPolicy>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -44,10 +51,17 @@ struct UpdateChannelRequest {
     display_name: Option<String>,
     description: Option<String>,
     category: Option<String>,
-    pricing_tier: Option<PricingTier>,
+    pricing_tier: Option<String>,
     price_cents: Option<i32>,
     is_public: Option<bool>,
-    status: Option<ChannelStatus>,
+    status: Option<String>,
+    /// `null` clears the channel's policy, going back to the tier default.
+    #[serde(default, deserialize_with = "nullable")]
+    retry_policy: Option<Option<RetryPolicy>>,
+    /// Reject repeated title+body signals within this many seconds; 0 turns it off.
+    dedup_window_secs: Option<i32>,
+    /// Where to POST a delivery receipt for each signal; empty clears it.
+    delivery_callback_url: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -74,6 +88,11 @@ struct ChannelDetailResponse {
     price_cents: i32,
     status: ChannelStatus,
     is_public: bool,
+    retry_policy: Option<serde_json::Value>,
+    dedup_window_secs: i32,
+    /// Only shown to the channel's owner.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delivery_callback_url: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -91,10 +110,41 @@ struct DeleteChannelResponse {
     status: ChannelStatus,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TransferChannelRequest {
+    to_publisher_id: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TransferChannelResponse {
+    id: String,
+    publisher_id: String,
+    updated_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct ChannelListResponse {
     items: Vec<ChannelListItem>,
+    next_cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchChannelsQuery {
+    #[serde(default)]
+    q: String,
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListChannelsQuery {
+    limit: Option<i64>,
+    cursor: Option<String>,
+    category: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -107,28 +157,95 @@ struct ChannelListItem {
     price_cents: i32,
 }
 
+impl From<db::models::Channel> for ChannelListItem {
+    fn from(channel: db::models::Channel) -> Self {
+        Self {
+            id: channel.id,
+            slug: channel.slug,
+            display_name: channel.display_name,
+            pricing_tier: channel.pricing_tier,
+            price_cents: channel.price_cents,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListPublisherChannelsQuery {
+    limit: Option<i64>,
+    cursor: Option<String>,
+    /// Only return channels in this status; deleted ones are hidden otherwise.
+    status: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PublisherChannelItem {
+    id: String,
+    slug: String,
+    display_name: String,
+    status: ChannelStatus,
+    is_public: bool,
+    pricing_tier: PricingTier,
+    price_cents: i32,
+    subscriber_count: i32,
+    signal_count: i32,
+    created_at: DateTime<Utc>,
+}
+
+impl From<Channel> for PublisherChannelItem {
+    fn from(channel: Channel) -> Self {
+        Self {
+            id: channel.id,
+            slug: channel.slug,
+            display_name: channel.display_name,
+            status: channel.status,
+            is_public: channel.is_public,
+            pricing_tier: channel.pricing_tier,
+            price_cents: channel.price_cents,
+            subscriber_count: channel.subscriber_count,
+            signal_count: channel.signal_count,
+            created_at: channel.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PublisherChannelListResponse {
+    items: Vec<PublisherChannelItem>,
+    next_cursor: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct ChannelStatsResponse {
     signal_count: i32,
     subscriber_count: i32,
     delivery_success_rate: f64,
+    undeliverable_count: i64,
 }
 
 async fn create_channel(
     State(state): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    PublisherAuth(publisher_id): PublisherAuth,
     Extension(request_id): Extension<RequestId>,
     Json(payload): Json<CreateChannelRequest>,
 ) -> ApiResult<Json<ChannelSummaryResponse>> {
-    let publisher_id = require_publisher(&auth, &request_id)?;
-
     if payload.slug.trim().is_empty() || payload.display_name.trim().is_empty() {
         return Err(
             AppError::BadRequest("slug and displayName required".to_string())
                 .with_request_id(&request_id.0),
         );
     }
+    check_slug(&payload.slug).map_err(|err| err.with_request_id(&request_id.0))?;
+
+    let retry_policy = payload
+        .retry_policy
+        .as_ref()
+        .map(retry_policy_json)
+        .transpose()
+        .map_err(|err| err.with_request_id(&request_id.0))?;
 
     let pricing_tier = payload.pricing_tier.unwrap_or(PricingTier::Free);
     let price_cents = payload.price_cents.unwrap_or(0);
@@ -138,7 +255,7 @@ async fn create_channel(
     let channel = db::queries::channels::create(
         &state.db,
         &id,
-        publisher_id,
+        &publisher_id,
         &payload.slug,
         &payload.display_name,
         payload.description.as_deref(),
@@ -146,9 +263,10 @@ async fn create_channel(
         pricing_tier,
         price_cents,
         is_public,
+        retry_policy.as_ref(),
     )
     .await
-    .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+    .map_err(|err| create_channel_error(err).with_request_id(&request_id.0))?;
 
     Ok(Json(ChannelSummaryResponse {
         id: channel.id,
@@ -163,29 +281,89 @@ async fn create_channel(
 
 async fn list_channels(
     State(state): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    SubscriberAuth(_): SubscriberAuth,
     Extension(request_id): Extension<RequestId>,
+    Query(query): Query<ListChannelsQuery>,
 ) -> ApiResult<Json<ChannelListResponse>> {
-    require_subscriber(&auth, &request_id)?;
+    let limit = page_limit(&state.settings, query.limit);
+    let channels = db::queries::channels::list_marketplace(
+        state.read_pool(),
+        query.category.as_deref(),
+        limit,
+        query.cursor.as_deref(),
+    )
+    .await
+    .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
 
-    let channels = db::queries::channels::list_marketplace(&state.db)
-        .await
-        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+    let next_cursor = channels.last().map(|channel| channel.id.clone());
 
     Ok(Json(ChannelListResponse {
+        items: channels.into_iter().map(ChannelListItem::from).collect(),
+        next_cursor,
+    }))
+}
+
+async fn list_publisher_channels(
+    State(state): State<AppState>,
+    PublisherAuth(publisher_id): PublisherAuth,
+    Extension(request_id): Extension<RequestId>,
+    Query(query): Query<ListPublisherChannelsQuery>,
+) -> ApiResult<Json<PublisherChannelListResponse>> {
+    let status = query
+        .status
+        .as_deref()
+        .map(parse_channel_status)
+        .transpose()
+        .map_err(|err| err.with_request_id(&request_id.0))?;
+    let limit = page_limit(&state.settings, query.limit);
+    let channels = db::queries::channels::list_by_publisher(
+        &state.db,
+        &publisher_id,
+        status,
+        limit,
+        query.cursor.as_deref(),
+    )
+    .await
+    .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+
+    let next_cursor = channels.last().map(|channel| channel.id.clone());
+
+    Ok(Json(PublisherChannelListResponse {
         items: channels
             .into_iter()
-            .map(|channel| ChannelListItem {
-                id: channel.id,
-                slug: channel.slug,
-                display_name: channel.display_name,
-                pricing_tier: channel.pricing_tier,
-                price_cents: channel.price_cents,
-            })
+            .map(PublisherChannelItem::from)
             .collect(),
+        next_cursor,
+    }))
+}
+
+async fn search_channels(
+    State(state): State<AppState>,
+    SubscriberAuth(_): SubscriberAuth,
+    Extension(request_id): Extension<RequestId>,
+    Query(query): Query<SearchChannelsQuery>,
+) -> ApiResult<Json<ChannelListResponse>> {
+    let q = search_term(&query.q).map_err(|err| err.with_request_id(&request_id.0))?;
+    let limit = page_limit(&state.settings, query.limit);
+    let channels = db::queries::channels::search(state.read_pool(), q, limit)
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+
+    Ok(Json(ChannelListResponse {
+        items: channels.into_iter().map(ChannelListItem::from).collect(),
+        next_cursor: None,
     }))
 }
 
+/// Trimmed search text; blank queries are rejected rather than matching everything.
+fn search_term(q: &str) -> Result<&str, AppError> {
+    let q = q.trim();
+    if q.is_empty() {
+        return Err(AppError::BadRequest("q required".to_string()));
+    }
+    Ok(q)
+}
+
 async fn get_channel(
     State(state): State<AppState>,
     Extension(auth): Extension<AuthContext>,
@@ -193,28 +371,60 @@ async fn get_channel(
     Path(id): Path<String>,
 ) -> ApiResult<Json<ChannelDetailResponse>> {
     let channel = db::queries::channels::get_by_id(&state.db, &id)
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+
+    channel_detail(channel, &auth)
+        .map(Json)
+        .map_err(|err| err.with_request_id(&request_id.0))
+}
+
+async fn get_channel_by_slug(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    Extension(request_id): Extension<RequestId>,
+    Path(slug): Path<String>,
+) -> ApiResult<Json<ChannelDetailResponse>> {
+    let channel = db::queries::channels::get_by_slug(&state.db, &slug)
         .await
         .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
-        .ok_or_else(|| {
-            AppError::NotFound("channel not found".to_string()).with_request_id(&request_id.0)
-        })?;
+        .filter(|channel| listed_for(channel, &auth));
+
+    channel_detail(channel, &auth)
+        .map(Json)
+        .map_err(|err| err.with_request_id(&request_id.0))
+}
+
+/// Whether a slug lookup by `auth` resolves to `channel`: its owner always
+/// finds it, everyone else only when the marketplace would list it.
+fn listed_for(channel: &Channel, auth: &AuthContext) -> bool {
+    is_owner(channel, auth)
+        || (channel.is_public && matches!(channel.status, ChannelStatus::Active))
+}
+
+fn is_owner(channel: &Channel, auth: &AuthContext) -> bool {
+    auth.owner_type == db::models::ApiKeyOwner::Publisher && channel.publisher_id == auth.owner_id
+}
+
+/// Detail view of a channel as `auth` may see it. Deleted channels, and
+/// private ones looked up by anyone but their owner, are reported missing.
+fn channel_detail(
+    channel: Option<Channel>,
+    auth: &AuthContext,
+) -> Result<ChannelDetailResponse, AppError> {
+    let not_found = || AppError::NotFound("channel not found".to_string());
+    let channel = channel.ok_or_else(not_found)?;
 
     if matches!(channel.status, ChannelStatus::Deleted) {
-        return Err(
-            AppError::NotFound("channel not found".to_string()).with_request_id(&request_id.0)
-        );
+        return Err(not_found());
     }
 
-    if !channel.is_public
-        && (auth.owner_type != db::models::ApiKeyOwner::Publisher
-            || channel.publisher_id != auth.owner_id)
-    {
-        return Err(
-            AppError::NotFound("channel not found".to_string()).with_request_id(&request_id.0)
-        );
+    let is_owner = is_owner(&channel, auth);
+    if !channel.is_public && !is_owner {
+        return Err(not_found());
     }
 
-    Ok(Json(ChannelDetailResponse {
+    Ok(ChannelDetailResponse {
         id: channel.id,
         slug: channel.slug,
         display_name: channel.display_name,
@@ -224,17 +434,45 @@ async fn get_channel(
         price_cents: channel.price_cents,
         status: channel.status,
         is_public: channel.is_public,
-    }))
+        retry_policy: channel.retry_policy,
+        dedup_window_secs: channel.dedup_window_secs,
+        delivery_callback_url: channel.delivery_callback_url.filter(|_| is_owner),
+    })
 }
 
 async fn update_channel(
     State(state): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    PublisherAuth(publisher_id): PublisherAuth,
     Extension(request_id): Extension<RequestId>,
     Path(id): Path<String>,
     Json(payload): Json<UpdateChannelRequest>,
 ) -> ApiResult<Json<UpdateChannelResponse>> {
-    let publisher_id = require_publisher(&auth, &request_id)?;
+    let pricing_tier = payload
+        .pricing_tier
+        .as_deref()
+        .map(parse_pricing_tier)
+        .transpose()
+        .map_err(|err| err.with_request_id(&request_id.0))?;
+    let status = payload
+        .status
+        .as_deref()
+        .map(parse_channel_status)
+        .transpose()
+        .map_err(|err| err.with_request_id(&request_id.0))?;
+    let retry_policy = payload
+        .retry_policy
+        .as_ref()
+        .map(|policy| policy.as_ref().map(retry_policy_json).transpose())
+        .transpose()
+        .map_err(|err| err.with_request_id(&request_id.0))?;
+    if let Some(window) = payload.dedup_window_secs {
+        check_dedup_window(window).map_err(|err| err.with_request_id(&request_id.0))?;
+    }
+    if let Some(url) = payload.delivery_callback_url.as_deref() {
+        check_callback_url(url, &state.settings.herald_env)
+            .await
+            .map_err(|err| err.with_request_id(&request_id.0))?;
+    }
 
     let channel = db::queries::channels::get_by_id(&state.db, &id)
         .await
@@ -255,10 +493,13 @@ async fn update_channel(
         payload.display_name.as_deref(),
         payload.description.as_deref(),
         payload.category.as_deref(),
-        payload.pricing_tier,
+        pricing_tier,
         payload.price_cents,
         payload.is_public,
-        payload.status,
+        status,
+        retry_policy.as_ref().map(Option::as_ref),
+        payload.dedup_window_secs,
+        payload.delivery_callback_url.as_deref(),
     )
     .await
     .map_err(|err| {
@@ -278,12 +519,10 @@ async fn update_channel(
 
 async fn delete_channel(
     State(state): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    PublisherAuth(publisher_id): PublisherAuth,
     Extension(request_id): Extension<RequestId>,
     Path(id): Path<String>,
 ) -> ApiResult<Json<DeleteChannelResponse>> {
-    let publisher_id = require_publisher(&auth, &request_id)?;
-
     let channel = db::queries::channels::get_by_id(&state.db, &id)
         .await
         .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
@@ -307,15 +546,81 @@ async fn delete_channel(
     }))
 }
 
+/// Hand a channel, with its signals and subscriptions, to another publisher.
+///
+/// API keys belong to publishers, not channels, so the old owner's keys stop
+/// working for this channel and the new owner pushes with their own.
+async fn transfer_channel(
+    State(state): State<AppState>,
+    PublisherAuth(publisher_id): PublisherAuth,
+    Extension(request_id): Extension<RequestId>,
+    Path(id): Path<String>,
+    Json(payload): Json<TransferChannelRequest>,
+) -> ApiResult<Json<TransferChannelResponse>> {
+    let channel = db::queries::channels::get_by_id(&state.db, &id)
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
+        .ok_or_else(|| {
+            AppError::NotFound("channel not found".to_string()).with_request_id(&request_id.0)
+        })?;
+    let target = db::queries::publishers::get_by_id(&state.db, &payload.to_publisher_id)
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+    check_transfer(&channel, &publisher_id, target.as_ref())
+        .map_err(|err| err.with_request_id(&request_id.0))?;
+
+    let (id, publisher_id, updated_at) =
+        db::queries::channels::transfer(&state.db, &id, &publisher_id, &payload.to_publisher_id)
+            .await
+            .map_err(|err| AppError::from(err).with_request_id(&request_id.0))?
+            .ok_or_else(|| {
+                AppError::Conflict("channel changed during transfer".to_string())
+                    .with_request_id(&request_id.0)
+            })?;
+
+    Ok(Json(TransferChannelResponse {
+        id,
+        publisher_id,
+        updated_at,
+    }))
+}
+
+/// Check a transfer request against the channel and the target publisher.
+fn check_transfer(
+    channel: &Channel,
+    publisher_id: &str,
+    target: Option<&Publisher>,
+) -> Result<(), AppError> {
+    if channel.publisher_id != publisher_id {
+        return Err(AppError::Forbidden("not channel owner".to_string()));
+    }
+    if matches!(channel.status, ChannelStatus::Deleted) {
+        return Err(AppError::Conflict(
+            "deleted channels cannot be transferred".to_string(),
+        ));
+    }
+    let target =
+        target.ok_or_else(|| AppError::BadRequest("target publisher not found".to_string()))?;
+    if target.id == publisher_id {
+        return Err(AppError::BadRequest(
+            "channel already belongs to this publisher".to_string(),
+        ));
+    }
+    if !matches!(target.status, AccountStatus::Active) {
+        return Err(AppError::BadRequest(
+            "target publisher is not active".to_string(),
+        ));
+    }
+    Ok(())
+}
+
 async fn channel_stats(
     State(state): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    PublisherAuth(publisher_id): PublisherAuth,
     Extension(request_id): Extension<RequestId>,
     Path(id): Path<String>,
 ) -> ApiResult<Json<ChannelStatsResponse>> {
-    let publisher_id = require_publisher(&auth, &request_id)?;
-
-    let channel = db::queries::channels::get_by_id(&state.db, &id)
+    let channel = db::queries::channels::get_by_id(state.read_pool(), &id)
         .await
         .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
         .ok_or_else(|| {
@@ -337,7 +642,7 @@ async fn channel_stats(
         "#,
     )
     .bind(&id)
-    .fetch_one(&state.db)
+    .fetch_one(state.read_pool())
     .await
     .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
 
@@ -347,35 +652,433 @@ async fn channel_stats(
         0.0
     };
 
+    let undeliverable_count =
+        db::queries::deliveries::count_undeliverable_by_channel(state.read_pool(), &id)
+            .await
+            .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+
     Ok(Json(ChannelStatsResponse {
         signal_count: channel.signal_count,
         subscriber_count: channel.subscriber_count,
         delivery_success_rate,
+        undeliverable_count,
     }))
 }
 
-fn require_publisher<'a>(
-    auth: &'a AuthContext,
-    request_id: &RequestId,
-) -> Result<&'a str, ApiError> {
-    match auth.owner_type {
-        db::models::ApiKeyOwner::Publisher => Ok(auth.owner_id.as_str()),
-        db::models::ApiKeyOwner::Subscriber => {
-            Err(AppError::Forbidden("publisher access required".to_string())
-                .with_request_id(&request_id.0))
-        }
+/// Accepted channel slug lengths.
+const SLUG_LEN: std::ops::RangeInclusive<usize> = 3..=64;
+
+/// Validate a channel slug: lowercase ASCII letters, digits, and hyphens.
+fn check_slug(slug: &str) -> Result<(), AppError> {
+    let valid_chars = slug
+        .bytes()
+        .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-');
+    if !valid_chars || !SLUG_LEN.contains(&slug.len()) {
+        return Err(AppError::BadRequest(format!(
+            "slug must be {}-{} characters of lowercase letters, digits, and hyphens",
+            SLUG_LEN.start(),
+            SLUG_LEN.end()
+        )));
     }
+    Ok(())
 }
 
-fn require_subscriber<'a>(
-    auth: &'a AuthContext,
-    request_id: &RequestId,
-) -> Result<&'a str, ApiError> {
-    match auth.owner_type {
-        db::models::ApiKeyOwner::Subscriber => Ok(auth.owner_id.as_str()),
-        db::models::ApiKeyOwner::Publisher => Err(AppError::Forbidden(
-            "subscriber access required".to_string(),
-        )
-        .with_request_id(&request_id.0)),
+/// Map a channel insert failure; the only unique column is the slug.
+fn create_channel_error(err: sqlx::Error) -> AppError {
+    match AppError::from(err) {
+        AppError::Conflict(_) => AppError::Conflict("slug already taken".to_string()),
+        other => other,
+    }
+}
+
+/// Longest accepted signal deduplication window.
+const MAX_DEDUP_WINDOW_SECS: i32 = 86_400;
+
+/// Validate a channel's signal deduplication window.
+fn check_dedup_window(window: i32) -> Result<(), AppError> {
+    if !(0..=MAX_DEDUP_WINDOW_SECS).contains(&window) {
+        return Err(AppError::BadRequest(format!(
+            "dedupWindowSecs must be between 0 and {MAX_DEDUP_WINDOW_SECS}"
+        )));
+    }
+    Ok(())
+}
+
+/// Delivery callbacks follow the same URL rules as subscriber webhooks; an
+/// empty URL clears the callback.
+async fn check_callback_url(url: &str, env: &str) -> Result<(), AppError> {
+    if url.is_empty() {
+        return Ok(());
+    }
+    super::webhooks::validate_webhook_url(url, env)
+        .await
+        .map_err(|err| AppError::BadRequest(err.replacen("webhook url", "deliveryCallbackUrl", 1)))
+}
+
+/// Deserialize a field whose explicit `null` means something: absent is
+/// `None`, `null` is `Some(None)`.
+fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Validate a channel retry policy and encode it for storage.
+fn retry_policy_json(policy: &RetryPolicy) -> Result<serde_json::Value, AppError> {
+    policy.validate().map_err(AppError::BadRequest)?;
+    serde_json::to_value(policy).map_err(|_| AppError::Internal)
+}
+
+/// Validate a pricing tier string before it reaches the `pricing_tier` enum cast.
+fn parse_pricing_tier(value: &str) -> Result<PricingTier, AppError> {
+    PricingTier::parse(value).ok_or_else(|| {
+        AppError::BadRequest(format!(
+            "invalid pricing tier (allowed: {})",
+            PricingTier::VALUES.join(", ")
+        ))
+    })
+}
+
+/// Validate a channel status string before it reaches the `channel_status` enum cast.
+fn parse_channel_status(value: &str) -> Result<ChannelStatus, AppError> {
+    ChannelStatus::parse(value).ok_or_else(|| {
+        AppError::BadRequest(format!(
+            "invalid channel status (allowed: {})",
+            ChannelStatus::VALUES.join(", ")
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode, response::IntoResponse};
+
+    #[test]
+    fn test_parse_pricing_tier_valid() {
+        assert!(matches!(parse_pricing_tier("pro"), Ok(PricingTier::Pro)));
+        assert!(matches!(
+            parse_channel_status("paused"),
+            Ok(ChannelStatus::Paused)
+        ));
+    }
+
+    #[test]
+    fn test_invalid_pricing_tier_is_bad_request() {
+        let err = parse_pricing_tier("platinum").unwrap_err();
+        match &err {
+            AppError::BadRequest(msg) => {
+                assert_eq!(msg, "invalid pricing tier (allowed: free, pro, enterprise)")
+            }
+            other => panic!("expected BadRequest, got {other:?}"),
+        }
+        let response = err.with_request_id("req_1").into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_slug_format_is_validated() {
+        assert!(check_slug("tech-news").is_ok());
+        assert!(check_slug("abc").is_ok());
+        assert!(check_slug(&"a".repeat(64)).is_ok());
+
+        for slug in [
+            "Tech-News",
+            "tech news",
+            "ab",
+            &"a".repeat(65),
+            "tech_news",
+            "café",
+        ] {
+            let err = check_slug(slug).unwrap_err();
+            assert!(matches!(err, AppError::BadRequest(_)), "{slug:?}");
+        }
+    }
+
+    #[test]
+    fn test_duplicate_slug_is_conflict() {
+        let err = create_channel_error(crate::error::database_error("23505"));
+        match &err {
+            AppError::Conflict(msg) => assert_eq!(msg, "slug already taken"),
+            other => panic!("expected Conflict, got {other:?}"),
+        }
+        let response = err.with_request_id("req_1").into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        assert!(matches!(
+            create_channel_error(sqlx::Error::RowNotFound),
+            AppError::Internal
+        ));
+    }
+
+    #[test]
+    fn test_dedup_window_bounds() {
+        assert!(check_dedup_window(0).is_ok());
+        assert!(check_dedup_window(MAX_DEDUP_WINDOW_SECS).is_ok());
+        assert!(matches!(
+            check_dedup_window(-1),
+            Err(AppError::BadRequest(_))
+        ));
+        assert!(matches!(
+            check_dedup_window(MAX_DEDUP_WINDOW_SECS + 1),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_callback_url_rules() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            assert!(check_callback_url("", "prod").await.is_ok());
+            assert!(check_callback_url("https://93.184.216.34/receipts", "prod")
+                .await
+                .is_ok());
+            assert!(check_callback_url("https://acme.io/receipts", "dev")
+                .await
+                .is_ok());
+            match check_callback_url("http://acme.io/receipts", "dev").await {
+                Err(AppError::BadRequest(message)) => {
+                    assert_eq!(message, "deliveryCallbackUrl must be https")
+                }
+                other => panic!("unexpected {other:?}"),
+            }
+            assert!(check_callback_url("https://localhost/receipts", "prod")
+                .await
+                .is_err());
+            assert!(check_callback_url("https://10.1.2.3/receipts", "prod")
+                .await
+                .is_err());
+        });
+    }
+
+    #[test]
+    fn test_retry_policy_update_distinguishes_null_from_absent() {
+        let update = |body| serde_json::from_value::<UpdateChannelRequest>(body).unwrap();
+
+        assert_eq!(update(serde_json::json!({})).retry_policy, None);
+        assert_eq!(
+            update(serde_json::json!({ "retryPolicy": null })).retry_policy,
+            Some(None)
+        );
+        assert_eq!(
+            update(serde_json::json!({
+                "retryPolicy": { "maxAttempts": 3, "baseDelaySecs": 30, "multiplier": 2.0 }
+            }))
+            .retry_policy,
+            Some(Some(RetryPolicy {
+                max_attempts: 3,
+                base_delay_secs: 30,
+                multiplier: 2.0,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_invalid_retry_policy_is_bad_request() {
+        let policy = RetryPolicy {
+            max_attempts: 0,
+            base_delay_secs: 5,
+            multiplier: 2.0,
+        };
+        assert!(matches!(
+            retry_policy_json(&policy),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_channel_status_is_bad_request() {
+        let response = parse_channel_status("archived")
+            .unwrap_err()
+            .with_request_id("req_2")
+            .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_list_channels_query_params_are_optional() {
+        let query: ListChannelsQuery = serde_json::from_str("{}").unwrap();
+        assert!(query.limit.is_none() && query.cursor.is_none() && query.category.is_none());
+
+        let query: ListChannelsQuery =
+            serde_json::from_str(r#"{"limit": 20, "cursor": "ch_001", "category": "crypto"}"#)
+                .unwrap();
+        assert_eq!(query.limit, Some(20));
+        assert_eq!(query.cursor.as_deref(), Some("ch_001"));
+        assert_eq!(query.category.as_deref(), Some("crypto"));
+    }
+
+    #[test]
+    fn test_search_term_rejects_blank_queries() {
+        assert!(matches!(search_term(""), Err(AppError::BadRequest(_))));
+        assert!(matches!(search_term("   \t"), Err(AppError::BadRequest(_))));
+        assert_eq!(search_term("  btc alerts ").unwrap(), "btc alerts");
+    }
+
+    fn make_channel(status: ChannelStatus) -> Channel {
+        Channel {
+            id: "ch_1".to_string(),
+            publisher_id: "pub_old".to_string(),
+            slug: "tech-news".to_string(),
+            display_name: "Tech News".to_string(),
+            description: None,
+            category: None,
+            pricing_tier: PricingTier::Free,
+            price_cents: 0,
+            status,
+            is_public: true,
+            retry_policy: None,
+            dedup_window_secs: 0,
+            delivery_callback_url: None,
+            signal_count: 0,
+            subscriber_count: 0,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_publisher_channel_item_includes_owner_fields() {
+        let query: ListPublisherChannelsQuery =
+            serde_json::from_value(serde_json::json!({ "status": "paused", "limit": 5 })).unwrap();
+        assert!(matches!(
+            parse_channel_status(query.status.as_deref().unwrap()),
+            Ok(ChannelStatus::Paused)
+        ));
+        assert_eq!(query.limit, Some(5));
+
+        let item = PublisherChannelItem::from(make_channel(ChannelStatus::Paused));
+        let json = serde_json::to_value(&item).unwrap();
+        assert_eq!(json["id"], "ch_1");
+        assert_eq!(json["isPublic"], true);
+        assert_eq!(json["subscriberCount"], 0);
+        assert_eq!(
+            json["status"],
+            serde_json::to_value(ChannelStatus::Paused).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_channel_detail_visibility() {
+        let auth = |owner_type: db::models::ApiKeyOwner, owner_id: &str| AuthContext {
+            owner_type,
+            owner_id: owner_id.to_string(),
+            tier: db::models::AccountTier::Free,
+            key_id: "key_1".to_string(),
+        };
+        let owner = auth(db::models::ApiKeyOwner::Publisher, "pub_old");
+        let other_publisher = auth(db::models::ApiKeyOwner::Publisher, "pub_other");
+        let subscriber = auth(db::models::ApiKeyOwner::Subscriber, "pub_old");
+
+        let mut channel = make_channel(ChannelStatus::Active);
+        channel.delivery_callback_url = Some("https://example.com/receipts".to_string());
+        let detail = channel_detail(Some(channel.clone()), &subscriber).unwrap();
+        assert_eq!(detail.slug, "tech-news");
+        assert!(detail.delivery_callback_url.is_none());
+        assert!(channel_detail(Some(channel.clone()), &owner)
+            .unwrap()
+            .delivery_callback_url
+            .is_some());
+
+        channel.is_public = false;
+        assert!(channel_detail(Some(channel.clone()), &owner).is_ok());
+        for viewer in [&other_publisher, &subscriber] {
+            assert!(matches!(
+                channel_detail(Some(channel.clone()), viewer),
+                Err(AppError::NotFound(_))
+            ));
+        }
+
+        let deleted = make_channel(ChannelStatus::Deleted);
+        assert!(matches!(
+            channel_detail(Some(deleted), &owner),
+            Err(AppError::NotFound(_))
+        ));
+        assert!(matches!(
+            channel_detail(None, &owner),
+            Err(AppError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_slug_lookup_follows_marketplace_listing() {
+        let auth = |owner_type: db::models::ApiKeyOwner, owner_id: &str| AuthContext {
+            owner_type,
+            owner_id: owner_id.to_string(),
+            tier: db::models::AccountTier::Free,
+            key_id: "key_1".to_string(),
+        };
+        let owner = auth(db::models::ApiKeyOwner::Publisher, "pub_old");
+        let subscriber = auth(db::models::ApiKeyOwner::Subscriber, "sr_1");
+
+        let active = make_channel(ChannelStatus::Active);
+        assert!(listed_for(&active, &subscriber));
+
+        let paused = make_channel(ChannelStatus::Paused);
+        assert!(!listed_for(&paused, &subscriber));
+        assert!(listed_for(&paused, &owner));
+
+        let mut private = make_channel(ChannelStatus::Active);
+        private.is_public = false;
+        assert!(!listed_for(&private, &subscriber));
+        assert!(listed_for(&private, &owner));
+    }
+
+    fn make_publisher(id: &str, status: AccountStatus) -> Publisher {
+        Publisher {
+            id: id.to_string(),
+            name: "New Owner".to_string(),
+            email: "owner@example.com".to_string(),
+            stripe_customer_id: None,
+            stripe_connect_id: None,
+            tier: db::models::AccountTier::Free,
+            status,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_transfer_to_active_publisher_is_allowed() {
+        let channel = make_channel(ChannelStatus::Active);
+        let target = make_publisher("pub_new", AccountStatus::Active);
+        assert!(check_transfer(&channel, "pub_old", Some(&target)).is_ok());
+    }
+
+    #[test]
+    fn test_transfer_rejections() {
+        let active = make_channel(ChannelStatus::Active);
+        let target = make_publisher("pub_new", AccountStatus::Active);
+        assert!(matches!(
+            check_transfer(&active, "pub_other", Some(&target)),
+            Err(AppError::Forbidden(_))
+        ));
+        assert!(matches!(
+            check_transfer(
+                &make_channel(ChannelStatus::Deleted),
+                "pub_old",
+                Some(&target)
+            ),
+            Err(AppError::Conflict(_))
+        ));
+        assert!(matches!(
+            check_transfer(&active, "pub_old", None),
+            Err(AppError::BadRequest(_))
+        ));
+        let suspended = make_publisher("pub_new", AccountStatus::Suspended);
+        assert!(matches!(
+            check_transfer(&active, "pub_old", Some(&suspended)),
+            Err(AppError::BadRequest(_))
+        ));
+        let same = make_publisher("pub_old", AccountStatus::Active);
+        assert!(matches!(
+            check_transfer(&active, "pub_old", Some(&same)),
+            Err(AppError::BadRequest(_))
+        ));
     }
 }
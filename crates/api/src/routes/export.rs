@@ -0,0 +1,313 @@
+//! Subscriber data export (`GET /v1/subscriber/export`).
+//!
+//! Bundles everything Herald holds for a subscriber into one JSON document.
+//! Secrets are never exported: the webhook signing secret is omitted, webhook
+//! tokens are reduced to a flag, and custom header values are dropped.
+
+use axum::{extract::State, routing::get, Extension, Json, Router};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::{
+    error::{ApiResult, AppError},
+    middleware::auth::SubscriberAuth,
+    state::{AppState, RequestId},
+};
+use db::models::{
+    AccountStatus, AccountTier, Delivery, DeliveryMode, DeliveryStatus, Subscriber, Subscription,
+    SubscriptionStatus, Webhook, WebhookStatus,
+};
+
+/// Deliveries included in an export, newest first.
+const EXPORT_DELIVERY_LIMIT: usize = 1000;
+
+pub fn router(state: AppState) -> Router {
+    Router::new()
+        .route("/v1/subscriber/export", get(export_subscriber_data))
+        .with_state(state)
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportBundle {
+    exported_at: DateTime<Utc>,
+    profile: ExportProfile,
+    webhooks: Vec<ExportWebhook>,
+    subscriptions: Vec<ExportSubscription>,
+    deliveries: Vec<ExportDelivery>,
+    /// True when older deliveries were left out to respect the export cap.
+    deliveries_truncated: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportProfile {
+    id: String,
+    name: String,
+    email: String,
+    tier: AccountTier,
+    status: AccountStatus,
+    delivery_mode: DeliveryMode,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportWebhook {
+    id: String,
+    name: String,
+    url: String,
+    has_token: bool,
+    header_names: Vec<String>,
+    status: WebhookStatus,
+    failure_count: i32,
+    last_success_at: Option<DateTime<Utc>>,
+    last_failure_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportSubscription {
+    id: String,
+    channel_id: String,
+    webhook_id: Option<String>,
+    emergency_webhook_id: Option<String>,
+    status: SubscriptionStatus,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportDelivery {
+    id: String,
+    signal_id: String,
+    subscription_id: String,
+    webhook_id: Option<String>,
+    delivery_mode: DeliveryMode,
+    attempt: i32,
+    status: DeliveryStatus,
+    status_code: Option<i32>,
+    error_message: Option<String>,
+    latency_ms: Option<i32>,
+    created_at: DateTime<Utc>,
+}
+
+impl ExportBundle {
+    /// Assemble the bundle. `deliveries` may hold one row past the cap, which
+    /// is how truncation is detected.
+    fn new(
+        subscriber: Subscriber,
+        webhooks: Vec<Webhook>,
+        subscriptions: Vec<Subscription>,
+        mut deliveries: Vec<Delivery>,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let deliveries_truncated = deliveries.len() > EXPORT_DELIVERY_LIMIT;
+        deliveries.truncate(EXPORT_DELIVERY_LIMIT);
+
+        Self {
+            exported_at: now,
+            profile: ExportProfile {
+                id: subscriber.id,
+                name: subscriber.name,
+                email: subscriber.email,
+                tier: subscriber.tier,
+                status: subscriber.status,
+                delivery_mode: subscriber.delivery_mode,
+                created_at: subscriber.created_at,
+            },
+            webhooks: webhooks
+                .into_iter()
+                .map(|webhook| ExportWebhook {
+                    has_token: webhook.token.is_some(),
+                    header_names: webhook
+                        .headers
+                        .as_ref()
+                        .and_then(|headers| headers.as_object())
+                        .map(|headers| headers.keys().cloned().collect())
+                        .unwrap_or_default(),
+                    id: webhook.id,
+                    name: webhook.name,
+                    url: webhook.url,
+                    status: webhook.status,
+                    failure_count: webhook.failure_count,
+                    last_success_at: webhook.last_success_at,
+                    last_failure_at: webhook.last_failure_at,
+                    created_at: webhook.created_at,
+                })
+                .collect(),
+            subscriptions: subscriptions
+                .into_iter()
+                .map(|subscription| ExportSubscription {
+                    id: subscription.id,
+                    channel_id: subscription.channel_id,
+                    webhook_id: subscription.webhook_id,
+                    emergency_webhook_id: subscription.emergency_webhook_id,
+                    status: subscription.status,
+                    created_at: subscription.created_at,
+                })
+                .collect(),
+            deliveries: deliveries
+                .into_iter()
+                .map(|delivery| ExportDelivery {
+                    id: delivery.id,
+                    signal_id: delivery.signal_id,
+                    subscription_id: delivery.subscription_id,
+                    webhook_id: delivery.webhook_id,
+                    delivery_mode: delivery.delivery_mode,
+                    attempt: delivery.attempt,
+                    status: delivery.status,
+                    status_code: delivery.status_code,
+                    error_message: delivery.error_message,
+                    latency_ms: delivery.latency_ms,
+                    created_at: delivery.created_at,
+                })
+                .collect(),
+            deliveries_truncated,
+        }
+    }
+}
+
+async fn export_subscriber_data(
+    State(state): State<AppState>,
+    SubscriberAuth(subscriber_id): SubscriberAuth,
+    Extension(request_id): Extension<RequestId>,
+) -> ApiResult<Json<ExportBundle>> {
+    let subscriber = db::queries::subscribers::get_by_id(state.read_pool(), &subscriber_id)
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
+        .ok_or_else(|| {
+            AppError::NotFound("subscriber not found".to_string()).with_request_id(&request_id.0)
+        })?;
+
+    let webhooks = db::queries::webhooks::list_by_subscriber(state.read_pool(), &subscriber_id)
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+
+    let subscriptions =
+        db::queries::subscriptions::list_by_subscriber(state.read_pool(), &subscriber_id)
+            .await
+            .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+
+    let deliveries = db::queries::deliveries::list_recent_by_subscriber(
+        state.read_pool(),
+        &subscriber_id,
+        EXPORT_DELIVERY_LIMIT as i64 + 1,
+    )
+    .await
+    .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+
+    Ok(Json(ExportBundle::new(
+        subscriber,
+        webhooks,
+        subscriptions,
+        deliveries,
+        Utc::now(),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn subscriber() -> Subscriber {
+        Subscriber {
+            id: "sr_001".to_string(),
+            name: "Alice".to_string(),
+            email: "alice@example.com".to_string(),
+            webhook_secret: "whsec_supersecret".to_string(),
+            previous_webhook_secret: Some("whsec_oldsupersecret".to_string()),
+            previous_webhook_secret_expires_at: None,
+            stripe_customer_id: None,
+            tier: AccountTier::Pro,
+            status: AccountStatus::Active,
+            delivery_mode: DeliveryMode::Webhook,
+            agent_last_connected_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn webhook() -> Webhook {
+        Webhook {
+            id: "wh_001".to_string(),
+            subscriber_id: "sr_001".to_string(),
+            url: "https://example.com/hook".to_string(),
+            name: "Primary".to_string(),
+            token: Some("tok_supersecret".to_string()),
+            headers: Some(json!({ "X-Api-Key": "key_supersecret" })),
+            payload_schema: None,
+            timeout_ms: 30_000,
+            max_in_flight: 10,
+            max_per_second: None,
+            signature_algorithm: db::models::SignatureAlgorithm::Sha256,
+            legacy_signature: false,
+            status: WebhookStatus::Active,
+            failure_count: 0,
+            last_success_at: None,
+            last_failure_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn delivery(id: usize) -> Delivery {
+        Delivery {
+            id: format!("del_{id}"),
+            signal_id: "sig_001".to_string(),
+            subscription_id: "sub_001".to_string(),
+            webhook_id: Some("wh_001".to_string()),
+            delivery_mode: DeliveryMode::Webhook,
+            attempt: 0,
+            status: DeliveryStatus::Success,
+            status_code: Some(200),
+            error_message: None,
+            response_body: None,
+            latency_ms: Some(42),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_export_includes_sections_and_omits_secrets() {
+        let bundle = ExportBundle::new(
+            subscriber(),
+            vec![webhook()],
+            Vec::new(),
+            vec![delivery(1)],
+            Utc::now(),
+        );
+        let json = serde_json::to_value(&bundle).unwrap();
+
+        for section in [
+            "exportedAt",
+            "profile",
+            "webhooks",
+            "subscriptions",
+            "deliveries",
+        ] {
+            assert!(json.get(section).is_some(), "missing {section}");
+        }
+        assert_eq!(json["profile"]["email"], "alice@example.com");
+        assert_eq!(json["webhooks"][0]["hasToken"], true);
+        assert_eq!(json["webhooks"][0]["headerNames"], json!(["X-Api-Key"]));
+        assert_eq!(json["deliveries"][0]["id"], "del_1");
+        assert_eq!(json["deliveriesTruncated"], false);
+
+        let text = json.to_string();
+        assert!(!text.contains("supersecret"), "secret leaked: {text}");
+    }
+
+    #[test]
+    fn test_export_caps_deliveries() {
+        let deliveries = (0..=EXPORT_DELIVERY_LIMIT).map(delivery).collect();
+        let bundle =
+            ExportBundle::new(subscriber(), Vec::new(), Vec::new(), deliveries, Utc::now());
+
+        assert_eq!(bundle.deliveries.len(), EXPORT_DELIVERY_LIMIT);
+        assert!(bundle.deliveries_truncated);
+    }
+}
@@ -1,24 +1,128 @@
-use axum::{routing::get, Json, Router};
+use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
 use serde::Serialize;
+use std::future::Future;
+use std::time::Duration;
+use tracing::warn;
 
 use crate::state::{AppState, METRICS};
 
+/// How long a readiness probe waits on each dependency.
+const READY_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
 #[derive(Serialize)]
 struct HealthResponse {
     status: &'static str,
 }
 
+#[derive(Debug, Serialize)]
+struct ReadyResponse {
+    status: &'static str,
+    checks: ReadyChecks,
+}
+
+#[derive(Debug, Serialize)]
+struct ReadyChecks {
+    database: &'static str,
+    redis: &'static str,
+}
+
 pub fn router(state: AppState) -> Router {
     Router::new()
-        .route("/health", get(health))
+        .route("/health", get(live))
+        .route("/health/live", get(live))
+        .route("/health/ready", get(ready))
         .route("/metrics", get(metrics))
         .with_state(state)
 }
 
-async fn health() -> Json<HealthResponse> {
+/// Liveness: the process is up and serving requests.
+async fn live() -> Json<HealthResponse> {
     Json(HealthResponse { status: "ok" })
 }
 
+/// Readiness: Postgres and Redis are both reachable.
+async fn ready(State(state): State<AppState>) -> (StatusCode, Json<ReadyResponse>) {
+    let database = check("database", async {
+        sqlx::query("SELECT 1").execute(&state.db).await?;
+        Ok::<_, anyhow::Error>(())
+    });
+    let redis = check("redis", async {
+        let mut conn = state.redis.get_multiplexed_async_connection().await?;
+        redis::cmd("PING")
+            .query_async::<_, String>(&mut conn)
+            .await?;
+        Ok::<_, anyhow::Error>(())
+    });
+    let (database, redis) = tokio::join!(database, redis);
+    let (status, body) = readiness(database, redis);
+    (status, Json(body))
+}
+
+/// Run one dependency check, logging why it failed.
+async fn check<F>(dependency: &'static str, probe: F) -> bool
+where
+    F: Future<Output = anyhow::Result<()>>,
+{
+    match tokio::time::timeout(READY_CHECK_TIMEOUT, probe).await {
+        Ok(Ok(())) => true,
+        Ok(Err(err)) => {
+            warn!(dependency, error = %err, "readiness check failed");
+            false
+        }
+        Err(_) => {
+            warn!(dependency, "readiness check timed out");
+            false
+        }
+    }
+}
+
+fn readiness(database: bool, redis: bool) -> (StatusCode, ReadyResponse) {
+    let label = |ok: bool| if ok { "ok" } else { "unavailable" };
+    let ready = database && redis;
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status,
+        ReadyResponse {
+            status: label(ready),
+            checks: ReadyChecks {
+                database: label(database),
+                redis: label(redis),
+            },
+        },
+    )
+}
+
 async fn metrics() -> String {
     METRICS.gather()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ready_when_all_dependencies_up() {
+        let (status, body) = readiness(true, true);
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            serde_json::to_value(body).unwrap(),
+            serde_json::json!({
+                "status": "ok",
+                "checks": {"database": "ok", "redis": "ok"},
+            })
+        );
+    }
+
+    #[test]
+    fn test_not_ready_reports_failing_dependency() {
+        let (status, body) = readiness(true, false);
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body.status, "unavailable");
+        assert_eq!(body.checks.database, "ok");
+        assert_eq!(body.checks.redis, "unavailable");
+    }
+}
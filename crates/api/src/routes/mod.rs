@@ -1,5 +1,8 @@
+pub mod accounts;
 pub mod admin;
+pub mod api_keys;
 pub mod channels;
+pub mod export;
 pub mod health;
 pub mod publisher;
 pub mod signals;
@@ -14,11 +17,14 @@ use crate::tunnel;
 pub fn v1_router(state: AppState) -> Router {
     Router::new()
         .merge(tunnel::router(state.clone()))
+        .merge(accounts::router(state.clone()))
         .merge(channels::router(state.clone()))
         .merge(signals::router(state.clone()))
         .merge(subscriptions::router(state.clone()))
+        .merge(export::router(state.clone()))
         .merge(webhooks::router(state.clone()))
         .merge(publisher::router(state.clone()))
+        .merge(api_keys::router(state.clone()))
         .merge(admin::router(state))
 }
 
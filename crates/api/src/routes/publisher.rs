@@ -1,27 +1,19 @@
-use axum::{
-    extract::{Path, State},
-    routing::{delete, get},
-    Extension, Json, Router,
-};
-use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use axum::{extract::State, routing::get, Extension, Json, Router};
+use serde::Serialize;
 
 use crate::{
-    error::{ApiError, ApiResult, AppError},
-    middleware::auth::AuthContext,
+    error::{ApiResult, AppError},
+    middleware::auth::PublisherAuth,
+    profile::{update_profile_error, UpdateProfileRequest},
     state::{AppState, RequestId},
 };
-use core::auth::{generate_api_key, PUBLISHER_PREFIX};
-use db::models::{ApiKeyOwner, ApiKeyStatus};
 
 pub fn router(state: AppState) -> Router {
     Router::new()
-        .route("/v1/publisher/me", get(get_publisher_profile))
         .route(
-            "/v1/publisher/api-keys",
-            get(list_api_keys).post(create_api_key),
+            "/v1/publisher/me",
+            get(get_publisher_profile).patch(update_publisher_profile),
         )
-        .route("/v1/publisher/api-keys/{id}", delete(revoke_api_key))
         .with_state(state)
 }
 
@@ -35,48 +27,12 @@ struct PublisherProfileResponse {
     status: db::models::AccountStatus,
 }
 
-#[derive(Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct ApiKeyItem {
-    id: String,
-    prefix: String,
-    name: Option<String>,
-    status: ApiKeyStatus,
-    created_at: DateTime<Utc>,
-}
-
-#[derive(Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct ListApiKeysResponse {
-    items: Vec<ApiKeyItem>,
-}
-
-#[derive(Debug, Deserialize)]
-struct CreateApiKeyRequest {
-    name: Option<String>,
-}
-
-#[derive(Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct CreateApiKeyResponse {
-    id: String,
-    key: String,
-    prefix: String,
-}
-
-#[derive(Debug, Serialize)]
-struct RevokeApiKeyResponse {
-    status: ApiKeyStatus,
-}
-
 async fn get_publisher_profile(
     State(state): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    PublisherAuth(publisher_id): PublisherAuth,
     Extension(request_id): Extension<RequestId>,
 ) -> ApiResult<Json<PublisherProfileResponse>> {
-    let publisher_id = require_publisher(&auth, &request_id)?;
-
-    let publisher = db::queries::publishers::get_by_id(&state.db, publisher_id)
+    let publisher = db::queries::publishers::get_by_id(&state.db, &publisher_id)
         .await
         .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
         .ok_or_else(|| {
@@ -92,89 +48,33 @@ async fn get_publisher_profile(
     }))
 }
 
-async fn list_api_keys(
-    State(state): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
-    Extension(request_id): Extension<RequestId>,
-) -> ApiResult<Json<ListApiKeysResponse>> {
-    let publisher_id = require_publisher(&auth, &request_id)?;
-
-    let keys =
-        db::queries::api_keys::list_by_owner(&state.db, ApiKeyOwner::Publisher, publisher_id)
-            .await
-            .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
-
-    Ok(Json(ListApiKeysResponse {
-        items: keys
-            .into_iter()
-            .map(|key| ApiKeyItem {
-                id: key.id,
-                prefix: key.key_prefix,
-                name: key.name,
-                status: key.status,
-                created_at: key.created_at,
-            })
-            .collect(),
-    }))
-}
-
-async fn create_api_key(
+async fn update_publisher_profile(
     State(state): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    PublisherAuth(publisher_id): PublisherAuth,
     Extension(request_id): Extension<RequestId>,
-    Json(payload): Json<CreateApiKeyRequest>,
-) -> ApiResult<Json<CreateApiKeyResponse>> {
-    let publisher_id = require_publisher(&auth, &request_id)?;
-
-    let (raw, hash, prefix) = generate_api_key(PUBLISHER_PREFIX);
-    let id = format!("key_{}", nanoid::nanoid!(12));
+    Json(payload): Json<UpdateProfileRequest>,
+) -> ApiResult<Json<PublisherProfileResponse>> {
+    payload
+        .validate()
+        .map_err(|err| err.with_request_id(&request_id.0))?;
 
-    db::queries::api_keys::create(
+    let publisher = db::queries::publishers::update_profile(
         &state.db,
-        &id,
-        &hash,
-        &prefix,
-        ApiKeyOwner::Publisher,
-        publisher_id,
-        payload.name.as_deref(),
-        &[],
+        &publisher_id,
+        payload.name(),
+        payload.email(),
     )
     .await
-    .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
-
-    Ok(Json(CreateApiKeyResponse {
-        id,
-        key: raw,
-        prefix,
-    }))
-}
+    .map_err(|err| update_profile_error(err).with_request_id(&request_id.0))?
+    .ok_or_else(|| {
+        AppError::NotFound("publisher not found".to_string()).with_request_id(&request_id.0)
+    })?;
 
-async fn revoke_api_key(
-    State(state): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
-    Extension(request_id): Extension<RequestId>,
-    Path(id): Path<String>,
-) -> ApiResult<Json<RevokeApiKeyResponse>> {
-    let _publisher_id = require_publisher(&auth, &request_id)?;
-
-    db::queries::api_keys::revoke(&state.db, &id)
-        .await
-        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
-
-    Ok(Json(RevokeApiKeyResponse {
-        status: ApiKeyStatus::Revoked,
+    Ok(Json(PublisherProfileResponse {
+        id: publisher.id,
+        name: publisher.name,
+        email: publisher.email,
+        tier: publisher.tier,
+        status: publisher.status,
     }))
 }
-
-fn require_publisher<'a>(
-    auth: &'a AuthContext,
-    request_id: &RequestId,
-) -> Result<&'a str, ApiError> {
-    match auth.owner_type {
-        ApiKeyOwner::Publisher => Ok(auth.owner_id.as_str()),
-        ApiKeyOwner::Subscriber => {
-            Err(AppError::Forbidden("publisher access required".to_string())
-                .with_request_id(&request_id.0))
-        }
-    }
-}
@@ -1,18 +1,30 @@
 use axum::{
     extract::{Path, Query, State},
-    routing::post,
+    http::HeaderMap,
+    routing::{delete, get, post},
     Extension, Json, Router,
 };
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use tracing::warn;
 
 use crate::{
-    error::{ApiError, ApiResult, AppError},
-    middleware::auth::AuthContext,
+    error::{ApiResult, AppError},
+    middleware::auth::{AuthContext, PublisherAuth},
+    pagination::page_limit,
     state::{AppState, RequestId, METRICS},
 };
-use core::types::DeliveryJob;
-use db::models::{ChannelStatus, SignalUrgency};
+use core::idempotency::{
+    check_replay, hash_request, signal_content_hash, validate_key, ReplayCheck,
+    IDEMPOTENCY_KEY_HEADER,
+};
+use core::limits::Limits;
+use core::quotas::{self, QuotaDecision, QuotaWindow};
+use core::receipts::{self, DeliveryReceipt, RECEIPT_RETRY_DELAYS};
+use db::models::{
+    deliverable_subscriptions, delivery_jobs, AccountTier, ChannelStatus, Delivery, DeliveryMode,
+    DeliveryStatus, IdempotencyKey, Signal, SignalStatus, SignalUrgency,
+};
 
 pub fn router(state: AppState) -> Router {
     Router::new()
@@ -20,25 +32,53 @@ pub fn router(state: AppState) -> Router {
             "/v1/channels/{id}/signals",
             post(push_signal).get(list_signals),
         )
+        .route(
+            "/v1/channels/{id}/signals/{signal_id}",
+            delete(delete_signal),
+        )
+        .route(
+            "/v1/channels/{id}/signals/{signal_id}/deliveries",
+            get(list_signal_deliveries),
+        )
         .with_state(state)
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct PushSignalRequest {
     title: String,
     body: String,
     urgency: Option<SignalUrgency>,
     metadata: Option<serde_json::Value>,
+    /// Only deliver to subscribers at or above this tier.
+    min_subscriber_tier: Option<AccountTier>,
+    /// Hold delivery until this time; past or missing means send now.
+    send_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct PushSignalResponse {
     id: String,
     channel_id: String,
     status: String,
     created_at: DateTime<Utc>,
+    /// Number of subscriptions the signal was queued for.
+    #[serde(default)]
+    enqueued_deliveries: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    scheduled_at: Option<DateTime<Utc>>,
+    /// Set when the channel's dedup window matched an earlier signal, whose
+    /// id is returned instead of creating a new one.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    deduplicated: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeleteSignalResponse {
+    id: String,
+    status: &'static str,
 }
 
 #[derive(Debug, Deserialize)]
@@ -64,20 +104,69 @@ struct ListSignalsResponse {
     next_cursor: Option<String>,
 }
 
+/// One delivery attempt as shown to the publisher. Subscriber response bodies
+/// and webhook ids stay private.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SignalDeliveryItem {
+    id: String,
+    subscription_id: String,
+    delivery_mode: DeliveryMode,
+    status: DeliveryStatus,
+    attempt: i32,
+    status_code: Option<i32>,
+    error_message: Option<String>,
+    latency_ms: Option<i32>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl From<Delivery> for SignalDeliveryItem {
+    fn from(delivery: Delivery) -> Self {
+        Self {
+            id: delivery.id,
+            subscription_id: delivery.subscription_id,
+            delivery_mode: delivery.delivery_mode,
+            status: delivery.status,
+            attempt: delivery.attempt,
+            status_code: delivery.status_code,
+            error_message: delivery.error_message,
+            latency_ms: delivery.latency_ms,
+            created_at: delivery.created_at,
+            updated_at: delivery.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ListSignalDeliveriesResponse {
+    items: Vec<SignalDeliveryItem>,
+    next_cursor: Option<String>,
+}
+
 async fn push_signal(
     State(state): State<AppState>,
+    PublisherAuth(publisher_id): PublisherAuth,
     Extension(auth): Extension<AuthContext>,
     Extension(request_id): Extension<RequestId>,
     Path(channel_id): Path<String>,
+    headers: HeaderMap,
     Json(payload): Json<PushSignalRequest>,
 ) -> ApiResult<Json<PushSignalResponse>> {
-    let publisher_id = require_publisher(&auth, &request_id)?;
-
     if payload.title.trim().is_empty() || payload.body.trim().is_empty() {
         return Err(AppError::BadRequest("title and body required".to_string())
             .with_request_id(&request_id.0));
     }
 
+    let limits = state.settings.tier_limits.for_tier(&(&auth.tier).into());
+    check_content_size(&payload.title, &payload.body, limits)
+        .map_err(|err| err.with_request_id(&request_id.0))?;
+    if let Some(metadata) = payload.metadata.as_ref() {
+        check_metadata_size(metadata, limits.max_metadata_bytes)
+            .map_err(|err| err.with_request_id(&request_id.0))?;
+    }
+
     let channel = db::queries::channels::get_by_id(&state.db, &channel_id)
         .await
         .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
@@ -96,25 +185,186 @@ async fn push_signal(
             .with_request_id(&request_id.0));
     }
 
+    let idempotency_key = headers
+        .get(IDEMPOTENCY_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string);
+    if let Some(key) = idempotency_key.as_deref() {
+        validate_key(key)
+            .map_err(|err| AppError::BadRequest(err).with_request_id(&request_id.0))?;
+    }
+    // Keys are scoped to the channel and honoured for the retention window.
+    let idempotency_not_before =
+        Utc::now() - chrono::Duration::hours(state.settings.idempotency_retention_hours);
+    let request_hash = hash_request(
+        &serde_json::to_vec(&payload)
+            .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?,
+    );
+
+    if let Some(key) = idempotency_key.as_deref() {
+        let existing =
+            db::queries::idempotency_keys::get(&state.db, &channel_id, key, idempotency_not_before)
+                .await
+                .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+        if let Some(existing) = existing {
+            return replay_response(existing, &request_hash, &request_id);
+        }
+    }
+
+    let scheduled_at = schedule_for(payload.send_at, Utc::now())
+        .map_err(|err| AppError::BadRequest(err).with_request_id(&request_id.0))?;
+
     let urgency = payload.urgency.unwrap_or(SignalUrgency::Normal);
     let metadata = payload.metadata.unwrap_or_else(|| serde_json::json!({}));
+
+    let mut tx = state
+        .db
+        .begin()
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+
+    // Checked under a lock held until commit, so two concurrent pushes of the
+    // same content can't both miss each other and both insert.
+    let content_hash = signal_content_hash(&payload.title, &payload.body);
+    if channel.dedup_window_secs > 0 {
+        db::queries::signals::lock_content_hash(&mut *tx, &channel_id, &content_hash)
+            .await
+            .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+        let since = Utc::now() - chrono::Duration::seconds(channel.dedup_window_secs.into());
+        let duplicate =
+            db::queries::signals::find_recent_by_hash(&mut *tx, &channel_id, &content_hash, since)
+                .await
+                .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+        if let Some(existing) = duplicate {
+            return Ok(Json(deduplicated_response(existing)));
+        }
+    }
+
+    // Only pushes that create a signal count: replays and duplicates are free.
+    let now = Utc::now();
+    match quotas::consume(&state.redis, &channel_id, limits, now).await {
+        Ok(QuotaDecision::Allowed) => {}
+        Ok(QuotaDecision::Exceeded {
+            window,
+            limit,
+            resets_at,
+        }) => {
+            warn!(channel_id = %channel_id, window = window.as_str(), limit, "signal quota exceeded");
+            return Err(
+                quota_exceeded(window, limit, resets_at, now).with_request_id(&request_id.0)
+            );
+        }
+        Err(err) => {
+            warn!(channel_id = %channel_id, error = %err, "signal quota check failed");
+            return Err(AppError::Internal.with_request_id(&request_id.0));
+        }
+    }
+    // Returned unless the signal commits: failed writes and a lost
+    // idempotency race create nothing.
+    let quota_refund = QuotaRefund {
+        redis: Some(state.redis.clone()),
+        channel_id: channel_id.clone(),
+        consumed_at: now,
+    };
+
     let id = format!("sig_{}", nanoid::nanoid!(12));
 
     let signal = db::queries::signals::create(
-        &state.db,
+        &mut *tx,
         &id,
         &channel_id,
         &payload.title,
         &payload.body,
         urgency.clone(),
         metadata,
+        payload.min_subscriber_tier,
+        scheduled_at,
+        &content_hash,
     )
     .await
     .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
 
-    db::queries::channels::increment_signal_count(&state.db, &channel_id, 1)
+    db::queries::channels::increment_signal_count(&mut *tx, &channel_id, 1)
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+
+    // Scheduled signals are fanned out by the worker once they come due.
+    let (deliverable, skipped) = if scheduled_at.is_some() {
+        (Vec::new(), 0)
+    } else {
+        let targets =
+            db::queries::subscriptions::list_active_targets_by_channel(&mut *tx, &channel_id)
+                .await
+                .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+        deliverable_subscriptions(
+            targets,
+            signal.min_subscriber_tier.as_ref(),
+            &signal.urgency,
+        )
+    };
+
+    if scheduled_at.is_none() {
+        db::queries::signals::set_expected_deliveries(
+            &mut *tx,
+            &signal.id,
+            deliverable.len() as i32,
+        )
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+    }
+
+    let response = PushSignalResponse {
+        id: signal.id.clone(),
+        channel_id: signal.channel_id.clone(),
+        status: if scheduled_at.is_some() {
+            "scheduled"
+        } else {
+            "active"
+        }
+        .to_string(),
+        created_at: signal.created_at,
+        enqueued_deliveries: deliverable.len(),
+        scheduled_at,
+        deduplicated: false,
+    };
+
+    if let Some(key) = idempotency_key.as_deref() {
+        let snapshot = serde_json::to_value(&response)
+            .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+        let inserted = db::queries::idempotency_keys::create(
+            &mut *tx,
+            key,
+            &channel_id,
+            &request_hash,
+            snapshot,
+            idempotency_not_before,
+        )
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+
+        if !inserted {
+            // A concurrent request under the same key committed first; drop our
+            // signal and answer with theirs.
+            let _ = tx.rollback().await;
+            let existing = db::queries::idempotency_keys::get(
+                &state.db,
+                &channel_id,
+                key,
+                idempotency_not_before,
+            )
+            .await
+            .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
+            .ok_or_else(|| AppError::Internal.with_request_id(&request_id.0))?;
+            return replay_response(existing, &request_hash, &request_id);
+        }
+    }
+
+    tx.commit()
         .await
         .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+    quota_refund.keep();
 
     let urgency_label = match urgency {
         SignalUrgency::Low => "low",
@@ -122,50 +372,168 @@ async fn push_signal(
         SignalUrgency::High => "high",
         SignalUrgency::Critical => "critical",
     };
-    METRICS.record_signal(&channel_id, urgency_label);
+    METRICS.record_signal(&channel.slug, urgency_label);
+    for _ in 0..skipped {
+        METRICS.record_delivery("skipped_tier");
+    }
 
-    let subs = db::queries::subscriptions::list_active_by_channel(&state.db, &channel_id)
-        .await
-        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+    // Nothing to deliver means nothing will ever settle; report the empty
+    // receipt now.
+    if scheduled_at.is_none() && deliverable.is_empty() {
+        if let Some(url) = channel.delivery_callback_url.clone() {
+            spawn_receipt(&state, url, DeliveryReceipt::new(&signal.id, 0, 0));
+        }
+    }
 
-    let queue = match urgency {
-        SignalUrgency::High | SignalUrgency::Critical => "delivery-high",
-        _ => "delivery-normal",
-    };
+    // The signal is already committed; a job that fails to enqueue is logged
+    // and settled as failed rather than failing the push for every other
+    // subscriber.
+    let queue = urgency.queue();
+    let priority = state
+        .settings
+        .delivery_priority
+        .for_signal(&(&urgency).into(), &(&channel.pricing_tier).into());
+    let mut unqueued = 0;
+    for job in delivery_jobs(&signal.id, priority, deliverable) {
+        let subscription_id = job.subscription_id.clone();
+        if let Err(err) = state.storage.push(queue, job).await {
+            warn!(
+                signal_id = %signal.id,
+                subscription_id = %subscription_id,
+                error = %err,
+                request_id = %request_id.0,
+                "failed to enqueue delivery job"
+            );
+            METRICS.record_delivery("enqueue_failed");
+            unqueued += 1;
+        }
+    }
+    if unqueued > 0 {
+        settle_unqueued(&state, &signal.id, unqueued).await;
+    }
+
+    Ok(Json(response))
+}
+
+/// A signal counted against the channel's quota that has not committed yet.
+/// Dropping it refunds the count; [`QuotaRefund::keep`] once the signal exists.
+struct QuotaRefund {
+    redis: Option<redis::Client>,
+    channel_id: String,
+    consumed_at: DateTime<Utc>,
+}
+
+impl QuotaRefund {
+    fn keep(mut self) {
+        self.redis = None;
+    }
+}
 
-    for sub in subs {
-        let job = DeliveryJob {
-            signal_id: signal.id.clone(),
-            subscription_id: sub.id,
-            webhook_id: sub.webhook_id,
-            attempt: 0,
+impl Drop for QuotaRefund {
+    fn drop(&mut self) {
+        let Some(redis) = self.redis.take() else {
+            return;
         };
+        let channel_id = std::mem::take(&mut self.channel_id);
+        let consumed_at = self.consumed_at;
+        tokio::spawn(async move {
+            if let Err(err) = quotas::refund(&redis, &channel_id, consumed_at).await {
+                warn!(channel_id = %channel_id, error = %err, "failed to refund signal quota");
+            }
+        });
+    }
+}
 
-        state
-            .storage
-            .push(queue, job)
-            .await
-            .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+/// Count jobs that never reached the queue as failed deliveries, sending the
+/// channel's delivery receipt if they were the last outstanding.
+async fn settle_unqueued(state: &AppState, signal_id: &str, unqueued: i32) {
+    let settlement =
+        match db::queries::signals::settle_deliveries(&state.db, signal_id, unqueued).await {
+            Ok(settlement) => settlement,
+            Err(err) => {
+                warn!(signal_id = %signal_id, error = %err, "failed to settle unqueued deliveries");
+                return;
+            }
+        };
+    let Some(settlement) = settlement else {
+        return;
+    };
+    if let Some(url) = settlement.callback_url.clone() {
+        spawn_receipt(state, url, settlement.receipt());
     }
+}
 
-    Ok(Json(PushSignalResponse {
-        id: signal.id,
-        channel_id: signal.channel_id,
-        status: "active".to_string(),
-        created_at: signal.created_at,
-    }))
+/// Send `receipt` to the channel's callback `url` in the background.
+fn spawn_receipt(state: &AppState, url: String, receipt: DeliveryReceipt) {
+    let client = state.http_client.clone();
+    let secret = state.settings.hmac_secret.clone();
+    tokio::spawn(async move {
+        receipts::send(&client, &url, &secret, &receipt, &RECEIPT_RETRY_DELAYS).await;
+    });
+}
+
+/// How far ahead a signal may be scheduled.
+const MAX_SCHEDULE_AHEAD_DAYS: i64 = 90;
+
+/// Resolve `sendAt` into a schedule time, or `None` to deliver immediately.
+fn schedule_for(
+    send_at: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Result<Option<DateTime<Utc>>, String> {
+    let Some(send_at) = send_at.filter(|send_at| *send_at > now) else {
+        return Ok(None);
+    };
+    if send_at > now + chrono::Duration::days(MAX_SCHEDULE_AHEAD_DAYS) {
+        return Err(format!(
+            "sendAt must be within {MAX_SCHEDULE_AHEAD_DAYS} days"
+        ));
+    }
+    Ok(Some(send_at))
+}
+
+/// Response pointing at the earlier signal a push was deduplicated against.
+fn deduplicated_response(existing: Signal) -> PushSignalResponse {
+    PushSignalResponse {
+        status: if matches!(existing.status, SignalStatus::Scheduled) {
+            "scheduled"
+        } else {
+            "active"
+        }
+        .to_string(),
+        id: existing.id,
+        channel_id: existing.channel_id,
+        created_at: existing.created_at,
+        enqueued_deliveries: 0,
+        scheduled_at: existing.scheduled_at,
+        deduplicated: true,
+    }
+}
+
+/// Answer a request whose idempotency key was already used.
+fn replay_response(
+    existing: IdempotencyKey,
+    request_hash: &str,
+    request_id: &RequestId,
+) -> ApiResult<Json<PushSignalResponse>> {
+    match check_replay(&existing.request_hash, request_hash) {
+        ReplayCheck::Replay => serde_json::from_value(existing.response_snapshot)
+            .map(Json)
+            .map_err(|_| AppError::Internal.with_request_id(&request_id.0)),
+        ReplayCheck::Conflict => Err(AppError::Conflict(
+            "idempotency key was already used with a different request body".to_string(),
+        )
+        .with_request_id(&request_id.0)),
+    }
 }
 
 async fn list_signals(
     State(state): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    PublisherAuth(publisher_id): PublisherAuth,
     Extension(request_id): Extension<RequestId>,
     Path(channel_id): Path<String>,
     Query(query): Query<ListSignalsQuery>,
 ) -> ApiResult<Json<ListSignalsResponse>> {
-    let publisher_id = require_publisher(&auth, &request_id)?;
-
-    let channel = db::queries::channels::get_by_id(&state.db, &channel_id)
+    let channel = db::queries::channels::get_by_id(state.read_pool(), &channel_id)
         .await
         .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
         .ok_or_else(|| {
@@ -178,9 +546,9 @@ async fn list_signals(
         );
     }
 
-    let limit = query.limit.unwrap_or(50).min(100);
+    let limit = page_limit(&state.settings, query.limit);
     let signals = db::queries::signals::list_by_channel(
-        &state.db,
+        state.read_pool(),
         &channel_id,
         limit,
         query.cursor.as_deref(),
@@ -204,15 +572,415 @@ async fn list_signals(
     }))
 }
 
-fn require_publisher<'a>(
-    auth: &'a AuthContext,
-    request_id: &RequestId,
-) -> Result<&'a str, ApiError> {
-    match auth.owner_type {
-        db::models::ApiKeyOwner::Publisher => Ok(auth.owner_id.as_str()),
-        db::models::ApiKeyOwner::Subscriber => {
-            Err(AppError::Forbidden("publisher access required".to_string())
-                .with_request_id(&request_id.0))
+/// Soft-delete a signal. Deleting it again returns 404.
+async fn delete_signal(
+    State(state): State<AppState>,
+    PublisherAuth(publisher_id): PublisherAuth,
+    Extension(request_id): Extension<RequestId>,
+    Path((channel_id, signal_id)): Path<(String, String)>,
+) -> ApiResult<Json<DeleteSignalResponse>> {
+    let channel = db::queries::channels::get_by_id(&state.db, &channel_id)
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
+        .ok_or_else(|| {
+            AppError::NotFound("channel not found".to_string()).with_request_id(&request_id.0)
+        })?;
+
+    if channel.publisher_id != publisher_id {
+        return Err(
+            AppError::Forbidden("not channel owner".to_string()).with_request_id(&request_id.0)
+        );
+    }
+
+    let signal = db::queries::signals::get_by_id(&state.db, &signal_id)
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
+        .filter(|signal| is_live_in_channel(signal, &channel_id))
+        .ok_or_else(|| {
+            AppError::NotFound("signal not found".to_string()).with_request_id(&request_id.0)
+        })?;
+
+    db::queries::signals::update_status(&state.db, &signal.id, SignalStatus::Deleted)
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+
+    Ok(Json(DeleteSignalResponse {
+        id: signal.id,
+        status: "deleted",
+    }))
+}
+
+/// Delivery attempts for one of the publisher's signals, newest first, one
+/// row per attempt so retries show up alongside the final outcome.
+async fn list_signal_deliveries(
+    State(state): State<AppState>,
+    PublisherAuth(publisher_id): PublisherAuth,
+    Extension(request_id): Extension<RequestId>,
+    Path((channel_id, signal_id)): Path<(String, String)>,
+    Query(query): Query<ListSignalsQuery>,
+) -> ApiResult<Json<ListSignalDeliveriesResponse>> {
+    let channel = db::queries::channels::get_by_id(state.read_pool(), &channel_id)
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
+        .ok_or_else(|| {
+            AppError::NotFound("channel not found".to_string()).with_request_id(&request_id.0)
+        })?;
+
+    if channel.publisher_id != publisher_id {
+        return Err(
+            AppError::Forbidden("not channel owner".to_string()).with_request_id(&request_id.0)
+        );
+    }
+
+    // Deleted signals keep their delivery history.
+    let signal = db::queries::signals::get_by_id(state.read_pool(), &signal_id)
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
+        .filter(|signal| signal.channel_id == channel_id)
+        .ok_or_else(|| {
+            AppError::NotFound("signal not found".to_string()).with_request_id(&request_id.0)
+        })?;
+
+    let limit = page_limit(&state.settings, query.limit);
+    let deliveries = db::queries::deliveries::list_by_signal_page(
+        state.read_pool(),
+        &signal.id,
+        limit,
+        query.cursor.as_deref(),
+    )
+    .await
+    .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+
+    let next_cursor = deliveries.last().map(|delivery| delivery.id.clone());
+
+    Ok(Json(ListSignalDeliveriesResponse {
+        items: deliveries
+            .into_iter()
+            .map(SignalDeliveryItem::from)
+            .collect(),
+        next_cursor,
+    }))
+}
+
+/// The 429 for a push over the channel's `window` quota of `limit` signals.
+fn quota_exceeded(
+    window: QuotaWindow,
+    limit: u64,
+    resets_at: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> AppError {
+    AppError::QuotaExceeded {
+        message: format!(
+            "{} signal quota of {limit} reached for this channel; resets at {}",
+            window.as_str(),
+            resets_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+        ),
+        retry_after_secs: (resets_at - now).num_seconds().max(1) as u64,
+    }
+}
+
+/// Reject a title or body over the tier's limit, naming the field.
+fn check_content_size(title: &str, body: &str, limits: &Limits) -> Result<(), AppError> {
+    let title_chars = title.chars().count();
+    if title_chars > limits.max_title_chars {
+        return Err(AppError::BadRequest(format!(
+            "title is {title_chars} characters; the limit for this tier is {}",
+            limits.max_title_chars
+        )));
+    }
+    if body.len() > limits.max_body_bytes {
+        return Err(AppError::BadRequest(format!(
+            "body is {} bytes; the limit for this tier is {}",
+            body.len(),
+            limits.max_body_bytes
+        )));
+    }
+    Ok(())
+}
+
+/// Reject signal metadata whose JSON encoding exceeds the publisher's tier limit.
+fn check_metadata_size(metadata: &serde_json::Value, max_bytes: usize) -> Result<(), AppError> {
+    let size = serde_json::to_vec(metadata)
+        .map_err(|_| AppError::Internal)?
+        .len();
+    if size > max_bytes {
+        return Err(AppError::BadRequest(format!(
+            "metadata is {size} bytes; the limit for this tier is {max_bytes}"
+        )));
+    }
+    Ok(())
+}
+
+fn is_live_in_channel(signal: &Signal, channel_id: &str) -> bool {
+    signal.channel_id == channel_id && !matches!(signal.status, SignalStatus::Deleted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::limits::TierLimits;
+    use db::models::{Subscription, SubscriptionStatus, SubscriptionTarget};
+
+    fn target(id: &str, status: SubscriptionStatus, tier: AccountTier) -> SubscriptionTarget {
+        SubscriptionTarget {
+            subscription: Subscription {
+                id: id.to_string(),
+                subscriber_id: format!("sr_{id}"),
+                channel_id: "ch_test".to_string(),
+                webhook_id: None,
+                emergency_webhook_id: None,
+                status,
+                min_urgency: SignalUrgency::Low,
+                stripe_subscription_id: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            },
+            subscriber_tier: tier,
+        }
+    }
+
+    #[test]
+    fn test_enqueued_count_matches_active_subscriptions() {
+        let targets = vec![
+            target("a", SubscriptionStatus::Active, AccountTier::Free),
+            target("b", SubscriptionStatus::Active, AccountTier::Pro),
+            target("c", SubscriptionStatus::Paused, AccountTier::Pro),
+        ];
+
+        let (deliverable, skipped) =
+            deliverable_subscriptions(targets, None, &SignalUrgency::Normal);
+        let ids: Vec<_> = deliverable.iter().map(|sub| sub.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b"]);
+        assert_eq!(skipped, 0);
+    }
+
+    #[test]
+    fn test_enqueued_count_excludes_tier_filtered() {
+        let targets = vec![
+            target("a", SubscriptionStatus::Active, AccountTier::Free),
+            target("b", SubscriptionStatus::Active, AccountTier::Enterprise),
+        ];
+
+        let (deliverable, skipped) =
+            deliverable_subscriptions(targets, Some(&AccountTier::Pro), &SignalUrgency::Normal);
+        assert_eq!(deliverable.len(), 1);
+        assert_eq!(deliverable[0].id, "b");
+        assert_eq!(skipped, 1);
+    }
+
+    #[test]
+    fn test_min_urgency_threshold_is_inclusive() {
+        let mut high_only = target("high_only", SubscriptionStatus::Active, AccountTier::Free);
+        high_only.subscription.min_urgency = SignalUrgency::High;
+        let mut critical_only = target(
+            "critical_only",
+            SubscriptionStatus::Active,
+            AccountTier::Free,
+        );
+        critical_only.subscription.min_urgency = SignalUrgency::Critical;
+        let everything = target("everything", SubscriptionStatus::Active, AccountTier::Free);
+
+        let (deliverable, skipped) = deliverable_subscriptions(
+            vec![high_only, critical_only, everything],
+            None,
+            &SignalUrgency::High,
+        );
+        let ids: Vec<_> = deliverable.iter().map(|sub| sub.id.as_str()).collect();
+        assert_eq!(ids, vec!["high_only", "everything"]);
+        assert_eq!(skipped, 0);
+    }
+
+    fn signal(channel_id: &str, status: SignalStatus) -> Signal {
+        Signal {
+            id: "sig_test".to_string(),
+            channel_id: channel_id.to_string(),
+            title: "t".to_string(),
+            body: "b".to_string(),
+            urgency: SignalUrgency::Normal,
+            metadata: serde_json::json!({}),
+            delivery_count: 0,
+            delivered_count: 0,
+            failed_count: 0,
+            status,
+            min_subscriber_tier: None,
+            scheduled_at: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_only_live_signals_in_channel_can_be_deleted() {
+        assert!(is_live_in_channel(
+            &signal("ch_a", SignalStatus::Active),
+            "ch_a"
+        ));
+        assert!(!is_live_in_channel(
+            &signal("ch_a", SignalStatus::Deleted),
+            "ch_a"
+        ));
+        assert!(!is_live_in_channel(
+            &signal("ch_b", SignalStatus::Active),
+            "ch_a"
+        ));
+    }
+
+    #[test]
+    fn test_deduplicated_response_points_at_existing_signal() {
+        let response = deduplicated_response(signal("ch_a", SignalStatus::Active));
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["id"], "sig_test");
+        assert_eq!(json["status"], "active");
+        assert_eq!(json["enqueuedDeliveries"], 0);
+        assert_eq!(json["deduplicated"], true);
+
+        let fresh = PushSignalResponse {
+            deduplicated: false,
+            ..response
+        };
+        let json = serde_json::to_value(&fresh).unwrap();
+        assert!(json.get("deduplicated").is_none());
+    }
+
+    #[test]
+    fn test_delete_response_uses_lowercase_status() {
+        let json = serde_json::to_value(DeleteSignalResponse {
+            id: "sig_1".to_string(),
+            status: "deleted",
+        })
+        .unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"id": "sig_1", "status": "deleted"})
+        );
+    }
+
+    #[test]
+    fn test_signal_delivery_item_hides_subscriber_details() {
+        let item = SignalDeliveryItem::from(Delivery {
+            id: "del_1".to_string(),
+            signal_id: "sig_1".to_string(),
+            subscription_id: "sub_1".to_string(),
+            webhook_id: Some("wh_1".to_string()),
+            delivery_mode: DeliveryMode::Webhook,
+            attempt: 2,
+            status: DeliveryStatus::Success,
+            status_code: Some(200),
+            error_message: None,
+            response_body: Some("internal detail".to_string()),
+            latency_ms: Some(42),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        });
+        let json = serde_json::to_value(&item).unwrap();
+        assert_eq!(json["subscriptionId"], "sub_1");
+        assert_eq!(json["attempt"], 2);
+        assert_eq!(json["latencyMs"], 42);
+        assert!(json.get("responseBody").is_none());
+        assert!(json.get("webhookId").is_none());
+    }
+
+    #[test]
+    fn test_missing_or_past_send_at_delivers_now() {
+        let now = Utc::now();
+        assert_eq!(schedule_for(None, now), Ok(None));
+        assert_eq!(
+            schedule_for(Some(now - chrono::Duration::minutes(5)), now),
+            Ok(None)
+        );
+        assert_eq!(schedule_for(Some(now), now), Ok(None));
+    }
+
+    #[test]
+    fn test_future_send_at_is_scheduled() {
+        let now = Utc::now();
+        let send_at = now + chrono::Duration::days(3);
+        assert_eq!(schedule_for(Some(send_at), now), Ok(Some(send_at)));
+        let limit = now + chrono::Duration::days(MAX_SCHEDULE_AHEAD_DAYS);
+        assert_eq!(schedule_for(Some(limit), now), Ok(Some(limit)));
+    }
+
+    #[test]
+    fn test_send_at_beyond_90_days_is_rejected() {
+        let now = Utc::now();
+        let send_at =
+            now + chrono::Duration::days(MAX_SCHEDULE_AHEAD_DAYS) + chrono::Duration::seconds(1);
+        assert!(schedule_for(Some(send_at), now).is_err());
+    }
+
+    #[test]
+    fn test_three_subscriber_channel_yields_three_jobs() {
+        let targets = vec![
+            target("sub_1", SubscriptionStatus::Active, AccountTier::Free),
+            target("sub_2", SubscriptionStatus::Active, AccountTier::Pro),
+            target("sub_3", SubscriptionStatus::Active, AccountTier::Enterprise),
+        ];
+        let (deliverable, _) = deliverable_subscriptions(targets, None, &SignalUrgency::Normal);
+        let jobs = delivery_jobs("sig_fanout", 100, deliverable);
+
+        assert_eq!(jobs.len(), 3);
+        assert!(jobs
+            .iter()
+            .all(|job| job.signal_id == "sig_fanout" && job.attempt == 0));
+        let ids: Vec<_> = jobs
+            .iter()
+            .map(|job| job.subscription_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["sub_1", "sub_2", "sub_3"]);
+        assert_eq!(SignalUrgency::Critical.queue(), "delivery-high");
+        assert_eq!(SignalUrgency::Low.queue(), "delivery-normal");
+    }
+
+    #[test]
+    fn test_metadata_size_limit() {
+        let metadata = serde_json::json!({ "note": "x".repeat(100) });
+        let size = serde_json::to_vec(&metadata).unwrap().len();
+
+        assert!(check_metadata_size(&metadata, size).is_ok());
+        assert!(matches!(
+            check_metadata_size(&metadata, size - 1),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_content_size_limit_names_field() {
+        let limits = TierLimits::default().free;
+
+        let title = "é".repeat(limits.max_title_chars);
+        assert!(check_content_size(&title, "body", &limits).is_ok());
+
+        let long_title = "t".repeat(limits.max_title_chars + 1);
+        assert!(matches!(
+            check_content_size(&long_title, "body", &limits),
+            Err(AppError::BadRequest(msg)) if msg.starts_with("title is")
+        ));
+
+        let long_body = "b".repeat(limits.max_body_bytes + 1);
+        assert!(matches!(
+            check_content_size("title", &long_body, &limits),
+            Err(AppError::BadRequest(msg)) if msg.starts_with("body is")
+        ));
+    }
+
+    #[test]
+    fn test_quota_exceeded_names_window_and_reset() {
+        let now = DateTime::parse_from_rfc3339("2026-10-17T23:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let resets_at = quotas::day_reset(now);
+        match quota_exceeded(QuotaWindow::Day, 100, resets_at, now) {
+            AppError::QuotaExceeded {
+                message,
+                retry_after_secs,
+            } => {
+                assert_eq!(
+                    message,
+                    "daily signal quota of 100 reached for this channel; \
+                     resets at 2026-10-18T00:00:00Z"
+                );
+                assert_eq!(retry_after_secs, 3600);
+            }
+            other => panic!("expected QuotaExceeded, got {other:?}"),
         }
     }
 }
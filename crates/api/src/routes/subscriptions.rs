@@ -1,16 +1,20 @@
 use axum::{
-    extract::{Path, State},
-    routing::{delete, get, post},
+    extract::{Path, Query, State},
+    routing::{get, patch, post},
     Extension, Json, Router,
 };
 use serde::{Deserialize, Serialize};
+use tracing::warn;
 
 use crate::{
-    error::{ApiError, ApiResult, AppError},
-    middleware::auth::AuthContext,
+    error::{ApiResult, AppError},
+    middleware::auth::SubscriberAuth,
+    pagination::page_limit,
+    profile::{update_profile_error, UpdateProfileRequest},
     state::{AppState, RequestId},
 };
-use db::models::{ApiKeyOwner, SubscriptionStatus};
+use core::auth::generate_webhook_secret;
+use db::models::{ChannelStatus, SignalUrgency, SubscribedChannel, SubscriptionStatus};
 
 pub fn router(state: AppState) -> Router {
     Router::new()
@@ -18,8 +22,21 @@ pub fn router(state: AppState) -> Router {
             "/v1/subscriptions",
             post(create_subscription).get(list_subscriptions),
         )
-        .route("/v1/subscriptions/{id}", delete(delete_subscription))
-        .route("/v1/subscriber/me", get(get_subscriber_profile))
+        .route(
+            "/v1/subscriptions/{id}",
+            patch(update_subscription).delete(delete_subscription),
+        )
+        .route("/v1/subscriptions/{id}/pause", post(pause_subscription))
+        .route("/v1/subscriptions/{id}/resume", post(resume_subscription))
+        .route(
+            "/v1/subscriber/me",
+            get(get_subscriber_profile).patch(update_subscriber_profile),
+        )
+        .route(
+            "/v1/subscriber/me/rotate-secret",
+            post(rotate_webhook_secret),
+        )
+        .route("/v1/subscriber/channels", get(list_subscribed_channels))
         .with_state(state)
 }
 
@@ -28,6 +45,16 @@ pub fn router(state: AppState) -> Router {
 struct CreateSubscriptionRequest {
     channel_id: String,
     webhook_id: Option<String>,
+    /// Webhook used instead of `webhook_id` for critical signals.
+    emergency_webhook_id: Option<String>,
+    /// Skip signals below this urgency; defaults to `Low` (everything).
+    min_urgency: Option<SignalUrgency>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateSubscriptionRequest {
+    min_urgency: Option<SignalUrgency>,
 }
 
 #[derive(Debug, Serialize)]
@@ -43,7 +70,9 @@ struct SubscriptionItem {
     id: String,
     channel_id: String,
     webhook_id: Option<String>,
+    emergency_webhook_id: Option<String>,
     status: SubscriptionStatus,
+    min_urgency: SignalUrgency,
 }
 
 #[derive(Debug, Serialize)]
@@ -52,6 +81,53 @@ struct ListSubscriptionsResponse {
     items: Vec<SubscriptionItem>,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListSubscribedChannelsQuery {
+    limit: Option<i64>,
+    cursor: Option<String>,
+    #[serde(default)]
+    include_canceled: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SubscribedChannelItem {
+    subscription_id: String,
+    subscription_status: SubscriptionStatus,
+    channel_id: String,
+    slug: String,
+    display_name: String,
+    channel_status: ChannelStatus,
+}
+
+impl From<SubscribedChannel> for SubscribedChannelItem {
+    fn from(row: SubscribedChannel) -> Self {
+        Self {
+            subscription_id: row.subscription_id,
+            subscription_status: row.subscription_status,
+            channel_id: row.channel_id,
+            slug: row.slug,
+            display_name: row.display_name,
+            channel_status: row.channel_status,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ListSubscribedChannelsResponse {
+    items: Vec<SubscribedChannelItem>,
+    next_cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SubscriptionStatusResponse {
+    id: String,
+    status: SubscriptionStatus,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct DeleteSubscriptionResponse {
@@ -67,16 +143,42 @@ struct SubscriberProfileResponse {
     email: String,
     tier: db::models::AccountTier,
     status: db::models::AccountStatus,
+    /// Whether an agent is connected to any API instance.
+    agent_connected: bool,
+    agent_last_connected_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl SubscriberProfileResponse {
+    fn new(subscriber: db::models::Subscriber, agent_connected: bool) -> Self {
+        Self {
+            id: subscriber.id,
+            name: subscriber.name,
+            email: subscriber.email,
+            tier: subscriber.tier,
+            status: subscriber.status,
+            agent_connected,
+            agent_last_connected_at: subscriber.agent_last_connected_at,
+        }
+    }
+}
+
+/// How long the replaced webhook secret stays valid after a rotation.
+const PREVIOUS_SECRET_GRACE_HOURS: i64 = 24;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RotateSecretResponse {
+    /// Only ever returned here; it cannot be read back later.
+    webhook_secret: String,
+    previous_secret_expires_at: chrono::DateTime<chrono::Utc>,
 }
 
 async fn create_subscription(
     State(state): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    SubscriberAuth(subscriber_id): SubscriberAuth,
     Extension(request_id): Extension<RequestId>,
     Json(payload): Json<CreateSubscriptionRequest>,
 ) -> ApiResult<Json<CreateSubscriptionResponse>> {
-    let subscriber_id = require_subscriber(&auth, &request_id)?;
-
     let channel = db::queries::channels::get_by_id(&state.db, &payload.channel_id)
         .await
         .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
@@ -93,7 +195,26 @@ async fn create_subscription(
             .with_request_id(&request_id.0));
     }
 
-    if let Some(webhook_id) = payload.webhook_id.as_deref() {
+    let publisher = db::queries::publishers::get_by_id(&state.db, &channel.publisher_id)
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
+        .ok_or_else(|| AppError::Internal.with_request_id(&request_id.0))?;
+    let max_fanout = state
+        .settings
+        .tier_limits
+        .for_tier(&(&publisher.tier).into())
+        .max_fanout;
+    if i64::from(channel.subscriber_count) >= max_fanout {
+        return Err(
+            AppError::Forbidden("channel has reached its subscriber limit".to_string())
+                .with_request_id(&request_id.0),
+        );
+    }
+
+    for webhook_id in [&payload.webhook_id, &payload.emergency_webhook_id]
+        .into_iter()
+        .flatten()
+    {
         let webhook = db::queries::webhooks::get_by_id(&state.db, webhook_id)
             .await
             .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
@@ -112,20 +233,14 @@ async fn create_subscription(
     let subscription = db::queries::subscriptions::create(
         &state.db,
         &id,
-        subscriber_id,
+        &subscriber_id,
         &payload.channel_id,
         payload.webhook_id.as_deref(),
+        payload.emergency_webhook_id.as_deref(),
+        payload.min_urgency.unwrap_or(SignalUrgency::Low),
     )
     .await
-    .map_err(|err| {
-        if let sqlx::Error::Database(db_err) = &err {
-            if db_err.code() == Some(std::borrow::Cow::Borrowed("23505")) {
-                return AppError::BadRequest("already subscribed".to_string())
-                    .with_request_id(&request_id.0);
-            }
-        }
-        AppError::Internal.with_request_id(&request_id.0)
-    })?;
+    .map_err(|err| create_subscription_error(err).with_request_id(&request_id.0))?;
 
     db::queries::channels::increment_subscriber_count(&state.db, &payload.channel_id, 1)
         .await
@@ -137,14 +252,21 @@ async fn create_subscription(
     }))
 }
 
+/// Map a subscription insert failure; the only unique key is the subscriber
+/// and channel pair.
+fn create_subscription_error(err: sqlx::Error) -> AppError {
+    match AppError::from(err) {
+        AppError::Conflict(_) => AppError::Conflict("already subscribed".to_string()),
+        other => other,
+    }
+}
+
 async fn list_subscriptions(
     State(state): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    SubscriberAuth(subscriber_id): SubscriberAuth,
     Extension(request_id): Extension<RequestId>,
 ) -> ApiResult<Json<ListSubscriptionsResponse>> {
-    let subscriber_id = require_subscriber(&auth, &request_id)?;
-
-    let subs = db::queries::subscriptions::list_by_subscriber(&state.db, subscriber_id)
+    let subs = db::queries::subscriptions::list_by_subscriber(&state.db, &subscriber_id)
         .await
         .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
 
@@ -155,20 +277,82 @@ async fn list_subscriptions(
                 id: sub.id,
                 channel_id: sub.channel_id,
                 webhook_id: sub.webhook_id,
+                emergency_webhook_id: sub.emergency_webhook_id,
                 status: sub.status,
+                min_urgency: sub.min_urgency,
             })
             .collect(),
     }))
 }
 
+async fn list_subscribed_channels(
+    State(state): State<AppState>,
+    SubscriberAuth(subscriber_id): SubscriberAuth,
+    Extension(request_id): Extension<RequestId>,
+    Query(query): Query<ListSubscribedChannelsQuery>,
+) -> ApiResult<Json<ListSubscribedChannelsResponse>> {
+    let limit = page_limit(&state.settings, query.limit);
+    let rows = db::queries::subscriptions::list_channels_by_subscriber(
+        state.read_pool(),
+        &subscriber_id,
+        query.include_canceled,
+        limit,
+        query.cursor.as_deref(),
+    )
+    .await
+    .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+
+    let next_cursor = rows.last().map(|row| row.subscription_id.clone());
+
+    Ok(Json(ListSubscribedChannelsResponse {
+        items: rows.into_iter().map(SubscribedChannelItem::from).collect(),
+        next_cursor,
+    }))
+}
+
+async fn update_subscription(
+    State(state): State<AppState>,
+    SubscriberAuth(subscriber_id): SubscriberAuth,
+    Extension(request_id): Extension<RequestId>,
+    Path(id): Path<String>,
+    Json(payload): Json<UpdateSubscriptionRequest>,
+) -> ApiResult<Json<SubscriptionItem>> {
+    let min_urgency = payload.min_urgency.ok_or_else(|| {
+        AppError::BadRequest("no fields to update".to_string()).with_request_id(&request_id.0)
+    })?;
+
+    let subscription = db::queries::subscriptions::get_by_id(&state.db, &id)
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
+        .ok_or_else(|| {
+            AppError::NotFound("subscription not found".to_string()).with_request_id(&request_id.0)
+        })?;
+
+    if subscription.subscriber_id != subscriber_id {
+        return Err(AppError::Forbidden("not subscription owner".to_string())
+            .with_request_id(&request_id.0));
+    }
+
+    let sub = db::queries::subscriptions::set_min_urgency(&state.db, &id, min_urgency)
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+
+    Ok(Json(SubscriptionItem {
+        id: sub.id,
+        channel_id: sub.channel_id,
+        webhook_id: sub.webhook_id,
+        emergency_webhook_id: sub.emergency_webhook_id,
+        status: sub.status,
+        min_urgency: sub.min_urgency,
+    }))
+}
+
 async fn delete_subscription(
     State(state): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    SubscriberAuth(subscriber_id): SubscriberAuth,
     Extension(request_id): Extension<RequestId>,
     Path(id): Path<String>,
 ) -> ApiResult<Json<DeleteSubscriptionResponse>> {
-    let subscriber_id = require_subscriber(&auth, &request_id)?;
-
     let subscription = db::queries::subscriptions::get_by_id(&state.db, &id)
         .await
         .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
@@ -195,38 +379,298 @@ async fn delete_subscription(
     }))
 }
 
+async fn pause_subscription(
+    State(state): State<AppState>,
+    SubscriberAuth(subscriber_id): SubscriberAuth,
+    Extension(request_id): Extension<RequestId>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<SubscriptionStatusResponse>> {
+    set_paused(&state, &subscriber_id, &request_id, id, true).await
+}
+
+async fn resume_subscription(
+    State(state): State<AppState>,
+    SubscriberAuth(subscriber_id): SubscriberAuth,
+    Extension(request_id): Extension<RequestId>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<SubscriptionStatusResponse>> {
+    set_paused(&state, &subscriber_id, &request_id, id, false).await
+}
+
+async fn set_paused(
+    state: &AppState,
+    subscriber_id: &str,
+    request_id: &RequestId,
+    id: String,
+    pause: bool,
+) -> ApiResult<Json<SubscriptionStatusResponse>> {
+    let subscription = db::queries::subscriptions::get_by_id(&state.db, &id)
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
+        .ok_or_else(|| {
+            AppError::NotFound("subscription not found".to_string()).with_request_id(&request_id.0)
+        })?;
+
+    if subscription.subscriber_id != subscriber_id {
+        return Err(AppError::Forbidden("not subscription owner".to_string())
+            .with_request_id(&request_id.0));
+    }
+
+    let status = if pause {
+        pause_status(&subscription.status)
+    } else {
+        let channel = db::queries::channels::get_by_id(&state.db, &subscription.channel_id)
+            .await
+            .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+        resume_status(
+            &subscription.status,
+            channel.as_ref().map(|channel| &channel.status),
+        )
+    }
+    .map_err(|err| err.with_request_id(&request_id.0))?;
+
+    db::queries::subscriptions::update_status(&state.db, &id, status.clone())
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+
+    Ok(Json(SubscriptionStatusResponse { id, status }))
+}
+
+/// Status after a pause request; canceled subscriptions stay canceled.
+fn pause_status(current: &SubscriptionStatus) -> Result<SubscriptionStatus, AppError> {
+    if matches!(current, SubscriptionStatus::Canceled) {
+        return Err(AppError::Conflict("subscription is canceled".to_string()));
+    }
+    Ok(SubscriptionStatus::Paused)
+}
+
+/// Status after a resume request. The channel (`None` if it no longer exists)
+/// must still be active.
+fn resume_status(
+    current: &SubscriptionStatus,
+    channel_status: Option<&ChannelStatus>,
+) -> Result<SubscriptionStatus, AppError> {
+    if matches!(current, SubscriptionStatus::Canceled) {
+        return Err(AppError::Conflict("subscription is canceled".to_string()));
+    }
+    if !matches!(channel_status, Some(ChannelStatus::Active)) {
+        return Err(AppError::Conflict("channel is not active".to_string()));
+    }
+    Ok(SubscriptionStatus::Active)
+}
+
 async fn get_subscriber_profile(
     State(state): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    SubscriberAuth(subscriber_id): SubscriberAuth,
     Extension(request_id): Extension<RequestId>,
 ) -> ApiResult<Json<SubscriberProfileResponse>> {
-    let subscriber_id = require_subscriber(&auth, &request_id)?;
-
-    let subscriber = db::queries::subscribers::get_by_id(&state.db, subscriber_id)
+    let subscriber = db::queries::subscribers::get_by_id(&state.db, &subscriber_id)
         .await
         .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
         .ok_or_else(|| {
             AppError::NotFound("subscriber not found".to_string()).with_request_id(&request_id.0)
         })?;
 
-    Ok(Json(SubscriberProfileResponse {
-        id: subscriber.id,
-        name: subscriber.name,
-        email: subscriber.email,
-        tier: subscriber.tier,
-        status: subscriber.status,
+    let agent_connected = agent_connected(&state, &subscriber_id).await;
+    Ok(Json(SubscriberProfileResponse::new(
+        subscriber,
+        agent_connected,
+    )))
+}
+
+async fn update_subscriber_profile(
+    State(state): State<AppState>,
+    SubscriberAuth(subscriber_id): SubscriberAuth,
+    Extension(request_id): Extension<RequestId>,
+    Json(payload): Json<UpdateProfileRequest>,
+) -> ApiResult<Json<SubscriberProfileResponse>> {
+    payload
+        .validate()
+        .map_err(|err| err.with_request_id(&request_id.0))?;
+
+    let subscriber = db::queries::subscribers::update_profile(
+        &state.db,
+        &subscriber_id,
+        payload.name(),
+        payload.email(),
+    )
+    .await
+    .map_err(|err| update_profile_error(err).with_request_id(&request_id.0))?
+    .ok_or_else(|| {
+        AppError::NotFound("subscriber not found".to_string()).with_request_id(&request_id.0)
+    })?;
+
+    let agent_connected = agent_connected(&state, &subscriber_id).await;
+    Ok(Json(SubscriberProfileResponse::new(
+        subscriber,
+        agent_connected,
+    )))
+}
+
+/// Whether an agent is connected here or, per its Redis presence, to another
+/// instance. A failed lookup reports only the local state.
+async fn agent_connected(state: &AppState, subscriber_id: &str) -> bool {
+    if state.tunnel_registry.get(subscriber_id).await.is_some() {
+        return true;
+    }
+    match core::presence::lookup(&state.redis, subscriber_id).await {
+        Ok(presence) => presence.is_some(),
+        Err(err) => {
+            warn!(subscriber_id = %subscriber_id, error = %err, "agent presence lookup failed");
+            false
+        }
+    }
+}
+
+async fn rotate_webhook_secret(
+    State(state): State<AppState>,
+    SubscriberAuth(subscriber_id): SubscriberAuth,
+    Extension(request_id): Extension<RequestId>,
+) -> ApiResult<Json<RotateSecretResponse>> {
+    let webhook_secret = generate_webhook_secret();
+    let previous_secret_expires_at =
+        chrono::Utc::now() + chrono::Duration::hours(PREVIOUS_SECRET_GRACE_HOURS);
+
+    let rotated = db::queries::subscribers::rotate_webhook_secret(
+        &state.db,
+        &subscriber_id,
+        &webhook_secret,
+        previous_secret_expires_at,
+    )
+    .await
+    .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+    if !rotated {
+        return Err(
+            AppError::NotFound("subscriber not found".to_string()).with_request_id(&request_id.0)
+        );
+    }
+
+    Ok(Json(RotateSecretResponse {
+        webhook_secret,
+        previous_secret_expires_at,
     }))
 }
 
-fn require_subscriber<'a>(
-    auth: &'a AuthContext,
-    request_id: &RequestId,
-) -> Result<&'a str, ApiError> {
-    match auth.owner_type {
-        ApiKeyOwner::Subscriber => Ok(auth.owner_id.as_str()),
-        ApiKeyOwner::Publisher => Err(AppError::Forbidden(
-            "subscriber access required".to_string(),
-        )
-        .with_request_id(&request_id.0)),
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode, response::IntoResponse};
+
+    #[test]
+    fn test_duplicate_subscription_is_conflict() {
+        let err = create_subscription_error(crate::error::database_error("23505"));
+        match &err {
+            AppError::Conflict(msg) => assert_eq!(msg, "already subscribed"),
+            other => panic!("expected Conflict, got {other:?}"),
+        }
+        let response = err.with_request_id("req_1").into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        assert!(matches!(
+            create_subscription_error(crate::error::database_error("23503")),
+            AppError::BadRequest(_)
+        ));
+    }
+
+    #[test]
+    fn test_profile_reports_agent_connection() {
+        let now = chrono::Utc::now();
+        let subscriber = db::models::Subscriber {
+            id: "sub_001".to_string(),
+            name: "Ops".to_string(),
+            email: "ops@example.com".to_string(),
+            webhook_secret: "whsec_x".to_string(),
+            previous_webhook_secret: None,
+            previous_webhook_secret_expires_at: None,
+            stripe_customer_id: None,
+            tier: db::models::AccountTier::Free,
+            status: db::models::AccountStatus::Active,
+            delivery_mode: db::models::DeliveryMode::Agent,
+            agent_last_connected_at: Some(now),
+            created_at: now,
+            updated_at: now,
+        };
+        let json = serde_json::to_value(SubscriberProfileResponse::new(subscriber, true)).unwrap();
+        assert_eq!(json["agentConnected"], true);
+        assert!(json["agentLastConnectedAt"].is_string());
+        assert!(json.get("webhookSecret").is_none());
+    }
+
+    #[test]
+    fn test_create_subscription_without_webhook_for_tunnel_only() {
+        let payload: CreateSubscriptionRequest =
+            serde_json::from_str(r#"{"channelId":"ch_001"}"#).unwrap();
+        assert_eq!(payload.channel_id, "ch_001");
+        assert!(payload.webhook_id.is_none());
+        assert!(payload.emergency_webhook_id.is_none());
+    }
+
+    #[test]
+    fn test_subscribed_channel_item_includes_channel_details() {
+        let row = SubscribedChannel {
+            subscription_id: "sub_001".to_string(),
+            subscription_status: SubscriptionStatus::Active,
+            channel_id: "ch_001".to_string(),
+            slug: "btc-alerts".to_string(),
+            display_name: "BTC Alerts".to_string(),
+            channel_status: ChannelStatus::Active,
+            subscribed_at: chrono::Utc::now(),
+        };
+
+        let json = serde_json::to_value(SubscribedChannelItem::from(row)).unwrap();
+        assert_eq!(json["subscriptionId"], "sub_001");
+        assert_eq!(json["subscriptionStatus"], "Active");
+        assert_eq!(json["channelId"], "ch_001");
+        assert_eq!(json["slug"], "btc-alerts");
+        assert_eq!(json["displayName"], "BTC Alerts");
+        assert_eq!(json["channelStatus"], "Active");
+    }
+
+    #[test]
+    fn test_canceled_excluded_by_default() {
+        let query: ListSubscribedChannelsQuery = serde_json::from_str("{}").unwrap();
+        assert!(!query.include_canceled);
+
+        let query: ListSubscribedChannelsQuery =
+            serde_json::from_str(r#"{"includeCanceled": true}"#).unwrap();
+        assert!(query.include_canceled);
+    }
+
+    #[test]
+    fn test_pause_and_resume_toggle_status() {
+        assert!(matches!(
+            pause_status(&SubscriptionStatus::Active),
+            Ok(SubscriptionStatus::Paused)
+        ));
+        assert!(matches!(
+            resume_status(&SubscriptionStatus::Paused, Some(&ChannelStatus::Active)),
+            Ok(SubscriptionStatus::Active)
+        ));
+    }
+
+    #[test]
+    fn test_resume_requires_active_channel() {
+        for channel_status in [
+            Some(&ChannelStatus::Paused),
+            Some(&ChannelStatus::Deleted),
+            None,
+        ] {
+            assert!(matches!(
+                resume_status(&SubscriptionStatus::Paused, channel_status),
+                Err(AppError::Conflict(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_canceled_subscription_cannot_be_paused_or_resumed() {
+        assert!(matches!(
+            pause_status(&SubscriptionStatus::Canceled),
+            Err(AppError::Conflict(_))
+        ));
+        assert!(matches!(
+            resume_status(&SubscriptionStatus::Canceled, Some(&ChannelStatus::Active)),
+            Err(AppError::Conflict(_))
+        ));
     }
 }
@@ -7,11 +7,19 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    error::{ApiError, ApiResult, AppError},
-    middleware::auth::AuthContext,
+    error::{ApiResult, AppError},
+    middleware::auth::{AuthContext, SubscriberAuth},
+    pagination::page_limit,
     state::{AppState, RequestId},
 };
-use db::models::{ApiKeyOwner, DeliveryStatus, WebhookStatus};
+use core::auth::SIGNATURE_ALGORITHM_HEADER;
+use core::payload::{build_payload, PayloadChannel, PayloadSignal};
+use core::types::{
+    SignalUrgency, Webhook, GLOBAL_SIGNATURE_HEADER, PAYLOAD_SCHEMA_VERSION, PAYLOAD_VERSION_HEADER,
+};
+use db::models::{Delivery, DeliveryMode, DeliveryStatus, SignatureAlgorithm, WebhookStatus};
+use serde_json::json;
+use std::time::Instant;
 
 pub fn router(state: AppState) -> Router {
     Router::new()
@@ -21,6 +29,8 @@ pub fn router(state: AppState) -> Router {
             patch(update_webhook).delete(delete_webhook),
         )
         .route("/v1/webhooks/{id}/deliveries", get(list_deliveries))
+        .route("/v1/webhooks/{id}/test", post(test_webhook))
+        .route("/v1/webhooks/{id}/validate-schema", post(validate_schema))
         .with_state(state)
 }
 
@@ -30,6 +40,16 @@ struct CreateWebhookRequest {
     name: String,
     url: String,
     token: Option<String>,
+    /// Extra headers sent with each delivery, e.g. a gateway key.
+    headers: Option<serde_json::Value>,
+    /// Delivery request timeout; defaults to 30s.
+    timeout_ms: Option<i32>,
+    /// Concurrent deliveries allowed to this endpoint; defaults to 10.
+    max_in_flight: Option<i32>,
+    /// Deliveries started per second; unlimited when omitted.
+    max_per_second: Option<i32>,
+    /// HMAC behind the `v1` signature; defaults to sha256.
+    signature_algorithm: Option<SignatureAlgorithm>,
 }
 
 #[derive(Debug, Serialize)]
@@ -45,6 +65,11 @@ struct WebhookItem {
     id: String,
     name: String,
     url: String,
+    timeout_ms: i32,
+    max_in_flight: i32,
+    max_per_second: Option<i32>,
+    signature_algorithm: SignatureAlgorithm,
+    legacy_signature: bool,
     status: WebhookStatus,
 }
 
@@ -59,6 +84,13 @@ struct ListWebhooksResponse {
 struct UpdateWebhookRequest {
     name: Option<String>,
     url: Option<String>,
+    headers: Option<serde_json::Value>,
+    timeout_ms: Option<i32>,
+    max_in_flight: Option<i32>,
+    max_per_second: Option<i32>,
+    signature_algorithm: Option<SignatureAlgorithm>,
+    /// `false` moves an endpoint off the legacy `sha256=` signature.
+    legacy_signature: Option<bool>,
 }
 
 #[derive(Debug, Serialize)]
@@ -81,16 +113,40 @@ struct DeleteWebhookResponse {
 struct ListDeliveriesQuery {
     limit: Option<i64>,
     cursor: Option<String>,
+    /// Only return deliveries in this status, e.g. `failed`.
+    status: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct DeliveryItem {
     id: String,
+    delivery_mode: DeliveryMode,
     status: DeliveryStatus,
     attempt: i32,
     status_code: Option<i32>,
+    error_message: Option<String>,
     latency_ms: Option<i32>,
+    response_body: Option<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl From<Delivery> for DeliveryItem {
+    fn from(delivery: Delivery) -> Self {
+        Self {
+            id: delivery.id,
+            delivery_mode: delivery.delivery_mode,
+            status: delivery.status,
+            attempt: delivery.attempt,
+            status_code: delivery.status_code,
+            error_message: delivery.error_message,
+            latency_ms: delivery.latency_ms,
+            response_body: delivery.response_body,
+            created_at: delivery.created_at,
+            updated_at: delivery.updated_at,
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -100,28 +156,92 @@ struct ListDeliveriesResponse {
     next_cursor: Option<String>,
 }
 
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TestWebhookResponse {
+    success: bool,
+    status_code: Option<u16>,
+    latency_ms: i64,
+    error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ValidateSchemaRequest {
+    schema: serde_json::Value,
+    /// Keep the schema so real deliveries are checked against it too.
+    #[serde(default)]
+    store: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ValidateSchemaResponse {
+    valid: bool,
+    violations: Vec<String>,
+    stored: bool,
+}
+
 async fn create_webhook(
     State(state): State<AppState>,
+    SubscriberAuth(subscriber_id): SubscriberAuth,
     Extension(auth): Extension<AuthContext>,
     Extension(request_id): Extension<RequestId>,
     Json(payload): Json<CreateWebhookRequest>,
 ) -> ApiResult<Json<CreateWebhookResponse>> {
-    let subscriber_id = require_subscriber(&auth, &request_id)?;
-
     validate_webhook_url(&payload.url, &state.settings.herald_env)
+        .await
+        .map_err(|msg| AppError::BadRequest(msg).with_request_id(&request_id.0))?;
+    if let Some(headers) = payload.headers.as_ref() {
+        validate_custom_headers(headers, payload.token.is_some())
+            .map_err(|msg| AppError::BadRequest(msg).with_request_id(&request_id.0))?;
+    }
+    let timeout_ms = payload.timeout_ms.unwrap_or(Webhook::DEFAULT_TIMEOUT_MS);
+    Webhook::validate_timeout_ms(timeout_ms)
+        .map_err(|msg| AppError::BadRequest(msg).with_request_id(&request_id.0))?;
+    let max_in_flight = payload
+        .max_in_flight
+        .unwrap_or(Webhook::DEFAULT_MAX_IN_FLIGHT);
+    Webhook::validate_max_in_flight(max_in_flight)
         .map_err(|msg| AppError::BadRequest(msg).with_request_id(&request_id.0))?;
+    if let Some(max_per_second) = payload.max_per_second {
+        Webhook::validate_max_per_second(max_per_second)
+            .map_err(|msg| AppError::BadRequest(msg).with_request_id(&request_id.0))?;
+    }
+
+    let registered = db::queries::webhooks::count_by_subscriber(&state.db, &subscriber_id)
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+    let max_webhooks = state
+        .settings
+        .tier_limits
+        .for_tier(&(&auth.tier).into())
+        .max_webhooks;
+    if registered >= max_webhooks {
+        return Err(AppError::Forbidden(format!(
+            "webhook limit reached ({max_webhooks} for this tier)"
+        ))
+        .with_request_id(&request_id.0));
+    }
 
     let id = format!("wh_{}", nanoid::nanoid!(12));
     let webhook = db::queries::webhooks::create(
         &state.db,
         &id,
-        subscriber_id,
+        &subscriber_id,
         &payload.url,
         &payload.name,
         payload.token.as_deref(),
+        payload.headers.as_ref(),
+        timeout_ms,
+        max_in_flight,
+        payload.max_per_second,
+        payload
+            .signature_algorithm
+            .unwrap_or(SignatureAlgorithm::Sha256),
     )
     .await
-    .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+    .map_err(|err| AppError::from(err).with_request_id(&request_id.0))?;
 
     Ok(Json(CreateWebhookResponse {
         id: webhook.id,
@@ -131,12 +251,10 @@ async fn create_webhook(
 
 async fn list_webhooks(
     State(state): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    SubscriberAuth(subscriber_id): SubscriberAuth,
     Extension(request_id): Extension<RequestId>,
 ) -> ApiResult<Json<ListWebhooksResponse>> {
-    let subscriber_id = require_subscriber(&auth, &request_id)?;
-
-    let hooks = db::queries::webhooks::list_by_subscriber(&state.db, subscriber_id)
+    let hooks = db::queries::webhooks::list_by_subscriber(&state.db, &subscriber_id)
         .await
         .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
 
@@ -147,6 +265,11 @@ async fn list_webhooks(
                 id: hook.id,
                 name: hook.name,
                 url: hook.url,
+                timeout_ms: hook.timeout_ms,
+                max_in_flight: hook.max_in_flight,
+                max_per_second: hook.max_per_second,
+                signature_algorithm: hook.signature_algorithm,
+                legacy_signature: hook.legacy_signature,
                 status: hook.status,
             })
             .collect(),
@@ -155,13 +278,11 @@ async fn list_webhooks(
 
 async fn update_webhook(
     State(state): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    SubscriberAuth(subscriber_id): SubscriberAuth,
     Extension(request_id): Extension<RequestId>,
     Path(id): Path<String>,
     Json(payload): Json<UpdateWebhookRequest>,
 ) -> ApiResult<Json<UpdateWebhookResponse>> {
-    let subscriber_id = require_subscriber(&auth, &request_id)?;
-
     let webhook = db::queries::webhooks::get_by_id(&state.db, &id)
         .await
         .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
@@ -177,14 +298,47 @@ async fn update_webhook(
 
     if let Some(url) = payload.url.as_deref() {
         validate_webhook_url(url, &state.settings.herald_env)
+            .await
             .map_err(|msg| AppError::BadRequest(msg).with_request_id(&request_id.0))?;
     }
+    if let Some(headers) = payload.headers.as_ref() {
+        validate_custom_headers(headers, webhook.token.is_some())
+            .map_err(|msg| AppError::BadRequest(msg).with_request_id(&request_id.0))?;
+    }
+    if let Some(timeout_ms) = payload.timeout_ms {
+        Webhook::validate_timeout_ms(timeout_ms)
+            .map_err(|msg| AppError::BadRequest(msg).with_request_id(&request_id.0))?;
+    }
+    if let Some(max_in_flight) = payload.max_in_flight {
+        Webhook::validate_max_in_flight(max_in_flight)
+            .map_err(|msg| AppError::BadRequest(msg).with_request_id(&request_id.0))?;
+    }
+    if let Some(max_per_second) = payload.max_per_second {
+        Webhook::validate_max_per_second(max_per_second)
+            .map_err(|msg| AppError::BadRequest(msg).with_request_id(&request_id.0))?;
+    }
+    let legacy_signature = payload.legacy_signature.unwrap_or(webhook.legacy_signature);
+    let signature_algorithm = payload
+        .signature_algorithm
+        .unwrap_or(webhook.signature_algorithm);
+    if legacy_signature && signature_algorithm != SignatureAlgorithm::Sha256 {
+        return Err(AppError::BadRequest(
+            "the legacy signature is sha256 only; set legacySignature to false".to_string(),
+        )
+        .with_request_id(&request_id.0));
+    }
 
     let (id, status, updated_at) = db::queries::webhooks::update(
         &state.db,
         &id,
         payload.name.as_deref(),
         payload.url.as_deref(),
+        payload.headers.as_ref(),
+        payload.timeout_ms,
+        payload.max_in_flight,
+        payload.max_per_second,
+        payload.signature_algorithm,
+        payload.legacy_signature,
         None,
     )
     .await
@@ -205,12 +359,10 @@ async fn update_webhook(
 
 async fn delete_webhook(
     State(state): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    SubscriberAuth(subscriber_id): SubscriberAuth,
     Extension(request_id): Extension<RequestId>,
     Path(id): Path<String>,
 ) -> ApiResult<Json<DeleteWebhookResponse>> {
-    let subscriber_id = require_subscriber(&auth, &request_id)?;
-
     let webhook = db::queries::webhooks::get_by_id(&state.db, &id)
         .await
         .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
@@ -224,23 +376,32 @@ async fn delete_webhook(
         );
     }
 
-    let (id, status, _updated_at) =
-        db::queries::webhooks::update(&state.db, &id, None, None, Some(WebhookStatus::Disabled))
-            .await
-            .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+    let (id, status, _updated_at) = db::queries::webhooks::update(
+        &state.db,
+        &id,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        Some(WebhookStatus::Disabled),
+    )
+    .await
+    .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
 
     Ok(Json(DeleteWebhookResponse { id, status }))
 }
 
 async fn list_deliveries(
     State(state): State<AppState>,
-    Extension(auth): Extension<AuthContext>,
+    SubscriberAuth(subscriber_id): SubscriberAuth,
     Extension(request_id): Extension<RequestId>,
     Path(id): Path<String>,
     Query(query): Query<ListDeliveriesQuery>,
 ) -> ApiResult<Json<ListDeliveriesResponse>> {
-    let subscriber_id = require_subscriber(&auth, &request_id)?;
-
     let webhook = db::queries::webhooks::get_by_id(&state.db, &id)
         .await
         .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
@@ -254,43 +415,267 @@ async fn list_deliveries(
         );
     }
 
-    let limit = query.limit.unwrap_or(50).min(100);
-    let deliveries =
-        db::queries::deliveries::list_by_webhook(&state.db, &id, limit, query.cursor.as_deref())
-            .await
-            .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+    let status = query
+        .status
+        .as_deref()
+        .map(parse_delivery_status)
+        .transpose()
+        .map_err(|err| err.with_request_id(&request_id.0))?;
+
+    let limit = page_limit(&state.settings, query.limit);
+    let cursor = query.cursor.as_deref();
+    let deliveries = match status {
+        Some(status) => {
+            db::queries::deliveries::list_by_webhook_status(&state.db, &id, status, limit, cursor)
+                .await
+        }
+        None => db::queries::deliveries::list_by_webhook(&state.db, &id, limit, cursor).await,
+    }
+    .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
 
     let next_cursor = deliveries.last().map(|delivery| delivery.id.clone());
 
     Ok(Json(ListDeliveriesResponse {
-        items: deliveries
-            .into_iter()
-            .map(|delivery| DeliveryItem {
-                id: delivery.id,
-                status: delivery.status,
-                attempt: delivery.attempt,
-                status_code: delivery.status_code,
-                latency_ms: delivery.latency_ms,
-            })
-            .collect(),
+        items: deliveries.into_iter().map(DeliveryItem::from).collect(),
         next_cursor,
     }))
 }
 
-fn require_subscriber<'a>(
-    auth: &'a AuthContext,
-    request_id: &RequestId,
-) -> Result<&'a str, ApiError> {
-    match auth.owner_type {
-        ApiKeyOwner::Subscriber => Ok(auth.owner_id.as_str()),
-        ApiKeyOwner::Publisher => Err(AppError::Forbidden(
-            "subscriber access required".to_string(),
-        )
-        .with_request_id(&request_id.0)),
+/// Validate a delivery status filter before it reaches the `delivery_status` enum cast.
+fn parse_delivery_status(value: &str) -> Result<DeliveryStatus, AppError> {
+    DeliveryStatus::parse(value).ok_or_else(|| {
+        AppError::BadRequest(format!(
+            "invalid delivery status (allowed: {})",
+            DeliveryStatus::VALUES.join(", ")
+        ))
+    })
+}
+
+/// Send a signed synthetic signal to the webhook and report the outcome.
+///
+/// Nothing is recorded: no delivery row is created and the webhook's
+/// failure count is untouched.
+async fn test_webhook(
+    State(state): State<AppState>,
+    SubscriberAuth(subscriber_id): SubscriberAuth,
+    Extension(request_id): Extension<RequestId>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<TestWebhookResponse>> {
+    let webhook = db::queries::webhooks::get_by_id(&state.db, &id)
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
+        .ok_or_else(|| {
+            AppError::NotFound("webhook not found".to_string()).with_request_id(&request_id.0)
+        })?;
+
+    if webhook.subscriber_id != subscriber_id {
+        return Err(
+            AppError::Forbidden("not webhook owner".to_string()).with_request_id(&request_id.0)
+        );
     }
+
+    let subscriber = db::queries::subscribers::get_by_id(&state.db, &subscriber_id)
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
+        .ok_or_else(|| {
+            AppError::NotFound("subscriber not found".to_string()).with_request_id(&request_id.0)
+        })?;
+
+    let delivery_id = format!("del_test_{}", nanoid::nanoid!(12));
+    let payload = build_test_payload(&delivery_id, &webhook.id, Utc::now());
+    let global_secret = state
+        .settings
+        .global_signature
+        .then_some(state.settings.hmac_secret.as_str());
+    let req = test_request(
+        &state.http_client,
+        &webhook,
+        &subscriber.webhook_secret,
+        global_secret,
+        &delivery_id,
+        payload.to_string(),
+        Utc::now().timestamp(),
+    );
+
+    let start = Instant::now();
+    let result = req.send().await;
+    let latency_ms = start.elapsed().as_millis() as i64;
+
+    let response = match result {
+        Ok(resp) => {
+            let status = resp.status();
+            TestWebhookResponse {
+                success: status.is_success(),
+                status_code: Some(status.as_u16()),
+                latency_ms,
+                error: (!status.is_success()).then(|| format!("HTTP {}", status.as_u16())),
+            }
+        }
+        Err(err) => TestWebhookResponse {
+            success: false,
+            status_code: None,
+            latency_ms,
+            error: Some(err.to_string()),
+        },
+    };
+
+    Ok(Json(response))
+}
+
+/// The test send, signed and timed out as the worker sends real deliveries:
+/// the webhook's own timeout, and the platform signature when
+/// `global_secret` is set.
+fn test_request(
+    client: &reqwest::Client,
+    webhook: &db::models::Webhook,
+    secret: &str,
+    global_secret: Option<&str>,
+    delivery_id: &str,
+    body: String,
+    timestamp: i64,
+) -> reqwest::RequestBuilder {
+    let mut req = client
+        .post(&webhook.url)
+        .timeout(webhook.timeout())
+        .header("Content-Type", "application/json")
+        .header("X-Herald-Signature", webhook.sign(secret, timestamp, &body))
+        .header("X-Herald-Timestamp", timestamp.to_string())
+        .header("X-Herald-Delivery-Id", delivery_id)
+        .header(PAYLOAD_VERSION_HEADER, PAYLOAD_SCHEMA_VERSION.to_string());
+    if !webhook.legacy_signature {
+        req = req.header(
+            SIGNATURE_ALGORITHM_HEADER,
+            webhook.signature_algorithm.as_str(),
+        );
+    }
+    if let Some(global_secret) = global_secret {
+        req = req.header(
+            GLOBAL_SIGNATURE_HEADER,
+            webhook.sign(global_secret, timestamp, &body),
+        );
+    }
+
+    if let Some(token) = webhook.token.as_deref() {
+        req = req.header("Authorization", format!("Bearer {}", token));
+    }
+    if let Some(headers) = webhook.headers.as_ref().and_then(|h| h.as_object()) {
+        for (name, value) in headers {
+            if let Some(value) = value.as_str() {
+                req = req.header(name.as_str(), value);
+            }
+        }
+    }
+    req.body(body)
+}
+
+/// Check a sample delivery payload against a subscriber-supplied JSON Schema.
+///
+/// With `store: true` the schema is saved on the webhook; the worker then
+/// logs real deliveries that don't match it without blocking them.
+async fn validate_schema(
+    State(state): State<AppState>,
+    SubscriberAuth(subscriber_id): SubscriberAuth,
+    Extension(request_id): Extension<RequestId>,
+    Path(id): Path<String>,
+    Json(payload): Json<ValidateSchemaRequest>,
+) -> ApiResult<Json<ValidateSchemaResponse>> {
+    let webhook = db::queries::webhooks::get_by_id(&state.db, &id)
+        .await
+        .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?
+        .ok_or_else(|| {
+            AppError::NotFound("webhook not found".to_string()).with_request_id(&request_id.0)
+        })?;
+
+    if webhook.subscriber_id != subscriber_id {
+        return Err(
+            AppError::Forbidden("not webhook owner".to_string()).with_request_id(&request_id.0)
+        );
+    }
+
+    let sample = sample_payload("del_test_sample", &webhook.id, Utc::now());
+    let violations = core::schema::payload_violations(&payload.schema, &sample)
+        .map_err(|err| AppError::BadRequest(err).with_request_id(&request_id.0))?;
+
+    if payload.store {
+        db::queries::webhooks::set_payload_schema(&state.db, &webhook.id, &payload.schema)
+            .await
+            .map_err(|_| AppError::Internal.with_request_id(&request_id.0))?;
+    }
+
+    Ok(Json(ValidateSchemaResponse {
+        valid: violations.is_empty(),
+        violations,
+        stored: payload.store,
+    }))
+}
+
+/// A delivery payload for a sample signal, built by the same
+/// [`build_payload`] the worker uses for real deliveries.
+fn sample_payload(delivery_id: &str, webhook_id: &str, now: DateTime<Utc>) -> serde_json::Value {
+    let metadata = json!({});
+    build_payload(
+        delivery_id,
+        Some(webhook_id),
+        PayloadChannel {
+            id: "ch_test",
+            slug: "herald-test",
+            display_name: "Herald Test",
+        },
+        PayloadSignal {
+            id: "sig_test",
+            title: "Herald webhook test",
+            body: "This is a test delivery from Herald. No action is needed.",
+            urgency: SignalUrgency::Normal,
+            metadata: &metadata,
+            created_at: now,
+        },
+    )
+}
+
+/// [`sample_payload`] marked with `"test": true` so receivers can ignore it.
+fn build_test_payload(
+    delivery_id: &str,
+    webhook_id: &str,
+    now: DateTime<Utc>,
+) -> serde_json::Value {
+    let mut payload = sample_payload(delivery_id, webhook_id, now);
+    payload["test"] = json!(true);
+    payload
 }
 
-fn validate_webhook_url(url: &str, env: &str) -> Result<(), String> {
+/// Custom headers must be a string-to-string object of valid HTTP headers that
+/// don't collide with the ones Herald sets itself.
+fn validate_custom_headers(headers: &serde_json::Value, has_token: bool) -> Result<(), String> {
+    if headers.is_null() {
+        return Ok(());
+    }
+    let object = headers
+        .as_object()
+        .ok_or_else(|| "headers must be an object".to_string())?;
+
+    for (name, value) in object {
+        let lowered = name.to_ascii_lowercase();
+        if lowered == "content-type"
+            || lowered.starts_with("x-herald-")
+            || (has_token && lowered == "authorization")
+        {
+            return Err(format!("header {} is reserved", name));
+        }
+        if axum::http::HeaderName::from_bytes(name.as_bytes()).is_err() {
+            return Err(format!("invalid header name {}", name));
+        }
+        let valid_value = value
+            .as_str()
+            .is_some_and(|v| axum::http::HeaderValue::from_str(v).is_ok());
+        if !valid_value {
+            return Err(format!("header {} must have a string value", name));
+        }
+    }
+
+    Ok(())
+}
+
+pub(super) async fn validate_webhook_url(url: &str, env: &str) -> Result<(), String> {
     if !url.starts_with("https://") {
         return Err("webhook url must be https".to_string());
     }
@@ -303,7 +688,311 @@ fn validate_webhook_url(url: &str, env: &str) -> Result<(), String> {
         {
             return Err("webhook url must not target localhost in prod".to_string());
         }
+        // Checked again at delivery time by the worker's resolver, since the
+        // host may resolve differently by then.
+        core::net::check_public_url(url)
+            .await
+            .map_err(|err| format!("webhook url must not target a private address: {err}"))?;
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode, response::IntoResponse};
+
+    #[test]
+    fn test_webhook_url_rejects_private_targets_in_prod() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            assert!(validate_webhook_url("http://169.254.169.254", "prod")
+                .await
+                .is_err());
+            assert!(
+                validate_webhook_url("https://169.254.169.254/latest", "prod")
+                    .await
+                    .is_err()
+            );
+            let err = validate_webhook_url("https://10.1.2.3", "prod")
+                .await
+                .unwrap_err();
+            assert!(err.contains("private address"), "{err}");
+            assert!(validate_webhook_url("https://93.184.216.34/hook", "prod")
+                .await
+                .is_ok());
+            // Development keeps allowing internal endpoints.
+            assert!(validate_webhook_url("https://10.1.2.3", "dev")
+                .await
+                .is_ok());
+        });
+    }
+
+    fn make_webhook(timeout_ms: i32) -> db::models::Webhook {
+        db::models::Webhook {
+            id: "wh_test".to_string(),
+            subscriber_id: "sr_test".to_string(),
+            url: "https://example.com/hook".to_string(),
+            name: "test".to_string(),
+            token: None,
+            headers: None,
+            payload_schema: None,
+            timeout_ms,
+            max_in_flight: 10,
+            max_per_second: None,
+            signature_algorithm: SignatureAlgorithm::Sha256,
+            legacy_signature: false,
+            status: WebhookStatus::Active,
+            failure_count: 0,
+            last_success_at: None,
+            last_failure_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_test_send_matches_worker_request() {
+        let client = reqwest::Client::new();
+        let webhook = make_webhook(2_500);
+        let body = r#"{"test":true}"#;
+
+        let request = test_request(
+            &client,
+            &webhook,
+            "whsec",
+            None,
+            "del_test_1",
+            body.into(),
+            1_000,
+        )
+        .build()
+        .unwrap();
+        assert_eq!(
+            request.timeout(),
+            Some(&std::time::Duration::from_millis(2_500))
+        );
+        assert!(request.headers().get(GLOBAL_SIGNATURE_HEADER).is_none());
+
+        let request = test_request(
+            &client,
+            &webhook,
+            "whsec",
+            Some("platform_secret"),
+            "del_test_1",
+            body.into(),
+            1_000,
+        )
+        .build()
+        .unwrap();
+        let global = request.headers()[GLOBAL_SIGNATURE_HEADER].to_str().unwrap();
+        assert!(core::auth::verify_signature(
+            "platform_secret",
+            1_000,
+            body,
+            global
+        ));
+        assert!(core::auth::verify_signature(
+            "whsec",
+            1_000,
+            body,
+            request.headers()["X-Herald-Signature"].to_str().unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_timeout_ms_bounds() {
+        assert!(Webhook::validate_timeout_ms(Webhook::DEFAULT_TIMEOUT_MS).is_ok());
+        assert!(Webhook::validate_timeout_ms(1_000).is_ok());
+        assert!(Webhook::validate_timeout_ms(60_000).is_ok());
+        assert!(Webhook::validate_timeout_ms(999).is_err());
+        assert!(Webhook::validate_timeout_ms(60_001).is_err());
+
+        let payload: UpdateWebhookRequest =
+            serde_json::from_value(json!({ "timeoutMs": 5000 })).unwrap();
+        assert_eq!(payload.timeout_ms, Some(5000));
+    }
+
+    #[test]
+    fn test_concurrency_cap_bounds() {
+        assert!(Webhook::validate_max_in_flight(Webhook::DEFAULT_MAX_IN_FLIGHT).is_ok());
+        assert!(Webhook::validate_max_in_flight(1).is_ok());
+        assert!(Webhook::validate_max_in_flight(0).is_err());
+        assert!(Webhook::validate_max_in_flight(101).is_err());
+        assert!(Webhook::validate_max_per_second(1_000).is_ok());
+        assert!(Webhook::validate_max_per_second(0).is_err());
+
+        let payload: UpdateWebhookRequest =
+            serde_json::from_value(json!({ "maxInFlight": 2, "maxPerSecond": 5 })).unwrap();
+        assert_eq!(payload.max_in_flight, Some(2));
+        assert_eq!(payload.max_per_second, Some(5));
+    }
+
+    #[test]
+    fn test_signature_scheme_fields() {
+        let payload: CreateWebhookRequest = serde_json::from_value(json!({
+            "name": "hook",
+            "url": "https://example.com/hook",
+            "signatureAlgorithm": "sha512"
+        }))
+        .unwrap();
+        assert_eq!(
+            payload.signature_algorithm,
+            Some(SignatureAlgorithm::Sha512)
+        );
+
+        let payload: UpdateWebhookRequest =
+            serde_json::from_value(json!({ "legacySignature": false })).unwrap();
+        assert_eq!(payload.legacy_signature, Some(false));
+        assert!(serde_json::from_value::<UpdateWebhookRequest>(
+            json!({ "signatureAlgorithm": "md5" })
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_delivery_item_serializes_camel_case() {
+        let created_at = "2026-02-08T07:20:00Z".parse::<DateTime<Utc>>().unwrap();
+        let item = DeliveryItem::from(Delivery {
+            id: "del_1".to_string(),
+            signal_id: "sig_1".to_string(),
+            subscription_id: "sub_1".to_string(),
+            webhook_id: Some("wh_1".to_string()),
+            delivery_mode: DeliveryMode::Webhook,
+            attempt: 2,
+            status: DeliveryStatus::Failed,
+            status_code: None,
+            error_message: Some("connection refused".to_string()),
+            response_body: None,
+            latency_ms: Some(40),
+            created_at,
+            updated_at: created_at,
+        });
+
+        assert_eq!(
+            serde_json::to_value(item).unwrap(),
+            json!({
+                "id": "del_1",
+                "deliveryMode": "Webhook",
+                "status": "Failed",
+                "attempt": 2,
+                "statusCode": null,
+                "errorMessage": "connection refused",
+                "latencyMs": 40,
+                "responseBody": null,
+                "createdAt": "2026-02-08T07:20:00Z",
+                "updatedAt": "2026-02-08T07:20:00Z",
+            })
+        );
+    }
+
+    #[test]
+    fn test_delivery_status_filter() {
+        let query: ListDeliveriesQuery =
+            serde_json::from_value(json!({ "status": "failed", "cursor": "del_9" })).unwrap();
+        assert!(matches!(
+            parse_delivery_status(query.status.as_deref().unwrap()),
+            Ok(DeliveryStatus::Failed)
+        ));
+        assert_eq!(query.cursor.as_deref(), Some("del_9"));
+
+        let response = parse_delivery_status("broken")
+            .unwrap_err()
+            .with_request_id("req_1")
+            .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_custom_headers_accepts_gateway_key() {
+        let headers = json!({"X-Api-Gateway-Key": "secret"});
+        assert!(validate_custom_headers(&headers, true).is_ok());
+        assert!(validate_custom_headers(&serde_json::Value::Null, false).is_ok());
+    }
+
+    #[test]
+    fn test_custom_headers_rejects_reserved() {
+        for name in ["Content-Type", "x-herald-signature", "X-Herald-Timestamp"] {
+            let headers = json!({ name: "value" });
+            assert!(validate_custom_headers(&headers, false).is_err(), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_custom_headers_authorization_only_reserved_with_token() {
+        let headers = json!({"Authorization": "Basic abc"});
+        assert!(validate_custom_headers(&headers, false).is_ok());
+        assert!(validate_custom_headers(&headers, true).is_err());
+    }
+
+    #[test]
+    fn test_custom_headers_rejects_invalid_shapes() {
+        assert!(validate_custom_headers(&json!(["X-Key"]), false).is_err());
+        assert!(validate_custom_headers(&json!({"X-Key": 42}), false).is_err());
+        assert!(validate_custom_headers(&json!({"bad header": "v"}), false).is_err());
+    }
+
+    #[test]
+    fn test_build_test_payload_is_marked_as_test() {
+        let payload = build_test_payload("del_test_1", "wh_1", Utc::now());
+
+        assert_eq!(payload["test"], true);
+        assert_eq!(payload["deliveryId"], "del_test_1");
+        assert_eq!(payload["webhookId"], "wh_1");
+        assert_eq!(payload["signal"]["urgency"], "normal");
+        assert!(payload["channel"]["slug"].is_string());
+    }
+
+    #[test]
+    fn test_sample_payload_matches_schema() {
+        let schema = json!({
+            "type": "object",
+            "required": ["deliveryId", "channel", "signal"],
+            "properties": {
+                "signal": {
+                    "type": "object",
+                    "required": ["id", "title", "urgency"],
+                    "properties": {"title": {"type": "string"}}
+                }
+            }
+        });
+        let sample = sample_payload("del_test", "wh_test", Utc::now());
+        assert!(core::schema::payload_violations(&schema, &sample)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_strict_schema_accepts_the_delivered_shape() {
+        // `additionalProperties: false` at the top level must not trip over
+        // fields only test sends carry.
+        let schema = json!({
+            "type": "object",
+            "additionalProperties": false,
+            "properties": {
+                "schemaVersion": {}, "deliveryId": {}, "webhookId": {},
+                "channel": {}, "signal": {}
+            }
+        });
+        let sample = sample_payload("del_test", "wh_test", Utc::now());
+        assert!(core::schema::payload_violations(&schema, &sample)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_sample_payload_reports_schema_violations() {
+        let schema = json!({
+            "type": "object",
+            "required": ["eventType"],
+            "properties": {"signal": {"properties": {"title": {"type": "integer"}}}}
+        });
+        let sample = sample_payload("del_test", "wh_test", Utc::now());
+        let violations = core::schema::payload_violations(&schema, &sample).unwrap();
+        assert_eq!(violations.len(), 2);
+    }
+}
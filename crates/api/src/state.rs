@@ -2,181 +2,81 @@ use apalis::postgres::PostgresStorage;
 use core::config::Settings;
 use core::types::DeliveryJob;
 use core::tunnel::AgentRegistry;
-use once_cell::sync::Lazy;
 use sqlx::PgPool;
-use std::collections::HashMap;
+use std::net::IpAddr;
 use std::sync::Arc;
-use std::sync::Mutex;
-use std::sync::MutexGuard;
-use tracing::warn;
+
+pub use core::metrics::METRICS;
 
 #[derive(Clone)]
 pub struct AppState {
     pub db: PgPool,
+    /// Read replica pool, if `DATABASE_REPLICA_URL` is configured.
+    pub read_db: Option<PgPool>,
     pub redis: redis::Client,
     pub storage: PostgresStorage<DeliveryJob>,
     pub settings: Settings,
     pub tunnel_registry: Arc<AgentRegistry>,
+    /// Names this process in agent presence and addresses its relay channel.
+    pub instance_id: String,
+    /// Outbound client for synchronous webhook test sends.
+    pub http_client: reqwest::Client,
 }
 
-#[derive(Debug, Clone)]
-pub struct RequestId(pub String);
-
-#[derive(Default)]
-struct MetricsStore {
-    http_requests: HashMap<(String, String, u16), u64>,
-    signals: HashMap<(String, String), u64>,
-    deliveries: HashMap<String, u64>,
-    latency: HashMap<String, (u64, f64)>,
-    queue_depth: HashMap<String, i64>,
+impl AppState {
+    /// Pool for read-only queries; the replica when configured, else the primary.
+    pub fn read_pool(&self) -> &PgPool {
+        select_read_pool(&self.db, self.read_db.as_ref())
+    }
 }
 
-pub struct Metrics {
-    store: Mutex<MetricsStore>,
+fn select_read_pool<'a>(primary: &'a PgPool, replica: Option<&'a PgPool>) -> &'a PgPool {
+    replica.unwrap_or(primary)
 }
 
-impl Metrics {
-    pub fn new() -> Self {
-        Self {
-            store: Mutex::new(MetricsStore::default()),
-        }
-    }
-
-    fn lock_store(&self) -> MutexGuard<'_, MetricsStore> {
-        match self.store.lock() {
-            Ok(guard) => guard,
-            Err(poisoned) => {
-                warn!("metrics store lock poisoned; continuing with inner state");
-                poisoned.into_inner()
-            }
-        }
-    }
-
-    pub fn record_http_request(&self, method: &str, path: &str, status: u16) {
-        let mut store = self.lock_store();
-        *store
-            .http_requests
-            .entry((method.to_string(), path.to_string(), status))
-            .or_insert(0) += 1;
-    }
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
 
-    pub fn record_signal(&self, channel: &str, urgency: &str) {
-        let mut store = self.lock_store();
-        *store
-            .signals
-            .entry((channel.to_string(), urgency.to_string()))
-            .or_insert(0) += 1;
-    }
+/// The caller's address as resolved by the `client_ip` middleware.
+#[derive(Debug, Clone, Copy)]
+pub struct ClientIp(pub IpAddr);
 
-    #[allow(dead_code)]
-    pub fn record_delivery(&self, status: &str) {
-        let mut store = self.lock_store();
-        *store.deliveries.entry(status.to_string()).or_insert(0) += 1;
-    }
-
-    #[allow(dead_code)]
-    pub fn record_delivery_latency(&self, channel: &str, seconds: f64) {
-        let mut store = self.lock_store();
-        let entry = store.latency.entry(channel.to_string()).or_insert((0, 0.0));
-        entry.0 += 1;
-        entry.1 += seconds;
+#[cfg(test)]
+mod tests {
+    use super::select_read_pool;
+    use sqlx::postgres::PgPoolOptions;
+
+    fn rt() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
     }
 
-    #[allow(dead_code)]
-    pub fn set_queue_depth(&self, queue: &str, depth: i64) {
-        let mut store = self.lock_store();
-        store.queue_depth.insert(queue.to_string(), depth);
+    fn lazy_pool(database: &str) -> sqlx::PgPool {
+        PgPoolOptions::new()
+            .connect_lazy(&format!("postgres://herald@localhost/{database}"))
+            .unwrap()
     }
 
-    pub fn gather(&self) -> String {
-        let store = self.lock_store();
-        let mut out = String::new();
-
-        out.push_str("# TYPE herald_http_requests_total counter\n");
-        for ((method, path, status), value) in &store.http_requests {
-            out.push_str(&format!(
-                "herald_http_requests_total{{method=\"{}\",path=\"{}\",status=\"{}\"}} {}\n",
-                method, path, status, value
-            ));
-        }
-
-        out.push_str("# TYPE herald_signals_total counter\n");
-        for ((channel, urgency), value) in &store.signals {
-            out.push_str(&format!(
-                "herald_signals_total{{channel=\"{}\",urgency=\"{}\"}} {}\n",
-                channel, urgency, value
-            ));
-        }
-
-        out.push_str("# TYPE herald_deliveries_total counter\n");
-        for (status, value) in &store.deliveries {
-            out.push_str(&format!(
-                "herald_deliveries_total{{status=\"{}\"}} {}\n",
-                status, value
-            ));
-        }
-
-        out.push_str("# TYPE herald_delivery_latency_seconds summary\n");
-        for (channel, (count, sum)) in &store.latency {
-            out.push_str(&format!(
-                "herald_delivery_latency_seconds_count{{channel=\"{}\"}} {}\n",
-                channel, count
-            ));
-            out.push_str(&format!(
-                "herald_delivery_latency_seconds_sum{{channel=\"{}\"}} {}\n",
-                channel, sum
-            ));
-        }
-
-        out.push_str("# TYPE herald_queue_depth gauge\n");
-        for (queue, depth) in &store.queue_depth {
-            out.push_str(&format!(
-                "herald_queue_depth{{queue=\"{}\"}} {}\n",
-                queue, depth
-            ));
-        }
-
-        out
+    #[test]
+    fn read_pool_uses_replica_when_configured() {
+        rt().block_on(async {
+            let primary = lazy_pool("primary");
+            let replica = lazy_pool("replica");
+
+            let pool = select_read_pool(&primary, Some(&replica));
+            assert_eq!(pool.connect_options().get_database(), Some("replica"));
+        });
     }
-}
-
-pub static METRICS: Lazy<Metrics> = Lazy::new(Metrics::new);
-
-#[cfg(test)]
-mod tests {
-    use super::Metrics;
 
     #[test]
-    fn metrics_gather_includes_recorded_values() {
-        let metrics = Metrics::new();
-
-        metrics.record_http_request("GET", "/health", 200);
-        metrics.record_http_request("GET", "/health", 200);
-        metrics.record_signal("ch_123", "high");
-        metrics.record_delivery("success");
-        metrics.record_delivery_latency("ch_123", 1.25);
-        metrics.set_queue_depth("delivery-normal", 3);
-
-        let output = metrics.gather();
-
-        assert!(output.contains("herald_http_requests_total"));
-        assert!(output.contains("method=\"GET\""));
-        assert!(output.contains("path=\"/health\""));
-        assert!(output.contains("status=\"200\""));
-        assert!(output.contains("} 2"));
-
-        assert!(output.contains("herald_signals_total"));
-        assert!(output.contains("channel=\"ch_123\""));
-        assert!(output.contains("urgency=\"high\""));
-
-        assert!(output.contains("herald_deliveries_total"));
-        assert!(output.contains("status=\"success\""));
-
-        assert!(output.contains("herald_delivery_latency_seconds_count"));
-        assert!(output.contains("herald_delivery_latency_seconds_sum"));
+    fn read_pool_falls_back_to_primary() {
+        rt().block_on(async {
+            let primary = lazy_pool("primary");
 
-        assert!(output.contains("herald_queue_depth"));
-        assert!(output.contains("queue=\"delivery-normal\""));
-        assert!(output.contains("} 3"));
+            let pool = select_read_pool(&primary, None);
+            assert_eq!(pool.connect_options().get_database(), Some("primary"));
+        });
     }
 }
@@ -4,10 +4,13 @@ use crate::state::AppState;
 
 pub mod protocol;
 pub mod registry;
+pub mod relay;
 pub mod server;
+pub mod status;
 
 pub fn router(state: AppState) -> Router {
     Router::new()
         .route("/v1/tunnel", get(server::tunnel_ws))
+        .route("/v1/tunnel/status", get(status::tunnel_status))
         .with_state(state)
 }
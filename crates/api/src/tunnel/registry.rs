@@ -1 +1,3 @@
-pub use core::tunnel::AgentConnection;
+pub use core::tunnel::{
+    AckOutcome, AgentConnection, AgentRegistry, ConnectionHealth, ConnectionHealthSnapshot,
+};
@@ -0,0 +1,157 @@
+//! Forwards deliveries relayed by workers to the agents connected here.
+//!
+//! Workers find this instance through the agent presence announced in
+//! `server`, publish to its relay channel, and wait on their own reply
+//! channel for the outcome. See `core::presence`.
+
+use core::presence::{self, RelayReply, RelayRequest};
+use futures_util::StreamExt;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::{
+    state::AppState,
+    tunnel::registry::{AckOutcome, AgentRegistry},
+};
+
+/// Pause before resubscribing after the relay listener loses Redis.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Forward relayed deliveries to local agents, resubscribing whenever the
+/// Redis connection drops.
+pub async fn serve_relay(state: AppState) {
+    loop {
+        if let Err(err) = receive_relays(&state).await {
+            warn!(error = %err, "tunnel relay listener failed; resubscribing");
+        }
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+async fn receive_relays(state: &AppState) -> redis::RedisResult<()> {
+    let mut pubsub = state.redis.get_async_pubsub().await?;
+    pubsub
+        .subscribe(presence::relay_channel(&state.instance_id))
+        .await?;
+    info!(instance_id = %state.instance_id, "listening for relayed tunnel deliveries");
+
+    let mut messages = pubsub.on_message();
+    while let Some(msg) = messages.next().await {
+        let payload: String = msg.get_payload()?;
+        match serde_json::from_str::<RelayRequest>(&payload) {
+            Ok(request) => {
+                tokio::spawn(forward(state.clone(), request));
+            }
+            Err(err) => warn!(error = %err, "invalid tunnel relay request"),
+        }
+    }
+    Ok(())
+}
+
+async fn forward(state: AppState, request: RelayRequest) {
+    let timeout = Duration::from_secs(state.settings.tunnel_ack_timeout_secs);
+    let outcome = deliver_locally(&state.tunnel_registry, &request, timeout).await;
+    let reply = RelayReply {
+        delivery_id: request.delivery_id.clone(),
+        outcome,
+    };
+    let channel = presence::reply_channel(&request.reply_to);
+    if let Err(err) = presence::publish(&state.redis, &channel, &reply).await {
+        warn!(
+            delivery_id = %request.delivery_id,
+            error = %err,
+            "failed to reply to relayed tunnel delivery"
+        );
+    }
+}
+
+/// Offer a relayed delivery to the subscriber's agents on this instance and
+/// wait for the ack. An agent that left before the request arrived counts as
+/// disconnected.
+async fn deliver_locally(
+    registry: &AgentRegistry,
+    request: &RelayRequest,
+    timeout: Duration,
+) -> AckOutcome {
+    let agents = registry.delivery_order(&request.subscriber_id).await;
+    match registry
+        .acks
+        .offer(&request.delivery_id, &agents, &request.message)
+    {
+        Ok(ack) => registry.acks.wait(&request.delivery_id, ack, timeout).await,
+        Err(err) => {
+            warn!(
+                subscriber_id = %request.subscriber_id,
+                delivery_id = %request.delivery_id,
+                error = err,
+                "relayed tunnel delivery not accepted"
+            );
+            AckOutcome::Disconnected
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tunnel::protocol::ServerMessage;
+    use crate::tunnel::registry::{AgentConnection, ConnectionHealth};
+    use chrono::Utc;
+    use std::sync::Arc;
+    use tokio::sync::mpsc;
+
+    fn rt() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+    }
+
+    fn request(subscriber_id: &str) -> RelayRequest {
+        RelayRequest {
+            subscriber_id: subscriber_id.to_string(),
+            delivery_id: "del_1".to_string(),
+            reply_to: "worker_1".to_string(),
+            message: ServerMessage::Ping,
+        }
+    }
+
+    #[test]
+    fn test_relayed_delivery_waits_for_local_ack() {
+        rt().block_on(async {
+            let registry = Arc::new(AgentRegistry::new());
+            let (tx, mut rx) = mpsc::channel(4);
+            registry
+                .register(AgentConnection {
+                    connection_id: "conn_1".to_string(),
+                    subscriber_id: "sr_1".to_string(),
+                    sender: tx,
+                    connected_at: Utc::now(),
+                    health: ConnectionHealth::default(),
+                })
+                .await;
+
+            let agent = registry.clone();
+            tokio::spawn(async move {
+                if rx.recv().await.is_some() {
+                    agent.acks.ack("del_1");
+                }
+            });
+
+            let outcome =
+                deliver_locally(&registry, &request("sr_1"), Duration::from_secs(1)).await;
+            assert_eq!(outcome, AckOutcome::Acked);
+        });
+    }
+
+    #[test]
+    fn test_relayed_delivery_without_local_agent_is_disconnected() {
+        rt().block_on(async {
+            let registry = AgentRegistry::new();
+            let outcome =
+                deliver_locally(&registry, &request("sr_gone"), Duration::from_secs(1)).await;
+            assert_eq!(outcome, AckOutcome::Disconnected);
+            assert_eq!(registry.acks.pending_count(), 0);
+        });
+    }
+}
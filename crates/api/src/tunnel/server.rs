@@ -1,39 +1,95 @@
 use axum::{
     extract::{
-        ws::{Message, WebSocket, WebSocketUpgrade},
+        ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade},
         State,
     },
+    http::{header::SEC_WEBSOCKET_PROTOCOL, HeaderMap},
     response::IntoResponse,
     Extension,
 };
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use futures_util::{SinkExt, StreamExt};
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::mpsc;
 use tracing::{error, info, warn};
 
 use crate::{
+    middleware::auth::key_expired,
     state::{AppState, RequestId},
     tunnel::protocol::{ClientMessage, ServerMessage, TunnelSignal},
-    tunnel::registry::AgentConnection,
+    tunnel::registry::{AgentConnection, AgentRegistry, ConnectionHealth},
+};
+use core::auth::{hash_api_key, normalize_token};
+use core::presence::{self, Presence};
+use core::tunnel::{
+    negotiate_version, parse_subprotocol, subprotocol, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION,
 };
-use core::auth::hash_api_key;
 use core::types::SignalUrgency as CoreSignalUrgency;
-use db::models::{ApiKeyOwner, SignalUrgency};
+use db::models::{ApiKeyAuth, ApiKeyOwner, SignalUrgency};
+
+/// Ping intervals an agent may miss before it is dropped.
+const MISSED_PINGS: u32 = 3;
+
+/// Most missed deliveries replayed on one reconnect; the rest follow on the
+/// next one.
+const REPLAY_LIMIT: i64 = 500;
 
 pub async fn tunnel_ws(
     State(state): State<AppState>,
     Extension(request_id): Extension<RequestId>,
+    headers: HeaderMap,
     ws: WebSocketUpgrade,
 ) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_socket(state, socket, request_id))
+    let ws = ws.protocols((MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).rev().map(subprotocol));
+    let header_version = ws
+        .selected_protocol()
+        .and_then(|p| p.to_str().ok())
+        .and_then(parse_subprotocol)
+        .or_else(|| offered_version(&headers));
+    ws.on_upgrade(move |socket| handle_socket(state, socket, request_id, header_version))
 }
 
-async fn handle_socket(state: AppState, socket: WebSocket, request_id: RequestId) {
+/// Newest `herald.vN` subprotocol the client offered, used when none of them
+/// matched a version this server speaks so negotiation can explain why.
+fn offered_version(headers: &HeaderMap) -> Option<u32> {
+    headers
+        .get_all(SEC_WEBSOCKET_PROTOCOL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(parse_subprotocol)
+        .max()
+}
+
+async fn handle_socket(
+    state: AppState,
+    socket: WebSocket,
+    request_id: RequestId,
+    header_version: Option<u32>,
+) {
     let (mut ws_sender, mut ws_receiver) = socket.split();
-    let (outbound_tx, mut outbound_rx) = mpsc::channel::<ServerMessage>(64);
+    let (outbound_tx, mut outbound_rx) =
+        mpsc::channel::<ServerMessage>(state.settings.tunnel_buffer);
 
+    let registry = state.tunnel_registry.clone();
     let send_task = tokio::spawn(async move {
-        while let Some(msg) = outbound_rx.recv().await {
+        loop {
+            let msg = tokio::select! {
+                msg = outbound_rx.recv() => msg,
+                _ = registry.shutdown_requested() => {
+                    // Going-away lets the agent reconnect to another instance.
+                    let frame = CloseFrame {
+                        code: close_code::AWAY,
+                        reason: "server shutting down".into(),
+                    };
+                    let _ = ws_sender.send(Message::Close(Some(frame))).await;
+                    break;
+                }
+            };
+            let Some(msg) = msg else {
+                break;
+            };
             let text = match serde_json::to_string(&msg) {
                 Ok(text) => text,
                 Err(err) => {
@@ -56,12 +112,23 @@ async fn handle_socket(state: AppState, socket: WebSocket, request_id: RequestId
         _ => None,
     };
 
-    let (subscriber_id, connection_id) = match auth_msg {
-        Some(ClientMessage::Auth { token }) => {
-            match authenticate(&state, &token, &request_id).await {
-                Ok(subscriber_id) => {
+    let (subscriber_id, connection_id, protocol_version, last_seen_seq) = match auth_msg {
+        Some(ClientMessage::Auth {
+            token,
+            version,
+            last_seen_seq,
+        }) => {
+            // A version in the auth message wins over the subprotocol header.
+            let authed = match negotiate_version(version.or(header_version)) {
+                Ok(protocol_version) => authenticate(&state, &token, &request_id)
+                    .await
+                    .map(|subscriber_id| (subscriber_id, protocol_version)),
+                Err(message) => Err(message),
+            };
+            match authed {
+                Ok((subscriber_id, protocol_version)) => {
                     let connection_id = format!("conn_{}", nanoid::nanoid!(12));
-                    (subscriber_id, connection_id)
+                    (subscriber_id, connection_id, protocol_version, last_seen_seq)
                 }
                 Err(message) => {
                     let _ = outbound_tx
@@ -90,8 +157,9 @@ async fn handle_socket(state: AppState, socket: WebSocket, request_id: RequestId
         subscriber_id: subscriber_id.clone(),
         sender: outbound_tx.clone(),
         connected_at: Utc::now(),
+        health: ConnectionHealth::default(),
     };
-    state.tunnel_registry.register(conn).await;
+    let connection = state.tunnel_registry.register(conn).await;
 
     let _ = db::queries::subscribers::update_agent_last_connected_at(
         &state.db,
@@ -104,32 +172,59 @@ async fn handle_socket(state: AppState, socket: WebSocket, request_id: RequestId
         .send(ServerMessage::AuthOk {
             connection_id: connection_id.clone(),
             subscriber_id: subscriber_id.clone(),
+            protocol_version,
         })
         .await;
 
-    let ping_tx = outbound_tx.clone();
-    let ping_task = tokio::spawn(async move {
-        let mut interval = tokio::time::interval(std::time::Duration::from_secs(30));
-        loop {
-            interval.tick().await;
-            if ping_tx.send(ServerMessage::Ping).await.is_err() {
-                break;
-            }
-        }
-    });
+    if let Some(after) = last_seen_seq {
+        replay_missed(&state, &connection, after).await;
+    }
+
+    let ping_interval = Duration::from_secs(state.settings.tunnel_ping_secs);
+    let pong_timeout = ping_interval * MISSED_PINGS;
+    let mut keepalive_task = tokio::spawn(keepalive(
+        state.tunnel_registry.clone(),
+        connection.clone(),
+        ping_interval,
+        pong_timeout,
+    ));
+    let presence = Presence {
+        instance_id: state.instance_id.clone(),
+        connection_id: connection_id.clone(),
+    };
+    let presence_task = tokio::spawn(announce_presence(
+        state.redis.clone(),
+        subscriber_id.clone(),
+        presence.clone(),
+        ping_interval,
+        pong_timeout,
+    ));
 
     info!(
         subscriber_id = %subscriber_id,
         connection_id = %connection_id,
+        protocol_version,
         "tunnel connected"
     );
 
-    while let Some(message) = ws_receiver.next().await {
+    let mut timed_out = false;
+    loop {
+        let message = tokio::select! {
+            message = ws_receiver.next() => message,
+            _ = state.tunnel_registry.shutdown_requested() => break,
+            result = &mut keepalive_task => {
+                timed_out = matches!(result, Ok(true));
+                break;
+            }
+        };
+        let Some(message) = message else {
+            break;
+        };
         match message {
-            Ok(Message::Text(text)) => handle_client_message(&subscriber_id, &text).await,
+            Ok(Message::Text(text)) => handle_client_message(&state, &connection, &text).await,
             Ok(Message::Binary(bytes)) => {
                 if let Ok(text) = String::from_utf8(bytes.to_vec()) {
-                    handle_client_message(&subscriber_id, &text).await;
+                    handle_client_message(&state, &connection, &text).await;
                 }
             }
             Ok(Message::Close(_)) => break,
@@ -141,9 +236,46 @@ async fn handle_socket(state: AppState, socket: WebSocket, request_id: RequestId
         }
     }
 
-    state.tunnel_registry.unregister(&subscriber_id).await;
-    ping_task.abort();
+    if timed_out {
+        warn!(
+            subscriber_id = %subscriber_id,
+            connection_id = %connection_id,
+            timeout_secs = pong_timeout.as_secs(),
+            "tunnel agent stopped answering pings, closing"
+        );
+    }
+
+    state
+        .tunnel_registry
+        .unregister(&subscriber_id, &connection_id)
+        .await;
+    let released = state.tunnel_registry.acks.disconnect(&connection_id);
+    if released > 0 {
+        warn!(
+            subscriber_id = %subscriber_id,
+            connection_id = %connection_id,
+            released,
+            "tunnel closed with unacked deliveries"
+        );
+    }
+    keepalive_task.abort();
+    presence_task.abort();
+    // Another agent of this subscriber still connected here keeps the entry.
+    if state.tunnel_registry.get(&subscriber_id).await.is_none() {
+        if let Err(err) = presence::withdraw(&state.redis, &subscriber_id, &presence).await {
+            warn!(
+                subscriber_id = %subscriber_id,
+                error = %err,
+                "failed to withdraw agent presence"
+            );
+        }
+    }
+    drop(connection);
     drop(outbound_tx);
+    if timed_out {
+        // The peer is gone; don't wait on writes to a dead socket.
+        send_task.abort();
+    }
     let _ = send_task.await;
 
     info!(
@@ -153,17 +285,70 @@ async fn handle_socket(state: AppState, socket: WebSocket, request_id: RequestId
     );
 }
 
+/// Pings the agent every `interval` and drops it from `registry` once it
+/// has gone `timeout` without a pong, so the worker stops routing to it and
+/// falls back to webhooks. Returns `true` on timeout, `false` if the
+/// connection's outbound channel closed first.
+async fn keepalive(
+    registry: Arc<AgentRegistry>,
+    connection: Arc<AgentConnection>,
+    interval: Duration,
+    timeout: Duration,
+) -> bool {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        let now = Utc::now();
+        if connection
+            .health
+            .is_unresponsive(connection.connected_at, now, timeout)
+        {
+            registry
+                .unregister(&connection.subscriber_id, &connection.connection_id)
+                .await;
+            return true;
+        }
+        if connection.sender.send(ServerMessage::Ping).await.is_err() {
+            return false;
+        }
+        connection.health.record_ping(now);
+    }
+}
+
+/// Records this instance as holding the subscriber's agent every `interval`,
+/// so workers relay deliveries here. Entries live for `ttl` and lapse on their
+/// own if the instance dies without withdrawing them.
+async fn announce_presence(
+    redis: redis::Client,
+    subscriber_id: String,
+    presence: Presence,
+    interval: Duration,
+    ttl: Duration,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        if let Err(err) = presence::announce(&redis, &subscriber_id, &presence, ttl).await {
+            warn!(
+                subscriber_id = %subscriber_id,
+                error = %err,
+                "failed to announce agent presence"
+            );
+        }
+    }
+}
+
 async fn authenticate(
     state: &AppState,
     token: &str,
     request_id: &RequestId,
 ) -> Result<String, String> {
-    if token.is_empty() {
+    let Some(token) = normalize_token(token) else {
         return Err("missing token".to_string());
-    }
+    };
 
     let hash = hash_api_key(token);
-    let api_key = db::queries::api_keys::get_by_hash(&state.db, &hash)
+    let api_key = db::queries::api_keys::authenticate(&state.db, &hash, true)
         .await
         .map_err(|err| {
             error!(error = %err, request_id = %request_id.0, "tunnel auth lookup failed");
@@ -171,14 +356,92 @@ async fn authenticate(
         })?
         .ok_or_else(|| "invalid token".to_string())?;
 
+    tunnel_subscriber(api_key, Utc::now())
+}
+
+/// The subscriber `api_key` opens a tunnel for, held to the same checks as
+/// HTTP auth: unexpired, with an owner that still exists.
+fn tunnel_subscriber(api_key: ApiKeyAuth, now: DateTime<Utc>) -> Result<String, String> {
+    if key_expired(api_key.expires_at, now) {
+        return Err("api key expired".to_string());
+    }
+    if api_key.owner_tier.is_none() {
+        return Err("api key owner not found".to_string());
+    }
     if api_key.owner_type != ApiKeyOwner::Subscriber {
         return Err("subscriber token required".to_string());
     }
-
     Ok(api_key.owner_id)
 }
 
-async fn handle_client_message(subscriber_id: &str, text: &str) {
+/// Resend the agent's unacknowledged deliveries numbered after `after`.
+/// Their acks arrive after the tracker gave up on them and are recorded by
+/// `record_late_ack`.
+async fn replay_missed(state: &AppState, connection: &AgentConnection, after: u64) {
+    let subscriber_id = connection.subscriber_id.as_str();
+    let after = i64::try_from(after).unwrap_or(i64::MAX);
+    let missed = match db::queries::deliveries::list_tunnel_replay(
+        &state.db,
+        subscriber_id,
+        after,
+        REPLAY_LIMIT,
+    )
+    .await
+    {
+        Ok(missed) => missed,
+        Err(err) => {
+            warn!(subscriber_id = %subscriber_id, error = %err, "tunnel: replay lookup failed");
+            return;
+        }
+    };
+    if missed.is_empty() {
+        return;
+    }
+
+    info!(
+        subscriber_id = %subscriber_id,
+        after_seq = after,
+        count = missed.len(),
+        "tunnel: replaying missed deliveries"
+    );
+    for replay in missed {
+        let message = ServerMessage::Signal {
+            delivery_id: replay.delivery_id,
+            channel_id: replay.signal.channel_id.clone(),
+            channel_slug: replay.channel_slug,
+            signal: to_tunnel_signal(&replay.signal),
+            seq: Some(replay.tunnel_seq as u64),
+        };
+        if connection.sender.send(message).await.is_err() {
+            break;
+        }
+        connection.health.record_delivered();
+    }
+}
+
+/// Record an ack the tracker no longer waits for. Returns whether it matched
+/// one of the subscriber's unsettled agent deliveries.
+async fn record_late_ack(state: &AppState, subscriber_id: &str, delivery_id: &str) -> bool {
+    let signal_id =
+        match db::queries::deliveries::record_late_ack(&state.db, delivery_id, subscriber_id).await
+        {
+            Ok(Some(signal_id)) => signal_id,
+            Ok(None) => return false,
+            Err(err) => {
+                warn!(delivery_id = %delivery_id, error = %err, "tunnel: late ack not recorded");
+                return false;
+            }
+        };
+    if let Err(err) =
+        db::queries::signals::increment_delivery_counts(&state.db, &signal_id, 1, 0, 0).await
+    {
+        warn!(signal_id = %signal_id, error = %err, "tunnel: late ack not counted");
+    }
+    true
+}
+
+async fn handle_client_message(state: &AppState, connection: &AgentConnection, text: &str) {
+    let subscriber_id = connection.subscriber_id.as_str();
     let Ok(message) = serde_json::from_str::<ClientMessage>(text) else {
         warn!(subscriber_id = %subscriber_id, "tunnel: invalid client message");
         return;
@@ -186,13 +449,24 @@ async fn handle_client_message(subscriber_id: &str, text: &str) {
 
     match message {
         ClientMessage::Ack { delivery_id } => {
+            if !state.tunnel_registry.acks.ack(&delivery_id)
+                && !record_late_ack(state, subscriber_id, &delivery_id).await
+            {
+                warn!(
+                    subscriber_id = %subscriber_id,
+                    delivery_id = %delivery_id,
+                    "tunnel: ack for unknown or expired delivery"
+                );
+                return;
+            }
+            connection.health.record_acked();
             info!(
                 subscriber_id = %subscriber_id,
                 delivery_id = %delivery_id,
                 "tunnel delivery acknowledged"
             );
         }
-        ClientMessage::Pong => {}
+        ClientMessage::Pong => connection.health.record_pong(Utc::now()),
         ClientMessage::Auth { .. } => {
             warn!(subscriber_id = %subscriber_id, "tunnel: unexpected auth message");
         }
@@ -201,7 +475,6 @@ async fn handle_client_message(subscriber_id: &str, text: &str) {
 
 /// Convert db SignalUrgency to core SignalUrgency.
 /// Used when delivering signals through the tunnel.
-#[allow(dead_code)]
 fn convert_urgency(urgency: &SignalUrgency) -> CoreSignalUrgency {
     match urgency {
         SignalUrgency::Low => CoreSignalUrgency::Low,
@@ -213,7 +486,6 @@ fn convert_urgency(urgency: &SignalUrgency) -> CoreSignalUrgency {
 
 /// Convert a db Signal to a TunnelSignal for delivery.
 /// Used when pushing signals through connected agents.
-#[allow(dead_code)]
 pub fn to_tunnel_signal(signal: &db::models::Signal) -> TunnelSignal {
     TunnelSignal {
         id: signal.id.clone(),
@@ -224,3 +496,128 @@ pub fn to_tunnel_signal(signal: &db::models::Signal) -> TunnelSignal {
         created_at: signal.created_at,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use db::models::AccountTier;
+
+    fn subscriber_key(expires_at: Option<DateTime<Utc>>) -> ApiKeyAuth {
+        ApiKeyAuth {
+            id: "key_1".to_string(),
+            owner_type: ApiKeyOwner::Subscriber,
+            owner_id: "sub_1".to_string(),
+            expires_at,
+            scopes: Vec::new(),
+            owner_tier: Some(AccountTier::Free),
+        }
+    }
+
+    #[test]
+    fn test_tunnel_subscriber_rejects_expired_key() {
+        let now = Utc::now();
+        let expired = subscriber_key(Some(now - chrono::Duration::minutes(1)));
+        assert_eq!(tunnel_subscriber(expired, now), Err("api key expired".to_string()));
+
+        let live = subscriber_key(Some(now + chrono::Duration::minutes(1)));
+        assert_eq!(tunnel_subscriber(live, now), Ok("sub_1".to_string()));
+        assert_eq!(tunnel_subscriber(subscriber_key(None), now), Ok("sub_1".to_string()));
+    }
+
+    #[test]
+    fn test_tunnel_subscriber_requires_subscriber_owner() {
+        let now = Utc::now();
+        let orphaned = ApiKeyAuth { owner_tier: None, ..subscriber_key(None) };
+        assert_eq!(
+            tunnel_subscriber(orphaned, now),
+            Err("api key owner not found".to_string())
+        );
+
+        let publisher = ApiKeyAuth { owner_type: ApiKeyOwner::Publisher, ..subscriber_key(None) };
+        assert_eq!(
+            tunnel_subscriber(publisher, now),
+            Err("subscriber token required".to_string())
+        );
+    }
+
+    #[test]
+    fn test_offered_version_reads_herald_subprotocols() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(offered_version(&headers), None);
+        headers.insert(SEC_WEBSOCKET_PROTOCOL, "chat, herald.v3, herald.v7".parse().unwrap());
+        assert_eq!(offered_version(&headers), Some(7));
+    }
+
+    #[test]
+    fn test_keepalive_unregisters_agent_that_never_pongs() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let registry = Arc::new(AgentRegistry::new());
+            let (tx, mut rx) = mpsc::channel(16);
+            let connection = registry
+                .register(AgentConnection {
+                    connection_id: "conn_silent".to_string(),
+                    subscriber_id: "sr_1".to_string(),
+                    sender: tx,
+                    connected_at: Utc::now(),
+                    health: ConnectionHealth::default(),
+                })
+                .await;
+
+            let timed_out = keepalive(
+                registry.clone(),
+                connection,
+                Duration::from_millis(20),
+                Duration::from_millis(100),
+            )
+            .await;
+
+            assert!(timed_out);
+            assert!(registry.get("sr_1").await.is_none());
+            // The agent was pinged, it just never answered.
+            assert!(matches!(rx.try_recv(), Ok(ServerMessage::Ping)));
+        });
+    }
+
+    #[test]
+    fn test_keepalive_keeps_agent_that_pongs() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let registry = Arc::new(AgentRegistry::new());
+            let (tx, mut rx) = mpsc::channel(16);
+            let connection = registry
+                .register(AgentConnection {
+                    connection_id: "conn_live".to_string(),
+                    subscriber_id: "sr_1".to_string(),
+                    sender: tx,
+                    connected_at: Utc::now(),
+                    health: ConnectionHealth::default(),
+                })
+                .await;
+
+            let agent = connection.clone();
+            let responder = tokio::spawn(async move {
+                while let Some(ServerMessage::Ping) = rx.recv().await {
+                    agent.health.record_pong(Utc::now());
+                }
+            });
+            let watchdog = tokio::spawn(keepalive(
+                registry.clone(),
+                connection,
+                Duration::from_millis(20),
+                Duration::from_millis(100),
+            ));
+
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            assert!(registry.get("sr_1").await.is_some());
+            watchdog.abort();
+            responder.abort();
+        });
+    }
+}
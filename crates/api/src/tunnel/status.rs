@@ -0,0 +1,87 @@
+use axum::{extract::State, Json};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::{
+    error::ApiResult,
+    middleware::auth::SubscriberAuth,
+    state::AppState,
+    tunnel::registry::{AgentConnection, ConnectionHealthSnapshot},
+};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TunnelStatusResponse {
+    pub subscriber_id: String,
+    pub connected: bool,
+    pub connection_id: Option<String>,
+    pub connected_at: Option<DateTime<Utc>>,
+    pub health: Option<ConnectionHealthSnapshot>,
+}
+
+impl TunnelStatusResponse {
+    pub fn new(subscriber_id: &str, connection: Option<&AgentConnection>) -> Self {
+        Self {
+            subscriber_id: subscriber_id.to_string(),
+            connected: connection.is_some(),
+            connection_id: connection.map(|conn| conn.connection_id.clone()),
+            connected_at: connection.map(|conn| conn.connected_at),
+            health: connection.map(|conn| conn.health.snapshot()),
+        }
+    }
+}
+
+/// Whether the caller's agent is connected, with its keepalive health.
+pub async fn tunnel_status(
+    State(state): State<AppState>,
+    SubscriberAuth(subscriber_id): SubscriberAuth,
+) -> ApiResult<Json<TunnelStatusResponse>> {
+    let connection = state.tunnel_registry.get(&subscriber_id).await;
+    Ok(Json(TunnelStatusResponse::new(
+        &subscriber_id,
+        connection.as_deref(),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tunnel::registry::ConnectionHealth;
+    use tokio::sync::mpsc;
+
+    #[test]
+    fn test_status_for_disconnected_subscriber() {
+        let status = TunnelStatusResponse::new("sr_1", None);
+        assert!(!status.connected);
+        assert!(status.health.is_none());
+    }
+
+    #[test]
+    fn test_status_reflects_connection_health() {
+        let (tx, _rx) = mpsc::channel(1);
+        let conn = AgentConnection {
+            connection_id: "conn_1".to_string(),
+            subscriber_id: "sr_1".to_string(),
+            sender: tx,
+            connected_at: Utc::now(),
+            health: ConnectionHealth::default(),
+        };
+        let ping_at = Utc::now();
+        conn.health.record_ping(ping_at);
+        conn.health
+            .record_pong(ping_at + chrono::Duration::milliseconds(15));
+        conn.health.record_delivered();
+        conn.health.record_acked();
+
+        let status = TunnelStatusResponse::new("sr_1", Some(&conn));
+        assert!(status.connected);
+        assert_eq!(status.connection_id.as_deref(), Some("conn_1"));
+        let health = status.health.unwrap();
+        assert_eq!(health.round_trip_ms, Some(15));
+        assert_eq!(health.messages_delivered, 1);
+        assert_eq!(health.messages_acked, 1);
+
+        let json = serde_json::to_value(TunnelStatusResponse::new("sr_1", Some(&conn))).unwrap();
+        assert_eq!(json["health"]["messagesAcked"], 1);
+    }
+}
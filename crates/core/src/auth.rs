@@ -1,9 +1,16 @@
 use hmac::{Hmac, Mac};
 use nanoid::nanoid;
-use sha2::{Digest, Sha256};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha512};
 
 pub const PUBLISHER_PREFIX: &str = "hld_pub_";
 pub const SUBSCRIBER_PREFIX: &str = "hld_sub_";
+pub const WEBHOOK_SECRET_PREFIX: &str = "whsec_";
+
+/// Names the HMAC behind a versioned `X-Herald-Signature`.
+pub const SIGNATURE_ALGORITHM_HEADER: &str = "X-Herald-Signature-Algorithm";
+/// Scheme tag of the current versioned signature (`t=<ts>,v1=<hmac>`).
+pub const SIGNATURE_VERSION: &str = "v1";
 
 pub fn generate_api_key(prefix: &str) -> (String, String, String) {
     let raw = format!("{}{}", prefix, nanoid!(24));
@@ -12,12 +19,40 @@ pub fn generate_api_key(prefix: &str) -> (String, String, String) {
     (raw, hash, key_prefix)
 }
 
+/// A fresh webhook signing secret.
+pub fn generate_webhook_secret() -> String {
+    format!("{}{}", WEBHOOK_SECRET_PREFIX, nanoid!(32))
+}
+
 pub fn hash_api_key(raw: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(raw.as_bytes());
     format!("{:x}", hasher.finalize())
 }
 
+/// Normalize a raw API token: surrounding whitespace is trimmed, and a token
+/// that is empty or contains inner whitespace is rejected.
+pub fn normalize_token(raw: &str) -> Option<&str> {
+    let token = raw.trim();
+    if token.is_empty() || token.contains(char::is_whitespace) {
+        return None;
+    }
+    Some(token)
+}
+
+/// Extract the token from an `Authorization: Bearer <token>` header value.
+///
+/// The scheme is matched case-insensitively and may be separated from the
+/// token by any run of whitespace; the token itself goes through
+/// [`normalize_token`].
+pub fn parse_bearer(header: &str) -> Option<&str> {
+    let (scheme, rest) = header.trim().split_once(char::is_whitespace)?;
+    if !scheme.eq_ignore_ascii_case("bearer") {
+        return None;
+    }
+    normalize_token(rest)
+}
+
 /// Sign a payload with HMAC-SHA256.
 /// 
 /// Note: new_from_slice only fails for algorithms with key length constraints.
@@ -31,9 +66,160 @@ pub fn sign_payload(secret: &str, timestamp: i64, body: &str) -> String {
     format!("sha256={:x}", mac.finalize().into_bytes())
 }
 
+/// HMAC used for versioned signatures.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureAlgorithm {
+    #[default]
+    Sha256,
+    Sha512,
+}
+
+impl SignatureAlgorithm {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SignatureAlgorithm::Sha256 => "sha256",
+            SignatureAlgorithm::Sha512 => "sha512",
+        }
+    }
+
+    /// Hex digest of the HMAC over `{timestamp}.{body}`.
+    fn hmac_hex(&self, secret: &str, timestamp: i64, body: &str) -> String {
+        let data = format!("{}.{}", timestamp, body);
+        // HMAC accepts any key length, so neither constructor can fail
+        match self {
+            SignatureAlgorithm::Sha256 => {
+                let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+                    .expect("HMAC-SHA256 accepts any key length");
+                mac.update(data.as_bytes());
+                format!("{:x}", mac.finalize().into_bytes())
+            }
+            SignatureAlgorithm::Sha512 => {
+                let mut mac = Hmac::<Sha512>::new_from_slice(secret.as_bytes())
+                    .expect("HMAC-SHA512 accepts any key length");
+                mac.update(data.as_bytes());
+                format!("{:x}", mac.finalize().into_bytes())
+            }
+        }
+    }
+
+    /// The algorithm whose hex digest has this length.
+    fn from_hex_len(len: usize) -> Option<Self> {
+        match len {
+            64 => Some(SignatureAlgorithm::Sha256),
+            128 => Some(SignatureAlgorithm::Sha512),
+            _ => None,
+        }
+    }
+}
+
+/// Sign a payload in the versioned format: `t=<timestamp>,v1=<hex hmac>`.
+///
+/// Verifiers should check every `vN` they understand and ignore the rest, so
+/// later schemes can be sent alongside `v1` without breaking them.
+pub fn sign_versioned(
+    secret: &str,
+    timestamp: i64,
+    body: &str,
+    algorithm: SignatureAlgorithm,
+) -> String {
+    format!(
+        "t={},{}={}",
+        timestamp,
+        SIGNATURE_VERSION,
+        algorithm.hmac_hex(secret, timestamp, body)
+    )
+}
+
+/// Check a signature in either the versioned format or the legacy
+/// `sha256=<hex>` one.
+///
+/// A versioned signature is valid when its `t` equals `timestamp` and any
+/// `v1` entry matches the HMAC (SHA-256 or SHA-512, told apart by length).
 pub fn verify_signature(secret: &str, timestamp: i64, body: &str, signature: &str) -> bool {
-    let expected = sign_payload(secret, timestamp, body);
-    subtle::ConstantTimeEq::ct_eq(expected.as_bytes(), signature.as_bytes()).into()
+    let signature = signature.trim();
+    if signature.starts_with("sha256=") {
+        let expected = sign_payload(secret, timestamp, body);
+        return subtle::ConstantTimeEq::ct_eq(expected.as_bytes(), signature.as_bytes()).into();
+    }
+
+    let mut signed_at = None;
+    let mut candidates = Vec::new();
+    for part in signature.split(',') {
+        let Some((key, value)) = part.trim().split_once('=') else {
+            return false;
+        };
+        match key {
+            "t" => signed_at = value.parse::<i64>().ok(),
+            SIGNATURE_VERSION => candidates.push(value),
+            // Schemes this version does not know are skipped
+            _ => {}
+        }
+    }
+    if signed_at != Some(timestamp) {
+        return false;
+    }
+
+    candidates.into_iter().any(|candidate| {
+        let Some(algorithm) = SignatureAlgorithm::from_hex_len(candidate.len()) else {
+            return false;
+        };
+        let expected = algorithm.hmac_hex(secret, timestamp, body);
+        subtle::ConstantTimeEq::ct_eq(expected.as_bytes(), candidate.as_bytes()).into()
+    })
+}
+
+/// Result of [`verify_signature_fresh`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureCheck {
+    Valid,
+    BadSignature,
+    /// The HMAC matches but the timestamp is outside the tolerance window.
+    Expired,
+}
+
+/// Like [`verify_signature`], but also rejects timestamps more than
+/// `tolerance_secs` away from `now` (unix seconds) to limit replays.
+pub fn verify_signature_fresh(
+    secret: &str,
+    timestamp: i64,
+    body: &str,
+    signature: &str,
+    tolerance_secs: i64,
+    now: i64,
+) -> SignatureCheck {
+    if !verify_signature(secret, timestamp, body, signature) {
+        return SignatureCheck::BadSignature;
+    }
+    if now.abs_diff(timestamp) > tolerance_secs.max(0) as u64 {
+        return SignatureCheck::Expired;
+    }
+    SignatureCheck::Valid
+}
+
+/// Check a versioned `X-Herald-Signature` header in one call: the HMAC, and
+/// that its `t` is within `max_age_secs` of `now` (unix seconds).
+///
+/// The timestamp comes from the header itself, so it is covered by the HMAC
+/// and cannot be swapped for a fresh one. Legacy `sha256=` headers carry no
+/// timestamp and are rejected; check those with [`verify_signature_fresh`].
+pub fn verify_signature_header(
+    secret: &str,
+    body: &str,
+    header: &str,
+    max_age_secs: i64,
+    now: i64,
+) -> SignatureCheck {
+    let timestamp = header
+        .split(',')
+        .find_map(|part| part.trim().strip_prefix("t="))
+        .and_then(|value| value.parse::<i64>().ok());
+    match timestamp {
+        Some(timestamp) => {
+            verify_signature_fresh(secret, timestamp, body, header, max_age_secs, now)
+        }
+        None => SignatureCheck::BadSignature,
+    }
 }
 
 #[cfg(test)]
@@ -62,6 +248,16 @@ mod tests {
         assert_eq!(hash.len(), 64);
     }
 
+    #[test]
+    fn test_generate_webhook_secret() {
+        let first = generate_webhook_secret();
+        let second = generate_webhook_secret();
+
+        assert!(first.starts_with(WEBHOOK_SECRET_PREFIX));
+        assert_eq!(first.len(), 6 + 32);
+        assert_ne!(first, second, "secrets should be unique");
+    }
+
     #[test]
     fn test_hash_api_key_deterministic() {
         let key = "hld_pub_test123456789012345678";
@@ -172,6 +368,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_sign_versioned_format() {
+        let sha256 = sign_versioned("secret", 1707379800, "body", SignatureAlgorithm::Sha256);
+        let sha512 = sign_versioned("secret", 1707379800, "body", SignatureAlgorithm::Sha512);
+
+        assert!(sha256.starts_with("t=1707379800,v1="));
+        assert_eq!(sha256.len(), "t=1707379800,v1=".len() + 64);
+        assert_eq!(sha512.len(), "t=1707379800,v1=".len() + 128);
+        // The legacy format carries the same SHA-256 HMAC
+        let legacy = sign_payload("secret", 1707379800, "body");
+        assert!(sha256.ends_with(legacy.trim_start_matches("sha256=")));
+    }
+
+    #[test]
+    fn test_verify_signature_versioned() {
+        for algorithm in [SignatureAlgorithm::Sha256, SignatureAlgorithm::Sha512] {
+            let signature = sign_versioned("secret", 1_000, "{}", algorithm);
+            assert!(verify_signature("secret", 1_000, "{}", &signature));
+            assert!(!verify_signature("other", 1_000, "{}", &signature));
+            assert!(!verify_signature("secret", 1_001, "{}", &signature));
+            assert!(!verify_signature("secret", 1_000, "{ }", &signature));
+        }
+    }
+
+    #[test]
+    fn test_verify_signature_checks_any_known_version() {
+        let v1 = sign_versioned("secret", 1_000, "{}", SignatureAlgorithm::Sha256);
+        let hmac = v1.trim_start_matches("t=1000,v1=");
+
+        // Unknown schemes are ignored and any matching v1 entry is enough
+        let with_v2 = format!("t=1000,v2=deadbeef,v1={hmac}");
+        assert!(verify_signature("secret", 1_000, "{}", &with_v2));
+        let rotated = format!("t=1000,v1={},v1={hmac}", "0".repeat(64));
+        assert!(verify_signature("secret", 1_000, "{}", &rotated));
+
+        assert!(!verify_signature("secret", 1_000, "{}", "t=1000,v2=deadbeef"));
+        assert!(!verify_signature("secret", 1_000, "{}", &format!("v1={hmac}")));
+        assert!(!verify_signature("secret", 1_000, "{}", &format!("t=1001,v1={hmac}")));
+        assert!(!verify_signature("secret", 1_000, "{}", &format!("t=1000,v1={hmac},junk")));
+    }
+
     #[test]
     fn test_api_key_uniqueness() {
         let (key1, _, _) = generate_api_key(PUBLISHER_PREFIX);
@@ -179,4 +416,85 @@ mod tests {
         
         assert_ne!(key1, key2, "generated keys should be unique");
     }
+
+    #[test]
+    fn test_normalize_token_trims_and_rejects_blank() {
+        assert_eq!(normalize_token("hld_sub_abc"), Some("hld_sub_abc"));
+        assert_eq!(normalize_token("  hld_sub_abc\t\n"), Some("hld_sub_abc"));
+        assert_eq!(normalize_token(""), None);
+        assert_eq!(normalize_token(" \t "), None);
+        assert_eq!(normalize_token("hld_sub abc"), None);
+    }
+
+    #[test]
+    fn test_parse_bearer_edge_cases() {
+        assert_eq!(parse_bearer("Bearer hld_pub_abc"), Some("hld_pub_abc"));
+        assert_eq!(parse_bearer("bearer hld_pub_abc"), Some("hld_pub_abc"));
+        assert_eq!(parse_bearer("Bearer  hld_pub_abc"), Some("hld_pub_abc"));
+        assert_eq!(parse_bearer("Bearer\thld_pub_abc"), Some("hld_pub_abc"));
+        assert_eq!(parse_bearer("Bearer hld_pub_abc  \t"), Some("hld_pub_abc"));
+        assert_eq!(parse_bearer("Bearer  "), None);
+        assert_eq!(parse_bearer("Bearer \t"), None);
+        assert_eq!(parse_bearer("Bearer"), None);
+        assert_eq!(parse_bearer(""), None);
+        assert_eq!(parse_bearer("Basic hld_pub_abc"), None);
+        assert_eq!(parse_bearer("Bearer hld_pub abc"), None);
+    }
+
+    #[test]
+    fn test_verify_signature_fresh_within_tolerance() {
+        let sig = sign_payload("secret", 1_000, "{}");
+        assert_eq!(verify_signature_fresh("secret", 1_000, "{}", &sig, 300, 1_000), SignatureCheck::Valid);
+        assert_eq!(verify_signature_fresh("secret", 1_000, "{}", &sig, 300, 1_300), SignatureCheck::Valid);
+        assert_eq!(verify_signature_fresh("secret", 1_000, "{}", &sig, 300, 700), SignatureCheck::Valid);
+    }
+
+    #[test]
+    fn test_verify_signature_fresh_rejects_stale_and_future() {
+        let sig = sign_payload("secret", 1_000, "{}");
+        assert_eq!(verify_signature_fresh("secret", 1_000, "{}", &sig, 300, 1_301), SignatureCheck::Expired);
+        assert_eq!(verify_signature_fresh("secret", 1_000, "{}", &sig, 300, 699), SignatureCheck::Expired);
+    }
+
+    #[test]
+    fn test_verify_signature_fresh_bad_signature() {
+        let sig = sign_payload("secret", 1_000, "{}");
+        assert_eq!(verify_signature_fresh("other", 1_000, "{}", &sig, 300, 1_000), SignatureCheck::BadSignature);
+        // A tampered timestamp invalidates the HMAC rather than reading as expired.
+        assert_eq!(verify_signature_fresh("secret", 5_000, "{}", &sig, 300, 5_000), SignatureCheck::BadSignature);
+    }
+
+    #[test]
+    fn test_verify_signature_header_checks_hmac_and_age() {
+        let header = sign_versioned("secret", 1_000, "{}", SignatureAlgorithm::Sha512);
+        assert_eq!(verify_signature_header("secret", "{}", &header, 300, 1_200), SignatureCheck::Valid);
+        assert_eq!(verify_signature_header("secret", "{}", &header, 300, 1_301), SignatureCheck::Expired);
+        assert_eq!(verify_signature_header("other", "{}", &header, 300, 1_000), SignatureCheck::BadSignature);
+
+        // Moving `t` forward to dodge the age check breaks the HMAC instead
+        let hmac = header.split_once(",v1=").unwrap().1;
+        let replayed = format!("t=5000,v1={hmac}");
+        assert_eq!(verify_signature_header("secret", "{}", &replayed, 300, 5_000), SignatureCheck::BadSignature);
+
+        let legacy = sign_payload("secret", 1_000, "{}");
+        assert_eq!(verify_signature_header("secret", "{}", &legacy, 300, 1_000), SignatureCheck::BadSignature);
+    }
+
+    #[test]
+    fn test_verify_signature_header_rejects_tampered_body_and_stale_timestamp() {
+        let body = r#"{"signal":{"title":"BTC above 100k"}}"#;
+        let header = sign_versioned("whsec_test", 1_700_000_000, body, SignatureAlgorithm::Sha256);
+        assert!(header.starts_with("t=1700000000,v1="));
+        assert_eq!(verify_signature_header("whsec_test", body, &header, 300, 1_700_000_100), SignatureCheck::Valid);
+
+        let tampered = r#"{"signal":{"title":"BTC above 200k"}}"#;
+        assert_eq!(
+            verify_signature_header("whsec_test", tampered, &header, 300, 1_700_000_100),
+            SignatureCheck::BadSignature
+        );
+        assert_eq!(
+            verify_signature_header("whsec_test", body, &header, 300, 1_700_000_301),
+            SignatureCheck::Expired
+        );
+    }
 }
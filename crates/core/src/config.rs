@@ -1,22 +1,74 @@
 use serde::Deserialize;
 
+use crate::limits::TierLimits;
+use crate::priority::PriorityWeights;
+use crate::rate_groups::RateGroups;
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct Settings {
     pub database_url: String,
+    /// Optional read replica used by read-heavy endpoints.
+    pub database_replica_url: Option<String>,
     pub redis_url: String,
     pub herald_env: String,
     pub api_bind: String,
     pub worker_concurrency: usize,
+    /// Platform-wide signing key for the optional global webhook signature.
     pub hmac_secret: String,
-    pub rate_limit_free: u32,
-    pub rate_limit_pro: u32,
-    pub rate_limit_ent: u32,
+    /// Also sign webhook bodies with `hmac_secret`, in
+    /// `X-Herald-Signature-Global`, next to the per-subscriber signature.
+    pub global_signature: bool,
+    /// Rate, size, and count limits for each account tier.
+    pub tier_limits: TierLimits,
+    /// Routes limited in their own buckets rather than the key's default one.
+    pub rate_limit_groups: RateGroups,
+    /// How long idempotency keys are kept before the worker purges them.
+    pub idempotency_retention_hours: i64,
+    /// Maximum bytes of a failed webhook's response body kept for debugging.
+    pub webhook_response_body_limit: usize,
+    /// Page size used by list endpoints when the client omits `limit`.
+    pub default_page_size: i64,
+    /// Upper bound on `limit` for list endpoints.
+    pub max_page_size: i64,
+    /// Seconds to wait for an agent `Ack` before a tunnel delivery is retried.
+    pub tunnel_ack_timeout_secs: u64,
+    /// Seconds between server pings to a connected agent. An agent that
+    /// misses three in a row is dropped.
+    pub tunnel_ping_secs: u64,
+    /// Outbound messages queued per agent connection before senders wait.
+    pub tunnel_buffer: usize,
+    /// Ceiling on simultaneous outbound webhook requests across the worker.
+    pub delivery_max_concurrency: usize,
+    /// Quick resends of a webhook request after a connect or timeout error,
+    /// within a single delivery attempt.
+    pub webhook_transient_retries: u32,
+    /// Urgency and channel-tier weights that set delivery job priority.
+    pub delivery_priority: PriorityWeights,
+    /// Port the worker serves its Prometheus `/metrics` endpoint on.
+    pub worker_metrics_port: u16,
+    /// Seconds a resolved API key is cached in Redis by the API; `0` turns
+    /// the cache off.
+    pub api_key_cache_secs: u64,
+    /// Requests per minute from one client IP, checked before authentication;
+    /// `0` turns the check off.
+    pub ip_rate_limit_per_min: u32,
+    /// Proxies in front of the API that append to `X-Forwarded-For`. The
+    /// default `0` ignores the header and uses the connection's peer address;
+    /// deployments behind a load balancer opt in with the hop count.
+    pub trusted_proxies: usize,
+    /// Bearer token for creating publisher and subscriber accounts; account
+    /// creation is disabled when unset.
+    pub bootstrap_token: Option<String>,
 }
 
 impl Settings {
     pub fn from_env() -> Result<Self, std::env::VarError> {
         let database_url =
             std::env::var("DATABASE_URL").or_else(|_| std::env::var("HERALD_DATABASE_URL"))?;
+        let database_replica_url = std::env::var("DATABASE_REPLICA_URL")
+            .or_else(|_| std::env::var("HERALD_DATABASE_REPLICA_URL"))
+            .ok()
+            .filter(|v| !v.trim().is_empty());
         let redis_url =
             std::env::var("REDIS_URL").or_else(|_| std::env::var("HERALD_REDIS_URL"))?;
         let herald_env = std::env::var("HERALD_ENV").unwrap_or_else(|_| "dev".to_string());
@@ -28,29 +80,126 @@ impl Settings {
             .unwrap_or(4);
         let hmac_secret =
             std::env::var("HERALD_HMAC_SECRET").or_else(|_| std::env::var("HMAC_SECRET"))?;
-        let rate_limit_free = std::env::var("HERALD_RATE_LIMIT_FREE")
+        let global_signature = std::env::var("HERALD_GLOBAL_SIGNATURE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(false);
+        let tier_limits = TierLimits::from_env();
+        let rate_limit_groups = RateGroups::from_env();
+        let idempotency_retention_hours = std::env::var("HERALD_IDEMPOTENCY_RETENTION_HOURS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(24);
+        let webhook_response_body_limit = std::env::var("HERALD_WEBHOOK_RESPONSE_BODY_LIMIT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(2048);
+        let default_page_size = std::env::var("HERALD_DEFAULT_PAGE_SIZE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(50);
+        let max_page_size = std::env::var("HERALD_MAX_PAGE_SIZE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(100);
+        let tunnel_ack_timeout_secs = std::env::var("HERALD_TUNNEL_ACK_TIMEOUT_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(30);
+        let tunnel_ping_secs = std::env::var("HERALD_TUNNEL_PING_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(30);
+        let tunnel_buffer = std::env::var("HERALD_TUNNEL_BUFFER")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(64);
+        let delivery_max_concurrency = std::env::var("HERALD_DELIVERY_MAX_CONCURRENCY")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(64);
+        let webhook_transient_retries = std::env::var("HERALD_WEBHOOK_TRANSIENT_RETRIES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(2);
+        let delivery_priority = PriorityWeights::from_env();
+        let worker_metrics_port = std::env::var("HERALD_WORKER_METRICS_PORT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(9091);
+        let api_key_cache_secs = std::env::var("HERALD_API_KEY_CACHE_SECS")
             .ok()
             .and_then(|v| v.parse().ok())
             .unwrap_or(60);
-        let rate_limit_pro = std::env::var("HERALD_RATE_LIMIT_PRO")
+        let ip_rate_limit_per_min = std::env::var("HERALD_IP_RATE_LIMIT")
             .ok()
             .and_then(|v| v.parse().ok())
-            .unwrap_or(600);
-        let rate_limit_ent = std::env::var("HERALD_RATE_LIMIT_ENT")
+            .unwrap_or(1200);
+        let trusted_proxies = std::env::var("HERALD_TRUSTED_PROXIES")
             .ok()
             .and_then(|v| v.parse().ok())
-            .unwrap_or(6000);
+            .unwrap_or(0);
+        let bootstrap_token = std::env::var("HERALD_BOOTSTRAP_TOKEN")
+            .ok()
+            .filter(|v| !v.trim().is_empty());
 
         Ok(Self {
             database_url,
+            database_replica_url,
             redis_url,
             herald_env,
             api_bind,
             worker_concurrency,
             hmac_secret,
-            rate_limit_free,
-            rate_limit_pro,
-            rate_limit_ent,
+            global_signature,
+            tier_limits,
+            rate_limit_groups,
+            idempotency_retention_hours,
+            webhook_response_body_limit,
+            default_page_size,
+            max_page_size,
+            tunnel_ack_timeout_secs,
+            tunnel_ping_secs,
+            tunnel_buffer,
+            delivery_max_concurrency,
+            webhook_transient_retries,
+            delivery_priority,
+            worker_metrics_port,
+            api_key_cache_secs,
+            ip_rate_limit_per_min,
+            trusted_proxies,
+            bootstrap_token,
         })
     }
+
+    /// Check cross-field constraints that env parsing alone cannot express.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_page_size < 1 {
+            return Err("HERALD_MAX_PAGE_SIZE must be at least 1".to_string());
+        }
+        if self.default_page_size < 1 || self.default_page_size > self.max_page_size {
+            return Err(format!(
+                "HERALD_DEFAULT_PAGE_SIZE must be between 1 and {}",
+                self.max_page_size
+            ));
+        }
+        self.tier_limits.validate()?;
+        self.rate_limit_groups.validate()?;
+        self.delivery_priority.validate()?;
+        if self.tunnel_ping_secs < 1 {
+            return Err("HERALD_TUNNEL_PING_SECS must be at least 1".to_string());
+        }
+        if self.tunnel_buffer < 1 {
+            return Err("HERALD_TUNNEL_BUFFER must be at least 1".to_string());
+        }
+        if self.global_signature && self.hmac_secret.trim().is_empty() {
+            return Err(
+                "HERALD_HMAC_SECRET must be set when HERALD_GLOBAL_SIGNATURE is on".to_string(),
+            );
+        }
+        if self.delivery_max_concurrency < 1 {
+            return Err("HERALD_DELIVERY_MAX_CONCURRENCY must be at least 1".to_string());
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,120 @@
+//! Idempotency-key helpers.
+//!
+//! Clients send an `Idempotency-Key` header on writes they may retry. The
+//! first request under a key stores a hash of its body alongside the response;
+//! a replay with the same body gets the stored response back, while a replay
+//! with a different body is rejected.
+//!
+//! Separately, channels may opt into rejecting repeated signals; those are
+//! matched on [`signal_content_hash`].
+
+use sha2::{Digest, Sha256};
+
+/// Request header carrying the client-chosen idempotency key.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// Longest accepted idempotency key, in characters.
+pub const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+
+/// Result of comparing a replayed request against the stored key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayCheck {
+    /// Same request body: return the stored response without re-executing.
+    Replay,
+    /// Different request body under the same key: reject.
+    Conflict,
+}
+
+/// Reject keys longer than [`MAX_IDEMPOTENCY_KEY_LEN`].
+pub fn validate_key(key: &str) -> Result<(), String> {
+    if key.chars().count() > MAX_IDEMPOTENCY_KEY_LEN {
+        return Err(format!(
+            "{IDEMPOTENCY_KEY_HEADER} must be at most {MAX_IDEMPOTENCY_KEY_LEN} characters"
+        ));
+    }
+    Ok(())
+}
+
+/// Hash a request body so replays can be compared with the original request.
+pub fn hash_request(body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(body);
+    format!("{:x}", hasher.finalize())
+}
+
+/// Hash a signal's title and body for per-channel deduplication.
+///
+/// The title is length-prefixed so moving text between the two fields yields
+/// a different hash.
+pub fn signal_content_hash(title: &str, body: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(title.len().to_string().as_bytes());
+    hasher.update(b":");
+    hasher.update(title.as_bytes());
+    hasher.update(body.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Compare the stored request hash for a key with the incoming one.
+pub fn check_replay(stored_hash: &str, incoming_hash: &str) -> ReplayCheck {
+    if subtle::ConstantTimeEq::ct_eq(stored_hash.as_bytes(), incoming_hash.as_bytes()).into() {
+        ReplayCheck::Replay
+    } else {
+        ReplayCheck::Conflict
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_request_deterministic() {
+        let body = br#"{"title":"Alert","body":"Disk full"}"#;
+        assert_eq!(hash_request(body), hash_request(body));
+        assert_eq!(hash_request(body).len(), 64);
+    }
+
+    #[test]
+    fn test_hash_request_different_bodies() {
+        assert_ne!(hash_request(b"one"), hash_request(b"two"));
+    }
+
+    #[test]
+    fn test_check_replay_same_body_replays() {
+        let first = hash_request(br#"{"title":"Alert"}"#);
+        let replay = hash_request(br#"{"title":"Alert"}"#);
+        assert_eq!(check_replay(&first, &replay), ReplayCheck::Replay);
+    }
+
+    #[test]
+    fn test_check_replay_different_body_conflicts() {
+        let first = hash_request(br#"{"title":"Alert"}"#);
+        let other = hash_request(br#"{"title":"Other"}"#);
+        assert_eq!(check_replay(&first, &other), ReplayCheck::Conflict);
+    }
+
+    #[test]
+    fn test_signal_content_hash_separates_title_and_body() {
+        assert_eq!(
+            signal_content_hash("Alert", "Disk full"),
+            signal_content_hash("Alert", "Disk full")
+        );
+        assert_ne!(
+            signal_content_hash("Alert", "Disk full"),
+            signal_content_hash("Alert Disk", " full")
+        );
+        assert_ne!(
+            signal_content_hash("ab", "c"),
+            signal_content_hash("a", "bc")
+        );
+    }
+
+    #[test]
+    fn test_validate_key_length_boundary() {
+        assert!(validate_key(&"k".repeat(MAX_IDEMPOTENCY_KEY_LEN)).is_ok());
+        assert!(validate_key(&"k".repeat(MAX_IDEMPOTENCY_KEY_LEN + 1)).is_err());
+        // Counted in characters, not bytes.
+        assert!(validate_key(&"é".repeat(MAX_IDEMPOTENCY_KEY_LEN)).is_ok());
+    }
+}
@@ -1,5 +1,17 @@
 pub mod auth;
 pub mod config;
+pub mod idempotency;
+pub mod limits;
+pub mod metrics;
+pub mod net;
+pub mod payload;
+pub mod presence;
+pub mod priority;
+pub mod quotas;
+pub mod rate_groups;
+pub mod receipts;
+pub mod schema;
+pub mod shutdown;
 pub mod tunnel;
 pub mod types;
 
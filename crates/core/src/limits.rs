@@ -0,0 +1,221 @@
+//! Per-tier account limits.
+//!
+//! Every tier-dependent limit lives in [`TierLimits`] so the API and worker
+//! enforce the same numbers. Defaults can be overridden per tier through
+//! `HERALD_{FREE,PRO,ENT}_*` environment variables.
+
+use serde::Deserialize;
+
+use crate::types::AccountTier;
+
+/// Limits that apply to one account tier.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Limits {
+    /// API requests per minute for each API key.
+    pub rate_limit_per_min: u32,
+    /// Longest signal `title`, in characters.
+    pub max_title_chars: usize,
+    /// Largest signal `body`, in bytes.
+    pub max_body_bytes: usize,
+    /// Largest serialized `metadata` object a publisher may attach to a signal.
+    pub max_metadata_bytes: usize,
+    /// Webhooks a subscriber may register.
+    pub max_webhooks: i64,
+    /// Subscribers a publisher's channel can fan signals out to.
+    pub max_fanout: i64,
+    /// Signals each of a publisher's channels may push per UTC day.
+    pub max_signals_per_day: u64,
+    /// Signals each of a publisher's channels may push per UTC calendar month.
+    pub max_signals_per_month: u64,
+}
+
+/// Limits for every account tier.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TierLimits {
+    pub free: Limits,
+    pub pro: Limits,
+    pub enterprise: Limits,
+}
+
+impl Default for TierLimits {
+    fn default() -> Self {
+        Self {
+            free: Limits {
+                rate_limit_per_min: 60,
+                max_title_chars: 512,
+                max_body_bytes: 16 * 1024,
+                max_metadata_bytes: 4 * 1024,
+                max_webhooks: 3,
+                max_fanout: 100,
+                max_signals_per_day: 100,
+                max_signals_per_month: 1_000,
+            },
+            pro: Limits {
+                rate_limit_per_min: 600,
+                max_title_chars: 512,
+                max_body_bytes: 64 * 1024,
+                max_metadata_bytes: 16 * 1024,
+                max_webhooks: 20,
+                max_fanout: 10_000,
+                max_signals_per_day: 10_000,
+                max_signals_per_month: 200_000,
+            },
+            enterprise: Limits {
+                rate_limit_per_min: 6000,
+                max_title_chars: 512,
+                max_body_bytes: 256 * 1024,
+                max_metadata_bytes: 64 * 1024,
+                max_webhooks: 100,
+                max_fanout: 100_000,
+                // Effectively unlimited.
+                max_signals_per_day: u64::MAX,
+                max_signals_per_month: u64::MAX,
+            },
+        }
+    }
+}
+
+impl TierLimits {
+    /// Defaults overridden by any `HERALD_{FREE,PRO,ENT}_*` variables that parse.
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            free: Limits::from_env("FREE", defaults.free),
+            pro: Limits::from_env("PRO", defaults.pro),
+            enterprise: Limits::from_env("ENT", defaults.enterprise),
+        }
+    }
+
+    pub fn for_tier(&self, tier: &AccountTier) -> &Limits {
+        match tier {
+            AccountTier::Free => &self.free,
+            AccountTier::Pro => &self.pro,
+            AccountTier::Enterprise => &self.enterprise,
+        }
+    }
+
+    /// Every limit must be positive and must not shrink as the tier goes up.
+    pub fn validate(&self) -> Result<(), String> {
+        let tiers = [
+            ("FREE", &self.free),
+            ("PRO", &self.pro),
+            ("ENT", &self.enterprise),
+        ];
+        for (name, limits) in tiers {
+            if limits.rate_limit_per_min == 0
+                || limits.max_title_chars == 0
+                || limits.max_body_bytes == 0
+                || limits.max_metadata_bytes == 0
+                || limits.max_webhooks < 1
+                || limits.max_fanout < 1
+                || limits.max_signals_per_day == 0
+                || limits.max_signals_per_month == 0
+            {
+                return Err(format!("HERALD_{name}_* limits must be at least 1"));
+            }
+        }
+        for pair in tiers.windows(2) {
+            let ((lower_name, lower), (upper_name, upper)) = (pair[0], pair[1]);
+            if !lower.within(upper) {
+                return Err(format!(
+                    "{lower_name} tier limits must not exceed {upper_name} tier limits"
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Limits {
+    fn from_env(tier: &str, defaults: Limits) -> Self {
+        // Rate limits keep their original variable names.
+        Self {
+            rate_limit_per_min: env_or(
+                &format!("HERALD_RATE_LIMIT_{tier}"),
+                defaults.rate_limit_per_min,
+            ),
+            max_title_chars: env_or(
+                &format!("HERALD_{tier}_MAX_TITLE_CHARS"),
+                defaults.max_title_chars,
+            ),
+            max_body_bytes: env_or(
+                &format!("HERALD_{tier}_MAX_BODY_BYTES"),
+                defaults.max_body_bytes,
+            ),
+            max_metadata_bytes: env_or(
+                &format!("HERALD_{tier}_MAX_METADATA_BYTES"),
+                defaults.max_metadata_bytes,
+            ),
+            max_webhooks: env_or(
+                &format!("HERALD_{tier}_MAX_WEBHOOKS"),
+                defaults.max_webhooks,
+            ),
+            max_fanout: env_or(&format!("HERALD_{tier}_MAX_FANOUT"), defaults.max_fanout),
+            max_signals_per_day: env_or(
+                &format!("HERALD_{tier}_MAX_SIGNALS_PER_DAY"),
+                defaults.max_signals_per_day,
+            ),
+            max_signals_per_month: env_or(
+                &format!("HERALD_{tier}_MAX_SIGNALS_PER_MONTH"),
+                defaults.max_signals_per_month,
+            ),
+        }
+    }
+
+    /// Whether every limit here is at most the matching limit in `other`.
+    fn within(&self, other: &Limits) -> bool {
+        self.rate_limit_per_min <= other.rate_limit_per_min
+            && self.max_title_chars <= other.max_title_chars
+            && self.max_body_bytes <= other.max_body_bytes
+            && self.max_metadata_bytes <= other.max_metadata_bytes
+            && self.max_webhooks <= other.max_webhooks
+            && self.max_fanout <= other.max_fanout
+            && self.max_signals_per_day <= other.max_signals_per_day
+            && self.max_signals_per_month <= other.max_signals_per_month
+    }
+}
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_limits_are_well_ordered() {
+        let limits = TierLimits::default();
+        assert!(limits.free.within(&limits.pro));
+        assert!(limits.pro.within(&limits.enterprise));
+        assert_eq!(limits.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_for_tier_selects_matching_limits() {
+        let limits = TierLimits::default();
+        assert_eq!(limits.for_tier(&AccountTier::Free).rate_limit_per_min, 60);
+        assert_eq!(limits.for_tier(&AccountTier::Pro).rate_limit_per_min, 600);
+        assert_eq!(
+            limits.for_tier(&AccountTier::Enterprise).rate_limit_per_min,
+            6000
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_inverted_tiers() {
+        let mut limits = TierLimits::default();
+        limits.free.max_webhooks = limits.pro.max_webhooks + 1;
+        assert!(limits.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_rejects_zero_limits() {
+        let mut limits = TierLimits::default();
+        limits.free.max_fanout = 0;
+        assert!(limits.validate().is_err());
+    }
+}
@@ -0,0 +1,362 @@
+//! Process-wide Prometheus metrics.
+//!
+//! The API and the worker each keep their own [`METRICS`] and serve it from
+//! their `/metrics` endpoint.
+
+use once_cell::sync::Lazy;
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::RwLock;
+use tracing::warn;
+
+/// Shards for the per-request HTTP counters.
+const COUNTER_SHARDS: usize = 16;
+
+/// Label-keyed counters split across shards by key hash.
+///
+/// Bumping an existing series takes a shard read lock plus an atomic add, so
+/// concurrent requests neither serialize on one mutex nor leave a counter
+/// half-updated if a thread panics. The write lock is only taken the first
+/// time a label set is seen.
+struct ShardedCounters<K> {
+    shards: Vec<RwLock<HashMap<K, AtomicU64>>>,
+    hasher: RandomState,
+}
+
+impl<K: Hash + Eq + Clone> ShardedCounters<K> {
+    fn new() -> Self {
+        Self {
+            shards: (0..COUNTER_SHARDS)
+                .map(|_| RwLock::new(HashMap::new()))
+                .collect(),
+            hasher: RandomState::new(),
+        }
+    }
+
+    fn shard(&self, key: &K) -> &RwLock<HashMap<K, AtomicU64>> {
+        let index = self.hasher.hash_one(key) as usize % self.shards.len();
+        &self.shards[index]
+    }
+
+    fn increment(&self, key: K) {
+        let shard = self.shard(&key);
+        {
+            let counters = shard
+                .read()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            if let Some(counter) = counters.get(&key) {
+                counter.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+        }
+        shard
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .entry(key)
+            .or_default()
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> Vec<(K, u64)> {
+        self.shards
+            .iter()
+            .flat_map(|shard| {
+                shard
+                    .read()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .iter()
+                    .map(|(key, counter)| (key.clone(), counter.load(Ordering::Relaxed)))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
+
+/// Upper bounds (seconds) of the delivery latency histogram buckets.
+const LATENCY_BUCKETS: [f64; 8] = [0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.0, 5.0];
+
+/// Per-bucket (non-cumulative) counts plus the running count and sum.
+#[derive(Default)]
+struct LatencyHistogram {
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl LatencyHistogram {
+    fn observe(&mut self, seconds: f64) {
+        if let Some(index) = LATENCY_BUCKETS.iter().position(|le| seconds <= *le) {
+            self.buckets[index] += 1;
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+}
+
+#[derive(Default)]
+struct MetricsStore {
+    signals: HashMap<(String, String), u64>,
+    deliveries: HashMap<String, u64>,
+    latency: HashMap<String, LatencyHistogram>,
+    queue_depth: HashMap<String, i64>,
+    tunnel_send_rejected: HashMap<String, u64>,
+    delivery_outcomes: HashMap<String, u64>,
+}
+
+pub struct Metrics {
+    http_requests: ShardedCounters<(String, String, u16)>,
+    store: Mutex<MetricsStore>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            http_requests: ShardedCounters::new(),
+            store: Mutex::new(MetricsStore::default()),
+        }
+    }
+
+    fn lock_store(&self) -> MutexGuard<'_, MetricsStore> {
+        match self.store.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => {
+                warn!("metrics store lock poisoned; continuing with inner state");
+                poisoned.into_inner()
+            }
+        }
+    }
+
+    pub fn record_http_request(&self, method: &str, path: &str, status: u16) {
+        self.http_requests
+            .increment((method.to_string(), path.to_string(), status));
+    }
+
+    pub fn record_signal(&self, channel: &str, urgency: &str) {
+        let mut store = self.lock_store();
+        *store
+            .signals
+            .entry((channel.to_string(), urgency.to_string()))
+            .or_insert(0) += 1;
+    }
+
+    pub fn record_delivery(&self, status: &str) {
+        let mut store = self.lock_store();
+        *store.deliveries.entry(status.to_string()).or_insert(0) += 1;
+    }
+
+    pub fn record_delivery_latency_histogram(&self, channel: &str, seconds: f64) {
+        let mut store = self.lock_store();
+        store
+            .latency
+            .entry(channel.to_string())
+            .or_default()
+            .observe(seconds);
+    }
+
+    /// A tunnel send the agent's connection refused: `full` when its buffer
+    /// had no room, `closed` when the connection had gone away.
+    pub fn record_tunnel_send_rejected(&self, reason: &str) {
+        let mut store = self.lock_store();
+        *store
+            .tunnel_send_rejected
+            .entry(reason.to_string())
+            .or_insert(0) += 1;
+    }
+
+    /// How a delivery job ended, e.g. `delivered` or `dead_lettered`.
+    pub fn record_delivery_outcome(&self, outcome: &str) {
+        let mut store = self.lock_store();
+        *store
+            .delivery_outcomes
+            .entry(outcome.to_string())
+            .or_insert(0) += 1;
+    }
+
+    pub fn set_queue_depth(&self, queue: &str, depth: i64) {
+        let mut store = self.lock_store();
+        store.queue_depth.insert(queue.to_string(), depth);
+    }
+
+    pub fn gather(&self) -> String {
+        let mut out = String::new();
+
+        out.push_str("# TYPE herald_http_requests_total counter\n");
+        for ((method, path, status), value) in self.http_requests.snapshot() {
+            out.push_str(&format!(
+                "herald_http_requests_total{{method=\"{}\",path=\"{}\",status=\"{}\"}} {}\n",
+                method, path, status, value
+            ));
+        }
+
+        let store = self.lock_store();
+        out.push_str("# TYPE herald_signals_total counter\n");
+        for ((channel, urgency), value) in &store.signals {
+            out.push_str(&format!(
+                "herald_signals_total{{channel=\"{}\",urgency=\"{}\"}} {}\n",
+                channel, urgency, value
+            ));
+        }
+
+        out.push_str("# TYPE herald_deliveries_total counter\n");
+        for (status, value) in &store.deliveries {
+            out.push_str(&format!(
+                "herald_deliveries_total{{status=\"{}\"}} {}\n",
+                status, value
+            ));
+        }
+
+        out.push_str("# TYPE herald_delivery_latency_seconds histogram\n");
+        for (channel, histogram) in &store.latency {
+            let mut cumulative = 0;
+            for (le, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+                cumulative += count;
+                out.push_str(&format!(
+                    "herald_delivery_latency_seconds_bucket{{channel=\"{}\",le=\"{}\"}} {}\n",
+                    channel, le, cumulative
+                ));
+            }
+            out.push_str(&format!(
+                "herald_delivery_latency_seconds_bucket{{channel=\"{}\",le=\"+Inf\"}} {}\n",
+                channel, histogram.count
+            ));
+            out.push_str(&format!(
+                "herald_delivery_latency_seconds_count{{channel=\"{}\"}} {}\n",
+                channel, histogram.count
+            ));
+            out.push_str(&format!(
+                "herald_delivery_latency_seconds_sum{{channel=\"{}\"}} {}\n",
+                channel, histogram.sum
+            ));
+        }
+
+        out.push_str("# TYPE herald_queue_depth gauge\n");
+        for (queue, depth) in &store.queue_depth {
+            out.push_str(&format!(
+                "herald_queue_depth{{queue=\"{}\"}} {}\n",
+                queue, depth
+            ));
+        }
+
+        out.push_str("# TYPE herald_tunnel_send_rejected_total counter\n");
+        for (reason, value) in &store.tunnel_send_rejected {
+            out.push_str(&format!(
+                "herald_tunnel_send_rejected_total{{reason=\"{}\"}} {}\n",
+                reason, value
+            ));
+        }
+
+        out.push_str("# TYPE herald_delivery_outcomes_total counter\n");
+        for (outcome, value) in &store.delivery_outcomes {
+            out.push_str(&format!(
+                "herald_delivery_outcomes_total{{outcome=\"{}\"}} {}\n",
+                outcome, value
+            ));
+        }
+
+        out
+    }
+}
+
+pub static METRICS: Lazy<Metrics> = Lazy::new(Metrics::new);
+
+#[cfg(test)]
+mod tests {
+    use super::Metrics;
+
+    #[test]
+    fn metrics_gather_includes_recorded_values() {
+        let metrics = Metrics::new();
+
+        metrics.record_http_request("GET", "/health", 200);
+        metrics.record_http_request("GET", "/health", 200);
+        metrics.record_signal("ch_123", "high");
+        metrics.record_delivery("success");
+        metrics.record_delivery_latency_histogram("ch_123", 1.25);
+        metrics.set_queue_depth("delivery-normal", 3);
+        metrics.record_tunnel_send_rejected("full");
+        metrics.record_delivery_outcome("dead_lettered");
+
+        let output = metrics.gather();
+
+        assert!(output.contains("herald_http_requests_total"));
+        assert!(output.contains("method=\"GET\""));
+        assert!(output.contains("path=\"/health\""));
+        assert!(output.contains("status=\"200\""));
+        assert!(output.contains("} 2"));
+
+        assert!(output.contains("herald_signals_total"));
+        assert!(output.contains("channel=\"ch_123\""));
+        assert!(output.contains("urgency=\"high\""));
+
+        assert!(output.contains("herald_deliveries_total"));
+        assert!(output.contains("status=\"success\""));
+
+        assert!(output.contains("herald_delivery_latency_seconds_count"));
+        assert!(output.contains("herald_delivery_latency_seconds_sum"));
+
+        assert!(output.contains("herald_queue_depth"));
+        assert!(output.contains("queue=\"delivery-normal\""));
+        assert!(output.contains("} 3"));
+
+        assert!(output.contains("herald_tunnel_send_rejected_total{reason=\"full\"} 1"));
+        assert!(output.contains("herald_delivery_outcomes_total{outcome=\"dead_lettered\"} 1"));
+    }
+
+    #[test]
+    fn metrics_latency_histogram_buckets_are_cumulative() {
+        let metrics = Metrics::new();
+        for seconds in [0.005, 0.08, 0.3, 1.5, 12.0] {
+            metrics.record_delivery_latency_histogram("ch_1", seconds);
+        }
+
+        let output = metrics.gather();
+        let bucket = |le: &str| {
+            format!("herald_delivery_latency_seconds_bucket{{channel=\"ch_1\",le=\"{le}\"}} ")
+        };
+
+        assert!(output.contains("# TYPE herald_delivery_latency_seconds histogram"));
+        assert!(output.contains(&format!("{}1\n", bucket("0.01"))));
+        assert!(output.contains(&format!("{}1\n", bucket("0.05"))));
+        assert!(output.contains(&format!("{}2\n", bucket("0.1"))));
+        assert!(output.contains(&format!("{}3\n", bucket("0.5"))));
+        assert!(output.contains(&format!("{}4\n", bucket("2"))));
+        assert!(output.contains(&format!("{}4\n", bucket("5"))));
+        assert!(output.contains(&format!("{}5\n", bucket("+Inf"))));
+        assert!(output.contains("herald_delivery_latency_seconds_count{channel=\"ch_1\"} 5\n"));
+    }
+
+    #[test]
+    fn metrics_http_counters_are_exact_under_concurrency() {
+        let metrics = std::sync::Arc::new(Metrics::new());
+        let handles: Vec<_> = (0..8)
+            .map(|thread| {
+                let metrics = metrics.clone();
+                std::thread::spawn(move || {
+                    for i in 0..1_000 {
+                        let path = if (i + thread) % 2 == 0 { "/a" } else { "/b" };
+                        metrics.record_http_request("GET", path, 200);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let counts = metrics.http_requests.snapshot();
+        let total: u64 = counts.iter().map(|(_, count)| count).sum();
+        assert_eq!(counts.len(), 2);
+        assert_eq!(total, 8_000);
+        assert!(counts.iter().all(|(_, count)| *count == 4_000));
+    }
+}
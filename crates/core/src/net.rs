@@ -0,0 +1,270 @@
+//! Network address policy for outbound webhook requests.
+//!
+//! Subscribers choose where Herald sends requests, so in prod every client
+//! that follows a subscriber-supplied URL is built with [`outbound_client`].
+//! Its resolver drops private addresses from each lookup, so the address that
+//! was checked is the one connected to and a hostname re-pointed at internal
+//! infra after registration (DNS rebinding) gets nowhere. IP literals skip
+//! DNS entirely and are checked by [`check_public_url`] and the redirect
+//! policy instead.
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+/// Redirects an outbound client follows before giving up, as reqwest does by
+/// default.
+const MAX_REDIRECTS: usize = 10;
+
+/// Addresses Herald must never send webhooks to: loopback, private (RFC 1918 /
+/// unique local), link-local (including cloud metadata), unspecified, and the
+/// IPv4 shared (CGNAT), benchmarking, and IETF protocol ranges.
+pub fn is_private_ip(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let octets = v4.octets();
+            v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                // 100.64.0.0/10 carrier-grade NAT shared space
+                || (octets[0] == 100 && (octets[1] & 0xc0) == 64)
+                // 198.18.0.0/15 benchmarking
+                || (octets[0] == 198 && (octets[1] & 0xfe) == 18)
+                // 192.0.0.0/24 IETF protocol assignments
+                || (octets[0] == 192 && octets[1] == 0 && octets[2] == 0)
+        }
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_private_ip(&IpAddr::V4(v4));
+            }
+            let segments = v6.segments();
+            v6.is_loopback()
+                || v6.is_unspecified()
+                // fc00::/7 unique local
+                || (segments[0] & 0xfe00) == 0xfc00
+                // fe80::/10 link-local
+                || (segments[0] & 0xffc0) == 0xfe80
+        }
+    }
+}
+
+/// Parse a URL host (as returned by `Url::host_str`) when it is an IP literal.
+pub fn host_ip(host: &str) -> Option<IpAddr> {
+    host.trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse()
+        .ok()
+}
+
+/// Why a URL's host may not be sent to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TargetError {
+    /// The URL has no usable host.
+    Invalid(String),
+    /// The host lookup failed; it may succeed later.
+    Unresolved(String),
+    /// The host is, or resolves only to, private addresses. A policy failure
+    /// that retrying will not fix.
+    Private(String),
+}
+
+impl std::fmt::Display for TargetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TargetError::Invalid(msg)
+            | TargetError::Unresolved(msg)
+            | TargetError::Private(msg) => f.write_str(msg),
+        }
+    }
+}
+
+/// Resolves hostnames to their public addresses only, failing when a name
+/// has none.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs = public_addrs(name.as_str(), 0).await.map_err(
+                |err| -> Box<dyn std::error::Error + Send + Sync> { err.to_string().into() },
+            )?;
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// The public addresses `host` resolves to. Errors when the lookup fails or
+/// every address is private.
+pub async fn public_addrs(host: &str, port: u16) -> Result<Vec<SocketAddr>, TargetError> {
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|err| TargetError::Unresolved(format!("could not resolve {host}: {err}")))?
+        .collect();
+    let public: Vec<SocketAddr> = addrs
+        .iter()
+        .copied()
+        .filter(|addr| !is_private_ip(&addr.ip()))
+        .collect();
+    if public.is_empty() && !addrs.is_empty() {
+        return Err(TargetError::Private(format!(
+            "{host} resolves to a private address"
+        )));
+    }
+    if public.is_empty() {
+        return Err(TargetError::Unresolved(format!("could not resolve {host}")));
+    }
+    Ok(public)
+}
+
+/// Reject a URL whose host is a private IP literal or, once resolved, has
+/// only private addresses.
+pub async fn check_public_url(url: &str) -> Result<(), TargetError> {
+    let url = reqwest::Url::parse(url).map_err(|_| TargetError::Invalid("invalid url".into()))?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| TargetError::Invalid("url has no host".into()))?;
+    if let Some(ip) = host_ip(host) {
+        return match is_private_ip(&ip) {
+            true => Err(TargetError::Private(format!("{ip} is a private address"))),
+            false => Ok(()),
+        };
+    }
+    public_addrs(host, url.port_or_known_default().unwrap_or(443))
+        .await
+        .map(|_| ())
+}
+
+/// An HTTP client for subscriber-supplied URLs. In prod it resolves through
+/// [`PublicResolver`] and refuses redirects to private IP literals.
+pub fn outbound_client(env: &str, timeout: Duration) -> reqwest::Result<reqwest::Client> {
+    let builder = reqwest::Client::builder().timeout(timeout);
+    if env != "prod" {
+        return builder.build();
+    }
+    builder
+        .dns_resolver(std::sync::Arc::new(PublicResolver))
+        .redirect(reqwest::redirect::Policy::custom(|attempt| {
+            let private = attempt
+                .url()
+                .host_str()
+                .and_then(host_ip)
+                .is_some_and(|ip| is_private_ip(&ip));
+            if private {
+                attempt.error("redirect to a private address")
+            } else if attempt.previous().len() >= MAX_REDIRECTS {
+                attempt.error("too many redirects")
+            } else {
+                attempt.follow()
+            }
+        }))
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_private_and_metadata_addresses_are_blocked() {
+        for ip in [
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "127.0.0.1",
+            "169.254.169.254",
+            "0.0.0.0",
+            "100.64.0.1",
+            "100.127.255.254",
+            "198.18.0.1",
+            "198.19.255.255",
+            "192.0.0.8",
+            "::ffff:100.64.0.1",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:10.0.0.1",
+        ] {
+            assert!(
+                is_private_ip(&ip.parse().unwrap()),
+                "{ip} should be blocked"
+            );
+        }
+    }
+
+    #[test]
+    fn test_public_addresses_are_allowed() {
+        for ip in [
+            "93.184.216.34",
+            "8.8.8.8",
+            "2606:4700::1111",
+            // Just outside the blocked special-purpose ranges
+            "100.63.255.255",
+            "100.128.0.1",
+            "198.17.255.255",
+            "198.20.0.1",
+            "192.0.1.1",
+        ] {
+            assert!(
+                !is_private_ip(&ip.parse().unwrap()),
+                "{ip} should be allowed"
+            );
+        }
+    }
+
+    #[test]
+    fn test_host_ip_parses_literals_only() {
+        assert_eq!(host_ip("10.0.0.1"), Some("10.0.0.1".parse().unwrap()));
+        assert_eq!(host_ip("[::1]"), Some("::1".parse().unwrap()));
+        assert_eq!(host_ip("example.com"), None);
+    }
+
+    #[tokio::test]
+    async fn test_check_public_url_rejects_private_targets() {
+        for url in [
+            "http://169.254.169.254",
+            "https://10.1.2.3",
+            "https://[::1]:8443/hook",
+            "https://localhost/hook",
+        ] {
+            assert!(
+                check_public_url(url).await.is_err(),
+                "{url} should be rejected"
+            );
+        }
+        assert!(check_public_url("https://93.184.216.34/hook").await.is_ok());
+        assert!(matches!(
+            check_public_url("not a url").await,
+            Err(TargetError::Invalid(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_resolver_drops_private_addresses() {
+        let err = public_addrs("localhost", 443).await.unwrap_err();
+        assert!(matches!(err, TargetError::Private(_)), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_outbound_client_refuses_private_redirects() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // An endpoint that bounces the client to cloud metadata.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0; 4096];
+            let _ = socket.read(&mut buf).await.unwrap();
+            let response = "HTTP/1.1 302 Found\r\nlocation: http://169.254.169.254/latest\r\n\
+                            content-length: 0\r\nconnection: close\r\n\r\n";
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        let client = outbound_client("prod", Duration::from_secs(5)).unwrap();
+        let err = client.get(&url).send().await.unwrap_err();
+        assert!(err.is_redirect(), "{err}");
+    }
+}
@@ -0,0 +1,91 @@
+//! The JSON body delivered to webhooks.
+//!
+//! Built in one place so real deliveries, test sends, and webhook schema
+//! checks all see the same fields.
+
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+
+use crate::types::{SignalUrgency, PAYLOAD_SCHEMA_VERSION};
+
+/// The channel fields a delivery payload carries.
+#[derive(Debug, Clone, Copy)]
+pub struct PayloadChannel<'a> {
+    pub id: &'a str,
+    pub slug: &'a str,
+    pub display_name: &'a str,
+}
+
+/// The signal fields a delivery payload carries.
+#[derive(Debug, Clone)]
+pub struct PayloadSignal<'a> {
+    pub id: &'a str,
+    pub title: &'a str,
+    pub body: &'a str,
+    pub urgency: SignalUrgency,
+    pub metadata: &'a Value,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The payload for delivery `delivery_id` of `signal`, pushed on `channel`.
+pub fn build_payload<'a>(
+    delivery_id: &str,
+    webhook_id: Option<&str>,
+    channel: impl Into<PayloadChannel<'a>>,
+    signal: impl Into<PayloadSignal<'a>>,
+) -> Value {
+    let channel = channel.into();
+    let signal = signal.into();
+    json!({
+        "schemaVersion": PAYLOAD_SCHEMA_VERSION,
+        "deliveryId": delivery_id,
+        "webhookId": webhook_id,
+        "channel": {
+            "id": channel.id,
+            "slug": channel.slug,
+            "displayName": channel.display_name,
+        },
+        "signal": {
+            "id": signal.id,
+            "title": signal.title,
+            "body": signal.body,
+            "urgency": signal.urgency,
+            "metadata": signal.metadata,
+            "createdAt": signal.created_at,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload_shape() {
+        let metadata = json!({"ticker": "BTC"});
+        let payload = build_payload(
+            "del_1",
+            None,
+            PayloadChannel {
+                id: "ch_1",
+                slug: "btc-alerts",
+                display_name: "BTC Alerts",
+            },
+            PayloadSignal {
+                id: "sig_1",
+                title: "Breakout",
+                body: "BTC above 100k",
+                urgency: SignalUrgency::High,
+                metadata: &metadata,
+                created_at: Utc::now(),
+            },
+        );
+
+        assert_eq!(payload["schemaVersion"], PAYLOAD_SCHEMA_VERSION);
+        assert!(payload["webhookId"].is_null());
+        assert_eq!(payload["channel"]["displayName"], "BTC Alerts");
+        assert_eq!(payload["signal"]["urgency"], "high");
+        assert_eq!(payload["signal"]["metadata"]["ticker"], "BTC");
+        assert!(payload.get("test").is_none());
+    }
+}
@@ -0,0 +1,182 @@
+//! Agent presence and tunnel delivery relay shared across instances.
+//!
+//! Agents hold a WebSocket to whichever API instance the load balancer picked,
+//! while deliveries are made by worker processes. Each API instance records
+//! the agents it holds under `presence:{subscriber_id}`, with a TTL refreshed
+//! at the keepalive ping interval so a crashed instance's entries lapse on
+//! their own, and listens on its relay channel. A worker that finds a
+//! subscriber present publishes a [`RelayRequest`] to the owning instance and
+//! waits for the [`RelayReply`] on its own reply channel.
+//!
+//! A subscriber with agents on several instances is recorded against the one
+//! that refreshed last; every instance holding an agent keeps refreshing, so
+//! the entry only disappears once none does.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::tunnel::{AckOutcome, ServerMessage};
+
+/// Which API instance holds a subscriber's agent connection.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Presence {
+    pub instance_id: String,
+    pub connection_id: String,
+}
+
+/// A tunnel message for the instance holding the subscriber's agent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayRequest {
+    pub subscriber_id: String,
+    pub delivery_id: String,
+    /// Instance whose reply channel receives the outcome.
+    pub reply_to: String,
+    pub message: ServerMessage,
+}
+
+/// How a relayed delivery ended on the instance holding the agent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelayReply {
+    pub delivery_id: String,
+    pub outcome: AckOutcome,
+}
+
+/// A process-unique id such as `api_V1StGXR8_Z`, used to address relay
+/// channels.
+pub fn instance_id(role: &str) -> String {
+    format!("{}_{}", role, nanoid::nanoid!(10))
+}
+
+pub fn presence_key(subscriber_id: &str) -> String {
+    format!("presence:{}", subscriber_id)
+}
+
+/// Channel an API instance receives relayed deliveries on.
+pub fn relay_channel(instance_id: &str) -> String {
+    format!("tunnel:relay:{}", instance_id)
+}
+
+/// Channel a worker receives relay outcomes on.
+pub fn reply_channel(instance_id: &str) -> String {
+    format!("tunnel:reply:{}", instance_id)
+}
+
+/// Record (or refresh) that `presence` holds the subscriber's agent.
+pub async fn announce(
+    redis: &redis::Client,
+    subscriber_id: &str,
+    presence: &Presence,
+    ttl: Duration,
+) -> redis::RedisResult<()> {
+    let mut conn = redis.get_multiplexed_async_connection().await?;
+    redis::cmd("SET")
+        .arg(presence_key(subscriber_id))
+        .arg(encode(presence))
+        .arg("PX")
+        .arg(ttl.as_millis() as u64)
+        .query_async(&mut conn)
+        .await
+}
+
+/// Remove the subscriber's presence if it still names `presence`, leaving an
+/// entry written since by another connection in place.
+pub async fn withdraw(
+    redis: &redis::Client,
+    subscriber_id: &str,
+    presence: &Presence,
+) -> redis::RedisResult<()> {
+    let mut conn = redis.get_multiplexed_async_connection().await?;
+    redis::Script::new(
+        r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+  return redis.call('DEL', KEYS[1])
+end
+return 0
+"#,
+    )
+    .key(presence_key(subscriber_id))
+    .arg(encode(presence))
+    .invoke_async::<_, i32>(&mut conn)
+    .await?;
+    Ok(())
+}
+
+/// The instance holding the subscriber's agent, if any.
+pub async fn lookup(
+    redis: &redis::Client,
+    subscriber_id: &str,
+) -> redis::RedisResult<Option<Presence>> {
+    let mut conn = redis.get_multiplexed_async_connection().await?;
+    let value: Option<String> = redis::cmd("GET")
+        .arg(presence_key(subscriber_id))
+        .query_async(&mut conn)
+        .await?;
+    Ok(value.and_then(|json| serde_json::from_str(&json).ok()))
+}
+
+/// Publish `message` as JSON on `channel`, returning how many subscribers
+/// received it.
+pub async fn publish<T: Serialize>(
+    redis: &redis::Client,
+    channel: &str,
+    message: &T,
+) -> redis::RedisResult<usize> {
+    let mut conn = redis.get_multiplexed_async_connection().await?;
+    redis::cmd("PUBLISH")
+        .arg(channel)
+        .arg(encode(message))
+        .query_async(&mut conn)
+        .await
+}
+
+fn encode<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_and_channels() {
+        assert_eq!(presence_key("sr_1"), "presence:sr_1");
+        assert_eq!(relay_channel("api_1"), "tunnel:relay:api_1");
+        assert_eq!(reply_channel("worker_1"), "tunnel:reply:worker_1");
+        let id = instance_id("api");
+        assert!(id.starts_with("api_"));
+        assert_ne!(id, instance_id("api"));
+    }
+
+    #[test]
+    fn test_presence_encoding_is_stable() {
+        // `withdraw` compares the stored JSON with a fresh encoding.
+        let presence = Presence {
+            instance_id: "api_1".to_string(),
+            connection_id: "conn_1".to_string(),
+        };
+        assert_eq!(encode(&presence), encode(&presence.clone()));
+        let parsed: Presence = serde_json::from_str(&encode(&presence)).unwrap();
+        assert_eq!(parsed, presence);
+    }
+
+    #[test]
+    fn test_relay_messages_round_trip() {
+        let request = RelayRequest {
+            subscriber_id: "sr_1".to_string(),
+            delivery_id: "del_1".to_string(),
+            reply_to: "worker_1".to_string(),
+            message: ServerMessage::Ping,
+        };
+        let parsed: RelayRequest = serde_json::from_str(&encode(&request)).unwrap();
+        assert_eq!(parsed.delivery_id, "del_1");
+        assert!(matches!(parsed.message, ServerMessage::Ping));
+
+        let reply = RelayReply {
+            delivery_id: "del_1".to_string(),
+            outcome: AckOutcome::Acked,
+        };
+        let json = encode(&reply);
+        assert!(json.contains("\"outcome\":\"acked\""));
+        assert_eq!(serde_json::from_str::<RelayReply>(&json).unwrap(), reply);
+    }
+}
@@ -0,0 +1,183 @@
+//! Delivery job priority.
+//!
+//! A job's priority is its signal's urgency weight plus a boost for the
+//! channel's pricing tier; higher values are polled first. With the defaults
+//! urgency always dominates and the tier only breaks ties, but raising a tier
+//! boost past one urgency step lets, say, a `normal` Enterprise signal
+//! outrank a `high` Free one. Weights are set through `HERALD_PRIORITY_*`.
+
+use serde::Deserialize;
+
+use crate::types::{PricingTier, SignalUrgency};
+
+/// Weights combined into a delivery job's priority.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct PriorityWeights {
+    pub urgency_low: i32,
+    pub urgency_normal: i32,
+    pub urgency_high: i32,
+    pub urgency_critical: i32,
+    pub tier_free: i32,
+    pub tier_pro: i32,
+    pub tier_enterprise: i32,
+}
+
+impl Default for PriorityWeights {
+    fn default() -> Self {
+        Self {
+            urgency_low: 0,
+            urgency_normal: 100,
+            urgency_high: 200,
+            urgency_critical: 300,
+            tier_free: 0,
+            tier_pro: 25,
+            tier_enterprise: 50,
+        }
+    }
+}
+
+impl PriorityWeights {
+    /// Defaults overridden by any `HERALD_PRIORITY_*` variables that parse.
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            urgency_low: env_or("HERALD_PRIORITY_URGENCY_LOW", defaults.urgency_low),
+            urgency_normal: env_or("HERALD_PRIORITY_URGENCY_NORMAL", defaults.urgency_normal),
+            urgency_high: env_or("HERALD_PRIORITY_URGENCY_HIGH", defaults.urgency_high),
+            urgency_critical: env_or(
+                "HERALD_PRIORITY_URGENCY_CRITICAL",
+                defaults.urgency_critical,
+            ),
+            tier_free: env_or("HERALD_PRIORITY_TIER_FREE", defaults.tier_free),
+            tier_pro: env_or("HERALD_PRIORITY_TIER_PRO", defaults.tier_pro),
+            tier_enterprise: env_or("HERALD_PRIORITY_TIER_ENT", defaults.tier_enterprise),
+        }
+    }
+
+    /// Priority for a signal of `urgency` published to a `tier` channel.
+    pub fn for_signal(&self, urgency: &SignalUrgency, tier: &PricingTier) -> i32 {
+        let urgency = match urgency {
+            SignalUrgency::Low => self.urgency_low,
+            SignalUrgency::Normal => self.urgency_normal,
+            SignalUrgency::High => self.urgency_high,
+            SignalUrgency::Critical => self.urgency_critical,
+        };
+        let boost = match tier {
+            PricingTier::Free => self.tier_free,
+            PricingTier::Pro => self.tier_pro,
+            PricingTier::Enterprise => self.tier_enterprise,
+        };
+        urgency.saturating_add(boost)
+    }
+
+    /// Weights must be non-negative and must not shrink as urgency or tier
+    /// goes up.
+    pub fn validate(&self) -> Result<(), String> {
+        let urgency = [
+            self.urgency_low,
+            self.urgency_normal,
+            self.urgency_high,
+            self.urgency_critical,
+        ];
+        let tier = [self.tier_free, self.tier_pro, self.tier_enterprise];
+        if urgency.iter().chain(&tier).any(|weight| *weight < 0) {
+            return Err("HERALD_PRIORITY_* weights must not be negative".to_string());
+        }
+        if !urgency.is_sorted() {
+            return Err(
+                "HERALD_PRIORITY_URGENCY_* weights must not decrease as urgency rises".to_string(),
+            );
+        }
+        if !tier.is_sorted() {
+            return Err(
+                "HERALD_PRIORITY_TIER_* weights must not decrease as tier rises".to_string(),
+            );
+        }
+        Ok(())
+    }
+}
+
+fn env_or(name: &str, default: i32) -> i32 {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URGENCIES: [SignalUrgency; 4] = [
+        SignalUrgency::Low,
+        SignalUrgency::Normal,
+        SignalUrgency::High,
+        SignalUrgency::Critical,
+    ];
+    const TIERS: [PricingTier; 3] = [PricingTier::Free, PricingTier::Pro, PricingTier::Enterprise];
+
+    #[test]
+    fn test_default_weights_order_by_urgency_then_tier() {
+        let weights = PriorityWeights::default();
+        assert_eq!(weights.validate(), Ok(()));
+
+        let mut combos = Vec::new();
+        for (u, urgency) in URGENCIES.iter().enumerate() {
+            for (t, tier) in TIERS.iter().enumerate() {
+                combos.push(((u, t), weights.for_signal(urgency, tier)));
+            }
+        }
+        for (a, pa) in &combos {
+            for (b, pb) in &combos {
+                // Lexicographic (urgency, tier) order must match priority order.
+                assert_eq!(a.cmp(b), pa.cmp(pb), "{a:?} vs {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_large_tier_boost_lets_enterprise_normal_outrank_free_high() {
+        let weights = PriorityWeights {
+            tier_enterprise: 150,
+            ..PriorityWeights::default()
+        };
+        assert_eq!(weights.validate(), Ok(()));
+
+        let enterprise_normal =
+            weights.for_signal(&SignalUrgency::Normal, &PricingTier::Enterprise);
+        let free_high = weights.for_signal(&SignalUrgency::High, &PricingTier::Free);
+        let free_critical = weights.for_signal(&SignalUrgency::Critical, &PricingTier::Free);
+        assert!(enterprise_normal > free_high);
+        assert!(enterprise_normal < free_critical);
+
+        // Within a tier, urgency still orders signals.
+        for tier in &TIERS {
+            let ranked: Vec<_> = URGENCIES
+                .iter()
+                .map(|u| weights.for_signal(u, tier))
+                .collect();
+            assert!(ranked.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+    }
+
+    #[test]
+    fn test_validate_rejects_inverted_or_negative_weights() {
+        let inverted = PriorityWeights {
+            urgency_high: 50,
+            ..PriorityWeights::default()
+        };
+        assert!(inverted.validate().is_err());
+
+        let inverted_tier = PriorityWeights {
+            tier_pro: 100,
+            ..PriorityWeights::default()
+        };
+        assert!(inverted_tier.validate().is_err());
+
+        let negative = PriorityWeights {
+            tier_free: -1,
+            ..PriorityWeights::default()
+        };
+        assert!(negative.validate().is_err());
+    }
+}
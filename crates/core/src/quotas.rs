@@ -0,0 +1,172 @@
+//! Per-channel signal quotas.
+//!
+//! Each channel may push at most its publisher tier's `max_signals_per_day`
+//! and `max_signals_per_month` signals, counted in UTC calendar windows. Both
+//! counters live in Redis and one script checks and increments them together,
+//! so concurrent pushes cannot overshoot and a push refused by either window
+//! counts against neither. A push that fails after being counted is handed
+//! back with [`refund`].
+
+use chrono::{DateTime, Datelike, TimeZone, Utc};
+
+use crate::limits::Limits;
+
+/// Counters outlive their window by this many seconds, so instances with
+/// slightly skewed clocks still find them.
+const EXPIRY_SLACK_SECS: i64 = 3600;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaWindow {
+    Day,
+    Month,
+}
+
+impl QuotaWindow {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuotaWindow::Day => "daily",
+            QuotaWindow::Month => "monthly",
+        }
+    }
+}
+
+/// Whether a push fits in the channel's quota.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuotaDecision {
+    Allowed,
+    Exceeded {
+        window: QuotaWindow,
+        limit: u64,
+        resets_at: DateTime<Utc>,
+    },
+}
+
+pub fn day_key(channel_id: &str, now: DateTime<Utc>) -> String {
+    format!("quota:signals:{}:{}", channel_id, now.format("%Y-%m-%d"))
+}
+
+pub fn month_key(channel_id: &str, now: DateTime<Utc>) -> String {
+    format!("quota:signals:{}:{}", channel_id, now.format("%Y-%m"))
+}
+
+/// Start of the next UTC day.
+pub fn day_reset(now: DateTime<Utc>) -> DateTime<Utc> {
+    let tomorrow = now.date_naive().succ_opt().unwrap_or(now.date_naive());
+    tomorrow.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc()
+}
+
+/// Start of the next UTC calendar month.
+pub fn month_reset(now: DateTime<Utc>) -> DateTime<Utc> {
+    let (year, month) = match now.month() {
+        12 => (now.year() + 1, 1),
+        month => (now.year(), month + 1),
+    };
+    Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0)
+        .single()
+        .unwrap_or(now)
+}
+
+/// Count one signal against the channel's quota if it fits in both windows.
+pub async fn consume(
+    redis: &redis::Client,
+    channel_id: &str,
+    limits: &Limits,
+    now: DateTime<Utc>,
+) -> redis::RedisResult<QuotaDecision> {
+    let mut conn = redis.get_multiplexed_async_connection().await?;
+    let (day_resets_at, month_resets_at) = (day_reset(now), month_reset(now));
+    let exceeded: i32 = redis::Script::new(
+        r#"
+local day = tonumber(redis.call('GET', KEYS[1]) or '0')
+local month = tonumber(redis.call('GET', KEYS[2]) or '0')
+if day >= tonumber(ARGV[1]) then
+  return 1
+end
+if month >= tonumber(ARGV[2]) then
+  return 2
+end
+redis.call('INCR', KEYS[1])
+redis.call('EXPIREAT', KEYS[1], ARGV[3])
+redis.call('INCR', KEYS[2])
+redis.call('EXPIREAT', KEYS[2], ARGV[4])
+return 0
+"#,
+    )
+    .key(day_key(channel_id, now))
+    .key(month_key(channel_id, now))
+    .arg(limits.max_signals_per_day)
+    .arg(limits.max_signals_per_month)
+    .arg(day_resets_at.timestamp() + EXPIRY_SLACK_SECS)
+    .arg(month_resets_at.timestamp() + EXPIRY_SLACK_SECS)
+    .invoke_async(&mut conn)
+    .await?;
+
+    Ok(match exceeded {
+        1 => QuotaDecision::Exceeded {
+            window: QuotaWindow::Day,
+            limit: limits.max_signals_per_day,
+            resets_at: day_resets_at,
+        },
+        2 => QuotaDecision::Exceeded {
+            window: QuotaWindow::Month,
+            limit: limits.max_signals_per_month,
+            resets_at: month_resets_at,
+        },
+        _ => QuotaDecision::Allowed,
+    })
+}
+
+/// Give back a signal [`consume`] counted at `now` for a push that never
+/// created it. Counters never drop below zero.
+pub async fn refund(
+    redis: &redis::Client,
+    channel_id: &str,
+    now: DateTime<Utc>,
+) -> redis::RedisResult<()> {
+    let mut conn = redis.get_multiplexed_async_connection().await?;
+    redis::Script::new(
+        r#"
+for _, key in ipairs(KEYS) do
+  if tonumber(redis.call('GET', key) or '0') > 0 then
+    redis.call('DECR', key)
+  end
+end
+return 0
+"#,
+    )
+    .key(day_key(channel_id, now))
+    .key(month_key(channel_id, now))
+    .invoke_async::<_, i32>(&mut conn)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(rfc3339: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(rfc3339).unwrap().into()
+    }
+
+    #[test]
+    fn test_keys_follow_utc_windows() {
+        let now = at("2026-10-17T23:59:59Z");
+        assert_eq!(day_key("ch_1", now), "quota:signals:ch_1:2026-10-17");
+        assert_eq!(month_key("ch_1", now), "quota:signals:ch_1:2026-10");
+        let later = at("2026-10-18T00:00:00Z");
+        assert_ne!(day_key("ch_1", now), day_key("ch_1", later));
+        assert_eq!(month_key("ch_1", now), month_key("ch_1", later));
+    }
+
+    #[test]
+    fn test_resets() {
+        let now = at("2026-10-17T09:30:00Z");
+        assert_eq!(day_reset(now), at("2026-10-18T00:00:00Z"));
+        assert_eq!(month_reset(now), at("2026-11-01T00:00:00Z"));
+
+        let new_year = at("2026-12-31T12:00:00Z");
+        assert_eq!(day_reset(new_year), at("2027-01-01T00:00:00Z"));
+        assert_eq!(month_reset(new_year), at("2027-01-01T00:00:00Z"));
+    }
+}
@@ -0,0 +1,261 @@
+//! Per-route rate limit groups.
+//!
+//! By default every request made with an API key draws from one token bucket.
+//! `HERALD_RATE_LIMIT_GROUPS` moves matching routes into buckets of their own,
+//! each sized as a multiple of the tier's per-minute limit, so an expensive
+//! route such as signal publishing can be held to a fraction of the budget
+//! while cheap reads keep the rest.
+//!
+//! The value is a `;`-separated list of `group:METHODS PATH=MULTIPLIER` rules:
+//!
+//! ```text
+//! publish:POST /v1/channels/{id}/signals=0.2;write:POST,PUT,PATCH,DELETE *=1
+//! ```
+//!
+//! `METHODS` is a comma list or `*` for any method. `PATH` is an axum route
+//! pattern as registered, a prefix ending in `*`, or `*` for any path. The
+//! first matching rule wins; unmatched requests use the key's default bucket.
+//! Rules naming the same group share one bucket and must agree on the
+//! multiplier.
+
+use serde::Deserialize;
+
+const ENV_VAR: &str = "HERALD_RATE_LIMIT_GROUPS";
+
+/// Configured rate limit groups, in match order.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct RateGroups {
+    /// The spec the rules were parsed from, kept so `validate` can report
+    /// why an invalid one was ignored.
+    pub spec: String,
+    pub rules: Vec<RateRule>,
+}
+
+/// One route pattern mapped to a bucket.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RateRule {
+    pub group: String,
+    /// Upper-case methods; empty matches any method.
+    pub methods: Vec<String>,
+    pub path: PathPattern,
+    /// Scale applied to the tier's per-minute limit for this group.
+    pub multiplier: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub enum PathPattern {
+    Any,
+    Exact(String),
+    Prefix(String),
+}
+
+impl RateGroups {
+    /// Rules from `HERALD_RATE_LIMIT_GROUPS`; none when unset or invalid.
+    pub fn from_env() -> Self {
+        let spec = std::env::var(ENV_VAR).unwrap_or_default();
+        let rules = parse_rules(&spec).unwrap_or_default();
+        Self { spec, rules }
+    }
+
+    /// Parse a spec, rejecting it as a whole if any rule is malformed.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        Ok(Self {
+            spec: spec.to_string(),
+            rules: parse_rules(spec)?,
+        })
+    }
+
+    /// The first rule matching a request's method and matched route.
+    pub fn rule_for(&self, method: &str, route: &str) -> Option<&RateRule> {
+        self.rules.iter().find(|rule| rule.matches(method, route))
+    }
+
+    /// One rule per distinct group, in the order groups first appear.
+    pub fn groups(&self) -> Vec<&RateRule> {
+        let mut groups: Vec<&RateRule> = Vec::new();
+        for rule in &self.rules {
+            if !groups.iter().any(|seen| seen.group == rule.group) {
+                groups.push(rule);
+            }
+        }
+        groups
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        parse_rules(&self.spec).map(|_| ())
+    }
+}
+
+impl RateRule {
+    fn matches(&self, method: &str, route: &str) -> bool {
+        let method_ok =
+            self.methods.is_empty() || self.methods.iter().any(|m| m.eq_ignore_ascii_case(method));
+        let path_ok = match &self.path {
+            PathPattern::Any => true,
+            PathPattern::Exact(path) => path == route,
+            PathPattern::Prefix(prefix) => route.starts_with(prefix.as_str()),
+        };
+        method_ok && path_ok
+    }
+
+    /// This group's bucket size for a tier allowing `base` requests a minute;
+    /// never below one.
+    pub fn capacity(&self, base: u32) -> u32 {
+        ((base as f64 * self.multiplier).round() as u32).max(1)
+    }
+}
+
+fn parse_rules(spec: &str) -> Result<Vec<RateRule>, String> {
+    let mut rules: Vec<RateRule> = Vec::new();
+    for entry in spec.split(';').map(str::trim).filter(|e| !e.is_empty()) {
+        let rule = parse_rule(entry).map_err(|err| format!("{ENV_VAR}: `{entry}`: {err}"))?;
+        if let Some(other) = rules.iter().find(|r| r.group == rule.group) {
+            if other.multiplier != rule.multiplier {
+                return Err(format!(
+                    "{ENV_VAR}: group `{}` has conflicting multipliers",
+                    rule.group
+                ));
+            }
+        }
+        rules.push(rule);
+    }
+    Ok(rules)
+}
+
+fn parse_rule(entry: &str) -> Result<RateRule, &'static str> {
+    let (group, rest) = entry
+        .split_once(':')
+        .ok_or("expected group:METHODS PATH=MULTIPLIER")?;
+    let group = group.trim();
+    if group.is_empty()
+        || !group
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err("group must be letters, digits, `_` or `-`");
+    }
+
+    let (matcher, multiplier) = rest.rsplit_once('=').ok_or("missing =MULTIPLIER")?;
+    let multiplier: f64 = multiplier
+        .trim()
+        .parse()
+        .map_err(|_| "multiplier must be a number")?;
+    if !multiplier.is_finite() || multiplier <= 0.0 {
+        return Err("multiplier must be positive");
+    }
+
+    let (methods, path) = matcher
+        .trim()
+        .split_once(char::is_whitespace)
+        .ok_or("expected METHODS PATH")?;
+    let methods = match methods {
+        "*" => Vec::new(),
+        list => list
+            .split(',')
+            .map(|m| m.trim().to_ascii_uppercase())
+            .filter(|m| !m.is_empty())
+            .collect(),
+    };
+    let path = match path.trim() {
+        "*" => PathPattern::Any,
+        p if !p.starts_with('/') => return Err("path must start with `/` or be `*`"),
+        p => match p.strip_suffix('*') {
+            Some(prefix) => PathPattern::Prefix(prefix.to_string()),
+            None => PathPattern::Exact(p.to_string()),
+        },
+    };
+
+    Ok(RateRule {
+        group: group.to_string(),
+        methods,
+        path,
+        multiplier,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPEC: &str =
+        "publish:POST /v1/channels/{id}/signals=0.2; write:POST,put,PATCH,DELETE *=1";
+
+    #[test]
+    fn test_empty_spec_has_no_rules() {
+        let groups = RateGroups::parse("").unwrap();
+        assert!(groups.rules.is_empty());
+        assert!(groups
+            .rule_for("POST", "/v1/channels/{id}/signals")
+            .is_none());
+        assert_eq!(groups.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let groups = RateGroups::parse(SPEC).unwrap();
+        let publish = groups
+            .rule_for("POST", "/v1/channels/{id}/signals")
+            .unwrap();
+        assert_eq!(publish.group, "publish");
+        assert_eq!(
+            groups.rule_for("PUT", "/v1/webhooks/{id}").unwrap().group,
+            "write"
+        );
+        assert!(groups
+            .rule_for("GET", "/v1/channels/{id}/signals")
+            .is_none());
+    }
+
+    #[test]
+    fn test_prefix_paths() {
+        let groups = RateGroups::parse("admin:* /v1/admin/*=0.5").unwrap();
+        assert!(groups.rule_for("GET", "/v1/admin/stats").is_some());
+        assert!(groups.rule_for("GET", "/v1/channels").is_none());
+    }
+
+    #[test]
+    fn test_capacity_scales_and_never_hits_zero() {
+        let groups = RateGroups::parse(SPEC).unwrap();
+        let publish = &groups.rules[0];
+        assert_eq!(publish.capacity(60), 12);
+        assert_eq!(publish.capacity(1), 1);
+        assert_eq!(groups.rules[1].capacity(600), 600);
+    }
+
+    #[test]
+    fn test_groups_are_listed_once_each() {
+        let groups = RateGroups::parse(
+            "publish:POST /v1/channels/{id}/signals=0.2;write:PUT *=1;publish:PATCH *=0.2",
+        )
+        .unwrap();
+        let names: Vec<_> = groups
+            .groups()
+            .iter()
+            .map(|rule| rule.group.as_str())
+            .collect();
+        assert_eq!(names, ["publish", "write"]);
+        assert!(RateGroups::parse("").unwrap().groups().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_specs_are_rejected() {
+        for spec in [
+            "publish POST /v1=1",
+            ":POST /v1=1",
+            "bad name:POST /v1=1",
+            "publish:POST /v1",
+            "publish:POST /v1=0",
+            "publish:POST /v1=abc",
+            "publish:POST v1=1",
+            "publish:POST=1",
+            "a:GET /x=1;a:POST /y=2",
+        ] {
+            assert!(RateGroups::parse(spec).is_err(), "{spec}");
+            let groups = RateGroups {
+                spec: spec.to_string(),
+                rules: Vec::new(),
+            };
+            assert!(groups.validate().is_err(), "{spec}");
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! Delivery receipts for publishers.
+//!
+//! A channel with a `delivery_callback_url` gets one receipt per signal,
+//! POSTed once every delivery the signal fanned out to has reached a final
+//! outcome: delivered, dead-lettered, or refused as undeliverable. The body is
+//! signed with the platform `hmac_secret` the same way webhook payloads are
+//! signed with a subscriber's secret.
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::auth::sign_payload;
+
+/// Pauses before each resend of a receipt the endpoint did not accept.
+pub const RECEIPT_RETRY_DELAYS: [Duration; 2] = [Duration::from_secs(5), Duration::from_secs(30)];
+
+/// How long a single receipt request may take.
+const RECEIPT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Body of a delivery receipt.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeliveryReceipt {
+    pub signal_id: String,
+    pub delivered: i32,
+    pub failed: i32,
+    pub total: i32,
+}
+
+impl DeliveryReceipt {
+    /// A receipt for `total` deliveries, of which `delivered` succeeded and
+    /// the rest failed for good.
+    pub fn new(signal_id: &str, delivered: i32, total: i32) -> Self {
+        Self {
+            signal_id: signal_id.to_string(),
+            delivered,
+            failed: (total - delivered).max(0),
+            total,
+        }
+    }
+}
+
+/// POST `receipt` to `url`, resending after each of `retry_delays` until the
+/// endpoint answers 2xx. Returns whether it did.
+pub async fn send(
+    client: &reqwest::Client,
+    url: &str,
+    secret: &str,
+    receipt: &DeliveryReceipt,
+    retry_delays: &[Duration],
+) -> bool {
+    let Ok(body) = serde_json::to_string(receipt) else {
+        return false;
+    };
+    let pauses = std::iter::once(Duration::ZERO).chain(retry_delays.iter().copied());
+    for (attempt, pause) in pauses.enumerate() {
+        tokio::time::sleep(pause).await;
+        let timestamp = Utc::now().timestamp();
+        let result = client
+            .post(url)
+            .timeout(RECEIPT_TIMEOUT)
+            .header("Content-Type", "application/json")
+            .header("X-Herald-Signature", sign_payload(secret, timestamp, &body))
+            .header("X-Herald-Timestamp", timestamp.to_string())
+            .header("X-Herald-Signal-Id", &receipt.signal_id)
+            .body(body.clone())
+            .send()
+            .await;
+        match result {
+            Ok(resp) if resp.status().is_success() => {
+                info!(signal_id = %receipt.signal_id, "delivery receipt sent");
+                return true;
+            }
+            Ok(resp) => warn!(
+                signal_id = %receipt.signal_id,
+                attempt = attempt + 1,
+                status = resp.status().as_u16(),
+                "delivery receipt rejected"
+            ),
+            Err(err) => warn!(
+                signal_id = %receipt.signal_id,
+                attempt = attempt + 1,
+                error = %err,
+                "delivery receipt failed"
+            ),
+        }
+    }
+    warn!(signal_id = %receipt.signal_id, "giving up on delivery receipt");
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::verify_signature;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Answers each request with the next status in `statuses`, keeping the
+    /// raw requests it saw.
+    async fn endpoint(statuses: Vec<u16>) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/receipts", listener.local_addr().unwrap());
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let requests = seen.clone();
+        tokio::spawn(async move {
+            for status in statuses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0; 8192];
+                let n = socket.read(&mut buf).await.unwrap();
+                requests
+                    .lock()
+                    .unwrap()
+                    .push(String::from_utf8_lossy(&buf[..n]).into_owned());
+                let response = format!(
+                    "HTTP/1.1 {status} X\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (url, seen)
+    }
+
+    fn header<'a>(request: &'a str, name: &str) -> &'a str {
+        request
+            .lines()
+            .find_map(|line| {
+                let (key, value) = line.split_once(':')?;
+                key.eq_ignore_ascii_case(name).then_some(value.trim())
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_receipt_counts_the_rest_as_failed() {
+        let receipt = DeliveryReceipt::new("sig_1", 7, 10);
+        assert_eq!(receipt.failed, 3);
+        let json = serde_json::to_value(&receipt).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "signalId": "sig_1", "delivered": 7, "failed": 3, "total": 10 })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_signs_the_receipt() {
+        let (url, seen) = endpoint(vec![200]).await;
+        let receipt = DeliveryReceipt::new("sig_1", 2, 2);
+
+        assert!(send(&reqwest::Client::new(), &url, "secret", &receipt, &[]).await);
+
+        let request = seen.lock().unwrap()[0].clone();
+        let body = request.split("\r\n\r\n").nth(1).unwrap();
+        let timestamp: i64 = header(&request, "x-herald-timestamp").parse().unwrap();
+        assert!(verify_signature(
+            "secret",
+            timestamp,
+            body,
+            header(&request, "x-herald-signature")
+        ));
+        assert_eq!(header(&request, "x-herald-signal-id"), "sig_1");
+    }
+
+    #[tokio::test]
+    async fn test_send_retries_a_bounded_number_of_times() {
+        let delays = [Duration::from_millis(1); 2];
+
+        let (url, seen) = endpoint(vec![500, 200]).await;
+        let receipt = DeliveryReceipt::new("sig_1", 1, 1);
+        assert!(send(&reqwest::Client::new(), &url, "s", &receipt, &delays).await);
+        assert_eq!(seen.lock().unwrap().len(), 2);
+
+        let (url, seen) = endpoint(vec![500, 502, 503, 200]).await;
+        assert!(!send(&reqwest::Client::new(), &url, "s", &receipt, &delays).await);
+        assert_eq!(seen.lock().unwrap().len(), 3);
+    }
+}
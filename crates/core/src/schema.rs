@@ -0,0 +1,133 @@
+//! JSON Schema checks for delivery payloads.
+
+use jsonschema::JSONSchema;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// A schema compiled once and checked against many payloads.
+pub struct CompiledSchema(JSONSchema);
+
+impl CompiledSchema {
+    pub fn compile(schema: &Value) -> Result<Self, String> {
+        JSONSchema::compile(schema)
+            .map(Self)
+            .map_err(|err| format!("invalid schema: {err}"))
+    }
+
+    /// One message per way `payload` breaks the schema.
+    pub fn violations(&self, payload: &Value) -> Vec<String> {
+        match self.0.validate(payload) {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors
+                .map(|err| {
+                    let path = err.instance_path.to_string();
+                    if path.is_empty() {
+                        err.to_string()
+                    } else {
+                        format!("{path}: {err}")
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Validate `payload` against `schema`, returning one message per violation.
+///
+/// Fails only when the schema itself does not compile.
+pub fn payload_violations(schema: &Value, payload: &Value) -> Result<Vec<String>, String> {
+    Ok(CompiledSchema::compile(schema)?.violations(payload))
+}
+
+type CachedSchema = Arc<Result<CompiledSchema, String>>;
+
+/// Compiled schemas by owner (a webhook id). A schema is compiled the first
+/// time it is used and again only after the owner's stored schema changes.
+#[derive(Default)]
+pub struct SchemaCache {
+    compiled: Mutex<HashMap<String, (Value, CachedSchema)>>,
+}
+
+impl SchemaCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// [`payload_violations`] with `owner`'s compiled `schema` reused.
+    pub fn violations(
+        &self,
+        owner: &str,
+        schema: &Value,
+        payload: &Value,
+    ) -> Result<Vec<String>, String> {
+        let compiled = self.compiled(owner, schema);
+        match compiled.as_ref() {
+            Ok(compiled) => Ok(compiled.violations(payload)),
+            Err(err) => Err(err.clone()),
+        }
+    }
+
+    fn compiled(&self, owner: &str, schema: &Value) -> CachedSchema {
+        let mut compiled = self.compiled.lock().unwrap();
+        if let Some((cached_schema, cached)) = compiled.get(owner) {
+            if cached_schema == schema {
+                return cached.clone();
+            }
+        }
+        let fresh = Arc::new(CompiledSchema::compile(schema));
+        compiled.insert(owner.to_string(), (schema.clone(), fresh.clone()));
+        fresh
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_matching_payload_has_no_violations() {
+        let schema = json!({"type": "object", "required": ["deliveryId"]});
+        let payload = json!({"deliveryId": "del_1"});
+        assert!(payload_violations(&schema, &payload).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_violations_include_instance_path() {
+        let schema = json!({
+            "type": "object",
+            "properties": {"signal": {"properties": {"title": {"type": "integer"}}}}
+        });
+        let payload = json!({"signal": {"title": "hello"}});
+        let violations = payload_violations(&schema, &payload).unwrap();
+        assert_eq!(violations.len(), 1);
+        assert!(violations[0].starts_with("/signal/title"));
+    }
+
+    #[test]
+    fn test_cache_compiles_once_per_schema() {
+        let cache = SchemaCache::new();
+        let schema = json!({"type": "object", "required": ["deliveryId"]});
+        let first = cache.compiled("wh_1", &schema);
+        let again = cache.compiled("wh_1", &schema);
+        assert!(Arc::ptr_eq(&first, &again));
+        assert_eq!(
+            cache.violations("wh_1", &schema, &json!({})).unwrap().len(),
+            1
+        );
+
+        let changed = json!({"type": "object"});
+        assert!(!Arc::ptr_eq(&first, &cache.compiled("wh_1", &changed)));
+        assert!(cache
+            .violations("wh_1", &changed, &json!({}))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_invalid_schema_is_an_error() {
+        let schema = json!({"type": "not-a-type"});
+        assert!(payload_violations(&schema, &json!({})).is_err());
+    }
+}
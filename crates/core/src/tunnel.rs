@@ -2,16 +2,69 @@ use chrono::{DateTime, Utc};
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::sync::Arc;
-use tokio::sync::{mpsc, RwLock};
+use std::sync::atomic::{AtomicI64, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{mpsc, oneshot, watch, RwLock};
+use tracing::warn;
 
+use crate::metrics::METRICS;
 use crate::types::SignalUrgency;
 
+/// Newest tunnel protocol version this build speaks.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Oldest tunnel protocol version still accepted.
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// `Sec-WebSocket-Protocol` name for a protocol version, e.g. `herald.v1`.
+pub fn subprotocol(version: u32) -> String {
+    format!("herald.v{}", version)
+}
+
+/// The version named by a `herald.vN` subprotocol.
+pub fn parse_subprotocol(name: &str) -> Option<u32> {
+    name.trim().strip_prefix("herald.v")?.parse().ok()
+}
+
+/// Pick the protocol version to speak with an agent whose newest version is
+/// `requested`. Agents that predate negotiation send no version and speak v1.
+/// A newer agent is talked down to [`PROTOCOL_VERSION`]; one older than
+/// [`MIN_PROTOCOL_VERSION`] is refused.
+pub fn negotiate_version(requested: Option<u32>) -> Result<u32, String> {
+    let requested = requested.unwrap_or(1);
+    if requested < MIN_PROTOCOL_VERSION {
+        return Err(format!(
+            "tunnel protocol v{} is no longer supported (server speaks v{} to v{}); \
+             upgrade herald-agent",
+            requested, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION
+        ));
+    }
+    Ok(requested.min(PROTOCOL_VERSION))
+}
+
+fn legacy_protocol_version() -> u32 {
+    1
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ClientMessage {
-    Auth { token: String },
-    Ack { delivery_id: String },
+    Auth {
+        token: String,
+        /// Newest protocol version the agent speaks; absent from agents that
+        /// predate negotiation.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        version: Option<u32>,
+        /// Highest `seq` the agent received before reconnecting; the server
+        /// replays unacknowledged deliveries numbered after it.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        last_seen_seq: Option<u64>,
+    },
+    Ack {
+        delivery_id: String,
+    },
     Pong,
 }
 
@@ -21,6 +74,9 @@ pub enum ServerMessage {
     AuthOk {
         connection_id: String,
         subscriber_id: String,
+        /// Version the rest of the session uses.
+        #[serde(default = "legacy_protocol_version")]
+        protocol_version: u32,
     },
     AuthError {
         message: String,
@@ -30,6 +86,9 @@ pub enum ServerMessage {
         channel_id: String,
         channel_slug: String,
         signal: TunnelSignal,
+        /// Per-subscriber delivery sequence number, increasing over time.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        seq: Option<u64>,
     },
     Ping,
 }
@@ -50,11 +109,242 @@ pub struct AgentConnection {
     pub subscriber_id: String,
     pub sender: mpsc::Sender<ServerMessage>,
     pub connected_at: DateTime<Utc>,
+    pub health: ConnectionHealth,
+}
+
+/// Keepalive and throughput counters for one agent connection.
+///
+/// Timestamps are unix millis, 0 meaning "never".
+#[derive(Debug, Default)]
+pub struct ConnectionHealth {
+    last_ping_sent_ms: AtomicI64,
+    last_pong_ms: AtomicI64,
+    round_trip_ms: AtomicI64,
+    delivered: AtomicU64,
+    acked: AtomicU64,
+}
+
+/// Point-in-time view of [`ConnectionHealth`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionHealthSnapshot {
+    pub last_ping_sent_at: Option<DateTime<Utc>>,
+    pub last_pong_at: Option<DateTime<Utc>>,
+    /// Time between the last ping and the pong that answered it.
+    pub round_trip_ms: Option<i64>,
+    pub messages_delivered: u64,
+    pub messages_acked: u64,
+}
+
+impl ConnectionHealth {
+    pub fn record_ping(&self, at: DateTime<Utc>) {
+        self.last_ping_sent_ms
+            .store(at.timestamp_millis(), Ordering::Relaxed);
+    }
+
+    pub fn record_pong(&self, at: DateTime<Utc>) {
+        let at = at.timestamp_millis();
+        self.last_pong_ms.store(at, Ordering::Relaxed);
+        let ping = self.last_ping_sent_ms.load(Ordering::Relaxed);
+        if ping > 0 && at >= ping {
+            self.round_trip_ms.store(at - ping, Ordering::Relaxed);
+        }
+    }
+
+    pub fn record_delivered(&self) {
+        self.delivered.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_acked(&self) {
+        self.acked.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Whether the agent has gone longer than `timeout` without a pong,
+    /// counting from `connected_at` until the first one arrives.
+    pub fn is_unresponsive(
+        &self,
+        connected_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+        timeout: Duration,
+    ) -> bool {
+        let last_pong_ms = self.last_pong_ms.load(Ordering::Relaxed);
+        let last_seen_ms = if last_pong_ms > 0 {
+            last_pong_ms
+        } else {
+            connected_at.timestamp_millis()
+        };
+        let timeout_ms = i64::try_from(timeout.as_millis()).unwrap_or(i64::MAX);
+        now.timestamp_millis() - last_seen_ms > timeout_ms
+    }
+
+    pub fn snapshot(&self) -> ConnectionHealthSnapshot {
+        let at = |millis: i64| {
+            (millis > 0)
+                .then(|| DateTime::from_timestamp_millis(millis))
+                .flatten()
+        };
+        let last_pong_ms = self.last_pong_ms.load(Ordering::Relaxed);
+        ConnectionHealthSnapshot {
+            last_ping_sent_at: at(self.last_ping_sent_ms.load(Ordering::Relaxed)),
+            last_pong_at: at(last_pong_ms),
+            round_trip_ms: (last_pong_ms > 0).then(|| self.round_trip_ms.load(Ordering::Relaxed)),
+            messages_delivered: self.delivered.load(Ordering::Relaxed),
+            messages_acked: self.acked.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// How a delivery pushed through the tunnel was resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AckOutcome {
+    Acked,
+    TimedOut,
+    Disconnected,
+}
+
+struct PendingAck {
+    connection_id: String,
+    resolve: oneshot::Sender<AckOutcome>,
+}
+
+/// Outstanding tunnel deliveries awaiting an agent `Ack`, keyed by delivery_id.
+#[derive(Default)]
+pub struct AckTracker {
+    pending: Mutex<HashMap<String, PendingAck>>,
 }
 
+impl AckTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start tracking a delivery sent over `connection_id`.
+    pub fn track(&self, delivery_id: &str, connection_id: &str) -> oneshot::Receiver<AckOutcome> {
+        let (resolve, rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(
+            delivery_id.to_string(),
+            PendingAck {
+                connection_id: connection_id.to_string(),
+                resolve,
+            },
+        );
+        rx
+    }
+
+    /// Stop tracking a delivery that never reached the agent.
+    pub fn forget(&self, delivery_id: &str) {
+        self.pending.lock().unwrap().remove(delivery_id);
+    }
+
+    /// Wait for the agent to ack, giving up after `timeout`.
+    pub async fn wait(
+        &self,
+        delivery_id: &str,
+        rx: oneshot::Receiver<AckOutcome>,
+        timeout: Duration,
+    ) -> AckOutcome {
+        match tokio::time::timeout(timeout, rx).await {
+            Ok(Ok(outcome)) => outcome,
+            Ok(Err(_)) => AckOutcome::Disconnected,
+            Err(_) => {
+                self.pending.lock().unwrap().remove(delivery_id);
+                AckOutcome::TimedOut
+            }
+        }
+    }
+
+    /// Resolve a delivery acked by the agent. Returns false for unknown or expired ids.
+    pub fn ack(&self, delivery_id: &str) -> bool {
+        self.resolve(delivery_id, AckOutcome::Acked)
+    }
+
+    /// Resolve a delivery with an outcome decided elsewhere, such as a relay
+    /// reply from the API instance holding the agent.
+    pub fn resolve(&self, delivery_id: &str, outcome: AckOutcome) -> bool {
+        let Some(pending) = self.pending.lock().unwrap().remove(delivery_id) else {
+            return false;
+        };
+        pending.resolve.send(outcome).is_ok()
+    }
+
+    /// Hand `message` to the first agent with room in its outbound buffer,
+    /// tracking the ack against that connection. Never waits: a full buffer or
+    /// a closed connection moves on to the next agent, and if none accepts,
+    /// the error is returned so the delivery takes the retry path instead of
+    /// stalling behind a slow agent.
+    pub fn offer(
+        &self,
+        delivery_id: &str,
+        agents: &[Arc<AgentConnection>],
+        message: &ServerMessage,
+    ) -> Result<oneshot::Receiver<AckOutcome>, &'static str> {
+        let mut error = "no agent connection accepted the delivery";
+        for agent in agents {
+            let ack = self.track(delivery_id, &agent.connection_id);
+            match agent.sender.try_send(message.clone()) {
+                Ok(()) => {
+                    agent.health.record_delivered();
+                    return Ok(ack);
+                }
+                Err(TrySendError::Full(_)) => {
+                    self.forget(delivery_id);
+                    METRICS.record_tunnel_send_rejected("full");
+                    warn!(
+                        delivery_id = %delivery_id,
+                        connection_id = %agent.connection_id,
+                        "agent buffer full, trying next agent connection"
+                    );
+                    error = "agent buffer full";
+                }
+                Err(TrySendError::Closed(_)) => {
+                    self.disconnect(&agent.connection_id);
+                    METRICS.record_tunnel_send_rejected("closed");
+                    warn!(
+                        delivery_id = %delivery_id,
+                        connection_id = %agent.connection_id,
+                        "agent connection closed, trying next agent connection"
+                    );
+                    error = "agent connection closed";
+                }
+            }
+        }
+        Err(error)
+    }
+
+    /// Fail every delivery still pending on a closed connection. Returns how many were released.
+    pub fn disconnect(&self, connection_id: &str) -> usize {
+        let released: Vec<PendingAck> = {
+            let mut pending = self.pending.lock().unwrap();
+            let ids: Vec<String> = pending
+                .iter()
+                .filter(|(_, p)| p.connection_id == connection_id)
+                .map(|(id, _)| id.clone())
+                .collect();
+            ids.iter().filter_map(|id| pending.remove(id)).collect()
+        };
+        let count = released.len();
+        for pending in released {
+            let _ = pending.resolve.send(AckOutcome::Disconnected);
+        }
+        count
+    }
+
+    pub fn pending_count(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+}
+
+/// Live agent connections, grouped by subscriber.
+///
+/// A subscriber may run several agents at once; each connection is tracked
+/// separately by `connection_id` and deliveries rotate between them.
 #[derive(Default)]
 pub struct AgentRegistry {
-    agents: RwLock<HashMap<String, Arc<AgentConnection>>>,
+    agents: RwLock<HashMap<String, Vec<Arc<AgentConnection>>>>,
+    cursor: AtomicUsize,
+    shutdown: watch::Sender<bool>,
+    pub acks: AckTracker,
 }
 
 impl AgentRegistry {
@@ -62,20 +352,85 @@ impl AgentRegistry {
         Self::default()
     }
 
-    pub async fn register(&self, conn: AgentConnection) {
+    pub async fn register(&self, conn: AgentConnection) -> Arc<AgentConnection> {
         let subscriber_id = conn.subscriber_id.clone();
+        let conn = Arc::new(conn);
         self.agents
             .write()
             .await
-            .insert(subscriber_id, Arc::new(conn));
+            .entry(subscriber_id)
+            .or_default()
+            .push(conn.clone());
+        conn
+    }
+
+    /// Tells every open tunnel to close so agents reconnect to another
+    /// instance. Connections made after this are closed straight away.
+    pub fn begin_shutdown(&self) {
+        self.shutdown.send_replace(true);
+    }
+
+    /// Resolves once [`AgentRegistry::begin_shutdown`] has been called.
+    pub async fn shutdown_requested(&self) {
+        let mut shutdown = self.shutdown.subscribe();
+        let _ = shutdown.wait_for(|closing| *closing).await;
     }
 
-    pub async fn unregister(&self, subscriber_id: &str) {
-        self.agents.write().await.remove(subscriber_id);
+    /// Drops one connection, leaving the subscriber's other agents in place.
+    pub async fn unregister(&self, subscriber_id: &str, connection_id: &str) {
+        let mut agents = self.agents.write().await;
+        if let Some(conns) = agents.get_mut(subscriber_id) {
+            conns.retain(|conn| conn.connection_id != connection_id);
+            if conns.is_empty() {
+                agents.remove(subscriber_id);
+            }
+        }
     }
 
+    /// The subscriber's longest-lived connection.
     pub async fn get(&self, subscriber_id: &str) -> Option<Arc<AgentConnection>> {
-        self.agents.read().await.get(subscriber_id).cloned()
+        self.agents
+            .read()
+            .await
+            .get(subscriber_id)
+            .and_then(|conns| conns.first().cloned())
+    }
+
+    /// Every connection the subscriber has open, oldest first.
+    pub async fn get_all(&self, subscriber_id: &str) -> Vec<Arc<AgentConnection>> {
+        self.agents
+            .read()
+            .await
+            .get(subscriber_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The subscriber's open connections in the order a delivery should try
+    /// them: rotated round-robin per call, skipping any whose outbound
+    /// channel has already closed.
+    pub async fn delivery_order(&self, subscriber_id: &str) -> Vec<Arc<AgentConnection>> {
+        let mut live: Vec<_> = self
+            .get_all(subscriber_id)
+            .await
+            .into_iter()
+            .filter(|conn| !conn.sender.is_closed())
+            .collect();
+        if !live.is_empty() {
+            let start = self.cursor.fetch_add(1, Ordering::Relaxed) % live.len();
+            live.rotate_left(start);
+        }
+        live
+    }
+
+    pub async fn list(&self) -> Vec<Arc<AgentConnection>> {
+        self.agents
+            .read()
+            .await
+            .values()
+            .flatten()
+            .cloned()
+            .collect()
     }
 }
 
@@ -16,14 +16,25 @@ mod tests {
     fn test_client_auth_message_serialization() {
         let msg = ClientMessage::Auth {
             token: "hld_sub_test123".to_string(),
+            version: Some(PROTOCOL_VERSION),
+            last_seen_seq: Some(41),
         };
         let json = serde_json::to_string(&msg).unwrap();
         assert!(json.contains("\"type\":\"auth\""));
         assert!(json.contains("\"token\":\"hld_sub_test123\""));
+        assert!(json.contains("\"last_seen_seq\":41"));
 
         let parsed: ClientMessage = serde_json::from_str(&json).unwrap();
         match parsed {
-            ClientMessage::Auth { token } => assert_eq!(token, "hld_sub_test123"),
+            ClientMessage::Auth {
+                token,
+                version,
+                last_seen_seq,
+            } => {
+                assert_eq!(token, "hld_sub_test123");
+                assert_eq!(version, Some(PROTOCOL_VERSION));
+                assert_eq!(last_seen_seq, Some(41));
+            }
             _ => panic!("Expected Auth message"),
         }
     }
@@ -59,11 +70,66 @@ mod tests {
         let msg = ServerMessage::AuthOk {
             connection_id: "conn_abc123".to_string(),
             subscriber_id: "sub_001".to_string(),
+            protocol_version: 1,
         };
         let json = serde_json::to_string(&msg).unwrap();
         assert!(json.contains("\"type\":\"auth_ok\""));
         assert!(json.contains("\"connection_id\":\"conn_abc123\""));
         assert!(json.contains("\"subscriber_id\":\"sub_001\""));
+        assert!(json.contains("\"protocol_version\":1"));
+    }
+
+    #[test]
+    fn test_auth_messages_without_version_are_legacy() {
+        let auth: ClientMessage =
+            serde_json::from_str(r#"{"type":"auth","token":"hld_sub_x"}"#).unwrap();
+        assert!(matches!(
+            auth,
+            ClientMessage::Auth {
+                version: None,
+                last_seen_seq: None,
+                ..
+            }
+        ));
+        let json = serde_json::to_string(&auth).unwrap();
+        assert!(!json.contains("version"));
+        assert!(!json.contains("last_seen_seq"));
+
+        let ok: ServerMessage = serde_json::from_str(
+            r#"{"type":"auth_ok","connection_id":"conn_1","subscriber_id":"sr_1"}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            ok,
+            ServerMessage::AuthOk {
+                protocol_version: 1,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_negotiate_version() {
+        assert_eq!(negotiate_version(None), Ok(1));
+        assert_eq!(
+            negotiate_version(Some(PROTOCOL_VERSION)),
+            Ok(PROTOCOL_VERSION)
+        );
+        assert_eq!(
+            negotiate_version(Some(PROTOCOL_VERSION + 5)),
+            Ok(PROTOCOL_VERSION)
+        );
+        let err = negotiate_version(Some(MIN_PROTOCOL_VERSION - 1)).unwrap_err();
+        assert!(err.contains("upgrade herald-agent"));
+    }
+
+    #[test]
+    fn test_subprotocol_names() {
+        assert_eq!(subprotocol(1), "herald.v1");
+        assert_eq!(parse_subprotocol("herald.v1"), Some(1));
+        assert_eq!(parse_subprotocol(" herald.v12"), Some(12));
+        assert_eq!(parse_subprotocol("herald.vx"), None);
+        assert_eq!(parse_subprotocol("graphql-ws"), None);
     }
 
     #[test]
@@ -90,11 +156,13 @@ mod tests {
                 metadata: serde_json::json!({"source": "test"}),
                 created_at: Utc::now(),
             },
+            seq: Some(7),
         };
         let json = serde_json::to_string(&msg).unwrap();
         assert!(json.contains("\"type\":\"signal\""));
         assert!(json.contains("\"channel_slug\":\"tech-news\""));
         assert!(json.contains("\"urgency\":\"high\""));
+        assert!(json.contains("\"seq\":7"));
     }
 
     #[test]
@@ -118,6 +186,7 @@ mod tests {
             subscriber_id: "sub_001".to_string(),
             sender: tx,
             connected_at: Utc::now(),
+            health: ConnectionHealth::default(),
         };
 
         registry.register(conn).await;
@@ -139,12 +208,13 @@ mod tests {
             subscriber_id: "sub_001".to_string(),
             sender: tx,
             connected_at: Utc::now(),
+            health: ConnectionHealth::default(),
         };
 
         registry.register(conn).await;
         assert!(registry.get("sub_001").await.is_some());
 
-        registry.unregister("sub_001").await;
+        registry.unregister("sub_001", "conn_test").await;
         assert!(registry.get("sub_001").await.is_none());
     }
 
@@ -154,33 +224,90 @@ mod tests {
         assert!(registry.get("nonexistent").await.is_none());
     }
 
+    fn agent(
+        connection_id: &str,
+        subscriber_id: &str,
+    ) -> (AgentConnection, mpsc::Receiver<ServerMessage>) {
+        let (tx, rx) = mpsc::channel(10);
+        let conn = AgentConnection {
+            connection_id: connection_id.to_string(),
+            subscriber_id: subscriber_id.to_string(),
+            sender: tx,
+            connected_at: Utc::now(),
+            health: ConnectionHealth::default(),
+        };
+        (conn, rx)
+    }
+
     #[tokio::test]
-    async fn test_registry_overwrite_connection() {
+    async fn test_registry_keeps_concurrent_connections() {
         let registry = AgentRegistry::new();
-        let (tx1, _rx1) = mpsc::channel(10);
-        let (tx2, _rx2) = mpsc::channel(10);
+        let (conn1, _rx1) = agent("conn_first", "sub_001");
+        let (conn2, _rx2) = agent("conn_second", "sub_001");
+        registry.register(conn1).await;
+        registry.register(conn2).await;
 
-        // Register first connection
-        let conn1 = AgentConnection {
-            connection_id: "conn_first".to_string(),
-            subscriber_id: "sub_001".to_string(),
-            sender: tx1,
-            connected_at: Utc::now(),
-        };
+        // A second agent joins rather than evicting the first.
+        let all = registry.get_all("sub_001").await;
+        let ids: Vec<_> = all.iter().map(|c| c.connection_id.as_str()).collect();
+        assert_eq!(ids, ["conn_first", "conn_second"]);
+        assert_eq!(
+            registry.get("sub_001").await.unwrap().connection_id,
+            "conn_first"
+        );
+        assert_eq!(registry.list().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_registry_unregister_removes_only_matching_connection() {
+        let registry = AgentRegistry::new();
+        let (conn1, _rx1) = agent("conn_first", "sub_001");
+        let (conn2, _rx2) = agent("conn_second", "sub_001");
         registry.register(conn1).await;
+        registry.register(conn2).await;
 
-        // Register second connection with same subscriber_id
-        let conn2 = AgentConnection {
-            connection_id: "conn_second".to_string(),
-            subscriber_id: "sub_001".to_string(),
-            sender: tx2,
-            connected_at: Utc::now(),
-        };
+        registry.unregister("sub_001", "conn_first").await;
+        let all = registry.get_all("sub_001").await;
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].connection_id, "conn_second");
+
+        registry.unregister("sub_001", "conn_second").await;
+        assert!(registry.get_all("sub_001").await.is_empty());
+        assert!(registry.list().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_registry_delivery_order_rotates_between_connections() {
+        let registry = AgentRegistry::new();
+        let (conn1, _rx1) = agent("conn_a", "sub_001");
+        let (conn2, _rx2) = agent("conn_b", "sub_001");
+        registry.register(conn1).await;
         registry.register(conn2).await;
 
-        // Should have the second connection
-        let agent = registry.get("sub_001").await.unwrap();
-        assert_eq!(agent.connection_id, "conn_second");
+        let first = registry.delivery_order("sub_001").await;
+        let second = registry.delivery_order("sub_001").await;
+        assert_eq!(first.len(), 2);
+        assert_eq!(second.len(), 2);
+        assert_ne!(first[0].connection_id, second[0].connection_id);
+        // The rest of the order is the failover path.
+        assert_eq!(first[1].connection_id, second[0].connection_id);
+    }
+
+    #[tokio::test]
+    async fn test_registry_delivery_order_skips_closed_connections() {
+        let registry = AgentRegistry::new();
+        let (conn1, rx1) = agent("conn_closed", "sub_001");
+        let (conn2, _rx2) = agent("conn_open", "sub_001");
+        registry.register(conn1).await;
+        registry.register(conn2).await;
+        drop(rx1);
+
+        for _ in 0..3 {
+            let order = registry.delivery_order("sub_001").await;
+            assert_eq!(order.len(), 1);
+            assert_eq!(order[0].connection_id, "conn_open");
+        }
+        assert!(registry.delivery_order("sub_missing").await.is_empty());
     }
 
     #[tokio::test]
@@ -198,6 +325,7 @@ mod tests {
                     subscriber_id: format!("sub_{}", i),
                     sender: tx,
                     connected_at: Utc::now(),
+                    health: ConnectionHealth::default(),
                 };
                 reg.register(conn).await;
             });
@@ -299,11 +427,13 @@ mod tests {
     fn test_client_auth_empty_token() {
         let msg = ClientMessage::Auth {
             token: "".to_string(),
+            version: None,
+            last_seen_seq: None,
         };
         let json = serde_json::to_string(&msg).unwrap();
         let parsed: ClientMessage = serde_json::from_str(&json).unwrap();
         match parsed {
-            ClientMessage::Auth { token } => assert!(token.is_empty()),
+            ClientMessage::Auth { token, .. } => assert!(token.is_empty()),
             _ => panic!("Expected Auth message"),
         }
     }
@@ -346,7 +476,9 @@ mod tests {
     async fn test_registry_unregister_nonexistent() {
         let registry = AgentRegistry::new();
         // Should not panic when unregistering non-existent subscriber
-        registry.unregister("nonexistent_subscriber").await;
+        registry
+            .unregister("nonexistent_subscriber", "conn_missing")
+            .await;
         assert!(registry.get("nonexistent_subscriber").await.is_none());
     }
 
@@ -377,4 +509,254 @@ mod tests {
             _ => panic!("Expected Ack message"),
         }
     }
+
+    // ============================================================
+    // Ack Tracker Tests
+    // ============================================================
+
+    #[tokio::test]
+    async fn test_ack_resolves_pending_delivery() {
+        let tracker = AckTracker::new();
+        let rx = tracker.track("del_1", "conn_a");
+        assert_eq!(tracker.pending_count(), 1);
+
+        assert!(tracker.ack("del_1"));
+        let outcome = tracker
+            .wait("del_1", rx, std::time::Duration::from_secs(1))
+            .await;
+        assert_eq!(outcome, AckOutcome::Acked);
+        assert_eq!(tracker.pending_count(), 0);
+        assert!(!tracker.ack("del_1"));
+    }
+
+    #[tokio::test]
+    async fn test_unacked_delivery_times_out() {
+        let tracker = AckTracker::new();
+        let rx = tracker.track("del_1", "conn_a");
+
+        let outcome = tracker
+            .wait("del_1", rx, std::time::Duration::from_millis(10))
+            .await;
+        assert_eq!(outcome, AckOutcome::TimedOut);
+        assert_eq!(tracker.pending_count(), 0);
+        // A late ack is ignored.
+        assert!(!tracker.ack("del_1"));
+    }
+
+    #[tokio::test]
+    async fn test_disconnect_releases_only_that_connections_deliveries() {
+        let tracker = AckTracker::new();
+        let rx1 = tracker.track("del_1", "conn_a");
+        let rx2 = tracker.track("del_2", "conn_a");
+        let _rx3 = tracker.track("del_3", "conn_b");
+
+        assert_eq!(tracker.disconnect("conn_a"), 2);
+        assert_eq!(tracker.pending_count(), 1);
+
+        let timeout = std::time::Duration::from_secs(1);
+        assert_eq!(
+            tracker.wait("del_1", rx1, timeout).await,
+            AckOutcome::Disconnected
+        );
+        assert_eq!(
+            tracker.wait("del_2", rx2, timeout).await,
+            AckOutcome::Disconnected
+        );
+    }
+
+    // ============================================================
+    // Connection Health Tests
+    // ============================================================
+
+    #[test]
+    fn test_health_starts_empty() {
+        let snapshot = ConnectionHealth::default().snapshot();
+        assert!(snapshot.last_ping_sent_at.is_none());
+        assert!(snapshot.last_pong_at.is_none());
+        assert!(snapshot.round_trip_ms.is_none());
+        assert_eq!(snapshot.messages_delivered, 0);
+        assert_eq!(snapshot.messages_acked, 0);
+    }
+
+    #[test]
+    fn test_health_tracks_ping_pong_round_trip() {
+        let health = ConnectionHealth::default();
+        let ping_at = Utc::now();
+        let pong_at = ping_at + chrono::Duration::milliseconds(42);
+
+        health.record_ping(ping_at);
+        let snapshot = health.snapshot();
+        assert!(snapshot.last_ping_sent_at.is_some());
+        assert!(snapshot.round_trip_ms.is_none());
+
+        health.record_pong(pong_at);
+        let snapshot = health.snapshot();
+        assert_eq!(
+            snapshot.last_pong_at.map(|t| t.timestamp_millis()),
+            Some(pong_at.timestamp_millis())
+        );
+        assert_eq!(snapshot.round_trip_ms, Some(42));
+    }
+
+    #[test]
+    fn test_health_counts_deliveries_and_acks() {
+        let health = ConnectionHealth::default();
+        health.record_delivered();
+        health.record_delivered();
+        health.record_acked();
+
+        let snapshot = health.snapshot();
+        assert_eq!(snapshot.messages_delivered, 2);
+        assert_eq!(snapshot.messages_acked, 1);
+    }
+
+    #[test]
+    fn test_health_unresponsive_without_pong_past_timeout() {
+        let health = ConnectionHealth::default();
+        let connected_at = Utc::now();
+        let timeout = std::time::Duration::from_secs(90);
+
+        assert!(!health.is_unresponsive(
+            connected_at,
+            connected_at + chrono::Duration::seconds(90),
+            timeout
+        ));
+        assert!(health.is_unresponsive(
+            connected_at,
+            connected_at + chrono::Duration::seconds(91),
+            timeout
+        ));
+
+        // A pong restarts the clock.
+        health.record_pong(connected_at + chrono::Duration::seconds(60));
+        assert!(!health.is_unresponsive(
+            connected_at,
+            connected_at + chrono::Duration::seconds(120),
+            timeout
+        ));
+        assert!(health.is_unresponsive(
+            connected_at,
+            connected_at + chrono::Duration::seconds(151),
+            timeout
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_registry_list_returns_connections() {
+        let registry = AgentRegistry::new();
+        let (tx, _rx) = mpsc::channel(1);
+        let conn = registry
+            .register(AgentConnection {
+                connection_id: "conn_list".to_string(),
+                subscriber_id: "sub_list".to_string(),
+                sender: tx,
+                connected_at: Utc::now(),
+                health: ConnectionHealth::default(),
+            })
+            .await;
+        conn.health.record_delivered();
+
+        let listed = registry.list().await;
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].health.snapshot().messages_delivered, 1);
+    }
+
+    #[tokio::test]
+    async fn test_registry_shutdown_wakes_waiters() {
+        let registry = Arc::new(AgentRegistry::new());
+        let waiter = tokio::spawn({
+            let registry = registry.clone();
+            async move { registry.shutdown_requested().await }
+        });
+        tokio::task::yield_now().await;
+        assert!(!waiter.is_finished());
+
+        registry.begin_shutdown();
+        tokio::time::timeout(std::time::Duration::from_secs(1), waiter)
+            .await
+            .expect("waiter should wake on shutdown")
+            .unwrap();
+
+        // Late callers see the shutdown immediately.
+        tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            registry.shutdown_requested(),
+        )
+        .await
+        .expect("shutdown already requested");
+    }
+
+    // ============================================================
+    // Ack Offer Tests
+    // ============================================================
+
+    fn make_test_agent(
+        connection_id: &str,
+        buffer: usize,
+    ) -> (
+        Arc<AgentConnection>,
+        tokio::sync::mpsc::Receiver<ServerMessage>,
+    ) {
+        let (tx, rx) = tokio::sync::mpsc::channel(buffer);
+        let agent = Arc::new(AgentConnection {
+            connection_id: connection_id.to_string(),
+            subscriber_id: "sr_test".to_string(),
+            sender: tx,
+            connected_at: Utc::now(),
+            health: ConnectionHealth::default(),
+        });
+        (agent, rx)
+    }
+
+    #[test]
+    fn test_full_agent_buffer_fails_delivery_instead_of_blocking() {
+        let acks = AckTracker::new();
+        let (agent, _rx) = make_test_agent("conn_slow", 1);
+        agent.sender.try_send(ServerMessage::Ping).unwrap();
+
+        // Returns immediately with an error, which sends the delivery down
+        // the retry path rather than parking the worker on the full buffer.
+        let agents = std::slice::from_ref(&agent);
+        let result = acks.offer("del_1", agents, &ServerMessage::Ping);
+        assert_eq!(result.err(), Some("agent buffer full"));
+        assert_eq!(acks.pending_count(), 0);
+        assert_eq!(agent.health.snapshot().messages_delivered, 0);
+    }
+
+    #[test]
+    fn test_full_agent_buffer_fails_over_to_next_agent() {
+        let acks = AckTracker::new();
+        let (slow, _slow_rx) = make_test_agent("conn_slow", 1);
+        slow.sender.try_send(ServerMessage::Ping).unwrap();
+        let (ready, mut ready_rx) = make_test_agent("conn_ready", 1);
+        let (closed, closed_rx) = make_test_agent("conn_closed", 1);
+        drop(closed_rx);
+
+        let result = acks.offer(
+            "del_1",
+            &[closed, slow, ready.clone()],
+            &ServerMessage::Ping,
+        );
+        assert!(result.is_ok());
+        assert!(matches!(ready_rx.try_recv(), Ok(ServerMessage::Ping)));
+        assert_eq!(ready.health.snapshot().messages_delivered, 1);
+        assert_eq!(acks.pending_count(), 1);
+    }
+
+    #[test]
+    fn test_resolve_reports_relayed_outcome() {
+        let acks = AckTracker::new();
+        let mut rx = acks.track("del_1", "relay:api_1");
+        assert!(acks.resolve("del_1", AckOutcome::Disconnected));
+        assert_eq!(rx.try_recv(), Ok(AckOutcome::Disconnected));
+        assert!(!acks.resolve("del_1", AckOutcome::Acked));
+    }
+
+    #[test]
+    fn test_ack_outcome_serializes_snake_case() {
+        assert_eq!(
+            serde_json::to_string(&AckOutcome::TimedOut).unwrap(),
+            "\"timed_out\""
+        );
+    }
 }
@@ -19,6 +19,21 @@ pub enum PricingTier {
     Enterprise,
 }
 
+impl PricingTier {
+    /// Accepted string values, matching the `pricing_tier` Postgres enum.
+    pub const VALUES: &'static [&'static str] = &["free", "pro", "enterprise"];
+
+    /// Parse a lowercase pricing tier string.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "free" => Some(PricingTier::Free),
+            "pro" => Some(PricingTier::Pro),
+            "enterprise" => Some(PricingTier::Enterprise),
+            _ => None,
+        }
+    }
+}
+
 /// Account tier for publishers and subscribers.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
@@ -28,6 +43,22 @@ pub enum AccountTier {
     Enterprise,
 }
 
+impl AccountTier {
+    /// Numeric rank for comparing tiers (Free < Pro < Enterprise).
+    pub fn rank(&self) -> u8 {
+        match self {
+            AccountTier::Free => 0,
+            AccountTier::Pro => 1,
+            AccountTier::Enterprise => 2,
+        }
+    }
+
+    /// Whether this tier satisfies an optional minimum tier requirement.
+    pub fn meets(&self, min: Option<&AccountTier>) -> bool {
+        min.is_none_or(|min| self.rank() >= min.rank())
+    }
+}
+
 /// Account lifecycle status.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
@@ -46,8 +77,26 @@ pub enum ChannelStatus {
     Deleted,
 }
 
+impl ChannelStatus {
+    /// Accepted string values, matching the `channel_status` Postgres enum.
+    pub const VALUES: &'static [&'static str] = &["active", "paused", "deleted"];
+
+    /// Parse a lowercase channel status string.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "active" => Some(ChannelStatus::Active),
+            "paused" => Some(ChannelStatus::Paused),
+            "deleted" => Some(ChannelStatus::Deleted),
+            _ => None,
+        }
+    }
+}
+
 /// Signal urgency level, affects delivery priority.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+///
+/// Variants are declared in ascending order, so the derived ordering is
+/// `Low < Normal < High < Critical`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 #[serde(rename_all = "lowercase")]
 pub enum SignalUrgency {
     Low,
@@ -56,12 +105,26 @@ pub enum SignalUrgency {
     Critical,
 }
 
+impl SignalUrgency {
+    /// Whether this urgency reaches a subscription's `min_urgency` threshold.
+    pub fn meets(&self, min: &SignalUrgency) -> bool {
+        self >= min
+    }
+
+    /// High and critical signals take the priority delivery path.
+    pub fn is_urgent(&self) -> bool {
+        *self >= SignalUrgency::High
+    }
+}
+
 /// Signal lifecycle status.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum SignalStatus {
     Active,
     Deleted,
+    /// Waiting for `scheduled_at` before fan-out.
+    Scheduled,
 }
 
 /// Subscription lifecycle status.
@@ -90,6 +153,8 @@ pub enum DeliveryStatus {
     Pending,
     Success,
     Failed,
+    /// Permanently failed (e.g. subscriber deleted); never retried or dead-lettered.
+    Undeliverable,
 }
 
 /// How signals are delivered to subscribers.
@@ -141,6 +206,10 @@ pub struct Subscriber {
     pub email: String,
     /// Secret used to sign webhook payloads for this subscriber.
     pub webhook_secret: String,
+    /// Secret replaced by the last rotation, still honoured by verifiers until
+    /// `previous_webhook_secret_expires_at`.
+    pub previous_webhook_secret: Option<String>,
+    pub previous_webhook_secret_expires_at: Option<DateTime<Utc>>,
     pub stripe_customer_id: Option<String>,
     pub tier: AccountTier,
     pub status: AccountStatus,
@@ -167,6 +236,10 @@ pub struct Channel {
     pub status: ChannelStatus,
     /// Whether the channel is listed in the marketplace.
     pub is_public: bool,
+    /// Optional `RetryPolicy` override, stored as JSON.
+    pub retry_policy: Option<serde_json::Value>,
+    /// Seconds within which a repeated title+body signal is deduplicated; 0 is off.
+    pub dedup_window_secs: i32,
     pub signal_count: i32,
     pub subscriber_count: i32,
     pub created_at: DateTime<Utc>,
@@ -188,6 +261,10 @@ pub struct Signal {
     pub delivered_count: i32,
     pub failed_count: i32,
     pub status: SignalStatus,
+    /// If set, only subscribers at or above this tier receive the signal.
+    pub min_subscriber_tier: Option<AccountTier>,
+    /// Future send time for scheduled signals.
+    pub scheduled_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -200,6 +277,16 @@ pub struct Webhook {
     pub name: String,
     /// Optional bearer token sent in Authorization header.
     pub token: Option<String>,
+    /// Extra request headers sent with every delivery (string -> string).
+    pub headers: Option<serde_json::Value>,
+    /// JSON Schema the subscriber expects deliveries to match.
+    pub payload_schema: Option<serde_json::Value>,
+    /// Per-request delivery timeout in milliseconds.
+    pub timeout_ms: i32,
+    /// Most deliveries the worker sends to this endpoint at once.
+    pub max_in_flight: i32,
+    /// Optional cap on deliveries started per second.
+    pub max_per_second: Option<i32>,
     pub status: WebhookStatus,
     /// Consecutive failure count (resets on success).
     pub failure_count: i32,
@@ -209,6 +296,49 @@ pub struct Webhook {
     pub updated_at: DateTime<Utc>,
 }
 
+impl Webhook {
+    pub const DEFAULT_TIMEOUT_MS: i32 = 30_000;
+    pub const MIN_TIMEOUT_MS: i32 = 1_000;
+    pub const MAX_TIMEOUT_MS: i32 = 60_000;
+    pub const DEFAULT_MAX_IN_FLIGHT: i32 = 10;
+    pub const MAX_MAX_IN_FLIGHT: i32 = 100;
+    pub const MAX_MAX_PER_SECOND: i32 = 1_000;
+
+    /// Reject delivery timeouts outside the supported range.
+    pub fn validate_timeout_ms(timeout_ms: i32) -> Result<(), String> {
+        if !(Self::MIN_TIMEOUT_MS..=Self::MAX_TIMEOUT_MS).contains(&timeout_ms) {
+            return Err(format!(
+                "timeoutMs must be between {} and {}",
+                Self::MIN_TIMEOUT_MS,
+                Self::MAX_TIMEOUT_MS
+            ));
+        }
+        Ok(())
+    }
+
+    /// Reject concurrency caps outside the supported range.
+    pub fn validate_max_in_flight(max_in_flight: i32) -> Result<(), String> {
+        if !(1..=Self::MAX_MAX_IN_FLIGHT).contains(&max_in_flight) {
+            return Err(format!(
+                "maxInFlight must be between 1 and {}",
+                Self::MAX_MAX_IN_FLIGHT
+            ));
+        }
+        Ok(())
+    }
+
+    /// Reject per-second caps outside the supported range.
+    pub fn validate_max_per_second(max_per_second: i32) -> Result<(), String> {
+        if !(1..=Self::MAX_MAX_PER_SECOND).contains(&max_per_second) {
+            return Err(format!(
+                "maxPerSecond must be between 1 and {}",
+                Self::MAX_MAX_PER_SECOND
+            ));
+        }
+        Ok(())
+    }
+}
+
 /// A subscription linking a subscriber to a channel.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Subscription {
@@ -217,7 +347,11 @@ pub struct Subscription {
     pub channel_id: String,
     /// If set, signals are delivered to this webhook instead of the default.
     pub webhook_id: Option<String>,
+    /// If set, critical signals are delivered to this webhook instead.
+    pub emergency_webhook_id: Option<String>,
     pub status: SubscriptionStatus,
+    /// Signals below this urgency are not delivered to the subscription.
+    pub min_urgency: SignalUrgency,
     pub stripe_subscription_id: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -237,6 +371,8 @@ pub struct Delivery {
     /// HTTP status code from webhook response (if applicable).
     pub status_code: Option<i32>,
     pub error_message: Option<String>,
+    /// Truncated response body from a failed webhook attempt.
+    pub response_body: Option<String>,
     /// Round-trip latency in milliseconds.
     pub latency_ms: Option<i32>,
     pub created_at: DateTime<Utc>,
@@ -274,9 +410,79 @@ pub struct DeadLetterEntry {
     /// Array of error messages from each failed attempt.
     pub error_history: serde_json::Value,
     pub resolved_at: Option<DateTime<Utc>>,
+    /// Set when the entry was closed without a replay, e.g. because its
+    /// signal or subscription was deleted.
+    pub resolution_note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Stored outcome of a write made under an `Idempotency-Key`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdempotencyKey {
+    pub key: String,
+    pub owner_id: String,
+    /// SHA-256 of the original request body.
+    pub request_hash: String,
+    /// Response returned to the first request, replayed verbatim.
+    pub response_snapshot: serde_json::Value,
     pub created_at: DateTime<Utc>,
 }
 
+/// Per-channel override of the delivery retry schedule.
+///
+/// Retry `n` (1-based) waits `base_delay_secs * multiplier^(n - 1)`; the
+/// delivery is dead-lettered once `max_attempts` attempts have failed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay_secs: u64,
+    pub multiplier: f64,
+}
+
+impl RetryPolicy {
+    pub const MAX_ATTEMPTS: u32 = 20;
+    pub const MAX_DELAY_SECS: u64 = 86_400;
+
+    /// Reject policies that would retry forever or back off unreasonably.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_attempts == 0 || self.max_attempts > Self::MAX_ATTEMPTS {
+            return Err(format!(
+                "maxAttempts must be between 1 and {}",
+                Self::MAX_ATTEMPTS
+            ));
+        }
+        if self.base_delay_secs == 0 || self.base_delay_secs > Self::MAX_DELAY_SECS {
+            return Err(format!(
+                "baseDelaySecs must be between 1 and {}",
+                Self::MAX_DELAY_SECS
+            ));
+        }
+        if !(1.0..=10.0).contains(&self.multiplier) {
+            return Err("multiplier must be between 1.0 and 10.0".to_string());
+        }
+        Ok(())
+    }
+
+    /// Delay before retry number `retry` (1 for the first retry), capped at a day.
+    pub fn delay_for(&self, retry: u32) -> std::time::Duration {
+        let exponent = retry.saturating_sub(1) as i32;
+        let secs = self.base_delay_secs as f64 * self.multiplier.powi(exponent);
+        std::time::Duration::from_secs(secs.min(Self::MAX_DELAY_SECS as f64) as u64)
+    }
+}
+
+/// Version of the JSON body delivered to subscribers, sent as `schemaVersion`.
+/// Bump whenever the payload shape changes.
+pub const PAYLOAD_SCHEMA_VERSION: u32 = 1;
+
+/// Webhook header repeating [`PAYLOAD_SCHEMA_VERSION`].
+pub const PAYLOAD_VERSION_HEADER: &str = "X-Herald-Payload-Version";
+
+/// Webhook header carrying a second signature made with the platform-wide
+/// `HERALD_HMAC_SECRET`, sent when `HERALD_GLOBAL_SIGNATURE` is on.
+pub const GLOBAL_SIGNATURE_HEADER: &str = "X-Herald-Signature-Global";
+
 /// Job payload for the delivery worker queue.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeliveryJob {
@@ -284,6 +490,15 @@ pub struct DeliveryJob {
     pub subscription_id: String,
     pub webhook_id: Option<String>,
     pub attempt: i32,
+    /// Higher values are polled first; see [`crate::priority`]. Jobs queued
+    /// before priorities existed deserialize as 0.
+    #[serde(default)]
+    pub priority: i32,
+    /// Channel the signal was pushed to; the worker shares delivery slots
+    /// fairly between channels. Jobs queued before it was recorded
+    /// deserialize empty.
+    #[serde(default)]
+    pub channel_id: String,
 }
 
 #[cfg(test)]
@@ -391,6 +606,64 @@ mod tests {
         assert_eq!(serde_json::to_string(&SignalStatus::Deleted).unwrap(), "\"deleted\"");
     }
 
+    // ============================================================
+    // Tier Ordering Tests
+    // ============================================================
+
+    #[test]
+    fn test_account_tier_rank_ordering() {
+        assert!(AccountTier::Free.rank() < AccountTier::Pro.rank());
+        assert!(AccountTier::Pro.rank() < AccountTier::Enterprise.rank());
+    }
+
+    #[test]
+    fn test_account_tier_meets_ungated() {
+        assert!(AccountTier::Free.meets(None));
+    }
+
+    #[test]
+    fn test_pro_gated_signal_skips_free_reaches_pro() {
+        let min = Some(&AccountTier::Pro);
+        assert!(!AccountTier::Free.meets(min));
+        assert!(AccountTier::Pro.meets(min));
+        assert!(AccountTier::Enterprise.meets(min));
+    }
+
+    #[test]
+    fn test_signal_urgency_ordering() {
+        assert!(SignalUrgency::Low < SignalUrgency::Normal);
+        assert!(SignalUrgency::Normal < SignalUrgency::High);
+        assert!(SignalUrgency::High < SignalUrgency::Critical);
+        assert!(SignalUrgency::Critical > SignalUrgency::Low);
+        assert_eq!(
+            [
+                SignalUrgency::High,
+                SignalUrgency::Low,
+                SignalUrgency::Critical,
+                SignalUrgency::Normal,
+            ]
+            .iter()
+            .max(),
+            Some(&SignalUrgency::Critical)
+        );
+    }
+
+    #[test]
+    fn test_signal_urgency_is_urgent() {
+        assert!(!SignalUrgency::Low.is_urgent());
+        assert!(!SignalUrgency::Normal.is_urgent());
+        assert!(SignalUrgency::High.is_urgent());
+        assert!(SignalUrgency::Critical.is_urgent());
+    }
+
+    #[test]
+    fn test_signal_urgency_meets_threshold_boundary() {
+        assert!(SignalUrgency::High.meets(&SignalUrgency::High));
+        assert!(SignalUrgency::Critical.meets(&SignalUrgency::High));
+        assert!(!SignalUrgency::Normal.meets(&SignalUrgency::High));
+        assert!(SignalUrgency::Low.meets(&SignalUrgency::Low));
+    }
+
     // ============================================================
     // Invalid Deserialization Tests
     // ============================================================
@@ -413,6 +686,8 @@ mod tests {
             subscription_id: "sub_456".to_string(),
             webhook_id: Some("wh_789".to_string()),
             attempt: 3,
+            priority: 125,
+            channel_id: "ch_1".to_string(),
         };
 
         let json = serde_json::to_string(&job).unwrap();
@@ -422,6 +697,7 @@ mod tests {
         let parsed: DeliveryJob = serde_json::from_str(&json).unwrap();
         assert_eq!(parsed.signal_id, "sig_123");
         assert_eq!(parsed.attempt, 3);
+        assert_eq!(parsed.priority, 125);
     }
 
     #[test]
@@ -431,6 +707,8 @@ mod tests {
             subscription_id: "sub_test".to_string(),
             webhook_id: None,
             attempt: 1,
+            priority: 0,
+            channel_id: "ch_1".to_string(),
         };
 
         let json = serde_json::to_string(&job).unwrap();
@@ -439,4 +717,37 @@ mod tests {
         let parsed: DeliveryJob = serde_json::from_str(&json).unwrap();
         assert!(parsed.webhook_id.is_none());
     }
+
+    #[test]
+    fn test_delivery_job_without_priority_defaults_to_zero() {
+        let json = r#"{"signal_id":"sig_old","subscription_id":"sub_old",
+            "webhook_id":null,"attempt":0}"#;
+        let parsed: DeliveryJob = serde_json::from_str(json).unwrap();
+        assert_eq!(parsed.priority, 0);
+        assert_eq!(parsed.channel_id, "");
+    }
+
+    // ============================================================
+    // RetryPolicy Tests
+    // ============================================================
+
+    #[test]
+    fn test_retry_policy_validation() {
+        let policy = RetryPolicy { max_attempts: 3, base_delay_secs: 30, multiplier: 2.0 };
+        assert!(policy.validate().is_ok());
+
+        assert!(RetryPolicy { max_attempts: 0, ..policy.clone() }.validate().is_err());
+        assert!(RetryPolicy { max_attempts: 21, ..policy.clone() }.validate().is_err());
+        assert!(RetryPolicy { base_delay_secs: 0, ..policy.clone() }.validate().is_err());
+        assert!(RetryPolicy { multiplier: 0.5, ..policy.clone() }.validate().is_err());
+        assert!(RetryPolicy { multiplier: f64::NAN, ..policy }.validate().is_err());
+    }
+
+    #[test]
+    fn test_retry_policy_delay_is_capped() {
+        let policy = RetryPolicy { max_attempts: 20, base_delay_secs: 3600, multiplier: 10.0 };
+        assert_eq!(policy.delay_for(1).as_secs(), 3600);
+        assert_eq!(policy.delay_for(2).as_secs(), 36_000);
+        assert_eq!(policy.delay_for(3).as_secs(), RetryPolicy::MAX_DELAY_SECS);
+    }
 }
@@ -8,6 +8,7 @@
 //! as well to maintain consistency.
 
 use chrono::{DateTime, Utc};
+use core::types::DeliveryJob;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
@@ -20,6 +21,31 @@ pub enum PricingTier {
     Enterprise,
 }
 
+impl PricingTier {
+    /// Accepted string values, matching the `pricing_tier` Postgres enum.
+    pub const VALUES: &'static [&'static str] = &["free", "pro", "enterprise"];
+
+    /// Parse a lowercase pricing tier string.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "free" => Some(PricingTier::Free),
+            "pro" => Some(PricingTier::Pro),
+            "enterprise" => Some(PricingTier::Enterprise),
+            _ => None,
+        }
+    }
+}
+
+impl From<&PricingTier> for core::types::PricingTier {
+    fn from(tier: &PricingTier) -> Self {
+        match tier {
+            PricingTier::Free => core::types::PricingTier::Free,
+            PricingTier::Pro => core::types::PricingTier::Pro,
+            PricingTier::Enterprise => core::types::PricingTier::Enterprise,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "account_tier", rename_all = "lowercase")]
 pub enum AccountTier {
@@ -28,6 +54,32 @@ pub enum AccountTier {
     Enterprise,
 }
 
+impl AccountTier {
+    /// Numeric rank for comparing tiers (Free < Pro < Enterprise).
+    pub fn rank(&self) -> u8 {
+        match self {
+            AccountTier::Free => 0,
+            AccountTier::Pro => 1,
+            AccountTier::Enterprise => 2,
+        }
+    }
+
+    /// Whether this tier satisfies an optional minimum tier requirement.
+    pub fn meets(&self, min: Option<&AccountTier>) -> bool {
+        min.is_none_or(|min| self.rank() >= min.rank())
+    }
+}
+
+impl From<&AccountTier> for core::types::AccountTier {
+    fn from(tier: &AccountTier) -> Self {
+        match tier {
+            AccountTier::Free => core::types::AccountTier::Free,
+            AccountTier::Pro => core::types::AccountTier::Pro,
+            AccountTier::Enterprise => core::types::AccountTier::Enterprise,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "account_status", rename_all = "lowercase")]
 pub enum AccountStatus {
@@ -44,7 +96,23 @@ pub enum ChannelStatus {
     Deleted,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type)]
+impl ChannelStatus {
+    /// Accepted string values, matching the `channel_status` Postgres enum.
+    pub const VALUES: &'static [&'static str] = &["active", "paused", "deleted"];
+
+    /// Parse a lowercase channel status string.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "active" => Some(ChannelStatus::Active),
+            "paused" => Some(ChannelStatus::Paused),
+            "deleted" => Some(ChannelStatus::Deleted),
+            _ => None,
+        }
+    }
+}
+
+/// Ordered `Low < Normal < High < Critical` by declaration order.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, sqlx::Type)]
 #[sqlx(type_name = "signal_urgency", rename_all = "lowercase")]
 pub enum SignalUrgency {
     Low,
@@ -53,11 +121,67 @@ pub enum SignalUrgency {
     Critical,
 }
 
+impl SignalUrgency {
+    /// Whether this urgency reaches a subscription's `min_urgency` threshold.
+    pub fn meets(&self, min: &SignalUrgency) -> bool {
+        self >= min
+    }
+
+    /// High and critical signals take the priority delivery path.
+    pub fn is_urgent(&self) -> bool {
+        *self >= SignalUrgency::High
+    }
+
+    /// Delivery queue for signals of this urgency.
+    pub fn queue(&self) -> &'static str {
+        if self.is_urgent() {
+            "delivery-high"
+        } else {
+            "delivery-normal"
+        }
+    }
+}
+
+impl<'a> From<&'a Signal> for core::payload::PayloadSignal<'a> {
+    fn from(signal: &'a Signal) -> Self {
+        Self {
+            id: &signal.id,
+            title: &signal.title,
+            body: &signal.body,
+            urgency: (&signal.urgency).into(),
+            metadata: &signal.metadata,
+            created_at: signal.created_at,
+        }
+    }
+}
+
+impl<'a> From<&'a Channel> for core::payload::PayloadChannel<'a> {
+    fn from(channel: &'a Channel) -> Self {
+        Self {
+            id: &channel.id,
+            slug: &channel.slug,
+            display_name: &channel.display_name,
+        }
+    }
+}
+
+impl From<&SignalUrgency> for core::types::SignalUrgency {
+    fn from(urgency: &SignalUrgency) -> Self {
+        match urgency {
+            SignalUrgency::Low => core::types::SignalUrgency::Low,
+            SignalUrgency::Normal => core::types::SignalUrgency::Normal,
+            SignalUrgency::High => core::types::SignalUrgency::High,
+            SignalUrgency::Critical => core::types::SignalUrgency::Critical,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "signal_status", rename_all = "lowercase")]
 pub enum SignalStatus {
     Active,
     Deleted,
+    Scheduled,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type)]
@@ -76,12 +200,52 @@ pub enum WebhookStatus {
     Disabled,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "signature_algorithm", rename_all = "lowercase")]
+pub enum SignatureAlgorithm {
+    Sha256,
+    Sha512,
+}
+
+impl SignatureAlgorithm {
+    pub fn as_str(&self) -> &'static str {
+        core::auth::SignatureAlgorithm::from(self).as_str()
+    }
+}
+
+impl From<&SignatureAlgorithm> for core::auth::SignatureAlgorithm {
+    fn from(algorithm: &SignatureAlgorithm) -> Self {
+        match algorithm {
+            SignatureAlgorithm::Sha256 => core::auth::SignatureAlgorithm::Sha256,
+            SignatureAlgorithm::Sha512 => core::auth::SignatureAlgorithm::Sha512,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "delivery_status", rename_all = "lowercase")]
 pub enum DeliveryStatus {
     Pending,
     Success,
     Failed,
+    Undeliverable,
+}
+
+impl DeliveryStatus {
+    /// Accepted string values, matching the `delivery_status` Postgres enum.
+    pub const VALUES: &'static [&'static str] = &["pending", "success", "failed", "undeliverable"];
+
+    /// Parse a lowercase delivery status string.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pending" => Some(DeliveryStatus::Pending),
+            "success" => Some(DeliveryStatus::Success),
+            "failed" => Some(DeliveryStatus::Failed),
+            "undeliverable" => Some(DeliveryStatus::Undeliverable),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type)]
@@ -125,6 +289,8 @@ pub struct Subscriber {
     pub name: String,
     pub email: String,
     pub webhook_secret: String,
+    pub previous_webhook_secret: Option<String>,
+    pub previous_webhook_secret_expires_at: Option<DateTime<Utc>>,
     pub stripe_customer_id: Option<String>,
     pub tier: AccountTier,
     pub status: AccountStatus,
@@ -146,6 +312,9 @@ pub struct Channel {
     pub price_cents: i32,
     pub status: ChannelStatus,
     pub is_public: bool,
+    pub retry_policy: Option<serde_json::Value>,
+    pub dedup_window_secs: i32,
+    pub delivery_callback_url: Option<String>,
     pub signal_count: i32,
     pub subscriber_count: i32,
     pub created_at: DateTime<Utc>,
@@ -164,9 +333,41 @@ pub struct Signal {
     pub delivered_count: i32,
     pub failed_count: i32,
     pub status: SignalStatus,
+    pub min_subscriber_tier: Option<AccountTier>,
+    pub scheduled_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
 }
 
+/// A signal's delivery totals after some of its deliveries settle.
+#[derive(Debug, Clone, FromRow)]
+pub struct SignalSettlement {
+    pub signal_id: String,
+    pub delivered_count: i32,
+    /// Deliveries the signal fanned out to; `None` until it has fanned out.
+    pub expected_deliveries: Option<i32>,
+    pub settled_deliveries: i32,
+    pub callback_url: Option<String>,
+}
+
+impl SignalSettlement {
+    /// Whether settling `settled` more deliveries took the signal from short
+    /// of its fan-out to complete.
+    pub fn completed_by(&self, settled: i32) -> bool {
+        self.expected_deliveries.is_some_and(|expected| {
+            self.settled_deliveries >= expected && self.settled_deliveries - settled < expected
+        })
+    }
+
+    /// The receipt reporting this signal's outcome.
+    pub fn receipt(&self) -> core::receipts::DeliveryReceipt {
+        core::receipts::DeliveryReceipt::new(
+            &self.signal_id,
+            self.delivered_count,
+            self.expected_deliveries.unwrap_or(self.settled_deliveries),
+        )
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Webhook {
     pub id: String,
@@ -174,6 +375,14 @@ pub struct Webhook {
     pub url: String,
     pub name: String,
     pub token: Option<String>,
+    pub headers: Option<serde_json::Value>,
+    pub payload_schema: Option<serde_json::Value>,
+    pub timeout_ms: i32,
+    pub max_in_flight: i32,
+    pub max_per_second: Option<i32>,
+    pub signature_algorithm: SignatureAlgorithm,
+    /// Send the pre-versioning `sha256=<hmac>` signature instead of `t=..,v1=..`.
+    pub legacy_signature: bool,
     pub status: WebhookStatus,
     pub failure_count: i32,
     pub last_success_at: Option<DateTime<Utc>>,
@@ -182,18 +391,122 @@ pub struct Webhook {
     pub updated_at: DateTime<Utc>,
 }
 
+impl Webhook {
+    /// `X-Herald-Signature` value for a delivery body, in this endpoint's scheme.
+    pub fn sign(&self, secret: &str, timestamp: i64, body: &str) -> String {
+        if self.legacy_signature {
+            core::auth::sign_payload(secret, timestamp, body)
+        } else {
+            core::auth::sign_versioned(secret, timestamp, body, (&self.signature_algorithm).into())
+        }
+    }
+
+    /// The endpoint's own request timeout, kept within the supported range in
+    /// case a stored value predates validation.
+    pub fn timeout(&self) -> std::time::Duration {
+        let ms = self.timeout_ms.clamp(
+            core::types::Webhook::MIN_TIMEOUT_MS,
+            core::types::Webhook::MAX_TIMEOUT_MS,
+        );
+        std::time::Duration::from_millis(ms as u64)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Subscription {
     pub id: String,
     pub subscriber_id: String,
     pub channel_id: String,
     pub webhook_id: Option<String>,
+    pub emergency_webhook_id: Option<String>,
     pub status: SubscriptionStatus,
+    /// Signals below this urgency are not delivered to the subscription.
+    pub min_urgency: SignalUrgency,
     pub stripe_subscription_id: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// An active subscription joined with its subscriber's tier, used for fan-out.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SubscriptionTarget {
+    #[sqlx(flatten)]
+    pub subscription: Subscription,
+    pub subscriber_tier: AccountTier,
+}
+
+/// A subscriber's subscription joined with the channel it points at.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SubscribedChannel {
+    pub subscription_id: String,
+    pub subscription_status: SubscriptionStatus,
+    pub channel_id: String,
+    pub slug: String,
+    pub display_name: String,
+    pub channel_status: ChannelStatus,
+    pub subscribed_at: DateTime<Utc>,
+}
+
+/// The slice of an API key the auth middleware needs, with its owner's tier.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ApiKeyAuth {
+    pub id: String,
+    pub owner_type: ApiKeyOwner,
+    pub owner_id: String,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub scopes: Vec<String>,
+    /// `None` when the owning publisher or subscriber row no longer exists.
+    pub owner_tier: Option<AccountTier>,
+}
+
+/// Split fan-out targets into the subscriptions that should receive a signal
+/// and the number skipped by its tier gate.
+///
+/// Subscriptions whose `min_urgency` is above `urgency` are left out without
+/// counting as skipped; that filter is the subscriber's own choice.
+pub fn deliverable_subscriptions(
+    targets: Vec<SubscriptionTarget>,
+    min_tier: Option<&AccountTier>,
+    urgency: &SignalUrgency,
+) -> (Vec<Subscription>, usize) {
+    let mut skipped = 0;
+    let deliverable = targets
+        .into_iter()
+        .filter_map(|target| {
+            if !matches!(target.subscription.status, SubscriptionStatus::Active)
+                || !urgency.meets(&target.subscription.min_urgency)
+            {
+                return None;
+            }
+            if !target.subscriber_tier.meets(min_tier) {
+                skipped += 1;
+                return None;
+            }
+            Some(target.subscription)
+        })
+        .collect();
+    (deliverable, skipped)
+}
+
+/// First-attempt delivery jobs for a signal, one per subscription.
+pub fn delivery_jobs(
+    signal_id: &str,
+    priority: i32,
+    subscriptions: Vec<Subscription>,
+) -> Vec<DeliveryJob> {
+    subscriptions
+        .into_iter()
+        .map(|subscription| DeliveryJob {
+            signal_id: signal_id.to_string(),
+            subscription_id: subscription.id,
+            webhook_id: subscription.webhook_id,
+            attempt: 0,
+            priority,
+            channel_id: subscription.channel_id,
+        })
+        .collect()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Delivery {
     pub id: String,
@@ -205,11 +518,22 @@ pub struct Delivery {
     pub status: DeliveryStatus,
     pub status_code: Option<i32>,
     pub error_message: Option<String>,
+    pub response_body: Option<String>,
     pub latency_ms: Option<i32>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// An unacknowledged tunnel delivery to resend to a reconnecting agent.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TunnelReplay {
+    pub delivery_id: String,
+    pub tunnel_seq: i64,
+    pub channel_slug: String,
+    #[sqlx(flatten)]
+    pub signal: Signal,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct ApiKey {
     pub id: String,
@@ -234,5 +558,15 @@ pub struct DeadLetterEntry {
     pub payload: serde_json::Value,
     pub error_history: serde_json::Value,
     pub resolved_at: Option<DateTime<Utc>>,
+    pub resolution_note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct IdempotencyKey {
+    pub key: String,
+    pub owner_id: String,
+    pub request_hash: String,
+    pub response_snapshot: serde_json::Value,
     pub created_at: DateTime<Utc>,
 }
@@ -1,9 +1,10 @@
-use crate::models::{ApiKey, ApiKeyOwner, ApiKeyStatus};
-use sqlx::PgPool;
+use crate::models::{ApiKey, ApiKeyAuth, ApiKeyOwner, ApiKeyStatus};
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool};
 
 #[allow(clippy::too_many_arguments)]
-pub async fn create(
-    pool: &PgPool,
+pub async fn create<'e, E>(
+    executor: E,
     id: &str,
     key_hash: &str,
     key_prefix: &str,
@@ -11,12 +12,16 @@ pub async fn create(
     owner_id: &str,
     name: Option<&str>,
     scopes: &[String],
-) -> Result<ApiKey, sqlx::Error> {
+    expires_at: Option<DateTime<Utc>>,
+) -> Result<ApiKey, sqlx::Error>
+where
+    E: PgExecutor<'e>,
+{
     sqlx::query_as::<_, ApiKey>(
         r#"
         INSERT INTO api_keys
-            (id, key_hash, key_prefix, owner_type, owner_id, name, scopes)
-        VALUES ($1, $2, $3, $4, $5, $6, $7)
+            (id, key_hash, key_prefix, owner_type, owner_id, name, scopes, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
         RETURNING id, key_hash, key_prefix, owner_type, owner_id, name,
                   scopes, last_used_at, expires_at, status, created_at
         "#,
@@ -28,20 +33,52 @@ pub async fn create(
     .bind(owner_id)
     .bind(name)
     .bind(scopes)
-    .fetch_one(pool)
+    .bind(expires_at)
+    .fetch_one(executor)
     .await
 }
 
-pub async fn get_by_hash(pool: &PgPool, key_hash: &str) -> Result<Option<ApiKey>, sqlx::Error> {
+/// Columns returned by [`authenticate`], including the owner's tier so auth
+/// needs no second lookup.
+const AUTH_COLUMNS: &str = r#"
+    id, owner_type, owner_id, expires_at, scopes,
+    CASE owner_type
+        WHEN 'publisher' THEN (SELECT tier FROM publishers p WHERE p.id = api_keys.owner_id)
+        ELSE (SELECT tier FROM subscribers s WHERE s.id = api_keys.owner_id)
+    END AS owner_tier
+"#;
+
+/// Resolve an active key by hash in one statement. With `touch` the same
+/// statement also stamps `last_used_at`.
+pub async fn authenticate(
+    pool: &PgPool,
+    key_hash: &str,
+    touch: bool,
+) -> Result<Option<ApiKeyAuth>, sqlx::Error> {
+    let sql = if touch {
+        format!(
+            "UPDATE api_keys SET last_used_at = now() \
+             WHERE key_hash = $1 AND status = 'active' RETURNING {AUTH_COLUMNS}"
+        )
+    } else {
+        format!("SELECT {AUTH_COLUMNS} FROM api_keys WHERE key_hash = $1 AND status = 'active'")
+    };
+    sqlx::query_as::<_, ApiKeyAuth>(&sql)
+        .bind(key_hash)
+        .fetch_optional(pool)
+        .await
+}
+
+pub async fn get_by_id(pool: &PgPool, id: &str) -> Result<Option<ApiKey>, sqlx::Error> {
     sqlx::query_as::<_, ApiKey>(
         r#"
         SELECT id, key_hash, key_prefix, owner_type, owner_id, name,
                scopes, last_used_at, expires_at, status, created_at
         FROM api_keys
-        WHERE key_hash = $1 AND status = 'active'
+        WHERE id = $1
         "#,
     )
-    .bind(key_hash)
+    .bind(id)
     .fetch_optional(pool)
     .await
 }
@@ -66,6 +103,8 @@ pub async fn list_by_owner(
     .await
 }
 
+/// Revoke a key. The API caches resolved keys by hash, so callers must also
+/// drop the cached entry or the key keeps working until it expires there.
 pub async fn revoke(pool: &PgPool, id: &str) -> Result<(), sqlx::Error> {
     sqlx::query(
         r#"
@@ -80,20 +119,8 @@ pub async fn revoke(pool: &PgPool, id: &str) -> Result<(), sqlx::Error> {
     Ok(())
 }
 
-pub async fn touch_last_used(pool: &PgPool, id: &str) -> Result<(), sqlx::Error> {
-    sqlx::query(
-        r#"
-        UPDATE api_keys
-        SET last_used_at = now()
-        WHERE id = $1
-        "#,
-    )
-    .bind(id)
-    .execute(pool)
-    .await?;
-    Ok(())
-}
-
+/// Set a key's status. As with [`revoke`], callers must drop the API's cached
+/// entry for the key.
 pub async fn update_status(
     pool: &PgPool,
     id: &str,
@@ -112,3 +139,20 @@ pub async fn update_status(
     .await?;
     Ok(())
 }
+
+/// Mark active keys whose expiry has passed as `expired`.
+///
+/// Auth already rejects such keys; this keeps listings accurate. Safe to run
+/// from several workers at once. Returns the number of keys expired.
+pub async fn expire_past_due(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE api_keys
+        SET status = 'expired'
+        WHERE status = 'active' AND expires_at <= now()
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
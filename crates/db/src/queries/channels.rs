@@ -5,7 +5,7 @@
 
 use crate::models::{Channel, ChannelStatus, PricingTier};
 use chrono::{DateTime, Utc};
-use sqlx::{PgPool, QueryBuilder};
+use sqlx::{PgExecutor, PgPool, QueryBuilder};
 
 /// Create a new channel for a publisher.
 ///
@@ -22,16 +22,17 @@ pub async fn create(
     pricing_tier: PricingTier,
     price_cents: i32,
     is_public: bool,
+    retry_policy: Option<&serde_json::Value>,
 ) -> Result<Channel, sqlx::Error> {
     sqlx::query_as::<_, Channel>(
         r#"
         INSERT INTO channels
             (id, publisher_id, slug, display_name, description, category,
-             pricing_tier, price_cents, is_public)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+             pricing_tier, price_cents, is_public, retry_policy)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
         RETURNING id, publisher_id, slug, display_name, description, category,
-                  pricing_tier, price_cents, status, is_public,
-                  signal_count, subscriber_count, created_at, updated_at
+                  pricing_tier, price_cents, status, is_public, retry_policy, dedup_window_secs,
+                  delivery_callback_url, signal_count, subscriber_count, created_at, updated_at
         "#,
     )
     .bind(id)
@@ -43,6 +44,7 @@ pub async fn create(
     .bind(pricing_tier)
     .bind(price_cents)
     .bind(is_public)
+    .bind(retry_policy)
     .fetch_one(pool)
     .await
 }
@@ -52,8 +54,8 @@ pub async fn get_by_id(pool: &PgPool, id: &str) -> Result<Option<Channel>, sqlx:
     sqlx::query_as::<_, Channel>(
         r#"
         SELECT id, publisher_id, slug, display_name, description, category,
-               pricing_tier, price_cents, status, is_public,
-               signal_count, subscriber_count, created_at, updated_at
+               pricing_tier, price_cents, status, is_public, retry_policy, dedup_window_secs,
+               delivery_callback_url, signal_count, subscriber_count, created_at, updated_at
         FROM channels
         WHERE id = $1
         "#,
@@ -63,27 +65,133 @@ pub async fn get_by_id(pool: &PgPool, id: &str) -> Result<Option<Channel>, sqlx:
     .await
 }
 
+/// Fetch a channel by its slug. Slugs are unique across all channels,
+/// deleted ones included.
+pub async fn get_by_slug(pool: &PgPool, slug: &str) -> Result<Option<Channel>, sqlx::Error> {
+    sqlx::query_as::<_, Channel>(
+        r#"
+        SELECT id, publisher_id, slug, display_name, description, category,
+               pricing_tier, price_cents, status, is_public, retry_policy, dedup_window_secs,
+               delivery_callback_url, signal_count, subscriber_count, created_at, updated_at
+        FROM channels
+        WHERE slug = $1
+        "#,
+    )
+    .bind(slug)
+    .fetch_optional(pool)
+    .await
+}
+
 /// List all public, active channels for the marketplace.
 ///
 /// Returns channels ordered by creation date (newest first).
-pub async fn list_marketplace(pool: &PgPool) -> Result<Vec<Channel>, sqlx::Error> {
+/// One page of public, active channels, newest first.
+///
+/// `cursor` is the id of the last channel on the previous page; `category`
+/// narrows the listing to a single category.
+pub async fn list_marketplace(
+    pool: &PgPool,
+    category: Option<&str>,
+    limit: i64,
+    cursor: Option<&str>,
+) -> Result<Vec<Channel>, sqlx::Error> {
+    let mut qb = QueryBuilder::new(
+        r#"
+        SELECT id, publisher_id, slug, display_name, description, category,
+               pricing_tier, price_cents, status, is_public, retry_policy, dedup_window_secs,
+               delivery_callback_url, signal_count, subscriber_count, created_at, updated_at
+        FROM channels
+        WHERE is_public = true AND status = 'active'"#,
+    );
+    if let Some(category) = category {
+        qb.push(" AND category = ").push_bind(category);
+    }
+    if let Some(cursor) = cursor {
+        qb.push(" AND (created_at, id) < (SELECT created_at, id FROM channels WHERE id = ")
+            .push_bind(cursor)
+            .push(")");
+    }
+    qb.push(" ORDER BY created_at DESC, id DESC LIMIT ")
+        .push_bind(limit);
+
+    qb.build_query_as::<Channel>().fetch_all(pool).await
+}
+
+/// One page of a publisher's channels, newest first.
+///
+/// Deleted channels are left out unless `status` asks for them; `cursor` is
+/// the id of the last channel on the previous page.
+pub async fn list_by_publisher(
+    pool: &PgPool,
+    publisher_id: &str,
+    status: Option<ChannelStatus>,
+    limit: i64,
+    cursor: Option<&str>,
+) -> Result<Vec<Channel>, sqlx::Error> {
+    let mut qb = QueryBuilder::new(
+        r#"
+        SELECT id, publisher_id, slug, display_name, description, category,
+               pricing_tier, price_cents, status, is_public, retry_policy, dedup_window_secs,
+               delivery_callback_url, signal_count, subscriber_count, created_at, updated_at
+        FROM channels
+        WHERE publisher_id = "#,
+    );
+    qb.push_bind(publisher_id);
+    match status {
+        Some(status) => qb.push(" AND status = ").push_bind(status),
+        None => qb.push(" AND status <> 'deleted'"),
+    };
+    if let Some(cursor) = cursor {
+        qb.push(" AND (created_at, id) < (SELECT created_at, id FROM channels WHERE id = ")
+            .push_bind(cursor)
+            .push(")");
+    }
+    qb.push(" ORDER BY created_at DESC, id DESC LIMIT ")
+        .push_bind(limit);
+
+    qb.build_query_as::<Channel>().fetch_all(pool).await
+}
+
+/// Public, active channels whose display name or description contains `query`
+/// (case-insensitive), newest first.
+pub async fn search(pool: &PgPool, query: &str, limit: i64) -> Result<Vec<Channel>, sqlx::Error> {
     sqlx::query_as::<_, Channel>(
         r#"
         SELECT id, publisher_id, slug, display_name, description, category,
-               pricing_tier, price_cents, status, is_public,
-               signal_count, subscriber_count, created_at, updated_at
+               pricing_tier, price_cents, status, is_public, retry_policy, dedup_window_secs,
+               delivery_callback_url, signal_count, subscriber_count, created_at, updated_at
         FROM channels
         WHERE is_public = true AND status = 'active'
-        ORDER BY created_at DESC
+          AND (display_name ILIKE $1 OR description ILIKE $1)
+        ORDER BY created_at DESC, id DESC
+        LIMIT $2
         "#,
     )
+    .bind(contains_pattern(query))
+    .bind(limit)
     .fetch_all(pool)
     .await
 }
 
+/// `ILIKE` pattern matching `query` anywhere, with its wildcards escaped.
+fn contains_pattern(query: &str) -> String {
+    let mut pattern = String::with_capacity(query.len() + 2);
+    pattern.push('%');
+    for ch in query.chars() {
+        if matches!(ch, '%' | '_' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(ch);
+    }
+    pattern.push('%');
+    pattern
+}
+
 /// Update a channel's mutable fields.
 ///
-/// Only non-None fields are updated. Returns an error if no fields are provided.
+/// Only non-None fields are updated; an empty `delivery_callback_url` clears
+/// it, as does `Some(None)` for `retry_policy`. Returns an error if no fields
+/// are provided.
 /// On success, returns (id, display_name, updated_at).
 #[allow(clippy::too_many_arguments)]
 pub async fn update(
@@ -96,6 +204,9 @@ pub async fn update(
     price_cents: Option<i32>,
     is_public: Option<bool>,
     status: Option<ChannelStatus>,
+    retry_policy: Option<Option<&serde_json::Value>>,
+    dedup_window_secs: Option<i32>,
+    delivery_callback_url: Option<&str>,
 ) -> Result<(String, String, DateTime<Utc>), sqlx::Error> {
     let mut qb = QueryBuilder::new("UPDATE channels SET ");
     let mut set = qb.separated(", ");
@@ -129,6 +240,20 @@ pub async fn update(
         set.push("status = ").push_bind(value);
         updated = true;
     }
+    if let Some(value) = retry_policy {
+        set.push("retry_policy = ").push_bind(value);
+        updated = true;
+    }
+    if let Some(value) = dedup_window_secs {
+        set.push("dedup_window_secs = ").push_bind(value);
+        updated = true;
+    }
+    if let Some(value) = delivery_callback_url {
+        set.push("delivery_callback_url = NULLIF(")
+            .push_bind_unseparated(value)
+            .push_unseparated(", '')");
+        updated = true;
+    }
 
     if !updated {
         return Err(sqlx::Error::Protocol("no fields to update".into()));
@@ -163,14 +288,44 @@ pub async fn soft_delete(pool: &PgPool, id: &str) -> Result<(), sqlx::Error> {
     Ok(())
 }
 
+/// Move a channel to another publisher.
+///
+/// Only succeeds while `from_publisher_id` still owns the channel and it is not
+/// deleted, so concurrent transfers or deletes can't race. Signals and
+/// subscriptions hang off the channel and move with it. Returns
+/// (id, publisher_id, updated_at), or `None` if the guard did not match.
+pub async fn transfer(
+    pool: &PgPool,
+    id: &str,
+    from_publisher_id: &str,
+    to_publisher_id: &str,
+) -> Result<Option<(String, String, DateTime<Utc>)>, sqlx::Error> {
+    sqlx::query_as::<_, (String, String, DateTime<Utc>)>(
+        r#"
+        UPDATE channels
+        SET publisher_id = $1, updated_at = now()
+        WHERE id = $2 AND publisher_id = $3 AND status <> 'deleted'
+        RETURNING id, publisher_id, updated_at
+        "#,
+    )
+    .bind(to_publisher_id)
+    .bind(id)
+    .bind(from_publisher_id)
+    .fetch_optional(pool)
+    .await
+}
+
 /// Atomically increment the signal count for a channel.
 ///
 /// Use negative delta to decrement (e.g., when a signal is deleted).
-pub async fn increment_signal_count(
-    pool: &PgPool,
+pub async fn increment_signal_count<'e, E>(
+    executor: E,
     channel_id: &str,
     delta: i32,
-) -> Result<(), sqlx::Error> {
+) -> Result<(), sqlx::Error>
+where
+    E: PgExecutor<'e>,
+{
     sqlx::query(
         r#"
         UPDATE channels
@@ -181,7 +336,7 @@ pub async fn increment_signal_count(
     )
     .bind(delta)
     .bind(channel_id)
-    .execute(pool)
+    .execute(executor)
     .await?;
     Ok(())
 }
@@ -208,3 +363,15 @@ pub async fn increment_subscriber_count(
     .await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::contains_pattern;
+
+    #[test]
+    fn test_contains_pattern_escapes_wildcards() {
+        assert_eq!(contains_pattern("btc"), "%btc%");
+        assert_eq!(contains_pattern("100%_sure"), "%100\\%\\_sure%");
+        assert_eq!(contains_pattern(r"a\b"), r"%a\\b%");
+    }
+}
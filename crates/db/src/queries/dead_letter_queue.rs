@@ -1,5 +1,5 @@
 use crate::models::DeadLetterEntry;
-use sqlx::PgPool;
+use sqlx::{PgPool, QueryBuilder};
 
 pub async fn create(
     pool: &PgPool,
@@ -16,7 +16,7 @@ pub async fn create(
             (id, delivery_id, signal_id, subscription_id, payload, error_history)
         VALUES ($1, $2, $3, $4, $5, $6)
         RETURNING id, delivery_id, signal_id, subscription_id, payload,
-                  error_history, resolved_at, created_at
+                  error_history, resolved_at, resolution_note, created_at
         "#,
     )
     .bind(id)
@@ -29,16 +29,64 @@ pub async fn create(
     .await
 }
 
-pub async fn list_unresolved(pool: &PgPool) -> Result<Vec<DeadLetterEntry>, sqlx::Error> {
-    sqlx::query_as::<_, DeadLetterEntry>(
+/// Unresolved entries newest first, `limit` per page. `cursor` is the id of
+/// the last entry on the previous page.
+pub async fn list_unresolved(
+    pool: &PgPool,
+    limit: i64,
+    cursor: Option<&str>,
+) -> Result<Vec<DeadLetterEntry>, sqlx::Error> {
+    let mut qb = QueryBuilder::new(
         r#"
         SELECT id, delivery_id, signal_id, subscription_id, payload,
-               error_history, resolved_at, created_at
+               error_history, resolved_at, resolution_note, created_at
         FROM dead_letter_queue
-        WHERE resolved_at IS NULL
-        ORDER BY created_at DESC
+        WHERE resolved_at IS NULL"#,
+    );
+    if let Some(cursor) = cursor {
+        qb.push(
+            " AND (created_at, id) < (SELECT created_at, id FROM dead_letter_queue WHERE id = ",
+        )
+        .push_bind(cursor)
+        .push(")");
+    }
+    qb.push(" ORDER BY created_at DESC, id DESC LIMIT ")
+        .push_bind(limit);
+
+    qb.build_query_as::<DeadLetterEntry>().fetch_all(pool).await
+}
+
+/// Number of unresolved entries, i.e. the DLQ backlog.
+pub async fn count_unresolved(pool: &PgPool) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM dead_letter_queue WHERE resolved_at IS NULL")
+        .fetch_one(pool)
+        .await
+}
+
+/// Oldest-first unresolved entries, optionally narrowed to one channel or
+/// subscription, at most `limit` of them.
+pub async fn list_unresolved_filtered(
+    pool: &PgPool,
+    channel_id: Option<&str>,
+    subscription_id: Option<&str>,
+    limit: i64,
+) -> Result<Vec<DeadLetterEntry>, sqlx::Error> {
+    sqlx::query_as::<_, DeadLetterEntry>(
+        r#"
+        SELECT d.id, d.delivery_id, d.signal_id, d.subscription_id, d.payload,
+               d.error_history, d.resolved_at, d.resolution_note, d.created_at
+        FROM dead_letter_queue d
+        JOIN signals s ON s.id = d.signal_id
+        WHERE d.resolved_at IS NULL
+          AND ($1::text IS NULL OR s.channel_id = $1)
+          AND ($2::text IS NULL OR d.subscription_id = $2)
+        ORDER BY d.created_at ASC
+        LIMIT $3
         "#,
     )
+    .bind(channel_id)
+    .bind(subscription_id)
+    .bind(limit)
     .fetch_all(pool)
     .await
 }
@@ -47,7 +95,7 @@ pub async fn get_by_id(pool: &PgPool, id: &str) -> Result<Option<DeadLetterEntry
     sqlx::query_as::<_, DeadLetterEntry>(
         r#"
         SELECT id, delivery_id, signal_id, subscription_id, payload,
-               error_history, resolved_at, created_at
+               error_history, resolved_at, resolution_note, created_at
         FROM dead_letter_queue
         WHERE id = $1
         "#,
@@ -57,15 +105,18 @@ pub async fn get_by_id(pool: &PgPool, id: &str) -> Result<Option<DeadLetterEntry
     .await
 }
 
-pub async fn resolve(pool: &PgPool, id: &str) -> Result<(), sqlx::Error> {
+/// Close an entry; `note` records why when it was not replayed.
+pub async fn resolve(pool: &PgPool, id: &str, note: Option<&str>) -> Result<(), sqlx::Error> {
     sqlx::query(
         r#"
         UPDATE dead_letter_queue
-        SET resolved_at = now()
+        SET resolved_at = now(),
+            resolution_note = $2
         WHERE id = $1
         "#,
     )
     .bind(id)
+    .bind(note)
     .execute(pool)
     .await?;
     Ok(())
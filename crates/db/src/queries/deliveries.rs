@@ -3,7 +3,8 @@
 //! Deliveries track individual attempts to send a signal to a subscriber,
 //! either via webhook or agent tunnel.
 
-use crate::models::{Delivery, DeliveryMode, DeliveryStatus};
+use crate::models::{Delivery, DeliveryMode, DeliveryStatus, TunnelReplay};
+use futures_util::stream::BoxStream;
 use sqlx::PgPool;
 
 /// Create a new delivery record for a signal-subscription pair.
@@ -23,7 +24,7 @@ pub async fn create(
         INSERT INTO deliveries (id, signal_id, subscription_id, webhook_id, delivery_mode, attempt)
         VALUES ($1, $2, $3, $4, $5, $6)
         RETURNING id, signal_id, subscription_id, webhook_id, delivery_mode, attempt,
-                  status, status_code, error_message, latency_ms,
+                  status, status_code, error_message, response_body, latency_ms,
                   created_at, updated_at
         "#,
     )
@@ -70,6 +71,123 @@ pub async fn update_status(
     Ok(())
 }
 
+/// Give a tunnel delivery the subscriber's next sequence number and return
+/// it. Numbers only grow, so an agent can name the last one it saw.
+pub async fn assign_tunnel_seq(
+    pool: &PgPool,
+    id: &str,
+    subscriber_id: &str,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+        WITH next AS (
+            UPDATE subscribers
+            SET tunnel_seq = tunnel_seq + 1
+            WHERE id = $2
+            RETURNING tunnel_seq
+        )
+        UPDATE deliveries
+        SET tunnel_seq = (SELECT tunnel_seq FROM next)
+        WHERE id = $1
+        RETURNING tunnel_seq
+        "#,
+    )
+    .bind(id)
+    .bind(subscriber_id)
+    .fetch_one(pool)
+    .await
+}
+
+/// Tunnel deliveries numbered after `after_seq` that the subscriber's agent
+/// never acknowledged, oldest first. Only the latest attempt for each
+/// signal and subscription is listed, and none for a pair that was since
+/// delivered some other way.
+pub async fn list_tunnel_replay(
+    pool: &PgPool,
+    subscriber_id: &str,
+    after_seq: i64,
+    limit: i64,
+) -> Result<Vec<TunnelReplay>, sqlx::Error> {
+    sqlx::query_as::<_, TunnelReplay>(
+        r#"
+        SELECT d.id AS delivery_id, d.tunnel_seq, c.slug AS channel_slug,
+               s.id, s.channel_id, s.title, s.body, s.urgency, s.metadata,
+               s.delivery_count, s.delivered_count, s.failed_count, s.status,
+               s.min_subscriber_tier, s.scheduled_at, s.created_at
+        FROM deliveries d
+        JOIN subscriptions sub ON sub.id = d.subscription_id
+        JOIN signals s ON s.id = d.signal_id
+        JOIN channels c ON c.id = s.channel_id
+        WHERE sub.subscriber_id = $1
+          AND d.tunnel_seq > $2
+          AND d.status IN ('pending', 'failed')
+          AND s.status <> 'deleted'
+          AND NOT EXISTS (
+              SELECT 1 FROM deliveries later
+              WHERE later.signal_id = d.signal_id
+                AND later.subscription_id = d.subscription_id
+                AND (later.status = 'success' OR later.tunnel_seq > d.tunnel_seq)
+          )
+        ORDER BY d.tunnel_seq
+        LIMIT $3
+        "#,
+    )
+    .bind(subscriber_id)
+    .bind(after_seq)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// Mark an agent delivery the subscriber acknowledged after it was given up
+/// on, such as a replayed or spooled one, as delivered. Returns its signal
+/// id, or `None` if the delivery is not the subscriber's or already settled.
+pub async fn record_late_ack(
+    pool: &PgPool,
+    id: &str,
+    subscriber_id: &str,
+) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+        UPDATE deliveries d
+        SET status = 'success',
+            error_message = NULL,
+            updated_at = now()
+        FROM subscriptions sub
+        WHERE d.id = $1
+          AND sub.id = d.subscription_id
+          AND sub.subscriber_id = $2
+          AND d.delivery_mode = 'agent'
+          AND d.status IN ('pending', 'failed')
+        RETURNING d.signal_id
+        "#,
+    )
+    .bind(id)
+    .bind(subscriber_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Store a bounded snippet of the endpoint's response body for a failed delivery.
+pub async fn set_response_body(
+    pool: &PgPool,
+    id: &str,
+    response_body: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE deliveries
+        SET response_body = $1, updated_at = now()
+        WHERE id = $2
+        "#,
+    )
+    .bind(response_body)
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
 /// List deliveries for a specific webhook with cursor-based pagination.
 ///
 /// Returns deliveries ordered by creation date (newest first).
@@ -83,7 +201,7 @@ pub async fn list_by_webhook(
         sqlx::query_as::<_, Delivery>(
             r#"
             SELECT id, signal_id, subscription_id, webhook_id, delivery_mode, attempt,
-                   status, status_code, error_message, latency_ms,
+                   status, status_code, error_message, response_body, latency_ms,
                    created_at, updated_at
             FROM deliveries
             WHERE webhook_id = $1 AND id < $2
@@ -100,7 +218,7 @@ pub async fn list_by_webhook(
         sqlx::query_as::<_, Delivery>(
             r#"
             SELECT id, signal_id, subscription_id, webhook_id, delivery_mode, attempt,
-                   status, status_code, error_message, latency_ms,
+                   status, status_code, error_message, response_body, latency_ms,
                    created_at, updated_at
             FROM deliveries
             WHERE webhook_id = $1
@@ -115,12 +233,83 @@ pub async fn list_by_webhook(
     }
 }
 
+/// List a webhook's deliveries in one status, with the same cursor-based
+/// pagination as [`list_by_webhook`].
+pub async fn list_by_webhook_status(
+    pool: &PgPool,
+    webhook_id: &str,
+    status: DeliveryStatus,
+    limit: i64,
+    cursor: Option<&str>,
+) -> Result<Vec<Delivery>, sqlx::Error> {
+    if let Some(cursor) = cursor {
+        sqlx::query_as::<_, Delivery>(
+            r#"
+            SELECT id, signal_id, subscription_id, webhook_id, delivery_mode, attempt,
+                   status, status_code, error_message, response_body, latency_ms,
+                   created_at, updated_at
+            FROM deliveries
+            WHERE webhook_id = $1 AND status = $2 AND id < $3
+            ORDER BY created_at DESC
+            LIMIT $4
+            "#,
+        )
+        .bind(webhook_id)
+        .bind(status)
+        .bind(cursor)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+    } else {
+        sqlx::query_as::<_, Delivery>(
+            r#"
+            SELECT id, signal_id, subscription_id, webhook_id, delivery_mode, attempt,
+                   status, status_code, error_message, response_body, latency_ms,
+                   created_at, updated_at
+            FROM deliveries
+            WHERE webhook_id = $1 AND status = $2
+            ORDER BY created_at DESC
+            LIMIT $3
+            "#,
+        )
+        .bind(webhook_id)
+        .bind(status)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+    }
+}
+
+/// Most recent deliveries across all of a subscriber's subscriptions.
+pub async fn list_recent_by_subscriber(
+    pool: &PgPool,
+    subscriber_id: &str,
+    limit: i64,
+) -> Result<Vec<Delivery>, sqlx::Error> {
+    sqlx::query_as::<_, Delivery>(
+        r#"
+        SELECT d.id, d.signal_id, d.subscription_id, d.webhook_id, d.delivery_mode, d.attempt,
+               d.status, d.status_code, d.error_message, d.response_body, d.latency_ms,
+               d.created_at, d.updated_at
+        FROM deliveries d
+        JOIN subscriptions s ON s.id = d.subscription_id
+        WHERE s.subscriber_id = $1
+        ORDER BY d.created_at DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(subscriber_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
 /// List all deliveries for a specific signal (across all subscribers).
 pub async fn list_by_signal(pool: &PgPool, signal_id: &str) -> Result<Vec<Delivery>, sqlx::Error> {
     sqlx::query_as::<_, Delivery>(
         r#"
         SELECT id, signal_id, subscription_id, webhook_id, delivery_mode, attempt,
-               status, status_code, error_message, latency_ms,
+               status, status_code, error_message, response_body, latency_ms,
                created_at, updated_at
         FROM deliveries
         WHERE signal_id = $1
@@ -132,12 +321,61 @@ pub async fn list_by_signal(pool: &PgPool, signal_id: &str) -> Result<Vec<Delive
     .await
 }
 
+/// One page of a signal's delivery attempts, newest first.
+///
+/// `cursor` is the id of the last delivery on the previous page.
+pub async fn list_by_signal_page(
+    pool: &PgPool,
+    signal_id: &str,
+    limit: i64,
+    cursor: Option<&str>,
+) -> Result<Vec<Delivery>, sqlx::Error> {
+    sqlx::query_as::<_, Delivery>(
+        r#"
+        SELECT id, signal_id, subscription_id, webhook_id, delivery_mode, attempt,
+               status, status_code, error_message, response_body, latency_ms,
+               created_at, updated_at
+        FROM deliveries
+        WHERE signal_id = $1
+          AND ($2::text IS NULL
+               OR (created_at, id) < (SELECT created_at, id FROM deliveries WHERE id = $2))
+        ORDER BY created_at DESC, id DESC
+        LIMIT $3
+        "#,
+    )
+    .bind(signal_id)
+    .bind(cursor)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// Stream every delivery for a signal, oldest first, without buffering the
+/// full result set.
+pub fn stream_by_signal<'a>(
+    pool: &'a PgPool,
+    signal_id: &'a str,
+) -> BoxStream<'a, Result<Delivery, sqlx::Error>> {
+    sqlx::query_as::<_, Delivery>(
+        r#"
+        SELECT id, signal_id, subscription_id, webhook_id, delivery_mode, attempt,
+               status, status_code, error_message, response_body, latency_ms,
+               created_at, updated_at
+        FROM deliveries
+        WHERE signal_id = $1
+        ORDER BY created_at ASC
+        "#,
+    )
+    .bind(signal_id)
+    .fetch(pool)
+}
+
 /// Fetch a delivery by its unique ID.
 pub async fn get_by_id(pool: &PgPool, id: &str) -> Result<Option<Delivery>, sqlx::Error> {
     sqlx::query_as::<_, Delivery>(
         r#"
         SELECT id, signal_id, subscription_id, webhook_id, delivery_mode, attempt,
-               status, status_code, error_message, latency_ms,
+               status, status_code, error_message, response_body, latency_ms,
                created_at, updated_at
         FROM deliveries
         WHERE id = $1
@@ -147,3 +385,21 @@ pub async fn get_by_id(pool: &PgPool, id: &str) -> Result<Option<Delivery>, sqlx
     .fetch_optional(pool)
     .await
 }
+
+/// Deliveries for a channel's signals that ended as `undeliverable`.
+pub async fn count_undeliverable_by_channel(
+    pool: &PgPool,
+    channel_id: &str,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar::<_, i64>(
+        r#"
+        SELECT COUNT(*)
+        FROM deliveries d
+        JOIN signals s ON s.id = d.signal_id
+        WHERE s.channel_id = $1 AND d.status = 'undeliverable'
+        "#,
+    )
+    .bind(channel_id)
+    .fetch_one(pool)
+    .await
+}
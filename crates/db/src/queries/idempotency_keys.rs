@@ -0,0 +1,82 @@
+//! Idempotency key database operations.
+//!
+//! Keys are scoped to their owner (for signal pushes, the channel) and store the response of the first request
+//! so that client retries can be answered without re-executing the write.
+
+use crate::models::IdempotencyKey;
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool};
+
+/// Fetch a stored key for an owner, ignoring keys created before `not_before`.
+pub async fn get(
+    pool: &PgPool,
+    owner_id: &str,
+    key: &str,
+    not_before: DateTime<Utc>,
+) -> Result<Option<IdempotencyKey>, sqlx::Error> {
+    sqlx::query_as::<_, IdempotencyKey>(
+        r#"
+        SELECT key, owner_id, request_hash, response_snapshot, created_at
+        FROM idempotency_keys
+        WHERE owner_id = $1 AND key = $2 AND created_at >= $3
+        "#,
+    )
+    .bind(owner_id)
+    .bind(key)
+    .bind(not_before)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Record a key on first use.
+///
+/// Intended to run inside the same transaction as the write it guards.
+/// An expired row (created before `not_before`) that the purge job has not
+/// removed yet is overwritten. Returns `false` if a live key already exists (a
+/// concurrent request won the race), in which case the caller should roll
+/// back and replay the stored row.
+pub async fn create<'e, E>(
+    executor: E,
+    key: &str,
+    owner_id: &str,
+    request_hash: &str,
+    response_snapshot: serde_json::Value,
+    not_before: DateTime<Utc>,
+) -> Result<bool, sqlx::Error>
+where
+    E: PgExecutor<'e>,
+{
+    let result = sqlx::query(
+        r#"
+        INSERT INTO idempotency_keys (key, owner_id, request_hash, response_snapshot)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (owner_id, key) DO UPDATE
+        SET request_hash = EXCLUDED.request_hash,
+            response_snapshot = EXCLUDED.response_snapshot,
+            created_at = now()
+        WHERE idempotency_keys.created_at < $5
+        "#,
+    )
+    .bind(key)
+    .bind(owner_id)
+    .bind(request_hash)
+    .bind(response_snapshot)
+    .bind(not_before)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+/// Delete keys created before `cutoff`. Returns the number of rows removed.
+pub async fn purge_older_than(pool: &PgPool, cutoff: DateTime<Utc>) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        DELETE FROM idempotency_keys
+        WHERE created_at < $1
+        "#,
+    )
+    .bind(cutoff)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
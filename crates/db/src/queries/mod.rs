@@ -2,6 +2,7 @@ pub mod api_keys;
 pub mod channels;
 pub mod dead_letter_queue;
 pub mod deliveries;
+pub mod idempotency_keys;
 pub mod publishers;
 pub mod signals;
 pub mod subscribers;
@@ -1,5 +1,31 @@
 use crate::models::Publisher;
-use sqlx::PgPool;
+use sqlx::{PgExecutor, PgPool, QueryBuilder};
+
+/// Insert a publisher on the free tier. A taken email fails with a unique
+/// violation.
+pub async fn create<'e, E>(
+    executor: E,
+    id: &str,
+    name: &str,
+    email: &str,
+) -> Result<Publisher, sqlx::Error>
+where
+    E: PgExecutor<'e>,
+{
+    sqlx::query_as::<_, Publisher>(
+        r#"
+        INSERT INTO publishers (id, name, email)
+        VALUES ($1, $2, $3)
+        RETURNING id, name, email, stripe_customer_id, stripe_connect_id,
+                  tier, status, created_at, updated_at
+        "#,
+    )
+    .bind(id)
+    .bind(name)
+    .bind(email)
+    .fetch_one(executor)
+    .await
+}
 
 pub async fn get_by_id(pool: &PgPool, id: &str) -> Result<Option<Publisher>, sqlx::Error> {
     sqlx::query_as::<_, Publisher>(
@@ -28,3 +54,41 @@ pub async fn get_by_email(pool: &PgPool, email: &str) -> Result<Option<Publisher
     .fetch_optional(pool)
     .await
 }
+
+/// Update a publisher's name and/or email.
+///
+/// Only non-None fields are updated. Returns an error if no fields are provided,
+/// and `None` if the publisher does not exist. A taken email fails with a
+/// unique violation.
+pub async fn update_profile(
+    pool: &PgPool,
+    id: &str,
+    name: Option<&str>,
+    email: Option<&str>,
+) -> Result<Option<Publisher>, sqlx::Error> {
+    let mut qb = QueryBuilder::new("UPDATE publishers SET ");
+    let mut set = qb.separated(", ");
+    let mut updated = false;
+
+    if let Some(value) = name {
+        set.push("name = ").push_bind(value);
+        updated = true;
+    }
+    if let Some(value) = email {
+        set.push("email = ").push_bind(value);
+        updated = true;
+    }
+
+    if !updated {
+        return Err(sqlx::Error::Protocol("no fields to update".into()));
+    }
+
+    set.push("updated_at = now()");
+    qb.push(" WHERE id = ").push_bind(id);
+    qb.push(
+        " RETURNING id, name, email, stripe_customer_id, stripe_connect_id,
+                 tier, status, created_at, updated_at",
+    );
+
+    qb.build_query_as::<Publisher>().fetch_optional(pool).await
+}
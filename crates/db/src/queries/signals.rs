@@ -2,28 +2,58 @@
 //!
 //! Signals are the core notification unit in Herald. Publishers create signals
 //! on channels, which are then delivered to all channel subscribers.
+//!
+//! Signals are immutable events. After insert only [`MUTABLE_COLUMNS`] may
+//! change, through [`update_status`] and [`increment_delivery_counts`]; a
+//! database trigger rejects anything else. A correction is published as a
+//! new signal rather than an edit, so every delivery matches what was sent.
+
+use crate::models::{AccountTier, Signal, SignalSettlement, SignalStatus, SignalUrgency};
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool};
 
-use crate::models::{Signal, SignalStatus, SignalUrgency};
-use sqlx::PgPool;
+/// The only signal columns any query may `UPDATE`.
+pub const MUTABLE_COLUMNS: &[&str] = &[
+    "status",
+    "delivered_count",
+    "failed_count",
+    "delivery_count",
+    "expected_deliveries",
+    "settled_deliveries",
+];
 
 /// Create a new signal on a channel.
 ///
 /// Returns the created signal with delivery counts initialized to zero.
-pub async fn create(
-    pool: &PgPool,
+/// Accepts any executor so it can participate in a caller's transaction.
+/// When `min_subscriber_tier` is set, only subscribers at or above that tier
+/// receive the signal. A signal with `scheduled_at` is stored as `Scheduled`
+/// and left for the worker to fan out once that time passes. `content_hash`
+/// identifies the title and body for per-channel deduplication.
+#[allow(clippy::too_many_arguments)]
+pub async fn create<'e, E>(
+    executor: E,
     id: &str,
     channel_id: &str,
     title: &str,
     body: &str,
     urgency: SignalUrgency,
     metadata: serde_json::Value,
-) -> Result<Signal, sqlx::Error> {
+    min_subscriber_tier: Option<AccountTier>,
+    scheduled_at: Option<DateTime<Utc>>,
+    content_hash: &str,
+) -> Result<Signal, sqlx::Error>
+where
+    E: PgExecutor<'e>,
+{
     sqlx::query_as::<_, Signal>(
         r#"
-        INSERT INTO signals (id, channel_id, title, body, urgency, metadata)
-        VALUES ($1, $2, $3, $4, $5, $6)
+        INSERT INTO signals (id, channel_id, title, body, urgency, metadata, min_subscriber_tier,
+                             status, scheduled_at, content_hash)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
         RETURNING id, channel_id, title, body, urgency, metadata,
-                  delivery_count, delivered_count, failed_count, status, created_at
+                  delivery_count, delivered_count, failed_count, status, min_subscriber_tier,
+                  scheduled_at, created_at
         "#,
     )
     .bind(id)
@@ -32,7 +62,64 @@ pub async fn create(
     .bind(body)
     .bind(urgency)
     .bind(metadata)
-    .fetch_one(pool)
+    .bind(min_subscriber_tier)
+    .bind(if scheduled_at.is_some() {
+        SignalStatus::Scheduled
+    } else {
+        SignalStatus::Active
+    })
+    .bind(scheduled_at)
+    .bind(content_hash)
+    .fetch_one(executor)
+    .await
+}
+
+/// Serialize pushes of the same content to a channel until the caller's
+/// transaction ends, so the dedup check and the insert that follows it are
+/// atomic. A unique index can't do this: the same content may be pushed
+/// again once the channel's window has passed.
+pub async fn lock_content_hash<'e, E>(
+    executor: E,
+    channel_id: &str,
+    content_hash: &str,
+) -> Result<(), sqlx::Error>
+where
+    E: PgExecutor<'e>,
+{
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended($1, 0))")
+        .bind(format!("signal-dedup:{channel_id}:{content_hash}"))
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+/// The newest live signal on a channel with `content_hash` created at or
+/// after `since`, if any.
+pub async fn find_recent_by_hash<'e, E>(
+    executor: E,
+    channel_id: &str,
+    content_hash: &str,
+    since: DateTime<Utc>,
+) -> Result<Option<Signal>, sqlx::Error>
+where
+    E: PgExecutor<'e>,
+{
+    sqlx::query_as::<_, Signal>(
+        r#"
+        SELECT id, channel_id, title, body, urgency, metadata,
+               delivery_count, delivered_count, failed_count, status, min_subscriber_tier,
+               scheduled_at, created_at
+        FROM signals
+        WHERE channel_id = $1 AND content_hash = $2 AND created_at >= $3
+          AND status <> 'deleted'
+        ORDER BY created_at DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(channel_id)
+    .bind(content_hash)
+    .bind(since)
+    .fetch_optional(executor)
     .await
 }
 
@@ -41,7 +128,8 @@ pub async fn get_by_id(pool: &PgPool, id: &str) -> Result<Option<Signal>, sqlx::
     sqlx::query_as::<_, Signal>(
         r#"
         SELECT id, channel_id, title, body, urgency, metadata,
-               delivery_count, delivered_count, failed_count, status, created_at
+               delivery_count, delivered_count, failed_count, status, min_subscriber_tier,
+               scheduled_at, created_at
         FROM signals
         WHERE id = $1
         "#,
@@ -53,7 +141,7 @@ pub async fn get_by_id(pool: &PgPool, id: &str) -> Result<Option<Signal>, sqlx::
 
 /// List signals for a channel with cursor-based pagination.
 ///
-/// Returns signals ordered by creation date (newest first).
+/// Returns signals ordered by creation date (newest first), excluding deleted ones.
 /// Use the last signal's ID as the cursor for the next page.
 pub async fn list_by_channel(
     pool: &PgPool,
@@ -65,9 +153,10 @@ pub async fn list_by_channel(
         sqlx::query_as::<_, Signal>(
             r#"
             SELECT id, channel_id, title, body, urgency, metadata,
-                   delivery_count, delivered_count, failed_count, status, created_at
+                   delivery_count, delivered_count, failed_count, status, min_subscriber_tier,
+                   scheduled_at, created_at
             FROM signals
-            WHERE channel_id = $1 AND id < $2
+            WHERE channel_id = $1 AND status <> 'deleted' AND id < $2
             ORDER BY created_at DESC
             LIMIT $3
             "#,
@@ -81,9 +170,10 @@ pub async fn list_by_channel(
         sqlx::query_as::<_, Signal>(
             r#"
             SELECT id, channel_id, title, body, urgency, metadata,
-                   delivery_count, delivered_count, failed_count, status, created_at
+                   delivery_count, delivered_count, failed_count, status, min_subscriber_tier,
+                   scheduled_at, created_at
             FROM signals
-            WHERE channel_id = $1
+            WHERE channel_id = $1 AND status <> 'deleted'
             ORDER BY created_at DESC
             LIMIT $2
             "#,
@@ -115,6 +205,42 @@ pub async fn update_status(
     Ok(())
 }
 
+/// Activate the earliest scheduled signal whose send time has passed, other
+/// than those in `skip`.
+///
+/// The row stays locked until `executor`'s transaction ends, so the caller
+/// should record the fan-out in the same transaction: if it rolls back, the
+/// signal is scheduled again and picked up by a later poll.
+pub async fn claim_next_due_scheduled<'e, E>(
+    executor: E,
+    now: DateTime<Utc>,
+    skip: &[String],
+) -> Result<Option<Signal>, sqlx::Error>
+where
+    E: PgExecutor<'e>,
+{
+    sqlx::query_as::<_, Signal>(
+        r#"
+        UPDATE signals
+        SET status = 'active'
+        WHERE id = (
+            SELECT id FROM signals
+            WHERE status = 'scheduled' AND scheduled_at <= $1 AND NOT (id = ANY($2))
+            ORDER BY scheduled_at
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING id, channel_id, title, body, urgency, metadata,
+                  delivery_count, delivered_count, failed_count, status, min_subscriber_tier,
+                  scheduled_at, created_at
+        "#,
+    )
+    .bind(now)
+    .bind(skip)
+    .fetch_optional(executor)
+    .await
+}
+
 /// Atomically update delivery statistics for a signal.
 ///
 /// Called by the delivery worker after each delivery attempt to track
@@ -143,3 +269,201 @@ pub async fn increment_delivery_counts(
     .await?;
     Ok(())
 }
+
+/// Record how many deliveries a signal fanned out to.
+///
+/// Must be written before any of its jobs are queued so that
+/// [`settle_deliveries`] can tell when the last one finishes. Accepts any
+/// executor so it can join the publishing transaction.
+pub async fn set_expected_deliveries<'e, E>(
+    executor: E,
+    signal_id: &str,
+    expected: i32,
+) -> Result<(), sqlx::Error>
+where
+    E: PgExecutor<'e>,
+{
+    sqlx::query(
+        r#"
+        UPDATE signals
+        SET expected_deliveries = $1
+        WHERE id = $2
+        "#,
+    )
+    .bind(expected)
+    .bind(signal_id)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Count `settled` of a signal's deliveries as having reached a final outcome.
+///
+/// Returns the signal's totals, with its channel's delivery callback URL, only
+/// to the call that settles the last expected delivery, so the receipt is
+/// reported exactly once.
+pub async fn settle_deliveries(
+    pool: &PgPool,
+    signal_id: &str,
+    settled: i32,
+) -> Result<Option<SignalSettlement>, sqlx::Error> {
+    let settlement = sqlx::query_as::<_, SignalSettlement>(
+        r#"
+        UPDATE signals
+        SET settled_deliveries = settled_deliveries + $1
+        WHERE id = $2
+        RETURNING id AS signal_id, delivered_count, expected_deliveries, settled_deliveries,
+                  (SELECT delivery_callback_url FROM channels
+                   WHERE channels.id = signals.channel_id) AS callback_url
+        "#,
+    )
+    .bind(settled)
+    .bind(signal_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(settlement.filter(|s| s.completed_by(settled)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    /// Columns assigned by each `UPDATE signals ... SET ...` statement in `source`.
+    fn updated_signal_columns(source: &str) -> Vec<String> {
+        let mut columns = Vec::new();
+        for (start, _) in source.match_indices("UPDATE signals") {
+            let statement = &source[start..];
+            let end = statement.find("WHERE").unwrap_or(statement.len());
+            let Some(set) = statement[..end].split_once("SET").map(|(_, set)| set) else {
+                continue;
+            };
+            for assignment in set.split(',') {
+                if let Some((column, _)) = assignment.split_once('=') {
+                    columns.push(column.trim().to_string());
+                }
+            }
+        }
+        columns
+    }
+
+    fn rust_sources(dir: &Path, out: &mut Vec<std::path::PathBuf>) {
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                rust_sources(&path, out);
+            } else if path.extension().is_some_and(|ext| ext == "rs") {
+                out.push(path);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parses_update_assignments() {
+        let sql =
+            "UPDATE signals\n SET status = $1, failed_count = failed_count + 1\n WHERE id = $2";
+        assert_eq!(updated_signal_columns(sql), vec!["status", "failed_count"]);
+    }
+
+    #[test]
+    fn test_signal_updates_only_touch_mutable_columns() {
+        let crates = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+        let mut files = Vec::new();
+        for krate in ["db", "api", "worker"] {
+            rust_sources(&crates.join(krate).join("src"), &mut files);
+        }
+
+        let mut checked = 0;
+        for file in files {
+            let source = std::fs::read_to_string(&file).unwrap();
+            // Test modules quote SQL in fixtures; only production code counts.
+            let source = source.split("#[cfg(test)]").next().unwrap_or_default();
+            for column in updated_signal_columns(source) {
+                checked += 1;
+                assert!(
+                    MUTABLE_COLUMNS.contains(&column.as_str()),
+                    "{} updates immutable signal column `{}`",
+                    file.display(),
+                    column
+                );
+            }
+        }
+        assert!(checked >= MUTABLE_COLUMNS.len());
+    }
+
+    /// Columns guarded by the newest `signals_reject_content_update` in the
+    /// migrations, which is the definition Postgres ends up with.
+    fn immutable_signal_columns() -> Vec<String> {
+        let migrations = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../migrations");
+        let mut files: Vec<_> = std::fs::read_dir(migrations)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "sql"))
+            .collect();
+        files.sort();
+
+        let newest = files
+            .iter()
+            .rev()
+            .map(|path| std::fs::read_to_string(path).unwrap())
+            .find(|sql| sql.contains("FUNCTION signals_reject_content_update"))
+            .expect("a migration defines signals_reject_content_update");
+        newest
+            .split("NEW.")
+            .skip(1)
+            .filter_map(|rest| rest.split_once(" IS DISTINCT FROM"))
+            .map(|(column, _)| column.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_immutable_columns_survive_trigger_redefinitions() {
+        let guarded = immutable_signal_columns();
+        // Made immutable by migrations 008, 009 and 012 respectively.
+        for column in [
+            "channel_id",
+            "title",
+            "body",
+            "urgency",
+            "metadata",
+            "min_subscriber_tier",
+            "created_at",
+            "scheduled_at",
+            "content_hash",
+        ] {
+            assert!(
+                guarded.iter().any(|c| c == column),
+                "signals.{column} is no longer guarded by the immutability trigger"
+            );
+        }
+        for column in MUTABLE_COLUMNS {
+            assert!(
+                !guarded.iter().any(|c| c == column),
+                "{column} should stay mutable"
+            );
+        }
+    }
+
+    #[test]
+    fn test_only_the_last_settlement_completes_a_signal() {
+        let settlement = |expected, settled| SignalSettlement {
+            signal_id: "sig_1".to_string(),
+            delivered_count: 2,
+            expected_deliveries: expected,
+            settled_deliveries: settled,
+            callback_url: None,
+        };
+        assert!(!settlement(Some(3), 2).completed_by(1));
+        assert!(settlement(Some(3), 3).completed_by(1));
+        assert!(settlement(Some(3), 4).completed_by(2));
+        // A redelivery replayed after completion does not report again.
+        assert!(!settlement(Some(3), 4).completed_by(1));
+        assert!(!settlement(None, 1).completed_by(1));
+
+        let receipt = settlement(Some(3), 3).receipt();
+        assert_eq!(
+            (receipt.delivered, receipt.failed, receipt.total),
+            (2, 1, 3)
+        );
+    }
+}
@@ -1,10 +1,42 @@
 use crate::models::Subscriber;
-use sqlx::PgPool;
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool, QueryBuilder};
+
+/// Insert a subscriber on the free tier. A taken email fails with a unique
+/// violation.
+pub async fn create<'e, E>(
+    executor: E,
+    id: &str,
+    name: &str,
+    email: &str,
+    webhook_secret: &str,
+) -> Result<Subscriber, sqlx::Error>
+where
+    E: PgExecutor<'e>,
+{
+    sqlx::query_as::<_, Subscriber>(
+        r#"
+        INSERT INTO subscribers (id, name, email, webhook_secret)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, name, email, webhook_secret, previous_webhook_secret,
+                  previous_webhook_secret_expires_at, stripe_customer_id,
+                  tier, status, delivery_mode, agent_last_connected_at,
+                  created_at, updated_at
+        "#,
+    )
+    .bind(id)
+    .bind(name)
+    .bind(email)
+    .bind(webhook_secret)
+    .fetch_one(executor)
+    .await
+}
 
 pub async fn get_by_id(pool: &PgPool, id: &str) -> Result<Option<Subscriber>, sqlx::Error> {
     sqlx::query_as::<_, Subscriber>(
         r#"
-        SELECT id, name, email, webhook_secret, stripe_customer_id,
+        SELECT id, name, email, webhook_secret, previous_webhook_secret,
+               previous_webhook_secret_expires_at, stripe_customer_id,
                tier, status, delivery_mode, agent_last_connected_at,
                created_at, updated_at
         FROM subscribers
@@ -19,7 +51,8 @@ pub async fn get_by_id(pool: &PgPool, id: &str) -> Result<Option<Subscriber>, sq
 pub async fn get_by_email(pool: &PgPool, email: &str) -> Result<Option<Subscriber>, sqlx::Error> {
     sqlx::query_as::<_, Subscriber>(
         r#"
-        SELECT id, name, email, webhook_secret, stripe_customer_id,
+        SELECT id, name, email, webhook_secret, previous_webhook_secret,
+               previous_webhook_secret_expires_at, stripe_customer_id,
                tier, status, delivery_mode, agent_last_connected_at,
                created_at, updated_at
         FROM subscribers
@@ -49,3 +82,72 @@ pub async fn update_agent_last_connected_at(
     .await?;
     Ok(())
 }
+
+/// Replace a subscriber's webhook secret with `new_secret`.
+///
+/// The outgoing secret moves to `previous_webhook_secret` until
+/// `previous_expires_at`; any secret kept from an earlier rotation is dropped.
+/// Returns `false` if the subscriber does not exist.
+pub async fn rotate_webhook_secret(
+    pool: &PgPool,
+    id: &str,
+    new_secret: &str,
+    previous_expires_at: DateTime<Utc>,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE subscribers
+        SET previous_webhook_secret = webhook_secret,
+            previous_webhook_secret_expires_at = $3,
+            webhook_secret = $2,
+            updated_at = now()
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(new_secret)
+    .bind(previous_expires_at)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+/// Update a subscriber's name and/or email.
+///
+/// Only non-None fields are updated. Returns an error if no fields are provided,
+/// and `None` if the subscriber does not exist. A taken email fails with a
+/// unique violation.
+pub async fn update_profile(
+    pool: &PgPool,
+    id: &str,
+    name: Option<&str>,
+    email: Option<&str>,
+) -> Result<Option<Subscriber>, sqlx::Error> {
+    let mut qb = QueryBuilder::new("UPDATE subscribers SET ");
+    let mut set = qb.separated(", ");
+    let mut updated = false;
+
+    if let Some(value) = name {
+        set.push("name = ").push_bind(value);
+        updated = true;
+    }
+    if let Some(value) = email {
+        set.push("email = ").push_bind(value);
+        updated = true;
+    }
+
+    if !updated {
+        return Err(sqlx::Error::Protocol("no fields to update".into()));
+    }
+
+    set.push("updated_at = now()");
+    qb.push(" WHERE id = ").push_bind(id);
+    qb.push(
+        " RETURNING id, name, email, webhook_secret, previous_webhook_secret,
+                 previous_webhook_secret_expires_at, stripe_customer_id,
+                 tier, status, delivery_mode, agent_last_connected_at,
+                 created_at, updated_at",
+    );
+
+    qb.build_query_as::<Subscriber>().fetch_optional(pool).await
+}
@@ -1,5 +1,7 @@
-use crate::models::{Subscription, SubscriptionStatus};
-use sqlx::PgPool;
+use crate::models::{
+    SignalUrgency, SubscribedChannel, Subscription, SubscriptionStatus, SubscriptionTarget,
+};
+use sqlx::{PgExecutor, PgPool, QueryBuilder};
 
 pub async fn create(
     pool: &PgPool,
@@ -7,19 +9,24 @@ pub async fn create(
     subscriber_id: &str,
     channel_id: &str,
     webhook_id: Option<&str>,
+    emergency_webhook_id: Option<&str>,
+    min_urgency: SignalUrgency,
 ) -> Result<Subscription, sqlx::Error> {
     sqlx::query_as::<_, Subscription>(
         r#"
-        INSERT INTO subscriptions (id, subscriber_id, channel_id, webhook_id)
-        VALUES ($1, $2, $3, $4)
-        RETURNING id, subscriber_id, channel_id, webhook_id, status,
-                  stripe_subscription_id, created_at, updated_at
+        INSERT INTO subscriptions
+            (id, subscriber_id, channel_id, webhook_id, emergency_webhook_id, min_urgency)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id, subscriber_id, channel_id, webhook_id, emergency_webhook_id,
+                  status, min_urgency, stripe_subscription_id, created_at, updated_at
         "#,
     )
     .bind(id)
     .bind(subscriber_id)
     .bind(channel_id)
     .bind(webhook_id)
+    .bind(emergency_webhook_id)
+    .bind(min_urgency)
     .fetch_one(pool)
     .await
 }
@@ -27,8 +34,8 @@ pub async fn create(
 pub async fn get_by_id(pool: &PgPool, id: &str) -> Result<Option<Subscription>, sqlx::Error> {
     sqlx::query_as::<_, Subscription>(
         r#"
-        SELECT id, subscriber_id, channel_id, webhook_id, status,
-               stripe_subscription_id, created_at, updated_at
+        SELECT id, subscriber_id, channel_id, webhook_id, emergency_webhook_id,
+               status, min_urgency, stripe_subscription_id, created_at, updated_at
         FROM subscriptions
         WHERE id = $1
         "#,
@@ -44,8 +51,8 @@ pub async fn list_by_subscriber(
 ) -> Result<Vec<Subscription>, sqlx::Error> {
     sqlx::query_as::<_, Subscription>(
         r#"
-        SELECT id, subscriber_id, channel_id, webhook_id, status,
-               stripe_subscription_id, created_at, updated_at
+        SELECT id, subscriber_id, channel_id, webhook_id, emergency_webhook_id,
+               status, min_urgency, stripe_subscription_id, created_at, updated_at
         FROM subscriptions
         WHERE subscriber_id = $1
         ORDER BY created_at DESC
@@ -56,14 +63,53 @@ pub async fn list_by_subscriber(
     .await
 }
 
+/// Channels a subscriber is subscribed to, newest subscription first.
+///
+/// Canceled subscriptions are left out unless `include_canceled` is set.
+/// `cursor` is the last `subscription_id` of the previous page.
+pub async fn list_channels_by_subscriber(
+    pool: &PgPool,
+    subscriber_id: &str,
+    include_canceled: bool,
+    limit: i64,
+    cursor: Option<&str>,
+) -> Result<Vec<SubscribedChannel>, sqlx::Error> {
+    let mut qb = QueryBuilder::new(
+        r#"
+        SELECT s.id AS subscription_id, s.status AS subscription_status,
+               c.id AS channel_id, c.slug, c.display_name, c.status AS channel_status,
+               s.created_at AS subscribed_at
+        FROM subscriptions s
+        JOIN channels c ON c.id = s.channel_id
+        WHERE s.subscriber_id = "#,
+    );
+    qb.push_bind(subscriber_id);
+    if !include_canceled {
+        qb.push(" AND s.status <> 'canceled'");
+    }
+    if let Some(cursor) = cursor {
+        qb.push(
+            " AND (s.created_at, s.id) < (SELECT created_at, id FROM subscriptions WHERE id = ",
+        )
+        .push_bind(cursor)
+        .push(")");
+    }
+    qb.push(" ORDER BY s.created_at DESC, s.id DESC LIMIT ")
+        .push_bind(limit);
+
+    qb.build_query_as::<SubscribedChannel>()
+        .fetch_all(pool)
+        .await
+}
+
 pub async fn list_active_by_channel(
     pool: &PgPool,
     channel_id: &str,
 ) -> Result<Vec<Subscription>, sqlx::Error> {
     sqlx::query_as::<_, Subscription>(
         r#"
-        SELECT id, subscriber_id, channel_id, webhook_id, status,
-               stripe_subscription_id, created_at, updated_at
+        SELECT id, subscriber_id, channel_id, webhook_id, emergency_webhook_id,
+               status, min_urgency, stripe_subscription_id, created_at, updated_at
         FROM subscriptions
         WHERE channel_id = $1 AND status = 'active'
         "#,
@@ -73,6 +119,31 @@ pub async fn list_active_by_channel(
     .await
 }
 
+/// Active subscriptions for a channel along with each subscriber's tier.
+/// Accepts any executor so fan-out can read the same snapshot as the
+/// transaction that creates the signal.
+pub async fn list_active_targets_by_channel<'e, E>(
+    executor: E,
+    channel_id: &str,
+) -> Result<Vec<SubscriptionTarget>, sqlx::Error>
+where
+    E: PgExecutor<'e>,
+{
+    sqlx::query_as::<_, SubscriptionTarget>(
+        r#"
+        SELECT s.id, s.subscriber_id, s.channel_id, s.webhook_id, s.emergency_webhook_id,
+               s.status, s.min_urgency, s.stripe_subscription_id, s.created_at, s.updated_at,
+               sr.tier AS subscriber_tier
+        FROM subscriptions s
+        JOIN subscribers sr ON sr.id = s.subscriber_id
+        WHERE s.channel_id = $1 AND s.status = 'active'
+        "#,
+    )
+    .bind(channel_id)
+    .fetch_all(executor)
+    .await
+}
+
 pub async fn update_status(
     pool: &PgPool,
     id: &str,
@@ -91,3 +162,24 @@ pub async fn update_status(
     .await?;
     Ok(())
 }
+
+/// Set the lowest signal urgency a subscription receives.
+pub async fn set_min_urgency(
+    pool: &PgPool,
+    id: &str,
+    min_urgency: SignalUrgency,
+) -> Result<Subscription, sqlx::Error> {
+    sqlx::query_as::<_, Subscription>(
+        r#"
+        UPDATE subscriptions
+        SET min_urgency = $1, updated_at = now()
+        WHERE id = $2
+        RETURNING id, subscriber_id, channel_id, webhook_id, emergency_webhook_id,
+                  status, min_urgency, stripe_subscription_id, created_at, updated_at
+        "#,
+    )
+    .bind(min_urgency)
+    .bind(id)
+    .fetch_one(pool)
+    .await
+}
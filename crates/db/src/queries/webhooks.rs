@@ -1,7 +1,8 @@
-use crate::models::{Webhook, WebhookStatus};
+use crate::models::{SignatureAlgorithm, Webhook, WebhookStatus};
 use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 
+#[allow(clippy::too_many_arguments)]
 pub async fn create(
     pool: &PgPool,
     id: &str,
@@ -9,12 +10,19 @@ pub async fn create(
     url: &str,
     name: &str,
     token: Option<&str>,
+    headers: Option<&serde_json::Value>,
+    timeout_ms: i32,
+    max_in_flight: i32,
+    max_per_second: Option<i32>,
+    signature_algorithm: SignatureAlgorithm,
 ) -> Result<Webhook, sqlx::Error> {
     sqlx::query_as::<_, Webhook>(
         r#"
-        INSERT INTO webhooks (id, subscriber_id, url, name, token)
-        VALUES ($1, $2, $3, $4, $5)
-        RETURNING id, subscriber_id, url, name, token, status,
+        INSERT INTO webhooks (id, subscriber_id, url, name, token, headers, timeout_ms,
+                              max_in_flight, max_per_second, signature_algorithm)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        RETURNING id, subscriber_id, url, name, token, headers, payload_schema, timeout_ms,
+                  max_in_flight, max_per_second, signature_algorithm, legacy_signature, status,
                   failure_count, last_success_at, last_failure_at,
                   created_at, updated_at
         "#,
@@ -24,6 +32,11 @@ pub async fn create(
     .bind(url)
     .bind(name)
     .bind(token)
+    .bind(headers)
+    .bind(timeout_ms)
+    .bind(max_in_flight)
+    .bind(max_per_second)
+    .bind(signature_algorithm)
     .fetch_one(pool)
     .await
 }
@@ -31,7 +44,8 @@ pub async fn create(
 pub async fn get_by_id(pool: &PgPool, id: &str) -> Result<Option<Webhook>, sqlx::Error> {
     sqlx::query_as::<_, Webhook>(
         r#"
-        SELECT id, subscriber_id, url, name, token, status,
+        SELECT id, subscriber_id, url, name, token, headers, payload_schema, timeout_ms,
+               max_in_flight, max_per_second, signature_algorithm, legacy_signature, status,
                failure_count, last_success_at, last_failure_at,
                created_at, updated_at
         FROM webhooks
@@ -49,7 +63,8 @@ pub async fn list_by_subscriber(
 ) -> Result<Vec<Webhook>, sqlx::Error> {
     sqlx::query_as::<_, Webhook>(
         r#"
-        SELECT id, subscriber_id, url, name, token, status,
+        SELECT id, subscriber_id, url, name, token, headers, payload_schema, timeout_ms,
+               max_in_flight, max_per_second, signature_algorithm, legacy_signature, status,
                failure_count, last_success_at, last_failure_at,
                created_at, updated_at
         FROM webhooks
@@ -62,11 +77,26 @@ pub async fn list_by_subscriber(
     .await
 }
 
+/// Number of webhooks registered by a subscriber.
+pub async fn count_by_subscriber(pool: &PgPool, subscriber_id: &str) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM webhooks WHERE subscriber_id = $1")
+        .bind(subscriber_id)
+        .fetch_one(pool)
+        .await
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn update(
     pool: &PgPool,
     id: &str,
     name: Option<&str>,
     url: Option<&str>,
+    headers: Option<&serde_json::Value>,
+    timeout_ms: Option<i32>,
+    max_in_flight: Option<i32>,
+    max_per_second: Option<i32>,
+    signature_algorithm: Option<SignatureAlgorithm>,
+    legacy_signature: Option<bool>,
     status: Option<WebhookStatus>,
 ) -> Result<(String, WebhookStatus, DateTime<Utc>), sqlx::Error> {
     let mut qb = sqlx::QueryBuilder::new("UPDATE webhooks SET ");
@@ -81,6 +111,30 @@ pub async fn update(
         set.push("url = ").push_bind(value);
         updated = true;
     }
+    if let Some(value) = headers {
+        set.push("headers = ").push_bind(value);
+        updated = true;
+    }
+    if let Some(value) = timeout_ms {
+        set.push("timeout_ms = ").push_bind(value);
+        updated = true;
+    }
+    if let Some(value) = max_in_flight {
+        set.push("max_in_flight = ").push_bind(value);
+        updated = true;
+    }
+    if let Some(value) = max_per_second {
+        set.push("max_per_second = ").push_bind(value);
+        updated = true;
+    }
+    if let Some(value) = signature_algorithm {
+        set.push("signature_algorithm = ").push_bind(value);
+        updated = true;
+    }
+    if let Some(value) = legacy_signature {
+        set.push("legacy_signature = ").push_bind(value);
+        updated = true;
+    }
     if let Some(value) = status {
         set.push("status = ").push_bind(value);
         updated = true;
@@ -102,22 +156,59 @@ pub async fn update(
     Ok(record)
 }
 
-pub async fn update_failure(
+pub async fn set_payload_schema(
     pool: &PgPool,
     id: &str,
-    last_failure_at: DateTime<Utc>,
+    payload_schema: &serde_json::Value,
 ) -> Result<(), sqlx::Error> {
     sqlx::query(
+        r#"
+        UPDATE webhooks
+        SET payload_schema = $1,
+            updated_at = now()
+        WHERE id = $2
+        "#,
+    )
+    .bind(payload_schema)
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Record a failed delivery and return the new consecutive failure count.
+pub async fn update_failure(
+    pool: &PgPool,
+    id: &str,
+    last_failure_at: DateTime<Utc>,
+) -> Result<i32, sqlx::Error> {
+    sqlx::query_scalar::<_, i32>(
         r#"
         UPDATE webhooks
         SET failure_count = failure_count + 1,
             last_failure_at = $1,
             updated_at = now()
         WHERE id = $2
+        RETURNING failure_count
         "#,
     )
     .bind(last_failure_at)
     .bind(id)
+    .fetch_one(pool)
+    .await
+}
+
+/// Trip the circuit breaker: stop all deliveries to the webhook.
+pub async fn disable(pool: &PgPool, id: &str) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE webhooks
+        SET status = 'disabled',
+            updated_at = now()
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
     .execute(pool)
     .await?;
     Ok(())
@@ -133,6 +224,7 @@ pub async fn update_success(
         UPDATE webhooks
         SET failure_count = 0,
             last_success_at = $1,
+            status = CASE WHEN status = 'disabled' THEN 'active'::webhook_status ELSE status END,
             updated_at = now()
         WHERE id = $2
         "#,
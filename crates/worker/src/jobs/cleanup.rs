@@ -0,0 +1,36 @@
+use chrono::Utc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// How often the idempotency purge runs.
+const IDEMPOTENCY_PURGE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// How often API keys past their expiry are marked expired.
+const API_KEY_EXPIRY_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Periodically delete idempotency keys older than the retention window.
+pub async fn purge_idempotency_keys(db: sqlx::PgPool, retention_hours: i64) {
+    let mut interval = tokio::time::interval(IDEMPOTENCY_PURGE_INTERVAL);
+    loop {
+        interval.tick().await;
+        let cutoff = Utc::now() - chrono::Duration::hours(retention_hours);
+        match db::queries::idempotency_keys::purge_older_than(&db, cutoff).await {
+            Ok(purged) if purged > 0 => info!(purged, "purged expired idempotency keys"),
+            Ok(_) => {}
+            Err(err) => warn!(error = %err, "failed to purge idempotency keys"),
+        }
+    }
+}
+
+/// Periodically flip API keys past their `expires_at` to `expired`.
+pub async fn expire_api_keys(db: sqlx::PgPool) {
+    let mut interval = tokio::time::interval(API_KEY_EXPIRY_INTERVAL);
+    loop {
+        interval.tick().await;
+        match db::queries::api_keys::expire_past_due(&db).await {
+            Ok(expired) if expired > 0 => info!(expired, "marked past-due api keys expired"),
+            Ok(_) => {}
+            Err(err) => warn!(error = %err, "failed to expire api keys"),
+        }
+    }
+}
@@ -1,13 +1,24 @@
 use anyhow::Context;
 use chrono::Utc;
-use core::{auth::sign_payload, types::DeliveryJob};
-use core::tunnel::{ServerMessage, TunnelSignal};
+use core::{auth::SIGNATURE_ALGORITHM_HEADER, types::DeliveryJob};
+use core::presence::Presence;
+use core::tunnel::{AckOutcome, AgentConnection, ServerMessage, TunnelSignal};
+use core::types::{
+    RetryPolicy, GLOBAL_SIGNATURE_HEADER, PAYLOAD_SCHEMA_VERSION, PAYLOAD_VERSION_HEADER,
+};
 use core::types::SignalUrgency as CoreSignalUrgency;
-use db::models::{DeliveryMode, DeliveryStatus, SignalUrgency};
+use core::net::{host_ip, is_private_ip, TargetError};
+use core::payload::build_payload;
+use core::metrics::METRICS;
+use db::models::{AccountStatus, DeliveryMode, DeliveryStatus, SignalUrgency, WebhookStatus};
+use rand::Rng;
 use serde_json::json;
+use std::sync::Arc;
 use std::time::Instant;
-use tracing::warn;
+use tracing::{info, warn};
 
+use super::relay;
+use super::throttle::{self, Admission, THROTTLE_REQUEUE_DELAY};
 use crate::WorkerState;
 
 fn convert_urgency(urgency: &SignalUrgency) -> CoreSignalUrgency {
@@ -19,9 +30,15 @@ fn convert_urgency(urgency: &SignalUrgency) -> CoreSignalUrgency {
     }
 }
 
-/// Backoff strategy for delivery retries.
-pub fn retry_policy(attempt: u32) -> std::time::Duration {
-    match attempt {
+/// Backoff strategy for delivery retries. Every urgency follows the same
+/// schedule, capped at the urgency's maximum delay, then jittered down by up
+/// to half so deliveries that failed together don't all retry together.
+pub fn retry_policy<R: Rng + ?Sized>(
+    attempt: u32,
+    urgency: &SignalUrgency,
+    rng: &mut R,
+) -> std::time::Duration {
+    let base = match attempt {
         0 => std::time::Duration::from_secs(0),
         1 => std::time::Duration::from_secs(60),
         2 => std::time::Duration::from_secs(300),
@@ -29,9 +46,111 @@ pub fn retry_policy(attempt: u32) -> std::time::Duration {
         4 => std::time::Duration::from_secs(7200),
         _ => std::time::Duration::from_secs(21600),
     }
+    .min(max_retry_delay(urgency));
+    let base_ms = base.as_millis() as u64;
+    std::time::Duration::from_millis(base_ms - rng.gen_range(0..=base_ms / 2))
 }
 
-pub async fn handle_delivery_job(state: &WorkerState, job: DeliveryJob) -> anyhow::Result<()> {
+/// Longest wait between retries for a signal of the given urgency.
+fn max_retry_delay(urgency: &SignalUrgency) -> std::time::Duration {
+    match urgency {
+        SignalUrgency::Critical => std::time::Duration::from_secs(3600),
+        SignalUrgency::High => std::time::Duration::from_secs(7200),
+        SignalUrgency::Normal | SignalUrgency::Low => std::time::Duration::from_secs(21600),
+    }
+}
+
+/// Attempts (including the first) before a delivery is dead-lettered when the
+/// channel has no retry policy override.
+fn default_max_attempts(urgency: &SignalUrgency) -> i32 {
+    match urgency {
+        SignalUrgency::Critical => 8,
+        SignalUrgency::High => 7,
+        SignalUrgency::Normal => 6,
+        SignalUrgency::Low => 5,
+    }
+}
+
+/// Consecutive failed deliveries after which a webhook is disabled. A
+/// successful delivery resets the count and re-activates the webhook.
+const WEBHOOK_DISABLE_THRESHOLD: i32 = 20;
+
+/// Error recorded on a delivery held until the subscriber's agent reconnects.
+const NO_AGENT_CONNECTED: &str = "no agent connected; held for replay";
+
+/// The channel's retry policy override, if one is set and parses.
+fn channel_retry_policy(channel: &db::models::Channel) -> Option<RetryPolicy> {
+    let value = channel.retry_policy.clone()?;
+    match serde_json::from_value(value) {
+        Ok(policy) => Some(policy),
+        Err(err) => {
+            warn!(channel_id = %channel.id, error = %err, "ignoring invalid channel retry policy");
+            None
+        }
+    }
+}
+
+/// Attempt budget from the channel policy, or the urgency default.
+fn max_attempts(policy: Option<&RetryPolicy>, urgency: &SignalUrgency) -> i32 {
+    policy.map_or_else(|| default_max_attempts(urgency), |policy| policy.max_attempts as i32)
+}
+
+/// Delay before retry number `retry`, from the channel policy or the default schedule.
+fn retry_delay(
+    policy: Option<&RetryPolicy>,
+    urgency: &SignalUrgency,
+    retry: u32,
+    rng: &mut impl Rng,
+) -> std::time::Duration {
+    policy.map_or_else(|| retry_policy(retry, urgency, rng), |policy| policy.delay_for(retry))
+}
+
+/// How a delivery job ended. Errors are reserved for infrastructure failures
+/// (database, queue); everything the delivery itself decided is an outcome.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryOutcome {
+    /// The subscriber's webhook or agent accepted the signal.
+    Delivered,
+    /// The attempt failed and a later attempt was queued.
+    RetryScheduled,
+    /// The attempt failed with no attempts left; the signal is in the DLQ.
+    DeadLettered,
+    /// Permanently refused (deleted subscriber, disabled or private webhook);
+    /// recorded as undeliverable or failed without a retry.
+    Undeliverable,
+    /// Not sent because the subscriber's tier is below the signal's minimum.
+    Skipped,
+    /// No agent connected and no webhook configured.
+    NoMethod,
+    /// The webhook was at its concurrency or rate cap; the job was re-queued
+    /// without counting an attempt.
+    Throttled,
+}
+
+impl DeliveryOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryOutcome::Delivered => "delivered",
+            DeliveryOutcome::RetryScheduled => "retry_scheduled",
+            DeliveryOutcome::DeadLettered => "dead_lettered",
+            DeliveryOutcome::Undeliverable => "undeliverable",
+            DeliveryOutcome::Skipped => "skipped",
+            DeliveryOutcome::NoMethod => "no_method",
+            DeliveryOutcome::Throttled => "throttled",
+        }
+    }
+
+    /// Whether the subscription is done with the signal. Scheduled retries
+    /// and throttled re-queues leave another job to finish it.
+    pub fn is_final(&self) -> bool {
+        !matches!(self, DeliveryOutcome::RetryScheduled | DeliveryOutcome::Throttled)
+    }
+}
+
+pub async fn handle_delivery_job(
+    state: &WorkerState,
+    job: DeliveryJob,
+) -> anyhow::Result<DeliveryOutcome> {
     let signal = db::queries::signals::get_by_id(&state.db, &job.signal_id)
         .await?
         .context("signal not found")?;
@@ -45,32 +164,95 @@ pub async fn handle_delivery_job(state: &WorkerState, job: DeliveryJob) -> anyho
         .await?
         .context("subscriber not found")?;
 
-    if let Some(agent) = state
-        .tunnel_registry
-        .get(&subscription.subscriber_id)
-        .await
-    {
-        let allow_retry = subscription.webhook_id.is_none();
-        if deliver_via_tunnel(
+    if !subscriber.tier.meets(signal.min_subscriber_tier.as_ref()) {
+        info!(
+            signal_id = %signal.id,
+            subscription_id = %subscription.id,
+            "skipping delivery: subscriber tier below signal minimum"
+        );
+        // The fan-out expected this delivery, so it still counts towards the
+        // receipt: every expected delivery ends up delivered or failed.
+        db::queries::signals::increment_delivery_counts(&state.db, &signal.id, 0, 1, 1).await?;
+        return Ok(DeliveryOutcome::Skipped);
+    }
+
+    if let Some(reason) = subscriber_permanent_failure(&subscriber) {
+        return mark_undeliverable(
             state,
             &signal,
             &subscription,
-            &channel,
-            &agent,
+            None,
+            subscriber.delivery_mode.clone(),
             job.attempt,
-            allow_retry,
+            reason,
         )
+        .await;
+    }
+
+    let agents = state
+        .tunnel_registry
+        .delivery_order(&subscription.subscriber_id)
+        .await;
+    let remote = if agents.is_empty() {
+        relay::owner(state, &subscription.subscriber_id).await
+    } else {
+        None
+    };
+    let agent_available = !agents.is_empty() || remote.is_some();
+    let webhook_id = select_webhook_id(&subscription, &signal.urgency);
+    let path = delivery_path(&subscriber.delivery_mode, agent_available, webhook_id);
+    let webhook_id = match path {
+        DeliveryPath::Tunnel { fallback_webhook } => {
+            if let Some(outcome) = deliver_via_tunnel(
+                state,
+                &signal,
+                &subscription,
+                &channel,
+                &agents,
+                remote.as_ref(),
+                job.attempt,
+                fallback_webhook.is_none(),
+            )
             .await?
-        {
-            return Ok(());
+            {
+                return Ok(outcome);
+            }
+            fallback_webhook
         }
-    }
+        DeliveryPath::Webhook(webhook_id) => Some(webhook_id),
+        DeliveryPath::Unavailable => None,
+    };
 
-    if let Some(webhook_id) = subscription.webhook_id.as_deref() {
+    if let Some(webhook_id) = webhook_id {
         let webhook = db::queries::webhooks::get_by_id(&state.db, webhook_id)
             .await?
             .context("webhook not found")?;
 
+        if let Some(reason) = webhook_permanent_failure(&webhook, &state.settings.herald_env) {
+            return mark_undeliverable(
+                state,
+                &signal,
+                &subscription,
+                Some(&webhook.id),
+                DeliveryMode::Webhook,
+                job.attempt,
+                reason,
+            )
+            .await;
+        }
+        if let Some(reason) = private_target_now(&webhook.url, &state.settings.herald_env).await {
+            return mark_undeliverable(
+                state,
+                &signal,
+                &subscription,
+                Some(&webhook.id),
+                DeliveryMode::Webhook,
+                job.attempt,
+                &reason,
+            )
+            .await;
+        }
+
         return deliver_via_webhook(
             state,
             &signal,
@@ -83,9 +265,185 @@ pub async fn handle_delivery_job(state: &WorkerState, job: DeliveryJob) -> anyho
         .await;
     }
 
-    Err(anyhow::anyhow!("No delivery method available"))
+    let delivery_id = hold_for_agent(state, &signal, &subscription, job.attempt).await?;
+    warn!(
+        signal_id = %signal.id,
+        subscription_id = %subscription.id,
+        delivery_id = %delivery_id,
+        "no delivery method available; held for agent replay"
+    );
+    Ok(DeliveryOutcome::NoMethod)
+}
+
+/// The route a delivery takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DeliveryPath<'a> {
+    /// Offer to connected agents, then the webhook if the tunnel attempt
+    /// fails and one is configured.
+    Tunnel { fallback_webhook: Option<&'a str> },
+    Webhook(&'a str),
+    /// No connected agent and no webhook.
+    Unavailable,
+}
+
+/// Choose the delivery route from the subscriber's preferred mode.
+///
+/// Each mode uses its own channel when it can and falls back to the other
+/// only when its own is missing: `agent` subscribers get their webhook when no
+/// agent is connected (or the tunnel attempt fails), and `webhook`
+/// subscribers are only sent through a connected agent when the subscription
+/// has no webhook.
+fn delivery_path<'a>(
+    mode: &DeliveryMode,
+    agents_connected: bool,
+    webhook_id: Option<&'a str>,
+) -> DeliveryPath<'a> {
+    match (mode, agents_connected, webhook_id) {
+        (DeliveryMode::Webhook, _, Some(webhook_id)) => DeliveryPath::Webhook(webhook_id),
+        (DeliveryMode::Agent, true, fallback_webhook)
+        | (DeliveryMode::Webhook, true, fallback_webhook @ None) => {
+            DeliveryPath::Tunnel { fallback_webhook }
+        }
+        (_, false, Some(webhook_id)) => DeliveryPath::Webhook(webhook_id),
+        (_, false, None) => DeliveryPath::Unavailable,
+    }
+}
+
+/// Why a subscriber can never receive deliveries, if that is the case.
+fn subscriber_permanent_failure(subscriber: &db::models::Subscriber) -> Option<&'static str> {
+    matches!(subscriber.status, AccountStatus::Deleted).then_some("subscriber account deleted")
+}
+
+/// Why a webhook can never receive deliveries, if that is the case.
+fn webhook_permanent_failure(webhook: &db::models::Webhook, env: &str) -> Option<&'static str> {
+    if matches!(webhook.status, WebhookStatus::Disabled) {
+        return Some("webhook disabled");
+    }
+    let private_target = reqwest::Url::parse(&webhook.url)
+        .ok()
+        .and_then(|url| url.host_str().and_then(host_ip))
+        .is_some_and(|ip| is_private_ip(&ip));
+    if env == "prod" && private_target {
+        return Some("webhook url targets a private address");
+    }
+    None
+}
+
+/// In prod, whether the webhook's host resolves only to private addresses
+/// right now, catching a name re-pointed at internal infra after the webhook
+/// was registered. Lookup failures are left to the delivery attempt, which
+/// retries them. The client's resolver applies the same rule when it
+/// connects, so a host that changes between this check and the request is
+/// still never reached.
+async fn private_target_now(url: &str, env: &str) -> Option<String> {
+    if env != "prod" {
+        return None;
+    }
+    match core::net::check_public_url(url).await {
+        Err(TargetError::Private(detail)) => {
+            Some(format!("webhook url targets a private address: {detail}"))
+        }
+        _ => None,
+    }
+}
+
+/// Record a terminal `Undeliverable` attempt, counted as a failed delivery;
+/// no retry is scheduled and nothing goes to the DLQ.
+#[allow(clippy::too_many_arguments)]
+async fn mark_undeliverable(
+    state: &WorkerState,
+    signal: &db::models::Signal,
+    subscription: &db::models::Subscription,
+    webhook_id: Option<&str>,
+    mode: DeliveryMode,
+    attempt: i32,
+    reason: &str,
+) -> anyhow::Result<DeliveryOutcome> {
+    let delivery_id = format!("del_{}", nanoid::nanoid!(12));
+    db::queries::deliveries::create(
+        &state.db,
+        &delivery_id,
+        &signal.id,
+        &subscription.id,
+        webhook_id,
+        mode,
+        attempt,
+    )
+    .await?;
+    db::queries::deliveries::update_status(
+        &state.db,
+        &delivery_id,
+        DeliveryStatus::Undeliverable,
+        None,
+        Some(reason),
+        None,
+    )
+    .await?;
+    db::queries::signals::increment_delivery_counts(&state.db, &signal.id, 0, 1, 1).await?;
+
+    warn!(
+        signal_id = %signal.id,
+        subscription_id = %subscription.id,
+        delivery_id = %delivery_id,
+        reason,
+        "delivery marked undeliverable"
+    );
+    Ok(DeliveryOutcome::Undeliverable)
+}
+
+/// Record a failed, sequenced agent delivery for a subscriber with no way to
+/// receive it now, so their agent is sent it on reconnecting. It counts as a
+/// failed delivery of the signal.
+async fn hold_for_agent(
+    state: &WorkerState,
+    signal: &db::models::Signal,
+    subscription: &db::models::Subscription,
+    attempt: i32,
+) -> anyhow::Result<String> {
+    let delivery_id = format!("del_{}", nanoid::nanoid!(12));
+    db::queries::deliveries::create(
+        &state.db,
+        &delivery_id,
+        &signal.id,
+        &subscription.id,
+        None,
+        DeliveryMode::Agent,
+        attempt,
+    )
+    .await?;
+    db::queries::deliveries::assign_tunnel_seq(
+        &state.db,
+        &delivery_id,
+        &subscription.subscriber_id,
+    )
+    .await?;
+    db::queries::deliveries::update_status(
+        &state.db,
+        &delivery_id,
+        DeliveryStatus::Failed,
+        None,
+        Some(NO_AGENT_CONNECTED),
+        None,
+    )
+    .await?;
+    db::queries::signals::increment_delivery_counts(&state.db, &signal.id, 0, 1, 1).await?;
+    Ok(delivery_id)
+}
+
+/// Pick the webhook for a signal: critical signals go to the subscription's
+/// emergency webhook when one is configured.
+fn select_webhook_id<'a>(
+    subscription: &'a db::models::Subscription,
+    urgency: &SignalUrgency,
+) -> Option<&'a str> {
+    match (urgency, subscription.emergency_webhook_id.as_deref()) {
+        (SignalUrgency::Critical, Some(emergency)) => Some(emergency),
+        _ => subscription.webhook_id.as_deref(),
+    }
 }
 
+/// Attempt the HTTP delivery. Callers have already routed webhooks that can
+/// never succeed (see [`webhook_permanent_failure`]) to `mark_undeliverable`.
 #[allow(clippy::too_many_arguments)]
 async fn deliver_via_webhook(
     state: &WorkerState,
@@ -95,7 +453,28 @@ async fn deliver_via_webhook(
     subscriber: &db::models::Subscriber,
     webhook: &db::models::Webhook,
     attempt: i32,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<DeliveryOutcome> {
+    let lease = throttle::slot_lease(
+        webhook.timeout(),
+        state.settings.webhook_transient_retries + 1,
+    );
+    let slot = match throttle::acquire(&state.redis, webhook, lease).await {
+        Admission::Admitted(slot) => Some(slot),
+        Admission::Unchecked => None,
+        Admission::Throttled => {
+            let webhook_id = Some(webhook.id.clone());
+            let job = delivery_job(state, signal, subscription, channel, webhook_id, attempt);
+            enqueue_after(state, signal.urgency.queue(), job, THROTTLE_REQUEUE_DELAY);
+            info!(
+                signal_id = %signal.id,
+                webhook_id = %webhook.id,
+                attempt,
+                "webhook at delivery cap; re-queued"
+            );
+            return Ok(DeliveryOutcome::Throttled);
+        }
+    };
+
     let delivery_id = format!("del_{}", nanoid::nanoid!(12));
     let delivery = db::queries::deliveries::create(
         &state.db,
@@ -109,26 +488,43 @@ async fn deliver_via_webhook(
     .await?;
 
     let payload = build_payload(&delivery.id, Some(&webhook.id), channel, signal);
-
-    let body = serde_json::to_string(&payload)?;
-    let timestamp = Utc::now().timestamp();
-    let signature = sign_payload(&subscriber.webhook_secret, timestamp, &body);
-
-    let mut req = state
-        .client
-        .post(&webhook.url)
-        .header("Content-Type", "application/json")
-        .header("X-Herald-Signature", signature)
-        .header("X-Herald-Timestamp", timestamp.to_string())
-        .header("X-Herald-Delivery-Id", delivery.id.clone());
-
-    if let Some(token) = webhook.token.as_deref() {
-        req = req.header("Authorization", format!("Bearer {}", token));
+    check_payload_schema(state, webhook, &delivery.id, &payload);
+
+    let global_secret = state
+        .settings
+        .global_signature
+        .then_some(state.settings.hmac_secret.as_str());
+    let req = webhook_request(
+        &state.client,
+        webhook,
+        &subscriber.webhook_secret,
+        global_secret,
+        &delivery.id,
+        &payload,
+    )?;
+    let permit = state.delivery_limit.acquire().await;
+    let start = Instant::now();
+    let result = with_transient_retries(
+        state.settings.webhook_transient_retries,
+        TRANSIENT_RETRY_BACKOFF,
+        is_transient_send_error,
+        || {
+            req.try_clone()
+                .expect("webhook request body is buffered")
+                .send()
+        },
+    )
+    .await;
+    let latency = start.elapsed();
+    let latency_ms = latency.as_millis() as i32;
+    drop(permit);
+    if let Some(slot) = slot {
+        slot.release(&state.redis).await;
     }
 
-    let start = Instant::now();
-    let result = req.body(body).send().await;
-    let latency_ms = start.elapsed().as_millis() as i32;
+    let delivered = matches!(&result, Ok(resp) if resp.status().is_success());
+    METRICS.record_delivery(if delivered { "success" } else { "failed" });
+    METRICS.record_delivery_latency_histogram(&channel.id, latency.as_secs_f64());
 
     match result {
         Ok(resp) => {
@@ -149,10 +545,15 @@ async fn deliver_via_webhook(
 
                 db::queries::webhooks::update_success(&state.db, &webhook.id, Utc::now()).await?;
 
-                return Ok(());
+                return Ok(DeliveryOutcome::Delivered);
             }
 
             let error_message = format!("HTTP {}", status_code);
+            let response_body =
+                read_body_snippet(resp, state.settings.webhook_response_body_limit).await;
+            if let Some(body) = response_body.as_deref() {
+                db::queries::deliveries::set_response_body(&state.db, &delivery.id, body).await?;
+            }
             handle_webhook_failure(
                 state,
                 signal,
@@ -163,7 +564,9 @@ async fn deliver_via_webhook(
                 attempt,
                 Some(status_code),
                 &error_message,
+                response_body.as_deref(),
                 latency_ms,
+                channel,
             )
             .await
         }
@@ -178,15 +581,187 @@ async fn deliver_via_webhook(
                 attempt,
                 None,
                 &err.to_string(),
+                None,
                 latency_ms,
+                channel,
             )
             .await
         }
     }
 }
 
-/// Common retry/DLQ handling for failed deliveries.
-/// Returns Ok(true) if sent to DLQ (max retries), Ok(false) if scheduled for retry.
+/// Signed POST of `payload` to the webhook, with Herald's and the webhook's
+/// custom headers. `global_secret` adds the platform-wide signature.
+fn webhook_request(
+    client: &reqwest::Client,
+    webhook: &db::models::Webhook,
+    secret: &str,
+    global_secret: Option<&str>,
+    delivery_id: &str,
+    payload: &serde_json::Value,
+) -> anyhow::Result<reqwest::RequestBuilder> {
+    let body = serde_json::to_string(payload)?;
+    let timestamp = Utc::now().timestamp();
+    let signature = webhook.sign(secret, timestamp, &body);
+
+    let mut req = client
+        .post(&webhook.url)
+        .timeout(webhook.timeout())
+        .header("Content-Type", "application/json")
+        .header("X-Herald-Signature", signature)
+        .header("X-Herald-Timestamp", timestamp.to_string())
+        .header("X-Herald-Delivery-Id", delivery_id)
+        .header(PAYLOAD_VERSION_HEADER, PAYLOAD_SCHEMA_VERSION.to_string());
+    if !webhook.legacy_signature {
+        req = req.header(SIGNATURE_ALGORITHM_HEADER, webhook.signature_algorithm.as_str());
+    }
+
+    if let Some(global_secret) = global_secret {
+        req = req.header(GLOBAL_SIGNATURE_HEADER, webhook.sign(global_secret, timestamp, &body));
+    }
+
+    if let Some(token) = webhook.token.as_deref() {
+        req = req.header("Authorization", format!("Bearer {}", token));
+    }
+    for (name, value) in custom_headers(webhook) {
+        req = req.header(name, value);
+    }
+    Ok(req.body(body))
+}
+
+/// Base pause between in-attempt resends; grows linearly with each resend.
+const TRANSIENT_RETRY_BACKOFF: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// Network failures worth resending straight away. HTTP error responses are
+/// not errors at this layer and never qualify.
+fn is_transient_send_error(err: &reqwest::Error) -> bool {
+    err.is_connect() || err.is_timeout()
+}
+
+/// Run `send`, repeating it up to `retries` more times while it fails with a
+/// transient error. All tries together form one logical delivery attempt.
+async fn with_transient_retries<T, E, F, Fut>(
+    retries: u32,
+    backoff: std::time::Duration,
+    is_transient: impl Fn(&E) -> bool,
+    mut send: F,
+) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T, E>>,
+{
+    let mut retry = 0;
+    loop {
+        match send().await {
+            Err(err) if retry < retries && is_transient(&err) => {
+                retry += 1;
+                tokio::time::sleep(backoff * retry).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Log (but still send) payloads that don't match the webhook's stored schema.
+fn check_payload_schema(
+    state: &WorkerState,
+    webhook: &db::models::Webhook,
+    delivery_id: &str,
+    payload: &serde_json::Value,
+) {
+    let Some(schema) = webhook.payload_schema.as_ref() else {
+        return;
+    };
+    match state.payload_schemas.violations(&webhook.id, schema, payload) {
+        Ok(violations) if violations.is_empty() => {}
+        Ok(violations) => {
+            warn!(
+                webhook_id = %webhook.id,
+                delivery_id = %delivery_id,
+                ?violations,
+                "delivery payload does not match webhook schema"
+            );
+        }
+        Err(err) => {
+            warn!(webhook_id = %webhook.id, error = %err, "stored webhook schema is invalid");
+        }
+    }
+}
+
+/// Subscriber-configured headers for a webhook; empty when none are set.
+fn custom_headers(webhook: &db::models::Webhook) -> Vec<(&str, &str)> {
+    webhook
+        .headers
+        .as_ref()
+        .and_then(|headers| headers.as_object())
+        .map(|object| {
+            object
+                .iter()
+                .filter_map(|(name, value)| Some((name.as_str(), value.as_str()?)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Read at most `limit` bytes of a response body without buffering the rest.
+async fn read_body_snippet(mut resp: reqwest::Response, limit: usize) -> Option<String> {
+    let mut buf = Vec::new();
+    while buf.len() < limit {
+        match resp.chunk().await {
+            Ok(Some(chunk)) => buf.extend_from_slice(&chunk),
+            _ => break,
+        }
+    }
+    if buf.is_empty() {
+        return None;
+    }
+    Some(truncate_utf8(&buf, limit))
+}
+
+/// Truncate to `limit` bytes without splitting a UTF-8 sequence.
+fn truncate_utf8(bytes: &[u8], limit: usize) -> String {
+    let cut = &bytes[..bytes.len().min(limit)];
+    let cut = match std::str::from_utf8(cut) {
+        Err(err) if err.error_len().is_none() => &cut[..err.valid_up_to()],
+        _ => cut,
+    };
+    String::from_utf8_lossy(cut).into_owned()
+}
+
+/// Chronological failure history for a dead-lettered delivery: every earlier
+/// failed attempt for the same subscription, then the attempt that gave up.
+fn build_error_history(
+    deliveries: &[db::models::Delivery],
+    subscription_id: &str,
+    final_delivery_id: &str,
+    final_attempt: serde_json::Value,
+) -> serde_json::Value {
+    let mut failed: Vec<_> = deliveries
+        .iter()
+        .filter(|d| d.subscription_id == subscription_id && d.id != final_delivery_id)
+        .filter(|d| matches!(d.status, DeliveryStatus::Failed))
+        .collect();
+    failed.sort_by_key(|d| d.created_at);
+
+    let mut history: Vec<_> = failed
+        .into_iter()
+        .map(|d| {
+            json!({
+                "attempt": d.attempt,
+                "error": d.error_message,
+                "statusCode": d.status_code,
+                "responseBody": d.response_body,
+                "at": d.created_at,
+            })
+        })
+        .collect();
+    history.push(final_attempt);
+    serde_json::Value::Array(history)
+}
+
+/// Common retry/DLQ handling for failed deliveries: dead-letters the signal
+/// once attempts run out, otherwise queues the next attempt.
+#[allow(clippy::too_many_arguments)]
 async fn schedule_retry_or_dlq(
     state: &WorkerState,
     signal: &db::models::Signal,
@@ -196,14 +771,23 @@ async fn schedule_retry_or_dlq(
     attempt: i32,
     status_code: Option<i32>,
     error_message: &str,
+    response_body: Option<&str>,
     webhook_id: Option<String>,
-) -> anyhow::Result<bool> {
-    if attempt >= 5 {
-        let error_history = json!([{
+    channel: &db::models::Channel,
+) -> anyhow::Result<DeliveryOutcome> {
+    let policy = channel_retry_policy(channel);
+    let policy = policy.as_ref();
+    if attempt + 1 >= max_attempts(policy, &signal.urgency) {
+        let prior = db::queries::deliveries::list_by_signal(&state.db, &signal.id).await?;
+        let final_attempt = json!({
             "attempt": attempt,
             "error": error_message,
             "statusCode": status_code,
-        }]);
+            "responseBody": response_body,
+            "at": Utc::now(),
+        });
+        let error_history =
+            build_error_history(&prior, &subscription.id, delivery_id, final_attempt);
         let dlq_id = format!("dlq_{}", nanoid::nanoid!(12));
         db::queries::dead_letter_queue::create(
             &state.db,
@@ -215,39 +799,56 @@ async fn schedule_retry_or_dlq(
             error_history,
         )
         .await?;
-        return Ok(true);
+        return Ok(DeliveryOutcome::DeadLettered);
     }
 
-    let queue = match signal.urgency {
-        SignalUrgency::High | SignalUrgency::Critical => "delivery-high",
-        _ => "delivery-normal",
-    };
+    let next_job = delivery_job(state, signal, subscription, channel, webhook_id, attempt + 1);
+    let retry = (attempt + 1) as u32;
+    let delay = retry_delay(policy, &signal.urgency, retry, &mut rand::thread_rng());
+    enqueue_after(state, signal.urgency.queue(), next_job, delay);
+
+    Ok(DeliveryOutcome::RetryScheduled)
+}
 
-    let next_job = DeliveryJob {
+/// A delivery job for `attempt` of the signal to the subscription.
+fn delivery_job(
+    state: &WorkerState,
+    signal: &db::models::Signal,
+    subscription: &db::models::Subscription,
+    channel: &db::models::Channel,
+    webhook_id: Option<String>,
+    attempt: i32,
+) -> DeliveryJob {
+    DeliveryJob {
         signal_id: signal.id.clone(),
         subscription_id: subscription.id.clone(),
         webhook_id,
-        attempt: attempt + 1,
-    };
+        attempt,
+        priority: state
+            .settings
+            .delivery_priority
+            .for_signal(&(&signal.urgency).into(), &(&channel.pricing_tier).into()),
+        channel_id: signal.channel_id.clone(),
+    }
+}
 
-    let delay = retry_policy((attempt + 1) as u32);
+/// Push `job` onto `queue` once `delay` has passed, without blocking the caller.
+fn enqueue_after(state: &WorkerState, queue: &str, job: DeliveryJob, delay: std::time::Duration) {
     let storage = state.storage.clone();
-    let delivery_id = delivery_id.to_string();
     let queue = queue.to_string();
     tokio::spawn(async move {
         tokio::time::sleep(delay).await;
-        if let Err(err) = storage.push(&queue, next_job).await {
+        let (signal_id, attempt) = (job.signal_id.clone(), job.attempt);
+        if let Err(err) = storage.push(&queue, job).await {
             warn!(
                 error = %err,
-                %delivery_id,
-                attempt = attempt + 1,
+                %signal_id,
+                attempt,
                 %queue,
-                "failed to enqueue retry delivery job"
+                "failed to enqueue delivery job"
             );
         }
     });
-
-    Ok(false)
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -261,8 +862,10 @@ async fn handle_webhook_failure(
     attempt: i32,
     status_code: Option<i32>,
     error_message: &str,
+    response_body: Option<&str>,
     latency_ms: i32,
-) -> anyhow::Result<()> {
+    channel: &db::models::Channel,
+) -> anyhow::Result<DeliveryOutcome> {
     db::queries::deliveries::update_status(
         &state.db,
         &delivery_id,
@@ -274,7 +877,18 @@ async fn handle_webhook_failure(
     .await?;
 
     db::queries::signals::increment_delivery_counts(&state.db, &signal.id, 0, 1, 1).await?;
-    db::queries::webhooks::update_failure(&state.db, &webhook.id, Utc::now()).await?;
+    let failure_count =
+        db::queries::webhooks::update_failure(&state.db, &webhook.id, Utc::now()).await?;
+
+    if breaker_tripped(failure_count) {
+        db::queries::webhooks::disable(&state.db, &webhook.id).await?;
+        warn!(
+            webhook_id = %webhook.id,
+            failure_count,
+            "webhook disabled after consecutive failures; not retrying"
+        );
+        return Ok(DeliveryOutcome::Undeliverable);
+    }
 
     schedule_retry_or_dlq(
         state,
@@ -285,11 +899,16 @@ async fn handle_webhook_failure(
         attempt,
         status_code,
         error_message,
+        response_body,
         Some(webhook.id.clone()),
+        channel,
     )
-    .await?;
+    .await
+}
 
-    Ok(())
+/// Whether a webhook's consecutive failures warrant disabling it.
+fn breaker_tripped(failure_count: i32) -> bool {
+    failure_count >= WEBHOOK_DISABLE_THRESHOLD
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -298,10 +917,11 @@ async fn deliver_via_tunnel(
     signal: &db::models::Signal,
     subscription: &db::models::Subscription,
     channel: &db::models::Channel,
-    agent: &std::sync::Arc<core::tunnel::AgentConnection>,
+    agents: &[Arc<AgentConnection>],
+    remote: Option<&Presence>,
     attempt: i32,
     allow_retry: bool,
-) -> anyhow::Result<bool> {
+) -> anyhow::Result<Option<DeliveryOutcome>> {
     let delivery_id = format!("del_{}", nanoid::nanoid!(12));
     let delivery = db::queries::deliveries::create(
         &state.db,
@@ -314,6 +934,13 @@ async fn deliver_via_tunnel(
     )
     .await?;
 
+    let seq = db::queries::deliveries::assign_tunnel_seq(
+        &state.db,
+        &delivery.id,
+        &subscription.subscriber_id,
+    )
+    .await?;
+
     let message = ServerMessage::Signal {
         delivery_id: delivery.id.clone(),
         channel_id: channel.id.clone(),
@@ -326,23 +953,59 @@ async fn deliver_via_tunnel(
             metadata: signal.metadata.clone(),
             created_at: signal.created_at,
         },
+        seq: Some(seq as u64),
     };
 
     let payload = build_payload(&delivery.id, subscription.webhook_id.as_deref(), channel, signal);
 
-    if let Err(err) = agent.sender.send(message).await {
-        handle_tunnel_failure(
+    // Agents held by this process take the delivery directly; otherwise it is
+    // relayed to the API instance named in the subscriber's presence.
+    let offered = match remote {
+        Some(owner) => {
+            relay::offer(state, &subscription.subscriber_id, &delivery.id, owner, &message).await
+        }
+        None => state
+            .tunnel_registry
+            .acks
+            .offer(&delivery.id, agents, &message),
+    };
+    let ack = match offered {
+        Ok(ack) => ack,
+        Err(send_error) => {
+            return handle_tunnel_failure(
+                state,
+                signal,
+                subscription,
+                &payload,
+                delivery.id,
+                attempt,
+                send_error,
+                allow_retry,
+                channel,
+            )
+            .await;
+        }
+    };
+
+    let timeout = std::time::Duration::from_secs(state.settings.tunnel_ack_timeout_secs);
+    let outcome = state
+        .tunnel_registry
+        .acks
+        .wait(&delivery.id, ack, timeout)
+        .await;
+    if let Some(error_message) = ack_failure_message(outcome) {
+        return handle_tunnel_failure(
             state,
             signal,
             subscription,
             &payload,
             delivery.id,
             attempt,
-            &err.to_string(),
+            error_message,
             allow_retry,
+            channel,
         )
-        .await?;
-        return Ok(false);
+        .await;
     }
 
     db::queries::deliveries::update_status(
@@ -357,9 +1020,21 @@ async fn deliver_via_tunnel(
 
     db::queries::signals::increment_delivery_counts(&state.db, &signal.id, 1, 0, 1).await?;
 
-    Ok(true)
+    Ok(Some(DeliveryOutcome::Delivered))
 }
 
+/// Error recorded for a tunnel delivery the agent never acknowledged.
+fn ack_failure_message(outcome: AckOutcome) -> Option<&'static str> {
+    match outcome {
+        AckOutcome::Acked => None,
+        AckOutcome::TimedOut => Some("agent did not ack delivery in time"),
+        AckOutcome::Disconnected => Some("agent disconnected before ack"),
+    }
+}
+
+/// Record a failed tunnel attempt. Returns `None` when `allow_retry` is off,
+/// leaving the caller to fall back to the subscriber's webhook.
+#[allow(clippy::too_many_arguments)]
 async fn handle_tunnel_failure(
     state: &WorkerState,
     signal: &db::models::Signal,
@@ -369,7 +1044,8 @@ async fn handle_tunnel_failure(
     attempt: i32,
     error_message: &str,
     allow_retry: bool,
-) -> anyhow::Result<()> {
+    channel: &db::models::Channel,
+) -> anyhow::Result<Option<DeliveryOutcome>> {
     db::queries::deliveries::update_status(
         &state.db,
         &delivery_id,
@@ -383,10 +1059,10 @@ async fn handle_tunnel_failure(
     db::queries::signals::increment_delivery_counts(&state.db, &signal.id, 0, 1, 1).await?;
 
     if !allow_retry {
-        return Ok(());
+        return Ok(None);
     }
 
-    schedule_retry_or_dlq(
+    let outcome = schedule_retry_or_dlq(
         state,
         signal,
         subscription,
@@ -395,66 +1071,77 @@ async fn handle_tunnel_failure(
         attempt,
         None,
         error_message,
+        None,
         subscription.webhook_id.clone(),
+        channel,
     )
     .await?;
 
-    Ok(())
-}
-
-fn build_payload(
-    delivery_id: &str,
-    webhook_id: Option<&str>,
-    channel: &db::models::Channel,
-    signal: &db::models::Signal,
-) -> serde_json::Value {
-    json!({
-        "deliveryId": delivery_id,
-        "webhookId": webhook_id,
-        "channel": {
-            "id": &channel.id,
-            "slug": &channel.slug,
-            "displayName": &channel.display_name,
-        },
-        "signal": {
-            "id": &signal.id,
-            "title": &signal.title,
-            "body": &signal.body,
-            "urgency": &signal.urgency,
-            "metadata": &signal.metadata,
-            "createdAt": &signal.created_at,
-        }
-    })
+    Ok(Some(outcome))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use core::auth::{sign_payload, verify_signature};
+    use rand::rngs::mock::StepRng;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
     use std::time::Duration;
 
+    /// An RNG whose draws are always zero, so backoff comes out unjittered.
+    fn no_jitter() -> StepRng {
+        StepRng::new(0, 0)
+    }
+
+    fn normal_retry(attempt: u32) -> Duration {
+        retry_policy(attempt, &SignalUrgency::Normal, &mut no_jitter())
+    }
+
     #[test]
     fn test_retry_policy_immediate_first_attempt() {
-        assert_eq!(retry_policy(0), Duration::from_secs(0));
+        assert_eq!(normal_retry(0), Duration::from_secs(0));
     }
 
     #[test]
     fn test_retry_policy_one_minute_second_attempt() {
-        assert_eq!(retry_policy(1), Duration::from_secs(60));
+        assert_eq!(normal_retry(1), Duration::from_secs(60));
     }
 
     #[test]
     fn test_retry_policy_exponential_backoff() {
-        assert_eq!(retry_policy(2), Duration::from_secs(300));    // 5 min
-        assert_eq!(retry_policy(3), Duration::from_secs(1800));   // 30 min
-        assert_eq!(retry_policy(4), Duration::from_secs(7200));   // 2 hours
+        assert_eq!(normal_retry(2), Duration::from_secs(300));    // 5 min
+        assert_eq!(normal_retry(3), Duration::from_secs(1800));   // 30 min
+        assert_eq!(normal_retry(4), Duration::from_secs(7200));   // 2 hours
     }
 
     #[test]
     fn test_retry_policy_max_backoff() {
         // After attempt 5, should cap at 6 hours
-        assert_eq!(retry_policy(5), Duration::from_secs(21600));
-        assert_eq!(retry_policy(6), Duration::from_secs(21600));
-        assert_eq!(retry_policy(100), Duration::from_secs(21600));
+        assert_eq!(normal_retry(5), Duration::from_secs(21600));
+        assert_eq!(normal_retry(6), Duration::from_secs(21600));
+        assert_eq!(normal_retry(100), Duration::from_secs(21600));
+    }
+
+    #[test]
+    fn test_retry_policy_jitter_stays_within_half_to_full_base() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let delays: Vec<_> = (0..50)
+            .map(|_| retry_policy(3, &SignalUrgency::Normal, &mut rng))
+            .collect();
+        let window = Duration::from_secs(900)..=Duration::from_secs(1800);
+        assert!(delays.iter().all(|delay| window.contains(delay)));
+        assert!(delays.iter().any(|delay| *delay != delays[0]), "delays should be spread");
+    }
+
+    #[test]
+    fn test_retries_and_requeues_are_not_final() {
+        assert!(DeliveryOutcome::Delivered.is_final());
+        assert!(DeliveryOutcome::DeadLettered.is_final());
+        assert!(DeliveryOutcome::Undeliverable.is_final());
+        assert!(DeliveryOutcome::NoMethod.is_final());
+        assert!(!DeliveryOutcome::RetryScheduled.is_final());
+        assert!(!DeliveryOutcome::Throttled.is_final());
     }
 
     #[test]
@@ -468,39 +1155,15 @@ mod tests {
     #[test]
     fn test_queue_selection_for_urgent_signals() {
         // High and Critical should go to delivery-high queue
-        assert_eq!(
-            match SignalUrgency::High {
-                SignalUrgency::High | SignalUrgency::Critical => "delivery-high",
-                _ => "delivery-normal",
-            },
-            "delivery-high"
-        );
-        assert_eq!(
-            match SignalUrgency::Critical {
-                SignalUrgency::High | SignalUrgency::Critical => "delivery-high",
-                _ => "delivery-normal",
-            },
-            "delivery-high"
-        );
+        assert_eq!(SignalUrgency::High.queue(), "delivery-high");
+        assert_eq!(SignalUrgency::Critical.queue(), "delivery-high");
     }
 
     #[test]
     fn test_queue_selection_for_normal_signals() {
         // Low and Normal should go to delivery-normal queue
-        assert_eq!(
-            match SignalUrgency::Low {
-                SignalUrgency::High | SignalUrgency::Critical => "delivery-high",
-                _ => "delivery-normal",
-            },
-            "delivery-normal"
-        );
-        assert_eq!(
-            match SignalUrgency::Normal {
-                SignalUrgency::High | SignalUrgency::Critical => "delivery-high",
-                _ => "delivery-normal",
-            },
-            "delivery-normal"
-        );
+        assert_eq!(SignalUrgency::Low.queue(), "delivery-normal");
+        assert_eq!(SignalUrgency::Normal.queue(), "delivery-normal");
     }
 
     // ============================================================
@@ -520,6 +1183,9 @@ mod tests {
             is_public: true,
             status: db::models::ChannelStatus::Active,
             signal_count: 0,
+            retry_policy: None,
+            dedup_window_secs: 0,
+            delivery_callback_url: None,
             subscriber_count: 0,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
@@ -538,6 +1204,8 @@ mod tests {
             delivery_count: 0,
             delivered_count: 0,
             failed_count: 0,
+            min_subscriber_tier: None,
+            scheduled_at: None,
             created_at: chrono::Utc::now(),
         }
     }
@@ -655,4 +1323,568 @@ mod tests {
             }
         }
     }
+
+    // ============================================================
+    // Emergency Webhook Routing Tests
+    // ============================================================
+
+    fn make_test_subscription(emergency_webhook_id: Option<&str>) -> db::models::Subscription {
+        db::models::Subscription {
+            id: "sub_test".to_string(),
+            subscriber_id: "sr_test".to_string(),
+            channel_id: "ch_test".to_string(),
+            webhook_id: Some("wh_normal".to_string()),
+            emergency_webhook_id: emergency_webhook_id.map(str::to_string),
+            status: db::models::SubscriptionStatus::Active,
+            min_urgency: SignalUrgency::Low,
+            stripe_subscription_id: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_critical_signal_uses_emergency_webhook() {
+        let subscription = make_test_subscription(Some("wh_emergency"));
+        assert_eq!(
+            select_webhook_id(&subscription, &SignalUrgency::Critical),
+            Some("wh_emergency")
+        );
+    }
+
+    #[test]
+    fn test_non_critical_signals_use_normal_webhook() {
+        let subscription = make_test_subscription(Some("wh_emergency"));
+        for urgency in [SignalUrgency::Low, SignalUrgency::Normal, SignalUrgency::High] {
+            assert_eq!(select_webhook_id(&subscription, &urgency), Some("wh_normal"));
+        }
+    }
+
+    #[test]
+    fn test_critical_signal_without_emergency_webhook_uses_normal() {
+        let subscription = make_test_subscription(None);
+        assert_eq!(
+            select_webhook_id(&subscription, &SignalUrgency::Critical),
+            Some("wh_normal")
+        );
+    }
+
+    #[test]
+    fn test_agent_mode_prefers_tunnel_and_falls_back_to_webhook() {
+        let agent = DeliveryMode::Agent;
+        assert_eq!(
+            delivery_path(&agent, true, Some("wh_1")),
+            DeliveryPath::Tunnel {
+                fallback_webhook: Some("wh_1")
+            }
+        );
+        assert_eq!(
+            delivery_path(&agent, true, None),
+            DeliveryPath::Tunnel {
+                fallback_webhook: None
+            }
+        );
+        assert_eq!(
+            delivery_path(&agent, false, Some("wh_1")),
+            DeliveryPath::Webhook("wh_1")
+        );
+        assert_eq!(delivery_path(&agent, false, None), DeliveryPath::Unavailable);
+    }
+
+    #[test]
+    fn test_webhook_mode_skips_connected_agents() {
+        let webhook = DeliveryMode::Webhook;
+        assert_eq!(
+            delivery_path(&webhook, true, Some("wh_1")),
+            DeliveryPath::Webhook("wh_1")
+        );
+        assert_eq!(
+            delivery_path(&webhook, true, None),
+            DeliveryPath::Tunnel {
+                fallback_webhook: None
+            }
+        );
+        assert_eq!(delivery_path(&webhook, false, None), DeliveryPath::Unavailable);
+    }
+
+    // ============================================================
+    // Response Body Capture Tests
+    // ============================================================
+
+    #[test]
+    fn test_truncate_utf8_caps_length() {
+        assert_eq!(truncate_utf8(b"abcdef", 4), "abcd");
+        assert_eq!(truncate_utf8(b"abc", 10), "abc");
+    }
+
+    #[test]
+    fn test_truncate_utf8_respects_char_boundary() {
+        // "é" is two bytes; cutting after its first byte must drop it entirely.
+        let body = "abé".as_bytes();
+        assert_eq!(truncate_utf8(body, 3), "ab");
+        assert_eq!(truncate_utf8(body, 4), "abé");
+    }
+
+    #[test]
+    fn test_failing_endpoint_body_is_captured_and_truncated() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let body = "x".repeat(5000);
+            tokio::spawn(async move {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 1024];
+                let _ = socket.read(&mut buf).await;
+                let response = format!(
+                    "HTTP/1.1 500 Internal Server Error\r\nContent-Length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            });
+
+            let resp = reqwest::Client::new()
+                .post(format!("http://{addr}/hook"))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(resp.status().as_u16(), 500);
+
+            let snippet = read_body_snippet(resp, 2048).await.unwrap();
+            assert_eq!(snippet.len(), 2048);
+            assert!(snippet.chars().all(|c| c == 'x'));
+        });
+    }
+
+    // ============================================================
+    // Custom Header Tests
+    // ============================================================
+
+    fn make_test_webhook(headers: Option<serde_json::Value>) -> db::models::Webhook {
+        db::models::Webhook {
+            id: "wh_test".to_string(),
+            subscriber_id: "sr_test".to_string(),
+            url: "https://example.com/hook".to_string(),
+            name: "test".to_string(),
+            token: None,
+            headers,
+            payload_schema: None,
+            timeout_ms: 30_000,
+            max_in_flight: 10,
+            max_per_second: None,
+            signature_algorithm: db::models::SignatureAlgorithm::Sha256,
+            legacy_signature: false,
+            status: db::models::WebhookStatus::Active,
+            failure_count: 0,
+            last_success_at: None,
+            last_failure_at: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_webhook_timeout_is_per_webhook_and_clamped() {
+        let mut webhook = make_test_webhook(None);
+        assert_eq!(webhook.timeout(), Duration::from_secs(30));
+
+        webhook.timeout_ms = 2_500;
+        assert_eq!(webhook.timeout(), Duration::from_millis(2_500));
+
+        webhook.timeout_ms = 10;
+        assert_eq!(webhook.timeout(), Duration::from_secs(1));
+        webhook.timeout_ms = 600_000;
+        assert_eq!(webhook.timeout(), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_custom_headers_from_webhook() {
+        let webhook = make_test_webhook(Some(json!({"X-Api-Gateway-Key": "k1"})));
+        assert_eq!(custom_headers(&webhook), vec![("X-Api-Gateway-Key", "k1")]);
+    }
+
+    #[test]
+    fn test_custom_headers_null_is_empty() {
+        assert!(custom_headers(&make_test_webhook(None)).is_empty());
+        assert!(custom_headers(&make_test_webhook(Some(serde_json::Value::Null))).is_empty());
+    }
+
+    // ============================================================
+    // Channel Retry Policy Tests
+    // ============================================================
+
+    #[test]
+    fn test_default_schedule_without_channel_policy() {
+        let normal = SignalUrgency::Normal;
+        let mut rng = no_jitter();
+        assert_eq!(max_attempts(None, &normal), 6);
+        assert_eq!(retry_delay(None, &normal, 1, &mut rng), Duration::from_secs(60));
+        assert_eq!(retry_delay(None, &normal, 5, &mut rng), Duration::from_secs(21600));
+    }
+
+    #[test]
+    fn test_default_schedule_follows_urgency() {
+        assert_eq!(max_attempts(None, &SignalUrgency::Critical), 8);
+        assert_eq!(max_attempts(None, &SignalUrgency::Low), 5);
+
+        let mut rng = no_jitter();
+        let critical: Vec<_> = (1..8)
+            .map(|retry| retry_delay(None, &SignalUrgency::Critical, retry, &mut rng))
+            .collect();
+        assert_eq!(critical[3], Duration::from_secs(3600));
+        assert!(critical.iter().all(|delay| *delay <= Duration::from_secs(3600)));
+
+        let low = retry_delay(None, &SignalUrgency::Low, 5, &mut rng);
+        assert_eq!(low, Duration::from_secs(21600));
+    }
+
+    #[test]
+    fn test_channel_policy_produces_expected_schedule() {
+        let mut channel = make_test_channel("ch_fast", "fast", "Fast");
+        channel.retry_policy = Some(json!({
+            "maxAttempts": 3,
+            "baseDelaySecs": 5,
+            "multiplier": 2.0,
+        }));
+
+        let policy = channel_retry_policy(&channel).unwrap();
+        let critical = SignalUrgency::Critical;
+        assert_eq!(max_attempts(Some(&policy), &critical), 3);
+
+        let schedule: Vec<_> = (1..max_attempts(Some(&policy), &critical) as u32)
+            .map(|retry| retry_delay(Some(&policy), &critical, retry, &mut no_jitter()))
+            .collect();
+        assert_eq!(
+            schedule,
+            vec![Duration::from_secs(5), Duration::from_secs(10)]
+        );
+    }
+
+    #[test]
+    fn test_invalid_stored_policy_falls_back_to_default() {
+        let mut channel = make_test_channel("ch_bad", "bad", "Bad");
+        channel.retry_policy = Some(json!({"maxAttempts": "lots"}));
+        assert!(channel_retry_policy(&channel).is_none());
+    }
+
+    // ============================================================
+    // Tunnel Ack Tests
+    // ============================================================
+
+    fn make_failed_delivery(
+        id: &str,
+        subscription_id: &str,
+        attempt: i32,
+        minutes_ago: i64,
+    ) -> db::models::Delivery {
+        let at = Utc::now() - chrono::Duration::minutes(minutes_ago);
+        db::models::Delivery {
+            id: id.to_string(),
+            signal_id: "sig_test".to_string(),
+            subscription_id: subscription_id.to_string(),
+            webhook_id: Some("wh_test".to_string()),
+            delivery_mode: DeliveryMode::Webhook,
+            attempt,
+            status: DeliveryStatus::Failed,
+            status_code: Some(500 + attempt),
+            error_message: Some(format!("HTTP {}", 500 + attempt)),
+            response_body: None,
+            latency_ms: None,
+            created_at: at,
+            updated_at: at,
+        }
+    }
+
+    #[test]
+    fn test_error_history_accumulates_prior_attempts_in_order() {
+        let mut other_sub = make_failed_delivery("del_x", "sub_other", 0, 50);
+        other_sub.error_message = Some("not ours".to_string());
+        let mut succeeded = make_failed_delivery("del_ok", "sub_test", 9, 45);
+        succeeded.status = DeliveryStatus::Success;
+        // list_by_signal returns newest first.
+        let deliveries = vec![
+            make_failed_delivery("del_4", "sub_test", 4, 1),
+            make_failed_delivery("del_2", "sub_test", 2, 20),
+            succeeded,
+            other_sub,
+            make_failed_delivery("del_3", "sub_test", 3, 10),
+            make_failed_delivery("del_1", "sub_test", 1, 30),
+            make_failed_delivery("del_0", "sub_test", 0, 40),
+        ];
+
+        let history = build_error_history(
+            &deliveries,
+            "sub_test",
+            "del_4",
+            json!({ "attempt": 4, "error": "final" }),
+        );
+        let history = history.as_array().unwrap();
+        let attempts: Vec<_> = history.iter().map(|h| h["attempt"].as_i64().unwrap()).collect();
+        assert_eq!(attempts, vec![0, 1, 2, 3, 4]);
+        assert_eq!(history[0]["error"], "HTTP 500");
+        assert_eq!(history[0]["statusCode"], 500);
+        assert!(history[0]["at"].is_string());
+        assert_eq!(history[4]["error"], "final");
+    }
+
+    #[test]
+    fn test_only_acked_tunnel_delivery_succeeds() {
+        assert_eq!(ack_failure_message(AckOutcome::Acked), None);
+        assert!(ack_failure_message(AckOutcome::TimedOut).is_some());
+        assert!(ack_failure_message(AckOutcome::Disconnected).is_some());
+    }
+
+    // ============================================================
+    // Undeliverable Tests
+    // ============================================================
+
+    fn make_test_subscriber(status: AccountStatus) -> db::models::Subscriber {
+        db::models::Subscriber {
+            id: "sr_test".to_string(),
+            name: "Test".to_string(),
+            email: "test@example.com".to_string(),
+            webhook_secret: "whsec_test".to_string(),
+            previous_webhook_secret: None,
+            previous_webhook_secret_expires_at: None,
+            stripe_customer_id: None,
+            tier: db::models::AccountTier::Free,
+            status,
+            delivery_mode: DeliveryMode::Webhook,
+            agent_last_connected_at: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_deleted_subscriber_is_undeliverable() {
+        assert_eq!(
+            subscriber_permanent_failure(&make_test_subscriber(AccountStatus::Deleted)),
+            Some("subscriber account deleted")
+        );
+        // Suspension can be lifted, so those deliveries still go through the retry path.
+        assert_eq!(
+            subscriber_permanent_failure(&make_test_subscriber(AccountStatus::Suspended)),
+            None
+        );
+        assert_eq!(
+            subscriber_permanent_failure(&make_test_subscriber(AccountStatus::Active)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_disabled_or_private_webhook_is_undeliverable() {
+        let mut webhook = make_test_webhook(None);
+        assert_eq!(webhook_permanent_failure(&webhook, "prod"), None);
+
+        webhook.status = WebhookStatus::Disabled;
+        assert_eq!(
+            webhook_permanent_failure(&webhook, "dev"),
+            Some("webhook disabled")
+        );
+
+        webhook.status = WebhookStatus::Active;
+        webhook.url = "https://169.254.169.254/latest/meta-data".to_string();
+        assert!(webhook_permanent_failure(&webhook, "prod").is_some());
+        assert_eq!(webhook_permanent_failure(&webhook, "dev"), None);
+    }
+
+    #[test]
+    fn test_host_resolving_to_private_address_is_undeliverable() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let reason = private_target_now("https://localhost/hook", "prod")
+                .await
+                .unwrap();
+            assert!(reason.contains("localhost resolves to a private address"));
+            assert_eq!(private_target_now("https://localhost/hook", "dev").await, None);
+            assert_eq!(
+                private_target_now("https://93.184.216.34/hook", "prod").await,
+                None
+            );
+        });
+    }
+
+    #[test]
+    fn test_breaker_trips_at_threshold() {
+        assert!(!breaker_tripped(1));
+        assert!(!breaker_tripped(WEBHOOK_DISABLE_THRESHOLD - 1));
+        assert!(breaker_tripped(WEBHOOK_DISABLE_THRESHOLD));
+        assert!(breaker_tripped(WEBHOOK_DISABLE_THRESHOLD + 5));
+    }
+
+    #[test]
+    fn test_transient_connect_error_is_retried_within_attempt() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            // Bind then drop a listener so the port refuses connections.
+            let addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+                .await
+                .unwrap()
+                .local_addr()
+                .unwrap();
+            let client = reqwest::Client::new();
+            let calls = std::cell::Cell::new(0);
+
+            let start = std::time::Instant::now();
+            let result = with_transient_retries(
+                2,
+                std::time::Duration::from_millis(10),
+                is_transient_send_error,
+                || {
+                    calls.set(calls.get() + 1);
+                    client.post(format!("http://{addr}/hook")).send()
+                },
+            )
+            .await;
+
+            assert!(result.unwrap_err().is_connect());
+            assert_eq!(calls.get(), 3);
+            assert!(start.elapsed() < std::time::Duration::from_secs(2));
+        });
+    }
+
+    #[test]
+    fn test_http_error_response_is_not_retried() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(async move {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 1024];
+                let _ = socket.read(&mut buf).await;
+                let _ = socket
+                    .write_all(b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n")
+                    .await;
+            });
+            let client = reqwest::Client::new();
+            let calls = std::cell::Cell::new(0);
+
+            let resp = with_transient_retries(
+                2,
+                std::time::Duration::from_millis(10),
+                is_transient_send_error,
+                || {
+                    calls.set(calls.get() + 1);
+                    client.post(format!("http://{addr}/hook")).send()
+                },
+            )
+            .await
+            .unwrap();
+
+            assert_eq!(resp.status().as_u16(), 503);
+            assert_eq!(calls.get(), 1);
+        });
+    }
+
+    #[test]
+    fn test_payload_version_matches_header() {
+        let channel = make_test_channel("ch_abc", "tech-news", "Tech News");
+        let signal = make_test_signal("sig_xyz", "Breaking", "Content", SignalUrgency::Normal);
+        let webhook = make_test_webhook(None);
+        let payload = build_payload("del_001", Some(&webhook.id), &channel, &signal);
+
+        let request = webhook_request(
+            &reqwest::Client::new(),
+            &webhook,
+            "whsec_test",
+            None,
+            "del_001",
+            &payload,
+        )
+        .unwrap()
+        .build()
+        .unwrap();
+
+        assert_eq!(payload["schemaVersion"], 1);
+        assert!(request.headers().get(GLOBAL_SIGNATURE_HEADER).is_none());
+        assert_eq!(
+            request.headers()[PAYLOAD_VERSION_HEADER],
+            payload["schemaVersion"].to_string()
+        );
+    }
+
+    #[test]
+    fn test_global_signature_verifies_with_platform_secret() {
+        let channel = make_test_channel("ch_abc", "tech-news", "Tech News");
+        let signal = make_test_signal("sig_xyz", "Breaking", "Content", SignalUrgency::Normal);
+        let webhook = make_test_webhook(None);
+        let payload = build_payload("del_001", Some(&webhook.id), &channel, &signal);
+
+        let request = webhook_request(
+            &reqwest::Client::new(),
+            &webhook,
+            "whsec_test",
+            Some("platform_secret"),
+            "del_001",
+            &payload,
+        )
+        .unwrap()
+        .build()
+        .unwrap();
+
+        let headers = request.headers();
+        let timestamp: i64 = headers["X-Herald-Timestamp"].to_str().unwrap().parse().unwrap();
+        let body = std::str::from_utf8(request.body().unwrap().as_bytes().unwrap()).unwrap();
+        let global = headers[GLOBAL_SIGNATURE_HEADER].to_str().unwrap();
+
+        assert!(verify_signature("platform_secret", timestamp, body, global));
+        assert_ne!(global, headers["X-Herald-Signature"].to_str().unwrap());
+    }
+
+    #[test]
+    fn test_signature_follows_webhook_scheme() {
+        let channel = make_test_channel("ch_abc", "tech-news", "Tech News");
+        let signal = make_test_signal("sig_xyz", "Breaking", "Content", SignalUrgency::Normal);
+        let mut webhook = make_test_webhook(None);
+        let payload = build_payload("del_001", Some(&webhook.id), &channel, &signal);
+        let sign = |webhook: &db::models::Webhook| {
+            let client = reqwest::Client::new();
+            webhook_request(&client, webhook, "whsec_test", None, "del_001", &payload)
+                .unwrap()
+                .build()
+                .unwrap()
+        };
+
+        webhook.signature_algorithm = db::models::SignatureAlgorithm::Sha512;
+        let request = sign(&webhook);
+        let headers = request.headers();
+        let timestamp: i64 = headers["X-Herald-Timestamp"].to_str().unwrap().parse().unwrap();
+        let body = std::str::from_utf8(request.body().unwrap().as_bytes().unwrap()).unwrap();
+        let signature = headers["X-Herald-Signature"].to_str().unwrap();
+        assert!(signature.starts_with(&format!("t={timestamp},v1=")));
+        assert_eq!(headers[SIGNATURE_ALGORITHM_HEADER], "sha512");
+        assert!(verify_signature("whsec_test", timestamp, body, signature));
+
+        webhook.signature_algorithm = db::models::SignatureAlgorithm::Sha256;
+        webhook.legacy_signature = true;
+        let request = sign(&webhook);
+        let headers = request.headers();
+        let timestamp: i64 = headers["X-Herald-Timestamp"].to_str().unwrap().parse().unwrap();
+        let body = std::str::from_utf8(request.body().unwrap().as_bytes().unwrap()).unwrap();
+        assert_eq!(
+            headers["X-Herald-Signature"].to_str().unwrap(),
+            sign_payload("whsec_test", timestamp, body)
+        );
+        assert!(headers.get(SIGNATURE_ALGORITHM_HEADER).is_none());
+    }
 }
@@ -0,0 +1,380 @@
+//! Fair job pickup across tenants.
+//!
+//! Jobs handed to the worker are parked per channel and dispatched
+//! round-robin, so one channel's fan-out to thousands of subscriptions takes
+//! a single turn in the rotation and cannot occupy every delivery slot while
+//! other channels wait behind it.
+
+use core::metrics::METRICS;
+use core::types::DeliveryJob;
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{oneshot, Notify, Semaphore};
+use tracing::{debug, info, warn};
+
+use super::delivery::DeliveryOutcome;
+use crate::WorkerState;
+
+/// How often per-channel in-flight counts are logged.
+const IN_FLIGHT_REPORT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often [`FairDispatcher::drain`] re-checks for outstanding jobs.
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Per-tenant FIFO queues served in round-robin order.
+#[derive(Debug)]
+pub struct FairQueue<K, T> {
+    queues: HashMap<K, VecDeque<T>>,
+    order: VecDeque<K>,
+}
+
+impl<K: Eq + Hash + Clone, T> Default for FairQueue<K, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Eq + Hash + Clone, T> FairQueue<K, T> {
+    pub fn new() -> Self {
+        Self {
+            queues: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, tenant: K, item: T) {
+        let queue = self.queues.entry(tenant.clone()).or_default();
+        if queue.is_empty() {
+            self.order.push_back(tenant);
+        }
+        queue.push_back(item);
+    }
+
+    /// Take the next job from the tenant at the front of the rotation.
+    pub fn pop(&mut self) -> Option<(K, T)> {
+        let tenant = self.order.pop_front()?;
+        let queue = self.queues.get_mut(&tenant)?;
+        let item = queue.pop_front()?;
+        if queue.is_empty() {
+            self.queues.remove(&tenant);
+        } else {
+            self.order.push_back(tenant.clone());
+        }
+        Some((tenant, item))
+    }
+
+    pub fn len(&self) -> usize {
+        self.queues.values().map(VecDeque::len).sum()
+    }
+}
+
+type PendingJob = (DeliveryJob, oneshot::Sender<anyhow::Result<()>>);
+
+/// Bounded dispatcher that pulls delivery jobs fairly across channels.
+pub struct FairDispatcher {
+    queue: Mutex<FairQueue<String, PendingJob>>,
+    ready: Notify,
+    slots: Arc<Semaphore>,
+    in_flight: Mutex<HashMap<String, usize>>,
+}
+
+impl FairDispatcher {
+    pub fn new(concurrency: usize) -> Self {
+        Self {
+            queue: Mutex::new(FairQueue::new()),
+            ready: Notify::new(),
+            slots: Arc::new(Semaphore::new(concurrency.max(1))),
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Queue a job and wait for the dispatcher to run it.
+    pub async fn submit(&self, job: DeliveryJob) -> anyhow::Result<()> {
+        let (tx, rx) = oneshot::channel();
+        let tenant = tenant(&job);
+        self.queue.lock().unwrap().push(tenant, (job, tx));
+        self.ready.notify_one();
+        rx.await
+            .map_err(|_| anyhow::anyhow!("dispatcher dropped delivery job"))?
+    }
+
+    /// In-flight delivery count per channel.
+    pub fn in_flight_snapshot(&self) -> HashMap<String, usize> {
+        self.in_flight.lock().unwrap().clone()
+    }
+
+    /// True when nothing is queued and no delivery is running.
+    fn is_idle(&self) -> bool {
+        let queue = self.queue.lock().unwrap();
+        queue.len() == 0 && self.in_flight.lock().unwrap().is_empty()
+    }
+
+    /// Wait for every accepted job, queued or running, to finish. Used on
+    /// shutdown once the monitor has stopped handing out new jobs.
+    pub async fn drain(&self) {
+        while !self.is_idle() {
+            tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+        }
+    }
+
+    fn start(&self, tenant: &str) -> usize {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        let count = in_flight.entry(tenant.to_string()).or_insert(0);
+        *count += 1;
+        *count
+    }
+
+    fn finish(&self, tenant: &str) {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if let Some(count) = in_flight.get_mut(tenant) {
+            *count -= 1;
+            if *count == 0 {
+                in_flight.remove(tenant);
+            }
+        }
+    }
+
+    /// Periodically log the per-channel in-flight gauge; `/metrics` exports
+    /// the same counts.
+    pub async fn report_in_flight(self: Arc<Self>) {
+        let mut interval = tokio::time::interval(IN_FLIGHT_REPORT_INTERVAL);
+        loop {
+            interval.tick().await;
+            let snapshot = self.in_flight_snapshot();
+            let queued = self.queue.lock().unwrap().len();
+            for (channel_id, in_flight) in &snapshot {
+                info!(%channel_id, in_flight, "delivery_in_flight");
+            }
+            debug!(tenants = snapshot.len(), queued, "fair dispatcher status");
+        }
+    }
+
+    /// Dispatch queued jobs as delivery slots free up.
+    pub async fn run(self: Arc<Self>, state: WorkerState) {
+        loop {
+            let permit = match self.slots.clone().acquire_owned().await {
+                Ok(permit) => permit,
+                Err(_) => return,
+            };
+            let (next, in_flight) = loop {
+                let notified = self.ready.notified();
+                if let Some(next) = self.queue.lock().unwrap().pop() {
+                    // Counted while the queue is still locked, so `drain`
+                    // can't catch the job between the two.
+                    let in_flight = self.start(&next.0);
+                    break (next, in_flight);
+                }
+                notified.await;
+            };
+
+            let (tenant, (job, reply)) = next;
+            debug!(channel_id = %tenant, in_flight, "dispatching delivery job");
+
+            let dispatcher = self.clone();
+            let state = state.clone();
+            tokio::spawn(async move {
+                let signal_id = job.signal_id.clone();
+                let subscription_id = job.subscription_id.clone();
+                let result = super::delivery::handle_delivery_job(&state, job).await;
+                match &result {
+                    Ok(outcome) => {
+                        METRICS.record_delivery_outcome(outcome.as_str());
+                        info!(
+                            %signal_id,
+                            %subscription_id,
+                            outcome = outcome.as_str(),
+                            "delivery job finished"
+                        );
+                    }
+                    Err(err) => warn!(
+                        %signal_id,
+                        %subscription_id,
+                        error = %err,
+                        "delivery job failed"
+                    ),
+                }
+                if settles(&result) {
+                    super::receipts::settle(&state, &signal_id, 1).await;
+                }
+                let result = result.map(|_| ());
+                dispatcher.finish(&tenant);
+                drop(permit);
+                let _ = reply.send(result);
+            });
+        }
+    }
+}
+
+/// Whether a finished job is its subscription's last word on the signal.
+/// A job that errored never queues another attempt, so it settles as
+/// failed; otherwise the signal could never complete and its receipt would
+/// never be sent.
+fn settles(result: &anyhow::Result<DeliveryOutcome>) -> bool {
+    result.as_ref().map_or(true, DeliveryOutcome::is_final)
+}
+
+/// The tenant a job is queued under: its channel. Jobs queued without one
+/// fall back to their signal, which still keeps a broadcast to one turn.
+fn tenant(job: &DeliveryJob) -> String {
+    if job.channel_id.is_empty() {
+        job.signal_id.clone()
+    } else {
+        job.channel_id.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tenants_interleave() {
+        let mut queue = FairQueue::new();
+        for job in ["a1", "a2", "a3", "a4"] {
+            queue.push("tenant-a", job);
+        }
+        queue.push("tenant-b", "b1");
+        queue.push("tenant-b", "b2");
+
+        let order: Vec<_> = std::iter::from_fn(|| queue.pop().map(|(_, job)| job)).collect();
+        assert_eq!(order, vec!["a1", "b1", "a2", "b2", "a3", "a4"]);
+    }
+
+    #[test]
+    fn test_tenant_rejoins_rotation_after_draining() {
+        let mut queue = FairQueue::new();
+        queue.push("tenant-a", 1);
+        assert_eq!(queue.pop(), Some(("tenant-a", 1)));
+        assert_eq!(queue.pop(), None);
+
+        queue.push("tenant-b", 2);
+        queue.push("tenant-a", 3);
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.pop(), Some(("tenant-b", 2)));
+        assert_eq!(queue.pop(), Some(("tenant-a", 3)));
+    }
+
+    #[test]
+    fn test_broadcast_is_one_tenant() {
+        let job = |signal_id: &str, subscription_id: &str, channel_id: &str| DeliveryJob {
+            signal_id: signal_id.to_string(),
+            subscription_id: subscription_id.to_string(),
+            webhook_id: None,
+            attempt: 0,
+            priority: 0,
+            channel_id: channel_id.to_string(),
+        };
+
+        let mut queue = FairQueue::new();
+        for n in 0..3 {
+            let job = job("sig_big", &format!("sub_{n}"), "ch_big");
+            queue.push(tenant(&job), job);
+        }
+        let small = job("sig_small", "sub_x", "ch_small");
+        queue.push(tenant(&small), small);
+
+        let order: Vec<_> = std::iter::from_fn(|| queue.pop().map(|(tenant, _)| tenant)).collect();
+        assert_eq!(order, vec!["ch_big", "ch_small", "ch_big", "ch_big"]);
+
+        assert_eq!(tenant(&job("sig_old", "sub_1", "")), "sig_old");
+    }
+
+    #[test]
+    fn test_undeliverable_subscription_still_completes_the_receipt() {
+        let mut settlement = db::models::SignalSettlement {
+            signal_id: "sig_1".to_string(),
+            delivered_count: 0,
+            expected_deliveries: Some(3),
+            settled_deliveries: 0,
+            callback_url: Some("https://example.com/receipts".to_string()),
+        };
+        let results: [anyhow::Result<DeliveryOutcome>; 4] = [
+            Ok(DeliveryOutcome::Delivered),
+            Ok(DeliveryOutcome::RetryScheduled),
+            Ok(DeliveryOutcome::Undeliverable),
+            Err(anyhow::anyhow!("webhook not found")),
+        ];
+
+        let mut completed = Vec::new();
+        for result in &results {
+            if !settles(result) {
+                continue;
+            }
+            if matches!(result, Ok(DeliveryOutcome::Delivered)) {
+                settlement.delivered_count += 1;
+            }
+            settlement.settled_deliveries += 1;
+            completed.push(settlement.completed_by(1));
+        }
+
+        assert_eq!(completed, vec![false, false, true]);
+        let receipt = settlement.receipt();
+        assert_eq!(
+            (receipt.delivered, receipt.failed, receipt.total),
+            (1, 2, 3)
+        );
+    }
+
+    #[test]
+    fn test_signal_with_only_an_undeliverable_target_gets_a_receipt() {
+        for outcome in [DeliveryOutcome::Undeliverable, DeliveryOutcome::NoMethod] {
+            let result = Ok(outcome);
+            assert!(settles(&result));
+            let settlement = db::models::SignalSettlement {
+                signal_id: "sig_1".to_string(),
+                delivered_count: 0,
+                expected_deliveries: Some(1),
+                settled_deliveries: 1,
+                callback_url: Some("https://example.com/receipts".to_string()),
+            };
+            assert!(settlement.completed_by(1));
+            let receipt = settlement.receipt();
+            assert_eq!(
+                (receipt.delivered, receipt.failed, receipt.total),
+                (0, 1, 1)
+            );
+        }
+    }
+
+    #[test]
+    fn test_in_flight_tracking() {
+        let dispatcher = FairDispatcher::new(2);
+        dispatcher.start("ch_a");
+        dispatcher.start("ch_a");
+        dispatcher.start("ch_b");
+        dispatcher.finish("ch_a");
+        dispatcher.finish("ch_b");
+
+        let snapshot = dispatcher.in_flight_snapshot();
+        assert_eq!(snapshot.get("ch_a"), Some(&1));
+        assert_eq!(snapshot.get("ch_b"), None);
+    }
+
+    #[test]
+    fn test_drain_waits_for_in_flight_jobs() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let dispatcher = Arc::new(FairDispatcher::new(1));
+            dispatcher.drain().await;
+
+            dispatcher.start("ch_a");
+            let drain = tokio::spawn({
+                let dispatcher = dispatcher.clone();
+                async move { dispatcher.drain().await }
+            });
+            tokio::time::sleep(DRAIN_POLL_INTERVAL * 2).await;
+            assert!(!drain.is_finished());
+
+            dispatcher.finish("ch_a");
+            tokio::time::timeout(Duration::from_secs(1), drain)
+                .await
+                .expect("drain should finish once idle")
+                .unwrap();
+        });
+    }
+}
@@ -0,0 +1,107 @@
+//! Global cap on simultaneous outbound webhook requests.
+//!
+//! Sends past the cap wait for a slot instead of failing, so a burst slows
+//! deliveries down rather than exhausting sockets or memory.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::info;
+
+/// How often the in-flight gauge is reported.
+const INFLIGHT_REPORT_INTERVAL: Duration = Duration::from_secs(60);
+
+pub struct DeliveryLimiter {
+    slots: Arc<Semaphore>,
+    in_flight: AtomicUsize,
+}
+
+/// Held for the duration of one outbound send.
+pub struct DeliveryPermit<'a> {
+    limiter: &'a DeliveryLimiter,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl DeliveryLimiter {
+    pub fn new(max_concurrency: usize) -> Self {
+        Self {
+            slots: Arc::new(Semaphore::new(max_concurrency.max(1))),
+            in_flight: AtomicUsize::new(0),
+        }
+    }
+
+    /// Wait for a free delivery slot.
+    pub async fn acquire(&self) -> DeliveryPermit<'_> {
+        let permit = self
+            .slots
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("delivery semaphore is never closed");
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        DeliveryPermit {
+            limiter: self,
+            _permit: permit,
+        }
+    }
+
+    /// Outbound deliveries currently holding a slot.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+
+    /// Periodically log the `herald_delivery_inflight_total` gauge.
+    pub async fn report_in_flight(self: Arc<Self>) {
+        let mut interval = tokio::time::interval(INFLIGHT_REPORT_INTERVAL);
+        loop {
+            interval.tick().await;
+            let in_flight = self.in_flight();
+            let available = self.slots.available_permits();
+            info!(in_flight, available, "herald_delivery_inflight_total");
+        }
+    }
+}
+
+impl Drop for DeliveryPermit<'_> {
+    fn drop(&mut self) {
+        self.limiter.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_concurrency_never_exceeds_cap() {
+        let rt = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(4)
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let limiter = Arc::new(DeliveryLimiter::new(3));
+            let peak = Arc::new(AtomicUsize::new(0));
+
+            let tasks: Vec<_> = (0..40)
+                .map(|_| {
+                    let limiter = limiter.clone();
+                    let peak = peak.clone();
+                    tokio::spawn(async move {
+                        let _permit = limiter.acquire().await;
+                        peak.fetch_max(limiter.in_flight(), Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(2)).await;
+                    })
+                })
+                .collect();
+            for task in tasks {
+                task.await.unwrap();
+            }
+
+            let peak = peak.load(Ordering::SeqCst);
+            assert!((1..=3).contains(&peak), "peak in-flight was {peak}");
+            assert_eq!(limiter.in_flight(), 0);
+        });
+    }
+}
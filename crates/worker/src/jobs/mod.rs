@@ -1,2 +1,9 @@
+pub mod cleanup;
 pub mod delivery;
+pub mod fairness;
+pub mod limits;
+pub mod receipts;
+pub mod relay;
+pub mod scheduler;
 pub mod stats;
+pub mod throttle;
@@ -0,0 +1,34 @@
+//! Settling delivery jobs and reporting delivery receipts to publishers.
+
+use core::receipts::{self, DeliveryReceipt, RECEIPT_RETRY_DELAYS};
+use tracing::warn;
+
+use crate::WorkerState;
+
+/// Count `settled` of the signal's deliveries as final and, if they were the
+/// last, send the channel's delivery receipt in the background.
+pub async fn settle(state: &WorkerState, signal_id: &str, settled: i32) {
+    let settlement =
+        match db::queries::signals::settle_deliveries(&state.db, signal_id, settled).await {
+            Ok(settlement) => settlement,
+            Err(err) => {
+                warn!(signal_id = %signal_id, error = %err, "failed to settle delivery");
+                return;
+            }
+        };
+    let Some(settlement) = settlement else {
+        return;
+    };
+    if let Some(url) = settlement.callback_url.clone() {
+        spawn_send(state, url, settlement.receipt());
+    }
+}
+
+/// Send `receipt` to the channel's callback `url` in the background.
+pub fn spawn_send(state: &WorkerState, url: String, receipt: DeliveryReceipt) {
+    let client = state.client.clone();
+    let secret = state.settings.hmac_secret.clone();
+    tokio::spawn(async move {
+        receipts::send(&client, &url, &secret, &receipt, &RECEIPT_RETRY_DELAYS).await;
+    });
+}
@@ -0,0 +1,106 @@
+//! Tunnel deliveries to agents connected to an API instance.
+//!
+//! Agents connect to the API, not the worker, so the worker finds the owning
+//! instance through the agent's Redis presence, publishes the delivery on that
+//! instance's relay channel, and resolves the tracked ack when the instance
+//! replies on this worker's reply channel. See `core::presence`.
+
+use core::presence::{self, Presence, RelayReply, RelayRequest};
+use core::tunnel::{AckOutcome, ServerMessage};
+use futures_util::StreamExt;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tracing::{info, warn};
+
+use crate::WorkerState;
+
+/// Pause before resubscribing after the reply listener loses Redis.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// The API instance holding the subscriber's agent, if any. A Redis failure
+/// is treated as no agent so the delivery can still go out by webhook.
+pub async fn owner(state: &WorkerState, subscriber_id: &str) -> Option<Presence> {
+    match presence::lookup(&state.redis, subscriber_id).await {
+        Ok(owner) => owner,
+        Err(err) => {
+            warn!(
+                subscriber_id = %subscriber_id,
+                error = %err,
+                "agent presence lookup failed"
+            );
+            None
+        }
+    }
+}
+
+/// Send `message` to the instance in `owner` and track its ack. Fails when no
+/// instance is listening on the relay channel, as after a crash that left the
+/// presence entry to expire.
+pub async fn offer(
+    state: &WorkerState,
+    subscriber_id: &str,
+    delivery_id: &str,
+    owner: &Presence,
+    message: &ServerMessage,
+) -> Result<oneshot::Receiver<AckOutcome>, &'static str> {
+    let acks = &state.tunnel_registry.acks;
+    let ack = acks.track(delivery_id, &format!("relay:{}", owner.instance_id));
+    let request = RelayRequest {
+        subscriber_id: subscriber_id.to_string(),
+        delivery_id: delivery_id.to_string(),
+        reply_to: state.instance_id.clone(),
+        message: message.clone(),
+    };
+    let channel = presence::relay_channel(&owner.instance_id);
+    match presence::publish(&state.redis, &channel, &request).await {
+        Ok(receivers) if receivers > 0 => Ok(ack),
+        Ok(_) => {
+            acks.forget(delivery_id);
+            Err("agent instance unreachable")
+        }
+        Err(err) => {
+            acks.forget(delivery_id);
+            warn!(
+                delivery_id = %delivery_id,
+                instance_id = %owner.instance_id,
+                error = %err,
+                "failed to relay tunnel delivery"
+            );
+            Err("agent instance unreachable")
+        }
+    }
+}
+
+/// Resolve relayed deliveries as their outcomes arrive, resubscribing
+/// whenever the Redis connection drops.
+pub async fn listen_for_replies(state: WorkerState) {
+    loop {
+        if let Err(err) = receive_replies(&state).await {
+            warn!(error = %err, "tunnel relay reply listener failed; resubscribing");
+        }
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+async fn receive_replies(state: &WorkerState) -> redis::RedisResult<()> {
+    let mut pubsub = state.redis.get_async_pubsub().await?;
+    pubsub
+        .subscribe(presence::reply_channel(&state.instance_id))
+        .await?;
+    info!(instance_id = %state.instance_id, "listening for tunnel relay replies");
+
+    let mut messages = pubsub.on_message();
+    while let Some(msg) = messages.next().await {
+        let payload: String = msg.get_payload()?;
+        match serde_json::from_str::<RelayReply>(&payload) {
+            Ok(reply) => {
+                state
+                    .tunnel_registry
+                    .acks
+                    .resolve(&reply.delivery_id, reply.outcome);
+            }
+            Err(err) => warn!(error = %err, "invalid tunnel relay reply"),
+        }
+    }
+    Ok(())
+}
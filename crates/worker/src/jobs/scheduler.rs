@@ -0,0 +1,222 @@
+//! Fan-out for signals published with a future `sendAt`.
+
+use chrono::Utc;
+use core::{metrics::METRICS, receipts::DeliveryReceipt, types::DeliveryJob};
+use db::models::{deliverable_subscriptions, delivery_jobs, Signal};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use super::receipts;
+use crate::WorkerState;
+
+/// How often due scheduled signals are picked up.
+const SCHEDULE_POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Signals claimed per poll.
+const SCHEDULE_BATCH_SIZE: usize = 100;
+
+/// Periodically activate scheduled signals that have come due and enqueue their deliveries.
+pub async fn dispatch_scheduled_signals(state: WorkerState) {
+    let mut interval = tokio::time::interval(SCHEDULE_POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+        // Signals whose fan-out failed this poll; they stay scheduled and are
+        // retried on the next one instead of blocking the rest of the batch.
+        let mut failed = Vec::new();
+        for _ in 0..SCHEDULE_BATCH_SIZE {
+            match dispatch_next(&state, &failed).await {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err((Some(signal_id), err)) => {
+                    warn!(signal_id = %signal_id, error = %err, "scheduled signal fan-out failed");
+                    failed.push(signal_id);
+                }
+                Err((None, err)) => {
+                    warn!(error = %err, "failed to claim scheduled signal");
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Claim one due signal and fan it out. Returns `false` once none are due.
+///
+/// The claim, the fan-out count, and the switch to `active` commit together,
+/// so a signal whose fan-out fails before its jobs are queued stays
+/// scheduled. On error the failed signal's id is returned, if one was claimed.
+async fn dispatch_next(
+    state: &WorkerState,
+    skip: &[String],
+) -> Result<bool, (Option<String>, anyhow::Error)> {
+    let unclaimed = |err: sqlx::Error| (None, err.into());
+    let mut tx = state.db.begin().await.map_err(unclaimed)?;
+    let Some(signal) = db::queries::signals::claim_next_due_scheduled(&mut *tx, Utc::now(), skip)
+        .await
+        .map_err(unclaimed)?
+    else {
+        return Ok(false);
+    };
+
+    let planned = plan_fan_out(state, &mut tx, &signal).await;
+    let FanOut {
+        queue,
+        jobs,
+        skipped,
+        callback_url,
+    } = match planned {
+        Ok(planned) => planned,
+        Err(err) => {
+            let _ = tx.rollback().await;
+            return Err((Some(signal.id), err));
+        }
+    };
+    tx.commit()
+        .await
+        .map_err(|err| (Some(signal.id.clone()), err.into()))?;
+
+    // Nothing to deliver means nothing will ever settle; report the empty
+    // receipt now.
+    if jobs.is_empty() {
+        if let Some(url) = callback_url {
+            receipts::spawn_send(state, url, DeliveryReceipt::new(&signal.id, 0, 0));
+        }
+    }
+
+    // The signal is active now; a job that fails to enqueue is logged and
+    // settled as failed rather than dropping the jobs after it.
+    let enqueued = jobs.len();
+    let mut unqueued = 0;
+    for job in jobs {
+        let subscription_id = job.subscription_id.clone();
+        if let Err(err) = state.storage.push(queue, job).await {
+            warn!(
+                signal_id = %signal.id,
+                subscription_id = %subscription_id,
+                error = %err,
+                "failed to enqueue delivery job"
+            );
+            METRICS.record_delivery("enqueue_failed");
+            unqueued += 1;
+        }
+    }
+    if unqueued > 0 {
+        receipts::settle(state, &signal.id, unqueued).await;
+    }
+
+    info!(
+        signal_id = %signal.id,
+        enqueued = enqueued - unqueued as usize,
+        unqueued,
+        skipped,
+        "scheduled signal dispatched"
+    );
+    Ok(true)
+}
+
+/// A claimed signal's delivery jobs, ready to enqueue once the claim commits.
+struct FanOut {
+    queue: &'static str,
+    jobs: Vec<DeliveryJob>,
+    /// Subscriptions left out by the signal's tier gate.
+    skipped: usize,
+    callback_url: Option<String>,
+}
+
+/// Build the signal's delivery jobs and record their count in `tx`.
+async fn plan_fan_out(
+    state: &WorkerState,
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    signal: &Signal,
+) -> anyhow::Result<FanOut> {
+    let targets =
+        db::queries::subscriptions::list_active_targets_by_channel(&state.db, &signal.channel_id)
+            .await?;
+    let (deliverable, skipped) = deliverable_subscriptions(
+        targets,
+        signal.min_subscriber_tier.as_ref(),
+        &signal.urgency,
+    );
+
+    let channel = db::queries::channels::get_by_id(&state.db, &signal.channel_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("channel {} not found", signal.channel_id))?;
+    let priority = state
+        .settings
+        .delivery_priority
+        .for_signal(&(&signal.urgency).into(), &(&channel.pricing_tier).into());
+
+    let jobs = delivery_jobs(&signal.id, priority, deliverable);
+    db::queries::signals::set_expected_deliveries(&mut **tx, &signal.id, jobs.len() as i32).await?;
+    Ok(FanOut {
+        queue: signal.urgency.queue(),
+        jobs,
+        skipped,
+        callback_url: channel.delivery_callback_url,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use db::models::{
+        AccountTier, SignalStatus, SignalUrgency, Subscription, SubscriptionStatus,
+        SubscriptionTarget,
+    };
+
+    fn target(id: &str, tier: AccountTier) -> SubscriptionTarget {
+        SubscriptionTarget {
+            subscription: Subscription {
+                id: id.to_string(),
+                subscriber_id: format!("sr_{id}"),
+                channel_id: "ch_test".to_string(),
+                webhook_id: Some(format!("wh_{id}")),
+                emergency_webhook_id: None,
+                status: SubscriptionStatus::Active,
+                min_urgency: SignalUrgency::Low,
+                stripe_subscription_id: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            },
+            subscriber_tier: tier,
+        }
+    }
+
+    #[test]
+    fn test_due_signal_fans_out_to_eligible_subscriptions() {
+        let signal = Signal {
+            id: "sig_sched".to_string(),
+            channel_id: "ch_test".to_string(),
+            title: "Launch".to_string(),
+            body: "Now live".to_string(),
+            urgency: SignalUrgency::High,
+            metadata: serde_json::json!({}),
+            delivery_count: 0,
+            delivered_count: 0,
+            failed_count: 0,
+            status: SignalStatus::Active,
+            min_subscriber_tier: Some(AccountTier::Pro),
+            scheduled_at: Some(Utc::now()),
+            created_at: Utc::now(),
+        };
+        let targets = vec![
+            target("sub_free", AccountTier::Free),
+            target("sub_pro", AccountTier::Pro),
+        ];
+
+        let (deliverable, skipped) = deliverable_subscriptions(
+            targets,
+            signal.min_subscriber_tier.as_ref(),
+            &signal.urgency,
+        );
+        let jobs = delivery_jobs(&signal.id, 200, deliverable);
+
+        assert_eq!(skipped, 1);
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].subscription_id, "sub_pro");
+        assert_eq!(jobs[0].webhook_id.as_deref(), Some("wh_sub_pro"));
+        assert_eq!(jobs[0].attempt, 0);
+        assert_eq!(jobs[0].priority, 200);
+        assert_eq!(signal.urgency.queue(), "delivery-high");
+    }
+}
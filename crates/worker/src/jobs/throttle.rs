@@ -0,0 +1,166 @@
+//! Per-webhook cap on concurrent and per-second deliveries.
+//!
+//! Slots live in Redis so the cap holds across every worker process. Each slot
+//! is a lease that expires on its own, so a worker that dies mid-send cannot
+//! wedge an endpoint. If Redis is unreachable deliveries go out unthrottled
+//! rather than stalling.
+
+use std::time::Duration;
+use tracing::warn;
+
+/// How long a throttled job waits before it is queued again.
+pub const THROTTLE_REQUEUE_DELAY: Duration = Duration::from_secs(1);
+
+/// Slack added to a slot lease beyond the longest the send can take.
+const SLOT_LEASE_MARGIN: Duration = Duration::from_secs(5);
+
+/// The result of asking for a delivery slot.
+pub enum Admission {
+    /// A slot was taken; release it once the request finishes.
+    Admitted(WebhookSlot),
+    /// Redis could not be reached, so the cap was not checked.
+    Unchecked,
+    /// The endpoint is at its in-flight or per-second cap.
+    Throttled,
+}
+
+/// One in-flight delivery counted against a webhook's cap.
+pub struct WebhookSlot {
+    key: String,
+    token: String,
+}
+
+/// How long a slot is held before Redis reclaims it: every send attempt
+/// timing out, plus a margin.
+pub fn slot_lease(timeout: Duration, send_attempts: u32) -> Duration {
+    timeout * send_attempts.max(1) + SLOT_LEASE_MARGIN
+}
+
+fn in_flight_key(webhook_id: &str) -> String {
+    format!("wh:inflight:{}", webhook_id)
+}
+
+fn rate_key(webhook_id: &str, now_ms: u64) -> String {
+    format!("wh:rate:{}:{}", webhook_id, now_ms / 1000)
+}
+
+/// Try to take a delivery slot for `webhook`.
+pub async fn acquire(
+    redis: &redis::Client,
+    webhook: &db::models::Webhook,
+    lease: Duration,
+) -> Admission {
+    match try_acquire(redis, webhook, lease).await {
+        Ok(Some(slot)) => Admission::Admitted(slot),
+        Ok(None) => Admission::Throttled,
+        Err(err) => {
+            warn!(
+                webhook_id = %webhook.id,
+                error = %err,
+                "webhook throttle unavailable; sending unchecked"
+            );
+            Admission::Unchecked
+        }
+    }
+}
+
+async fn try_acquire(
+    redis: &redis::Client,
+    webhook: &db::models::Webhook,
+    lease: Duration,
+) -> redis::RedisResult<Option<WebhookSlot>> {
+    let mut conn = redis.get_multiplexed_async_connection().await?;
+    let now_ms = unix_now_ms();
+    let key = in_flight_key(&webhook.id);
+    let token = nanoid::nanoid!(12);
+
+    let script = r#"
+local inflight = KEYS[1]
+local rate = KEYS[2]
+local now = tonumber(ARGV[1])
+local lease = tonumber(ARGV[2])
+local max_in_flight = tonumber(ARGV[3])
+local max_per_second = tonumber(ARGV[4])
+
+redis.call('ZREMRANGEBYSCORE', inflight, '-inf', now)
+if redis.call('ZCARD', inflight) >= max_in_flight then
+  return 0
+end
+
+if max_per_second > 0 then
+  local started = redis.call('INCR', rate)
+  if started == 1 then
+    redis.call('PEXPIRE', rate, 2000)
+  end
+  if started > max_per_second then
+    return 0
+  end
+end
+
+redis.call('ZADD', inflight, now + lease, ARGV[5])
+redis.call('PEXPIRE', inflight, lease)
+return 1
+"#;
+
+    let admitted: i32 = redis::Script::new(script)
+        .key(&key)
+        .key(rate_key(&webhook.id, now_ms))
+        .arg(now_ms)
+        .arg(lease.as_millis() as u64)
+        .arg(webhook.max_in_flight.max(1))
+        .arg(webhook.max_per_second.unwrap_or(0).max(0))
+        .arg(&token)
+        .invoke_async(&mut conn)
+        .await?;
+
+    Ok((admitted == 1).then_some(WebhookSlot { key, token }))
+}
+
+impl WebhookSlot {
+    /// Give the slot back. On failure the lease expires it instead.
+    pub async fn release(self, redis: &redis::Client) {
+        let result = async {
+            let mut conn = redis.get_multiplexed_async_connection().await?;
+            redis::cmd("ZREM")
+                .arg(&self.key)
+                .arg(&self.token)
+                .query_async::<_, i32>(&mut conn)
+                .await
+        }
+        .await;
+        if let Err(err) = result {
+            warn!(key = %self.key, error = %err, "failed to release webhook slot");
+        }
+    }
+}
+
+fn unix_now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slot_lease_covers_every_send_attempt() {
+        assert_eq!(
+            slot_lease(Duration::from_secs(30), 3),
+            Duration::from_secs(95)
+        );
+        assert_eq!(
+            slot_lease(Duration::from_secs(10), 0),
+            Duration::from_secs(15)
+        );
+    }
+
+    #[test]
+    fn test_rate_key_buckets_by_second() {
+        assert_eq!(rate_key("wh_1", 5_000), rate_key("wh_1", 5_999));
+        assert_ne!(rate_key("wh_1", 5_999), rate_key("wh_1", 6_000));
+        assert_eq!(in_flight_key("wh_1"), "wh:inflight:wh_1");
+    }
+}
@@ -4,16 +4,24 @@ use core::types::DeliveryJob;
 use core::tunnel::AgentRegistry;
 use sqlx::postgres::PgPoolOptions;
 use std::sync::Arc;
-use tracing::info;
+use tracing::{error, info};
 
 mod jobs;
+mod metrics;
 
 #[derive(Clone)]
 pub struct WorkerState {
     pub db: sqlx::PgPool,
+    pub redis: redis::Client,
     pub client: reqwest::Client,
     pub storage: apalis::postgres::PostgresStorage<DeliveryJob>,
     pub tunnel_registry: Arc<AgentRegistry>,
+    pub settings: Settings,
+    pub delivery_limit: Arc<jobs::limits::DeliveryLimiter>,
+    /// Webhook payload schemas, compiled once per webhook.
+    pub payload_schemas: Arc<core::schema::SchemaCache>,
+    /// Addresses this process's tunnel relay reply channel.
+    pub instance_id: String,
 }
 
 #[tokio::main]
@@ -25,6 +33,7 @@ async fn main() -> Result<()> {
         .init();
 
     let settings = Settings::from_env()?;
+    settings.validate().map_err(anyhow::Error::msg)?;
 
     let db = PgPoolOptions::new()
         .max_connections(5)
@@ -34,35 +43,71 @@ async fn main() -> Result<()> {
     let storage =
         apalis::postgres::PostgresStorage::<DeliveryJob>::new(&settings.database_url).await?;
 
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
-        .build()?;
+    let redis = redis::Client::open(settings.redis_url.clone())?;
+
+    // Subscriber-chosen URLs: in prod the client never connects to private
+    // addresses, however the host resolves at delivery time.
+    let client =
+        core::net::outbound_client(&settings.herald_env, std::time::Duration::from_secs(30))?;
 
     let state = WorkerState {
         db,
+        redis,
         client,
         storage,
         tunnel_registry: core::tunnel::AGENT_REGISTRY.clone(),
+        settings: settings.clone(),
+        delivery_limit: Arc::new(jobs::limits::DeliveryLimiter::new(
+            settings.delivery_max_concurrency,
+        )),
+        payload_schemas: Arc::new(core::schema::SchemaCache::new()),
+        instance_id: core::presence::instance_id("worker"),
     };
 
-    let handler_state = state.clone();
+    tokio::spawn(jobs::cleanup::purge_idempotency_keys(
+        state.db.clone(),
+        settings.idempotency_retention_hours,
+    ));
+    tokio::spawn(jobs::cleanup::expire_api_keys(state.db.clone()));
+
+    tokio::spawn(jobs::scheduler::dispatch_scheduled_signals(state.clone()));
+    tokio::spawn(jobs::relay::listen_for_replies(state.clone()));
+    tokio::spawn(state.delivery_limit.clone().report_in_flight());
+    tokio::spawn(metrics::report_queue_depth(state.storage.clone()));
+
+    let dispatcher = Arc::new(jobs::fairness::FairDispatcher::new(
+        settings.worker_concurrency,
+    ));
+    tokio::spawn(dispatcher.clone().run(state.clone()));
+    tokio::spawn(dispatcher.clone().report_in_flight());
+
+    let metrics_limit = state.delivery_limit.clone();
+    let metrics_dispatcher = dispatcher.clone();
+    tokio::spawn(async move {
+        let port = settings.worker_metrics_port;
+        if let Err(err) = metrics::serve(port, metrics_limit, metrics_dispatcher).await {
+            error!(error = %err, "worker metrics endpoint stopped");
+        }
+    });
+
+    let handler_dispatcher = dispatcher.clone();
     let worker_high = apalis::prelude::WorkerBuilder::new("delivery-high")
         .layer(apalis::layers::RetryLayer::new(
-            jobs::delivery::retry_policy,
+            jobs::delivery::retry_policy::<rand::rngs::ThreadRng>,
         ))
         .build_fn(move |job: DeliveryJob| {
-            let state = handler_state.clone();
-            async move { jobs::delivery::handle_delivery_job(&state, job).await }
+            let dispatcher = handler_dispatcher.clone();
+            async move { dispatcher.submit(job).await }
         });
 
-    let handler_state = state.clone();
+    let handler_dispatcher = dispatcher.clone();
     let worker_normal = apalis::prelude::WorkerBuilder::new("delivery-normal")
         .layer(apalis::layers::RetryLayer::new(
-            jobs::delivery::retry_policy,
+            jobs::delivery::retry_policy::<rand::rngs::ThreadRng>,
         ))
         .build_fn(move |job: DeliveryJob| {
-            let state = handler_state.clone();
-            async move { jobs::delivery::handle_delivery_job(&state, job).await }
+            let dispatcher = handler_dispatcher.clone();
+            async move { dispatcher.submit(job).await }
         });
 
     info!("worker starting");
@@ -70,8 +115,15 @@ async fn main() -> Result<()> {
     apalis::prelude::Monitor::new()
         .register(worker_high)
         .register(worker_normal)
-        .run()
+        .run_with_signal(async {
+            core::shutdown::shutdown_signal().await;
+            Ok(())
+        })
         .await?;
 
+    info!("worker stopped taking jobs, draining in-flight deliveries");
+    dispatcher.drain().await;
+    info!("worker stopped");
+
     Ok(())
 }
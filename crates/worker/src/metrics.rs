@@ -0,0 +1,103 @@
+//! Prometheus `/metrics` endpoint for the worker process.
+//!
+//! Delivery outcomes and latency are recorded into the worker's own
+//! [`METRICS`]; the API process keeps a separate store for its counters.
+
+use apalis::postgres::PostgresStorage;
+use axum::{routing::get, Router};
+use core::metrics::METRICS;
+use core::types::DeliveryJob;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tracing::{info, warn};
+
+use crate::jobs::fairness::FairDispatcher;
+use crate::jobs::limits::DeliveryLimiter;
+
+/// How often `herald_queue_depth` is refreshed.
+const QUEUE_DEPTH_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Delivery queues whose depth is reported.
+const DELIVERY_QUEUES: [&str; 2] = ["delivery-high", "delivery-normal"];
+
+/// Serve `/metrics` on `port` until the listener fails.
+pub async fn serve(
+    port: u16,
+    limiter: Arc<DeliveryLimiter>,
+    dispatcher: Arc<FairDispatcher>,
+) -> anyhow::Result<()> {
+    let app = Router::new().route(
+        "/metrics",
+        get(move || {
+            let limiter = limiter.clone();
+            let dispatcher = dispatcher.clone();
+            async move { render(&limiter, &dispatcher) }
+        }),
+    );
+
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    info!(%addr, "serving worker metrics");
+    let listener = TcpListener::bind(addr).await?;
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+fn render(limiter: &DeliveryLimiter, dispatcher: &FairDispatcher) -> String {
+    let mut out = METRICS.gather();
+    out.push_str("# TYPE herald_delivery_inflight_total gauge\n");
+    out.push_str(&format!(
+        "herald_delivery_inflight_total {}\n",
+        limiter.in_flight()
+    ));
+
+    let mut by_channel: Vec<_> = dispatcher.in_flight_snapshot().into_iter().collect();
+    by_channel.sort();
+    out.push_str("# TYPE herald_delivery_inflight_by_channel gauge\n");
+    for (channel, in_flight) in by_channel {
+        out.push_str(&format!(
+            "herald_delivery_inflight_by_channel{{channel=\"{}\"}} {}\n",
+            channel, in_flight
+        ));
+    }
+    out
+}
+
+/// Periodically record how many jobs are waiting on each delivery queue.
+pub async fn report_queue_depth(storage: PostgresStorage<DeliveryJob>) {
+    let mut interval = tokio::time::interval(QUEUE_DEPTH_INTERVAL);
+    loop {
+        interval.tick().await;
+        for queue in DELIVERY_QUEUES {
+            match storage.len(queue).await {
+                Ok(depth) => METRICS.set_queue_depth(queue, depth),
+                Err(err) => warn!(queue, error = %err, "failed to count pending jobs"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_includes_deliveries_and_inflight_gauge() {
+        METRICS.record_delivery("success");
+        METRICS.record_delivery_latency_histogram("ch_metrics", 0.2);
+        METRICS.set_queue_depth("delivery-high", 4);
+        let limiter = DeliveryLimiter::new(2);
+        let dispatcher = FairDispatcher::new(2);
+
+        let output = render(&limiter, &dispatcher);
+
+        assert!(output.contains("herald_deliveries_total{status=\"success\"}"));
+        assert!(output.contains(
+            "herald_delivery_latency_seconds_bucket{channel=\"ch_metrics\",le=\"0.25\"} 1\n"
+        ));
+        assert!(output.contains("herald_queue_depth{queue=\"delivery-high\"} 4\n"));
+        assert!(output.contains("herald_delivery_inflight_total 0\n"));
+        assert!(output.contains("# TYPE herald_delivery_inflight_by_channel gauge\n"));
+    }
+}
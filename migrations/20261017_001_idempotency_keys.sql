@@ -0,0 +1,11 @@
+-- idempotency_keys
+CREATE TABLE idempotency_keys (
+  key TEXT NOT NULL,
+  owner_id TEXT NOT NULL,
+  request_hash TEXT NOT NULL,
+  response_snapshot JSONB NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  PRIMARY KEY (owner_id, key)
+);
+
+CREATE INDEX idx_idempotency_keys_created ON idempotency_keys (created_at);
@@ -0,0 +1,2 @@
+-- signals: optional minimum subscriber tier for gated delivery
+ALTER TABLE signals ADD COLUMN min_subscriber_tier account_tier;
@@ -0,0 +1,3 @@
+-- subscriptions: optional secondary webhook used only for critical signals
+ALTER TABLE subscriptions
+  ADD COLUMN emergency_webhook_id TEXT REFERENCES webhooks(id) ON DELETE RESTRICT;
@@ -0,0 +1,2 @@
+-- deliveries: bounded snippet of the webhook response body on failure
+ALTER TABLE deliveries ADD COLUMN response_body TEXT;
@@ -0,0 +1,2 @@
+-- webhooks: optional custom request headers (object of string -> string)
+ALTER TABLE webhooks ADD COLUMN headers JSONB;
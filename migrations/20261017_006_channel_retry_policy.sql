@@ -0,0 +1,2 @@
+-- channels: optional retry policy override for deliveries of this channel's signals
+ALTER TABLE channels ADD COLUMN retry_policy JSONB;
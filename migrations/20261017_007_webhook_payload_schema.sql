@@ -0,0 +1,2 @@
+-- webhooks: optional JSON Schema that deliveries are checked against (logged, not enforced)
+ALTER TABLE webhooks ADD COLUMN payload_schema JSONB;
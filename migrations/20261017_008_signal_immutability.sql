@@ -0,0 +1,19 @@
+-- signals: append-only; only status and delivery counters may change after insert
+CREATE FUNCTION signals_reject_content_update() RETURNS trigger AS $$
+BEGIN
+  IF NEW.channel_id IS DISTINCT FROM OLD.channel_id
+     OR NEW.title IS DISTINCT FROM OLD.title
+     OR NEW.body IS DISTINCT FROM OLD.body
+     OR NEW.urgency IS DISTINCT FROM OLD.urgency
+     OR NEW.metadata IS DISTINCT FROM OLD.metadata
+     OR NEW.min_subscriber_tier IS DISTINCT FROM OLD.min_subscriber_tier
+     OR NEW.created_at IS DISTINCT FROM OLD.created_at THEN
+    RAISE EXCEPTION 'signal % is immutable; publish a new signal instead', OLD.id;
+  END IF;
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER signals_immutable
+  BEFORE UPDATE ON signals
+  FOR EACH ROW EXECUTE FUNCTION signals_reject_content_update();
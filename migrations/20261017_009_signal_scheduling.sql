@@ -0,0 +1,21 @@
+-- signals: optional future send time; such signals wait as 'scheduled' until the worker fans them out
+ALTER TYPE signal_status ADD VALUE IF NOT EXISTS 'scheduled';
+ALTER TABLE signals ADD COLUMN scheduled_at TIMESTAMPTZ;
+CREATE INDEX idx_signals_scheduled_at ON signals (scheduled_at) WHERE scheduled_at IS NOT NULL;
+
+-- scheduled_at is content too: keep it out of the mutable surface
+CREATE OR REPLACE FUNCTION signals_reject_content_update() RETURNS trigger AS $$
+BEGIN
+  IF NEW.channel_id IS DISTINCT FROM OLD.channel_id
+     OR NEW.title IS DISTINCT FROM OLD.title
+     OR NEW.body IS DISTINCT FROM OLD.body
+     OR NEW.urgency IS DISTINCT FROM OLD.urgency
+     OR NEW.metadata IS DISTINCT FROM OLD.metadata
+     OR NEW.min_subscriber_tier IS DISTINCT FROM OLD.min_subscriber_tier
+     OR NEW.scheduled_at IS DISTINCT FROM OLD.scheduled_at
+     OR NEW.created_at IS DISTINCT FROM OLD.created_at THEN
+    RAISE EXCEPTION 'signal % is immutable; publish a new signal instead', OLD.id;
+  END IF;
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
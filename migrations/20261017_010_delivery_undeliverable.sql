@@ -0,0 +1,2 @@
+-- deliveries: terminal status for permanent failures that are neither retried nor dead-lettered
+ALTER TYPE delivery_status ADD VALUE IF NOT EXISTS 'undeliverable';
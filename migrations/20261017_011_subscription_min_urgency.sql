@@ -0,0 +1,3 @@
+-- subscriptions: per-subscription urgency threshold for fan-out
+ALTER TABLE subscriptions
+  ADD COLUMN min_urgency signal_urgency NOT NULL DEFAULT 'low';
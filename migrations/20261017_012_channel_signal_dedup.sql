@@ -0,0 +1,28 @@
+-- channels/signals: opt-in window for rejecting repeated title+body signals
+ALTER TABLE channels
+  ADD COLUMN dedup_window_secs INTEGER NOT NULL DEFAULT 0
+  CHECK (dedup_window_secs >= 0);
+
+ALTER TABLE signals ADD COLUMN content_hash TEXT;
+
+CREATE INDEX idx_signals_channel_content_hash
+  ON signals (channel_id, content_hash, created_at DESC)
+  WHERE content_hash IS NOT NULL;
+
+-- The hash is derived from title and body, so it is as immutable as they are.
+CREATE OR REPLACE FUNCTION signals_reject_content_update() RETURNS trigger AS $$
+BEGIN
+  IF NEW.channel_id IS DISTINCT FROM OLD.channel_id
+     OR NEW.title IS DISTINCT FROM OLD.title
+     OR NEW.body IS DISTINCT FROM OLD.body
+     OR NEW.content_hash IS DISTINCT FROM OLD.content_hash
+     OR NEW.urgency IS DISTINCT FROM OLD.urgency
+     OR NEW.metadata IS DISTINCT FROM OLD.metadata
+     OR NEW.min_subscriber_tier IS DISTINCT FROM OLD.min_subscriber_tier
+     OR NEW.scheduled_at IS DISTINCT FROM OLD.scheduled_at
+     OR NEW.created_at IS DISTINCT FROM OLD.created_at THEN
+    RAISE EXCEPTION 'signal % is immutable; publish a new signal instead', OLD.id;
+  END IF;
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
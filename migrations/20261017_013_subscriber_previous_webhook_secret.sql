@@ -0,0 +1,4 @@
+-- subscribers: prior webhook secret kept for a grace period after rotation
+ALTER TABLE subscribers
+  ADD COLUMN previous_webhook_secret TEXT,
+  ADD COLUMN previous_webhook_secret_expires_at TIMESTAMPTZ;
@@ -0,0 +1,4 @@
+-- webhooks: per-endpoint delivery request timeout
+ALTER TABLE webhooks
+  ADD COLUMN timeout_ms INTEGER NOT NULL DEFAULT 30000
+    CHECK (timeout_ms BETWEEN 1000 AND 60000);
@@ -0,0 +1,3 @@
+-- dead_letter_queue: why an entry was resolved without being replayed
+ALTER TABLE dead_letter_queue
+  ADD COLUMN resolution_note TEXT;
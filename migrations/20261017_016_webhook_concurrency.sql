@@ -0,0 +1,6 @@
+-- webhooks: per-endpoint cap on concurrent and per-second deliveries
+ALTER TABLE webhooks
+  ADD COLUMN max_in_flight INTEGER NOT NULL DEFAULT 10
+    CHECK (max_in_flight BETWEEN 1 AND 100),
+  ADD COLUMN max_per_second INTEGER
+    CHECK (max_per_second BETWEEN 1 AND 1000);
@@ -0,0 +1,8 @@
+-- channels/signals: optional delivery receipt once every delivery of a signal settles
+ALTER TABLE channels ADD COLUMN delivery_callback_url TEXT;
+
+-- expected_deliveries is the fan-out size, written before any job is queued;
+-- settled_deliveries counts jobs that reached a final outcome.
+ALTER TABLE signals
+  ADD COLUMN expected_deliveries INTEGER,
+  ADD COLUMN settled_deliveries INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,8 @@
+-- subscribers/deliveries: per-subscriber sequence numbers on tunnel deliveries,
+-- so a reconnecting agent can ask for the ones it missed
+ALTER TABLE subscribers ADD COLUMN tunnel_seq BIGINT NOT NULL DEFAULT 0;
+
+ALTER TABLE deliveries ADD COLUMN tunnel_seq BIGINT;
+
+CREATE INDEX idx_deliveries_tunnel_seq ON deliveries (subscription_id, tunnel_seq)
+  WHERE tunnel_seq IS NOT NULL;
@@ -0,0 +1,12 @@
+-- webhooks: versioned signatures (`t=<ts>,v1=<hmac>`) with a choice of HMAC.
+-- Existing endpoints keep the legacy `sha256=<hmac>` header until they opt
+-- in; endpoints registered from now on get the versioned one.
+CREATE TYPE signature_algorithm AS ENUM ('sha256', 'sha512');
+
+ALTER TABLE webhooks
+  ADD COLUMN signature_algorithm signature_algorithm NOT NULL DEFAULT 'sha256',
+  ADD COLUMN legacy_signature BOOLEAN NOT NULL DEFAULT true,
+  ADD CONSTRAINT webhooks_legacy_signature_sha256
+    CHECK (NOT legacy_signature OR signature_algorithm = 'sha256');
+
+ALTER TABLE webhooks ALTER COLUMN legacy_signature SET DEFAULT false;